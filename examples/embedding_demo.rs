@@ -34,7 +34,7 @@ async fn main() -> Result<()> {
     println!("✓ 内存管理器创建完成");
 
     // 测试文本
-    let test_texts = vec![
+    let test_texts = [
         "今天天气很好，适合出去散步。",
         "我正在学习人工智能和机器学习。",
         "The weather is nice today, perfect for a walk.",
@@ -0,0 +1,83 @@
+//! 记忆图导出工具
+//!
+//! 将数据库中的记忆图导出为 GraphML、DOT 或 JSON node-link 格式。
+//!
+//! 用法：
+//!
+//! ```text
+//! cargo run --example graph_export -- <database_url> [--format graphml|dot|json]
+//!     [--memory <id>]... [--hops <k>] [--output <path>]
+//! ```
+//!
+//! 指定 `--memory` 时导出这些记忆周围的k跳子图（默认1跳），否则导出整个图。
+//! 未指定 `--output` 时输出到标准输出。
+
+use ai00_mem::config::Config;
+use ai00_mem::{ExportFormat, ExportScope, GraphExporter, VectorGraphDB};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+
+    let mut database_url = None;
+    let mut format = ExportFormat::GraphML;
+    let mut memory_ids = Vec::new();
+    let mut hops = 1;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" | "-f" => format = next_value(&mut args, &arg)?.parse()?,
+            "--memory" | "-m" => memory_ids.push(next_value(&mut args, &arg)?),
+            "--hops" | "-k" => hops = next_value(&mut args, &arg)?.parse()?,
+            "--output" | "-o" => output = Some(next_value(&mut args, &arg)?),
+            "--help" | "-h" => {
+                print_usage();
+                return Ok(());
+            }
+            _ if database_url.is_none() => database_url = Some(arg),
+            _ => return Err(format!("unexpected argument: {}", arg).into()),
+        }
+    }
+
+    let Some(database_url) = database_url else {
+        print_usage();
+        return Err("missing database url".into());
+    };
+
+    let mut config = Config::default();
+    config.database.url = database_url;
+    let db = VectorGraphDB::new(config).await?;
+
+    let scope = if memory_ids.is_empty() {
+        ExportScope::Full
+    } else {
+        ExportScope::Neighborhood { memory_ids, hops }
+    };
+
+    let exporter = GraphExporter::new();
+    match output {
+        Some(path) => {
+            exporter.export_to_file(&db, &scope, format, &path).await?;
+            eprintln!("已导出 {:?} 到 {}", format, path);
+        }
+        None => print!("{}", exporter.export(&db, &scope, format).await?),
+    }
+
+    Ok(())
+}
+
+fn next_value(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    args.next()
+        .ok_or_else(|| format!("missing value for {}", flag).into())
+}
+
+fn print_usage() {
+    eprintln!(
+        "用法: graph_export <database_url> [--format graphml|dot|json] \
+         [--memory <id>]... [--hops <k>] [--output <path>]"
+    );
+}
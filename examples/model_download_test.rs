@@ -17,7 +17,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📥 开始从镜像站下载模型...");
 
     // 尝试多个镜像源
-    let mirror_endpoints = vec![
+    let mirror_endpoints = [
         "https://hf-mirror.com",
         "https://huggingface.co",
         "https://hub.nuaa.cf",
@@ -61,7 +61,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n🧪 开始测试文本嵌入生成...");
 
     // 测试文本（中英文混合）
-    let test_texts = vec![
+    let test_texts = [
         "今天天气很好，适合出去散步。",
         "我正在学习人工智能和机器学习。",
         "The weather is nice today, perfect for a walk.",
@@ -128,8 +128,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     ];
 
     for (text1, text2) in similar_pairs {
-        let emb1 = &model.encode(&vec![text1.to_string()])[0];
-        let emb2 = &model.encode(&vec![text2.to_string()])[0];
+        let emb1 = &model.encode(&[text1.to_string()])[0];
+        let emb2 = &model.encode(&[text2.to_string()])[0];
 
         // 计算余弦相似度
         let dot_product: f32 = emb1.iter().zip(emb2.iter()).map(|(a, b)| a * b).sum();
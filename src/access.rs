@@ -0,0 +1,126 @@
+//! 访问统计模块
+//!
+//! 按配置在检索返回或明确使用时记录记忆的访问，访问记录先在内存中累积，
+//! 达到阈值后由记忆管理器批量写回，避免每次检索都重写记忆。
+
+use crate::config::{AccessConfig, AccessCountMode, Config};
+use crate::core::{InteractionType, MemoryId};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// 一个记忆待写回的访问记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingAccess {
+    /// 累积的访问次数
+    pub count: u64,
+    /// 最近一次访问的时间
+    pub last_accessed: DateTime<Utc>,
+}
+
+/// 访问统计器
+#[derive(Debug)]
+pub struct AccessTracker {
+    enabled: bool,
+    count_mode: AccessCountMode,
+    flush_threshold: usize,
+    reinforcement_strength: f32,
+    pending: RwLock<HashMap<MemoryId, PendingAccess>>,
+}
+
+impl AccessTracker {
+    pub fn new(config: &Config) -> Self {
+        let AccessConfig {
+            enabled,
+            count_mode,
+            flush_threshold,
+            reinforcement_strength,
+        } = config.access;
+
+        Self {
+            enabled,
+            count_mode,
+            flush_threshold: flush_threshold.max(1),
+            reinforcement_strength,
+            pending: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 写回时强化记忆的强度
+    pub fn reinforcement_strength(&self) -> f32 {
+        self.reinforcement_strength
+    }
+
+    /// 检索返回的记忆是否计为访问
+    pub fn counts_retrieval(&self) -> bool {
+        self.enabled && self.count_mode == AccessCountMode::Retrieval
+    }
+
+    /// 交互中的记忆是否计为访问
+    ///
+    /// 按检索计数时，查询交互中的记忆已在检索时计过，只统计浏览、反馈等其他交互。
+    pub fn counts_interaction(&self, interaction_type: &InteractionType) -> bool {
+        self.enabled
+            && match self.count_mode {
+                AccessCountMode::Retrieval => *interaction_type != InteractionType::Query,
+                AccessCountMode::ExplicitUse => true,
+            }
+    }
+
+    /// 记录一批访问，返回待写回的记忆数是否已达到阈值
+    pub async fn record<'a>(&self, memory_ids: impl IntoIterator<Item = &'a MemoryId>) -> bool {
+        let now = Utc::now();
+        let mut pending = self.pending.write().await;
+        for memory_id in memory_ids {
+            pending
+                .entry(memory_id.clone())
+                .and_modify(|access| {
+                    access.count += 1;
+                    access.last_accessed = now;
+                })
+                .or_insert(PendingAccess {
+                    count: 1,
+                    last_accessed: now,
+                });
+        }
+        pending.len() >= self.flush_threshold
+    }
+
+    /// 待写回的记忆数
+    pub async fn pending_count(&self) -> usize {
+        self.pending.read().await.len()
+    }
+
+    /// 取出全部待写回的访问记录
+    pub async fn take_pending(&self) -> HashMap<MemoryId, PendingAccess> {
+        std::mem::take(&mut *self.pending.write().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_access_batching() {
+        let mut config = Config::default();
+        config.access.flush_threshold = 2;
+        let tracker = AccessTracker::new(&config);
+        assert!(tracker.counts_retrieval());
+        assert!(!tracker.counts_interaction(&InteractionType::Query));
+        assert!(tracker.counts_interaction(&InteractionType::Browse));
+
+        let (a, b) = ("a".to_string(), "b".to_string());
+        assert!(!tracker.record([&a, &a]).await);
+        assert!(tracker.record([&b]).await);
+        let pending = tracker.take_pending().await;
+        assert_eq!(pending[&a].count, 2);
+        assert_eq!(pending[&b].count, 1);
+        assert_eq!(tracker.pending_count().await, 0);
+
+        config.access.count_mode = AccessCountMode::ExplicitUse;
+        let tracker = AccessTracker::new(&config);
+        assert!(!tracker.counts_retrieval());
+        assert!(tracker.counts_interaction(&InteractionType::Query));
+    }
+}
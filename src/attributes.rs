@@ -0,0 +1,294 @@
+//! 属性提取模块
+//!
+//! 不依赖大语言模型的记忆属性提取：语言检测、关键词、基于词典的情感标注、
+//! 按可配置标签体系自动打标签，以及从上下文中读取来源。
+
+use crate::config::{AttributeConfig, Config};
+use crate::core::Context;
+use crate::llm_tasks::{AttributeExtractionResponse, EmotionAnalysisResponse, Sentiment};
+use crate::segment::Segmenter;
+use crate::utils::TextUtils;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// 情感词典：(词, 情感, 极性)
+const EMOTION_LEXICON: &[(&str, &str, i8)] = &[
+    ("开心", "喜悦", 1),
+    ("高兴", "喜悦", 1),
+    ("快乐", "喜悦", 1),
+    ("成功", "喜悦", 1),
+    ("满意", "满足", 1),
+    ("喜欢", "喜爱", 1),
+    ("兴奋", "兴奋", 1),
+    ("期待", "期待", 1),
+    ("happy", "喜悦", 1),
+    ("glad", "喜悦", 1),
+    ("great", "喜悦", 1),
+    ("success", "喜悦", 1),
+    ("love", "喜爱", 1),
+    ("excited", "兴奋", 1),
+    ("难过", "悲伤", -1),
+    ("伤心", "悲伤", -1),
+    ("失望", "失望", -1),
+    ("失败", "失望", -1),
+    ("生气", "愤怒", -1),
+    ("愤怒", "愤怒", -1),
+    ("担心", "担忧", -1),
+    ("焦虑", "担忧", -1),
+    ("害怕", "恐惧", -1),
+    ("sad", "悲伤", -1),
+    ("disappointed", "失望", -1),
+    ("failed", "失望", -1),
+    ("angry", "愤怒", -1),
+    ("worried", "担忧", -1),
+    ("afraid", "恐惧", -1),
+];
+
+/// 否定词：紧邻情感词之前时翻转极性，且不计入该情感
+const NEGATIONS: &[&str] = &["不", "没", "not ", "never ", "no "];
+
+/// 上下文环境信息中表示来源的键
+pub const SOURCE_ENVIRONMENT_KEY: &str = "source";
+
+/// 基于情感词典分析文本情感
+pub fn analyze_emotion(text: &str) -> EmotionAnalysisResponse {
+    let text = text.to_lowercase();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut polarity = 0i32;
+    for (word, emotion, sign) in EMOTION_LEXICON {
+        // 英文词要求两侧不是字母或数字，避免 "unhappy" 命中 "happy"
+        let ascii = word.is_ascii();
+        let matches = text.match_indices(word).filter(|&(position, _)| {
+            if !ascii {
+                return true;
+            }
+            let before = text[..position].chars().next_back();
+            let after = text[position + word.len()..].chars().next();
+            !before.is_some_and(|c| c.is_alphanumeric())
+                && !after.is_some_and(|c| c.is_alphanumeric())
+        });
+        for (position, _) in matches {
+            let negated = NEGATIONS
+                .iter()
+                .any(|negation| text[..position].ends_with(negation));
+            if negated {
+                polarity -= *sign as i32;
+            } else {
+                *counts.entry(emotion).or_insert(0) += 1;
+                polarity += *sign as i32;
+            }
+        }
+    }
+
+    let sentiment = match polarity.signum() {
+        1 => Sentiment::Positive,
+        -1 => Sentiment::Negative,
+        _ => Sentiment::Neutral,
+    };
+    let total: usize = counts.values().sum();
+    if total == 0 {
+        return EmotionAnalysisResponse {
+            primary_emotion: "平静".to_string(),
+            emotion_scores: HashMap::new(),
+            sentiment,
+            confidence: if polarity == 0 { 0.5 } else { 0.4 },
+        };
+    }
+
+    let primary_emotion = counts
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(emotion, _)| emotion.to_string())
+        .unwrap_or_default();
+
+    EmotionAnalysisResponse {
+        primary_emotion,
+        emotion_scores: counts
+            .into_iter()
+            .map(|(emotion, n)| (emotion.to_string(), n as f32 / total as f32))
+            .collect(),
+        sentiment,
+        confidence: (0.4 + 0.1 * total as f32).min(0.8),
+    }
+}
+
+/// 规则属性提取器
+#[derive(Debug, Clone)]
+pub struct AttributeExtractor {
+    max_keywords: usize,
+    detect_emotion: bool,
+    min_tag_matches: usize,
+    /// 按标签名排序，保证结果稳定
+    taxonomy: Vec<(String, Vec<String>)>,
+    segmenter: Arc<Segmenter>,
+}
+
+impl AttributeExtractor {
+    pub fn new(config: &Config) -> Self {
+        let AttributeConfig {
+            max_keywords,
+            detect_emotion,
+            min_tag_matches,
+            taxonomy,
+        } = &config.attributes;
+
+        let mut taxonomy: Vec<(String, Vec<String>)> = taxonomy
+            .iter()
+            .map(|(tag, terms)| {
+                (
+                    tag.clone(),
+                    terms.iter().map(|t| t.to_lowercase()).collect(),
+                )
+            })
+            .collect();
+        taxonomy.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Self {
+            max_keywords: *max_keywords,
+            detect_emotion: *detect_emotion,
+            min_tag_matches: *min_tag_matches,
+            taxonomy,
+            segmenter: Segmenter::shared(),
+        }
+    }
+
+    /// 使用指定的分词器提取关键词
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.segmenter = segmenter;
+        self
+    }
+
+    /// 提取记忆属性
+    pub fn extract(&self, content: &str) -> AttributeExtractionResponse {
+        let keywords = self.segmenter.extract_keywords(content, self.max_keywords);
+        let tags = self.tags(content);
+        let language = TextUtils::detect_language(content);
+        let emotion = if self.detect_emotion {
+            let analysis = analyze_emotion(content);
+            (!analysis.emotion_scores.is_empty()).then_some(analysis.primary_emotion)
+        } else {
+            None
+        };
+
+        // 规则提取的置信度：识别出的属性越多越可信，但不超过0.8
+        let confidence = 0.5
+            + [language.is_some(), !keywords.is_empty(), !tags.is_empty()]
+                .iter()
+                .filter(|found| **found)
+                .count() as f32
+                * 0.1;
+
+        AttributeExtractionResponse {
+            keywords,
+            tags,
+            emotion,
+            importance: 0.5,
+            confidence,
+            language,
+            themes: Vec::new(),
+        }
+    }
+
+    /// 按标签体系打标签，命中词数多的标签排在前面
+    pub fn tags(&self, content: &str) -> Vec<String> {
+        let text = content.to_lowercase();
+        let words: HashSet<&str> = text
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        let mut matched: Vec<(&String, usize)> = self
+            .taxonomy
+            .iter()
+            .map(|(tag, terms)| {
+                let hits = terms
+                    .iter()
+                    .filter(|term| {
+                        if term.is_ascii() {
+                            words.contains(term.as_str())
+                        } else {
+                            text.contains(term.as_str())
+                        }
+                    })
+                    .count();
+                (tag, hits)
+            })
+            .filter(|(_, hits)| *hits >= self.min_tag_matches)
+            .collect();
+        matched.sort_by_key(|(_, hits)| std::cmp::Reverse(*hits));
+
+        matched.into_iter().map(|(tag, _)| tag.clone()).collect()
+    }
+
+    /// 从上下文读取来源：优先使用环境信息中的 `source`，其次为会话ID
+    pub fn source(context: &Context) -> Option<String> {
+        context
+            .environment
+            .get(SOURCE_ENVIRONMENT_KEY)
+            .and_then(|value| value.as_str())
+            .map(|source| source.to_string())
+            .or_else(|| {
+                context
+                    .session_id
+                    .as_ref()
+                    .map(|session| format!("session:{}", session))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_attributes() {
+        let extractor = AttributeExtractor::new(&Config::default());
+        let attributes = extractor.extract("今天学习了深度学习课程，项目演示也很成功，我很开心！");
+
+        assert_eq!(attributes.language.as_deref(), Some("zh"));
+        assert_eq!(attributes.emotion.as_deref(), Some("喜悦"));
+        assert_eq!(attributes.tags[0], "学习");
+        assert!(attributes.tags.contains(&"人工智能".to_string()));
+        assert!(attributes.tags.contains(&"工作".to_string()));
+        assert!(attributes.keywords.iter().all(|k| k.chars().count() <= 4));
+        assert!((attributes.confidence - 0.8).abs() < 1e-6);
+
+        // 英文标签按整词匹配，"said"不会命中"ai"
+        let attributes = extractor.extract("She said the weather was fine");
+        assert!(attributes.tags.is_empty());
+        assert_eq!(attributes.emotion, None);
+        assert_eq!(attributes.language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn test_emotion_negation_and_source() {
+        let analysis = analyze_emotion("项目没成功，我很失望");
+        assert_eq!(analysis.sentiment, Sentiment::Negative);
+        assert_eq!(analysis.primary_emotion, "失望");
+        assert!(!analysis.emotion_scores.contains_key("喜悦"));
+
+        // 英文情感词按整词匹配
+        let analysis = analyze_emotion("I am unhappy, it was not the greatest day");
+        assert_eq!(analysis.sentiment, Sentiment::Neutral);
+        assert!(analysis.emotion_scores.is_empty());
+        let analysis = analyze_emotion("So happy: a great, successful day");
+        assert_eq!(analysis.sentiment, Sentiment::Positive);
+        assert_eq!(analysis.emotion_scores["喜悦"], 1.0);
+
+        let mut context = Context {
+            session_id: Some("s1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            AttributeExtractor::source(&context).as_deref(),
+            Some("session:s1")
+        );
+        context
+            .environment
+            .insert(SOURCE_ENVIRONMENT_KEY.to_string(), "wechat".into());
+        assert_eq!(
+            AttributeExtractor::source(&context).as_deref(),
+            Some("wechat")
+        );
+    }
+}
@@ -0,0 +1,493 @@
+//! 因果关系模块
+//!
+//! 在写入记忆时检测记忆之间的因果关系，生成有向的因果连接。
+//! 默认使用基于中英文因果连接词的规则检测器，也可以换成遵循
+//! 《LLM集成接口文档》中“因果关系分析”约定的大语言模型分析器。
+
+use crate::config::Config;
+use crate::core::Memory;
+use crate::error::Result;
+use crate::llm::{LlmProvider, LlmTaskRunner};
+use crate::llm_tasks::CausalAnalysisTask;
+use crate::segment::{Segmenter, TokenKind};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// 表示结果的连接词：其后为结果，其前（或前文）为原因
+const EFFECT_MARKERS: &[&str] = &[
+    "所以",
+    "因此",
+    "因而",
+    "于是",
+    "结果",
+    "therefore",
+    "as a result",
+    "consequently",
+    "hence",
+    "thus",
+];
+
+/// 表示原因的连接词：其后为原因
+const CAUSE_MARKERS: &[&str] = &[
+    "因为",
+    "由于",
+    "because of",
+    "because",
+    "due to",
+    "owing to",
+];
+
+/// 因果动词：其前为原因，其后为结果
+const CAUSING_VERBS: &[&str] = &[
+    "导致",
+    "造成",
+    "引起",
+    "使得",
+    "led to",
+    "leads to",
+    "lead to",
+    "caused",
+    "causes",
+    "resulted in",
+    "results in",
+];
+
+/// 因果关系类型（从源记忆的角度）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CausalRelationType {
+    /// 源记忆是目标记忆的原因
+    Cause,
+    /// 源记忆是目标记忆的结果
+    Effect,
+    /// 相关但非因果
+    Correlation,
+    /// 无关系
+    None,
+}
+
+/// 因果关系分析请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CausalAnalysisRequest {
+    pub source_memory: Memory,
+    pub target_memory: Memory,
+    /// 相关上下文记忆
+    pub context: Vec<Memory>,
+}
+
+/// 因果关系分析结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CausalAnalysisResponse {
+    pub has_causal_relation: bool,
+    pub relation_type: CausalRelationType,
+    pub confidence: f32,
+    #[serde(default)]
+    pub explanation: String,
+    #[serde(default)]
+    pub causal_keywords: Vec<String>,
+    /// 支持该判断的原文片段
+    #[serde(default)]
+    pub evidence: String,
+}
+
+impl CausalAnalysisResponse {
+    /// 无因果关系
+    pub fn none() -> Self {
+        Self {
+            has_causal_relation: false,
+            relation_type: CausalRelationType::None,
+            confidence: 0.0,
+            explanation: String::new(),
+            causal_keywords: Vec::new(),
+            evidence: String::new(),
+        }
+    }
+}
+
+/// 因果关系分析器
+#[async_trait]
+pub trait CausalAnalyzer: Send + Sync + std::fmt::Debug {
+    /// 分析器名称，记录在因果边的属性中
+    fn name(&self) -> &str;
+
+    /// 分析源记忆与目标记忆之间的因果关系
+    async fn analyze(&self, request: &CausalAnalysisRequest) -> Result<CausalAnalysisResponse>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkerKind {
+    Effect,
+    Cause,
+    Verb,
+}
+
+/// 一处因果表达：连接词、所在句子，以及句中的原因和结果部分
+#[derive(Debug, Clone)]
+struct CausalSpan {
+    connective: String,
+    kind: MarkerKind,
+    sentence: String,
+    cause: String,
+    effect: String,
+}
+
+/// 基于连接词的规则因果检测器
+///
+/// 在一条记忆中找到因果连接词后，用词汇重叠判断另一条记忆对应原因
+/// 还是结果；以“所以”等开头的句子指向更早的记忆。
+#[derive(Debug, Clone)]
+pub struct RuleCausalDetector {
+    min_overlap: f32,
+    segmenter: Arc<Segmenter>,
+}
+
+impl RuleCausalDetector {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            min_overlap: config.graph.causal.min_overlap,
+            segmenter: Segmenter::shared(),
+        }
+    }
+
+    /// 使用指定的分词器计算词汇重叠
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.segmenter = segmenter;
+        self
+    }
+
+    /// 文本中出现的因果连接词
+    pub fn connectives(text: &str) -> Vec<String> {
+        let lower = text.to_ascii_lowercase();
+        EFFECT_MARKERS
+            .iter()
+            .chain(CAUSE_MARKERS)
+            .chain(CAUSING_VERBS)
+            .filter(|marker| !Self::find_marker(&lower, marker).is_empty())
+            .map(|marker| marker.to_string())
+            .collect()
+    }
+
+    /// 判断另一条记忆是否为该记忆中因果表达的原因（true）或结果（false）
+    ///
+    /// `adjacent` 表示另一条记忆属于上下文中的最近记忆，此时以“所以”
+    /// 开头的句子即使没有词汇重叠也视为承接它。
+    fn detect(
+        &self,
+        holder: &Memory,
+        other: &Memory,
+        adjacent: bool,
+    ) -> Option<(bool, f32, CausalSpan)> {
+        let other_terms = terms(&self.segmenter, &other.content);
+        if other_terms.is_empty() {
+            return None;
+        }
+
+        let mut best: Option<(bool, f32, CausalSpan)> = None;
+        for span in Self::spans(&holder.content) {
+            let cause_overlap = overlap(&other_terms, &terms(&self.segmenter, &span.cause));
+            let effect_overlap = overlap(&other_terms, &terms(&self.segmenter, &span.effect));
+
+            let found = if span.kind == MarkerKind::Effect
+                && is_blank(&span.cause)
+                && other.metadata.created_at <= holder.metadata.created_at
+                && (adjacent || effect_overlap > 0.0)
+            {
+                // 以“所以”开头，原因在前文中
+                Some((true, 0.55 + 0.4 * effect_overlap.max(cause_overlap)))
+            } else if cause_overlap >= self.min_overlap && cause_overlap > effect_overlap {
+                Some((true, 0.5 + 0.45 * cause_overlap))
+            } else if effect_overlap >= self.min_overlap && effect_overlap > cause_overlap {
+                Some((false, 0.5 + 0.45 * effect_overlap))
+            } else {
+                None
+            };
+
+            if let Some((other_is_cause, confidence)) = found {
+                let confidence = confidence.min(0.95);
+                if best.as_ref().is_none_or(|(_, c, _)| confidence > *c) {
+                    best = Some((other_is_cause, confidence, span));
+                }
+            }
+        }
+
+        best
+    }
+
+    fn spans(text: &str) -> Vec<CausalSpan> {
+        let mut spans = Vec::new();
+        for sentence in text.split(|c: char| "。！？!?;；\n".contains(c)) {
+            let lower = sentence.to_ascii_lowercase();
+            for (markers, kind) in [
+                (EFFECT_MARKERS, MarkerKind::Effect),
+                (CAUSE_MARKERS, MarkerKind::Cause),
+                (CAUSING_VERBS, MarkerKind::Verb),
+            ] {
+                for marker in markers {
+                    for start in Self::find_marker(&lower, marker) {
+                        let before = &sentence[..start];
+                        let after = &sentence[start + marker.len()..];
+                        let (cause, effect) = match kind {
+                            MarkerKind::Effect | MarkerKind::Verb => {
+                                (before.to_string(), after.to_string())
+                            }
+                            MarkerKind::Cause => {
+                                // 原因从连接词延续到下一个逗号
+                                let end = after.find([',', '，']).unwrap_or(after.len());
+                                (
+                                    after[..end].to_string(),
+                                    format!("{} {}", before, &after[end..]),
+                                )
+                            }
+                        };
+                        spans.push(CausalSpan {
+                            connective: marker.to_string(),
+                            kind,
+                            sentence: sentence.trim().to_string(),
+                            cause,
+                            effect,
+                        });
+                    }
+                }
+            }
+        }
+        spans
+    }
+
+    /// 查找连接词的位置；英文连接词需要在单词边界上
+    fn find_marker(lower: &str, marker: &str) -> Vec<usize> {
+        let ascii = marker.is_ascii();
+        lower
+            .match_indices(marker)
+            .map(|(start, _)| start)
+            .filter(|&start| {
+                if !ascii {
+                    return true;
+                }
+                let before = lower[..start].chars().next_back();
+                let after = lower[start + marker.len()..].chars().next();
+                !before.is_some_and(|c| c.is_alphanumeric())
+                    && !after.is_some_and(|c| c.is_alphanumeric())
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl CausalAnalyzer for RuleCausalDetector {
+    fn name(&self) -> &str {
+        "rule"
+    }
+
+    async fn analyze(&self, request: &CausalAnalysisRequest) -> Result<CausalAnalysisResponse> {
+        let source = &request.source_memory;
+        let target = &request.target_memory;
+
+        let adjacent = |memory: &Memory| request.context.iter().any(|m| m.id == memory.id);
+
+        // 连接词可能出现在任一条记忆中
+        let in_target = self
+            .detect(target, source, adjacent(source))
+            .map(|(source_is_cause, c, span)| (source_is_cause, c, span, "target"));
+        let in_source = self
+            .detect(source, target, adjacent(target))
+            .map(|(target_is_cause, c, span)| (!target_is_cause, c, span, "source"));
+
+        let best = match (in_target, in_source) {
+            (Some(a), Some(b)) => Some(if b.1 > a.1 { b } else { a }),
+            (a, b) => a.or(b),
+        };
+        let Some((source_is_cause, confidence, span, holder)) = best else {
+            return Ok(CausalAnalysisResponse::none());
+        };
+
+        Ok(CausalAnalysisResponse {
+            has_causal_relation: true,
+            relation_type: if source_is_cause {
+                CausalRelationType::Cause
+            } else {
+                CausalRelationType::Effect
+            },
+            confidence,
+            explanation: format!("Connective '{}' in {} memory", span.connective, holder),
+            causal_keywords: vec![span.connective],
+            evidence: span.sentence,
+        })
+    }
+}
+
+/// 基于大语言模型的因果分析器
+///
+/// 通过 [`CausalAnalysisTask`] 调用模型，模型调用失败或输出未通过校验时回退到规则检测器。
+#[derive(Debug, Clone)]
+pub struct LlmCausalAnalyzer {
+    runner: LlmTaskRunner,
+    task: CausalAnalysisTask,
+}
+
+impl LlmCausalAnalyzer {
+    pub fn new(provider: Arc<dyn LlmProvider>, config: &Config) -> Self {
+        Self {
+            runner: LlmTaskRunner::new(provider, &config.llm),
+            task: CausalAnalysisTask::new(config),
+        }
+    }
+
+    /// 回退的规则检测器使用指定的分词器
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.task = self.task.with_segmenter(segmenter);
+        self
+    }
+}
+
+#[async_trait]
+impl CausalAnalyzer for LlmCausalAnalyzer {
+    fn name(&self) -> &str {
+        self.runner.provider().name()
+    }
+
+    async fn analyze(&self, request: &CausalAnalysisRequest) -> Result<CausalAnalysisResponse> {
+        self.runner.run(&self.task, request).await
+    }
+}
+
+/// 用于重叠计算的词项：分词结果去除停用词和因果连接词，英文只取长度大于2的单词
+fn terms(segmenter: &Segmenter, text: &str) -> HashSet<String> {
+    let markers: HashSet<&str> = EFFECT_MARKERS
+        .iter()
+        .chain(CAUSE_MARKERS)
+        .chain(CAUSING_VERBS)
+        .copied()
+        .collect();
+    segmenter
+        .segment(text)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Alphanumeric || token.text.len() > 2)
+        .filter(|token| !segmenter.is_stop_word(&token.text))
+        .map(|token| token.text)
+        // 连接词本身不算作内容重叠
+        .filter(|term| !markers.contains(term.as_str()))
+        .collect()
+}
+
+/// 两个词项集合的重叠度（相对较小的集合）
+fn overlap(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let smaller = a.len().min(b.len());
+    if smaller == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / smaller as f32
+}
+
+fn is_blank(text: &str) -> bool {
+    text.chars().all(|c| !c.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SegmentationConfig;
+    use crate::core::{MemoryAttributes, MemoryType};
+    use crate::llm::ScriptedLlmProvider;
+    use chrono::{Duration, Utc};
+
+    fn memory(content: &str, hours_ago: i64) -> Memory {
+        let mut memory = Memory::new(
+            content.to_string(),
+            MemoryType::Event,
+            vec![1.0, 0.0],
+            MemoryAttributes::default(),
+        );
+        memory.metadata.created_at = Utc::now() - Duration::hours(hours_ago);
+        memory
+    }
+
+    fn request(source: &Memory, target: &Memory) -> CausalAnalysisRequest {
+        CausalAnalysisRequest {
+            source_memory: source.clone(),
+            target_memory: target.clone(),
+            context: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rule_detector_directions() {
+        let detector = RuleCausalDetector::new(&Config::default());
+
+        // 中文：结果记忆中的“因为”指向原因记忆
+        let slow = memory("用户反馈系统响应速度太慢", 24);
+        let index = memory("因为系统响应速度太慢，我们给数据库添加了索引", 0);
+        let response = detector.analyze(&request(&slow, &index)).await.unwrap();
+        assert_eq!(response.relation_type, CausalRelationType::Cause);
+        assert_eq!(response.causal_keywords, vec!["因为".to_string()]);
+        assert!(response.evidence.starts_with("因为系统响应速度太慢"));
+
+        // 英文：源记忆中的因果动词指向结果记忆
+        let storm = memory("The storm caused the flight delay to Tokyo", 5);
+        let delay = memory("My flight delay meant missing the Tokyo meeting", 1);
+        let response = detector.analyze(&request(&delay, &storm)).await.unwrap();
+        assert_eq!(response.relation_type, CausalRelationType::Effect);
+        assert!(response.confidence >= 0.5);
+
+        // 以“所以”开头的记忆承接更早的记忆
+        let rain = memory("下雨了", 1);
+        let umbrella = memory("所以我去买了伞", 0);
+        let mut adjacent = request(&rain, &umbrella);
+        assert!(
+            !detector
+                .analyze(&adjacent)
+                .await
+                .unwrap()
+                .has_causal_relation
+        );
+        adjacent.context.push(rain.clone());
+        let response = detector.analyze(&adjacent).await.unwrap();
+        assert_eq!(response.relation_type, CausalRelationType::Cause);
+
+        let unrelated = memory("今天吃了面条", 0);
+        let response = detector.analyze(&request(&slow, &unrelated)).await.unwrap();
+        assert!(!response.has_causal_relation);
+        assert!(RuleCausalDetector::connectives("Since then, nothing").is_empty());
+    }
+
+    #[test]
+    fn test_terms() {
+        let segmenter = Segmenter::default();
+        let found = terms(&segmenter, "因为下雨，所以比赛取消了 because of the storm");
+        assert!(found.contains("下雨"));
+        assert!(found.contains("storm"));
+        for excluded in ["因为", "所以", "了", "because", "the", "of"] {
+            assert!(!found.contains(excluded), "{}", excluded);
+        }
+
+        // 自定义词典中的词作为整体参与重叠计算
+        let config = SegmentationConfig {
+            custom_dictionary: vec!["量子纠缠".to_string()],
+            ..Default::default()
+        };
+        let segmenter = Segmenter::new(&config).unwrap();
+        assert!(terms(&segmenter, "量子纠缠实验").contains("量子纠缠"));
+    }
+
+    #[tokio::test]
+    async fn test_llm_analyzer_parses_and_falls_back() {
+        let provider = ScriptedLlmProvider::new([r#"```json
+{"has_causal_relation": true, "relation_type": "Cause", "confidence": 1.3,
+ "explanation": "反馈导致优化", "causal_keywords": ["反馈", "优化"]}
+```"#]);
+        let mut config = Config::default();
+        config.llm.retry_backoff_ms = 0;
+        let analyzer = LlmCausalAnalyzer::new(Arc::new(provider), &config);
+        let source = memory("用户反馈系统响应速度太慢", 24);
+        let target = memory("优化了数据库查询，添加了索引", 0);
+        let response = analyzer.analyze(&request(&source, &target)).await.unwrap();
+        assert_eq!(response.relation_type, CausalRelationType::Cause);
+        assert_eq!(response.confidence, 1.0);
+        assert_eq!(response.evidence, "反馈导致优化");
+
+        let broken =
+            LlmCausalAnalyzer::new(Arc::new(ScriptedLlmProvider::new(["not json"])), &config);
+        let response = broken.analyze(&request(&source, &target)).await.unwrap();
+        assert!(!response.has_causal_relation);
+    }
+}
@@ -0,0 +1,459 @@
+//! 记忆类型分类模块
+//!
+//! 创建记忆时根据内容和上下文推断记忆类型。默认使用基于规则特征的分类器
+//! （日期时间 → 事件、祈使句和待办 → 任务、“我想” → 目标等），
+//! 也可以换成基于大语言模型的分类器。
+
+use crate::attributes::analyze_emotion;
+use crate::config::Config;
+use crate::core::{Context, MemoryType};
+use crate::error::Result;
+use crate::llm::{LlmProvider, LlmTaskRunner};
+use crate::llm_tasks::{MemoryTypeClassificationRequest, MemoryTypeClassificationTask};
+use crate::segment::{Segmenter, TokenKind};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// 自定义属性中记录分类置信度的键
+pub const TYPE_CONFIDENCE_ATTRIBUTE: &str = "memory_type_confidence";
+/// 自定义属性中记录分类器名称的键
+pub const TYPE_CLASSIFIER_ATTRIBUTE: &str = "memory_type_classifier";
+
+/// 日期时间线索
+const EVENT_MARKERS: &[&str] = &[
+    "昨天",
+    "今天",
+    "明天",
+    "前天",
+    "后天",
+    "上午",
+    "下午",
+    "晚上",
+    "早上",
+    "周一",
+    "周二",
+    "周三",
+    "周四",
+    "周五",
+    "周六",
+    "周日",
+    "星期",
+    "上周",
+    "下周",
+    "参加了",
+    "见了",
+    "去了",
+    "发生",
+    "yesterday",
+    "today",
+    "tomorrow",
+    "tonight",
+    "this morning",
+    "last night",
+    "last week",
+    "next week",
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+    "happened",
+    "attended",
+];
+
+/// 待办线索
+const TASK_MARKERS: &[&str] = &[
+    "todo",
+    "to-do",
+    "待办",
+    "记得",
+    "别忘了",
+    "不要忘记",
+    "提醒我",
+    "需要完成",
+    "截止",
+    "deadline",
+    "remind me",
+    "don't forget",
+    "need to",
+    "must ",
+];
+
+/// 句首祈使动词（中文动词后紧跟“了”时视为已发生的事）
+const IMPERATIVE_VERBS: &[&str] = &[
+    "买", "完成", "提交", "修复", "联系", "预约", "整理", "buy", "call", "send", "finish", "fix",
+    "email", "book", "schedule", "submit", "prepare", "remember",
+];
+
+/// 目标线索
+const GOAL_MARKERS: &[&str] = &[
+    "我想",
+    "我要成为",
+    "我希望",
+    "目标",
+    "梦想",
+    "打算",
+    "计划在",
+    "争取",
+    "i want to",
+    "i'd like to",
+    "i hope to",
+    "i plan to",
+    "my goal",
+    "someday",
+];
+
+/// 习惯线索
+const HABIT_MARKERS: &[&str] = &[
+    "每天",
+    "每周",
+    "每晚",
+    "每个月",
+    "经常",
+    "总是",
+    "习惯",
+    "通常",
+    "一向",
+    "every day",
+    "every morning",
+    "every night",
+    "every week",
+    "usually",
+    "always",
+    "habit",
+    "often",
+];
+
+/// 情感线索
+const EMOTION_MARKERS: &[&str] = &["我感到", "感觉", "心情", "情绪", "i feel", "feeling"];
+
+/// 反思线索
+const REFLECTION_MARKERS: &[&str] = &[
+    "反思",
+    "总结",
+    "回顾",
+    "意识到",
+    "明白了",
+    "学到",
+    "教训",
+    "领悟",
+    "i realized",
+    "lesson",
+    "looking back",
+    "in hindsight",
+    "reflect",
+];
+
+/// 对话线索（出现在行首）
+const CONVERSATION_MARKERS: &[&str] = &["用户：", "助手：", "问：", "答：", "user:", "assistant:"];
+
+/// 记忆类型分类结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryTypeClassification {
+    pub memory_type: MemoryType,
+    /// 置信度 (0.0 - 1.0)
+    pub confidence: f32,
+    /// 判断依据
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// 记忆类型分类器
+#[async_trait]
+pub trait MemoryTypeClassifier: Send + Sync + std::fmt::Debug {
+    /// 分类器名称，记录在记忆的自定义属性中
+    fn name(&self) -> &str;
+
+    /// 根据内容和上下文推断记忆类型
+    async fn classify(&self, content: &str, context: &Context) -> Result<MemoryTypeClassification>;
+}
+
+/// 基于规则特征的记忆类型分类器
+#[derive(Debug, Clone)]
+pub struct RuleMemoryClassifier {
+    /// 低于该置信度时归为知识型记忆
+    min_confidence: f32,
+    segmenter: Arc<Segmenter>,
+}
+
+impl RuleMemoryClassifier {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            min_confidence: config.classification.min_confidence,
+            segmenter: Segmenter::shared(),
+        }
+    }
+
+    /// 使用指定的分词器切分英文单词
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.segmenter = segmenter;
+        self
+    }
+
+    /// 计算各类型的特征得分，返回 (类型, 得分, 命中的线索)
+    fn scores(&self, text: &str) -> Vec<(MemoryType, f32, Vec<String>)> {
+        let lower = text.to_lowercase();
+        let tokens = self.segmenter.segment(&lower);
+        let words: Vec<&str> = tokens
+            .iter()
+            .filter(|token| token.kind == TokenKind::Alphanumeric)
+            .map(|token| token.text.as_str())
+            .collect();
+        let hits = |markers: &[&str]| -> Vec<String> {
+            markers
+                .iter()
+                .filter(|m| lower.contains(*m))
+                .map(|m| m.trim().to_string())
+                .collect()
+        };
+
+        let mut event = hits(EVENT_MARKERS);
+        if has_clock_time(&lower, &words) {
+            event.push("时间".to_string());
+        }
+        if has_date(&lower) {
+            event.push("日期".to_string());
+        }
+
+        let mut task = hits(TASK_MARKERS);
+        let start = lower.trim_start();
+        if let Some(verb) = IMPERATIVE_VERBS.iter().find(|v| {
+            let rest = start.strip_prefix(*v);
+            match rest {
+                Some(rest) if v.is_ascii() => rest.starts_with(' '),
+                Some(rest) => !rest.starts_with('了'),
+                None => false,
+            }
+        }) {
+            task.push(verb.to_string());
+        }
+        if lower.starts_with("[ ]") || lower.starts_with("- [ ]") {
+            task.push("[ ]".to_string());
+        }
+
+        // 情感词在各类记忆中都常见，只有与情感表达同时出现时才计入
+        let mut emotion = hits(EMOTION_MARKERS);
+        let analysis = analyze_emotion(text);
+        if !emotion.is_empty() && !analysis.emotion_scores.is_empty() {
+            emotion.push(analysis.primary_emotion);
+        }
+
+        let conversation: Vec<String> = lower
+            .lines()
+            .filter_map(|line| {
+                CONVERSATION_MARKERS
+                    .iter()
+                    .find(|m| line.trim_start().starts_with(*m))
+                    .map(|m| m.to_string())
+            })
+            .collect();
+
+        vec![
+            (MemoryType::Event, 1.0, event),
+            (MemoryType::Task, 1.2, task),
+            (MemoryType::Goal, 1.2, hits(GOAL_MARKERS)),
+            (MemoryType::Habit, 1.1, hits(HABIT_MARKERS)),
+            (MemoryType::Emotion, 0.8, emotion),
+            (MemoryType::Reflection, 1.1, hits(REFLECTION_MARKERS)),
+            (MemoryType::Conversation, 1.5, conversation),
+        ]
+        .into_iter()
+        .map(|(memory_type, weight, cues)| (memory_type, weight * cues.len() as f32, cues))
+        .collect()
+    }
+
+    /// 同步分类，供其他分类器回退使用
+    pub fn classify_text(&self, content: &str, context: &Context) -> MemoryTypeClassification {
+        let mut scores = self.scores(content);
+        // 当前主题作为较弱的线索
+        if let Some(topic) = &context.current_topic {
+            for (score, (_, topic_score, _)) in scores.iter_mut().zip(self.scores(topic)) {
+                score.1 += topic_score * 0.5;
+            }
+        }
+
+        let total: f32 = scores.iter().map(|(_, score, _)| score).sum();
+        // 得分相同时取靠前的类型
+        let mut best: Option<(MemoryType, f32, Vec<String>)> = None;
+        for candidate in scores {
+            if candidate.1 > best.as_ref().map_or(0.0, |b| b.1) {
+                best = Some(candidate);
+            }
+        }
+
+        if let Some((memory_type, score, cues)) = best {
+            // 置信度由领先程度和线索强度共同决定
+            let confidence = (0.4 + 0.3 * score / total + 0.1 * score.min(3.0)).min(0.95);
+            if confidence >= self.min_confidence {
+                return MemoryTypeClassification {
+                    memory_type,
+                    confidence,
+                    reason: format!("Matched cues: {}", cues.join(", ")),
+                };
+            }
+        }
+
+        MemoryTypeClassification {
+            memory_type: MemoryType::Knowledge,
+            confidence: 0.5,
+            reason: "No type-specific cues, defaulting to knowledge".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl MemoryTypeClassifier for RuleMemoryClassifier {
+    fn name(&self) -> &str {
+        "rule"
+    }
+
+    async fn classify(&self, content: &str, context: &Context) -> Result<MemoryTypeClassification> {
+        Ok(self.classify_text(content, context))
+    }
+}
+
+/// 基于大语言模型的记忆类型分类器
+///
+/// 通过 [`MemoryTypeClassificationTask`] 调用模型，失败时回退到规则分类器。
+#[derive(Debug, Clone)]
+pub struct LlmMemoryClassifier {
+    runner: LlmTaskRunner,
+    task: MemoryTypeClassificationTask,
+}
+
+impl LlmMemoryClassifier {
+    pub fn new(provider: Arc<dyn LlmProvider>, config: &Config) -> Self {
+        Self {
+            runner: LlmTaskRunner::new(provider, &config.llm),
+            task: MemoryTypeClassificationTask::new(config),
+        }
+    }
+
+    /// 回退的规则分类器使用指定的分词器
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.task = self.task.with_segmenter(segmenter);
+        self
+    }
+}
+
+#[async_trait]
+impl MemoryTypeClassifier for LlmMemoryClassifier {
+    fn name(&self) -> &str {
+        self.runner.provider().name()
+    }
+
+    async fn classify(&self, content: &str, context: &Context) -> Result<MemoryTypeClassification> {
+        let request = MemoryTypeClassificationRequest {
+            content: content.to_string(),
+            context: context.clone(),
+        };
+        self.runner.run(&self.task, &request).await
+    }
+}
+
+/// 是否包含 “9:30”、“10点”、“9 am” 这样的时刻，`words` 为分词得到的英文单词和数字
+fn has_clock_time(text: &str, words: &[&str]) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    let colon = chars
+        .windows(3)
+        .any(|w| w[0].is_ascii_digit() && (w[1] == ':' || w[1] == '：') && w[2].is_ascii_digit());
+    let dian = chars
+        .windows(2)
+        .any(|w| w[0].is_ascii_digit() && w[1] == '点');
+
+    let meridiem = words.iter().enumerate().any(|(i, word)| {
+        let number = match word.strip_suffix("am").or_else(|| word.strip_suffix("pm")) {
+            Some("") if i > 0 => words[i - 1],
+            Some(number) => number,
+            None => return false,
+        };
+        !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+    });
+
+    colon || dian || meridiem
+}
+
+/// 是否包含 “2024-01-15”、“3月5日” 这样的日期
+fn has_date(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .windows(3)
+        .any(|w| w[0].is_ascii_digit() && w[1] == '-' && w[2].is_ascii_digit())
+        || chars
+            .windows(2)
+            .any(|w| w[0].is_ascii_digit() && (w[1] == '月' || w[1] == '日' || w[1] == '号'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ScriptedLlmProvider;
+
+    #[test]
+    fn test_rule_classifier() {
+        let classifier = RuleMemoryClassifier::new(&Config::default());
+        let context = Context::default();
+        let classify = |text: &str| classifier.classify_text(text, &context).memory_type;
+
+        assert_eq!(classify("昨天下午3点参加了产品发布会"), MemoryType::Event);
+        assert_eq!(classify("记得明天之前提交周报"), MemoryType::Task);
+        assert_eq!(classify("Buy milk and eggs"), MemoryType::Task);
+        assert_eq!(classify("我想在三年内成为架构师"), MemoryType::Goal);
+        assert_eq!(classify("I usually run every morning"), MemoryType::Habit);
+        assert_eq!(classify("我感到很难过，心情很差"), MemoryType::Emotion);
+        assert_eq!(
+            classify("回顾这次失败，我意识到测试不够"),
+            MemoryType::Reflection
+        );
+        assert_eq!(
+            classify("用户：你好\n助手：你好！"),
+            MemoryType::Conversation
+        );
+        assert_eq!(
+            classify("Rust的所有权系统保证内存安全"),
+            MemoryType::Knowledge
+        );
+        // "buyer"不是祈使动词"buy"，"i am"不是时刻
+        assert_eq!(
+            classify("Buyers prefer safe languages"),
+            MemoryType::Knowledge
+        );
+        assert_eq!(
+            classify("I am reading about data: types"),
+            MemoryType::Knowledge
+        );
+        assert_eq!(classify("The meeting starts at 9 am"), MemoryType::Event);
+        assert_eq!(
+            classify("The rollout was a great success"),
+            MemoryType::Knowledge
+        );
+
+        let result = classifier.classify_text("记得明天之前提交周报", &context);
+        assert!(result.confidence > 0.5 && result.confidence <= 0.95);
+        assert!(result.reason.contains("记得"));
+    }
+
+    #[tokio::test]
+    async fn test_llm_classifier_falls_back_to_rules() {
+        let mut config = Config::default();
+        config.llm.retry_backoff_ms = 0;
+        let provider = Arc::new(ScriptedLlmProvider::new([
+            r#"{"memory_type": "Goal", "confidence": 0.9, "reason": "长期愿望"}"#,
+            r#"{"memory_type": "Plan", "confidence": 0.9}"#,
+        ]));
+        let classifier = LlmMemoryClassifier::new(provider.clone(), &config);
+        let context = Context::default();
+
+        let result = classifier.classify("学好日语", &context).await.unwrap();
+        assert_eq!(result.memory_type, MemoryType::Goal);
+        assert_eq!(result.confidence, 0.9);
+
+        // 不合法的类型未通过校验，重试耗尽后回退到规则分类
+        let result = classifier.classify("记得交房租", &context).await.unwrap();
+        assert_eq!(result.memory_type, MemoryType::Task);
+        assert_eq!(provider.requests().len(), 4);
+    }
+}
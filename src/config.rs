@@ -34,6 +34,48 @@ pub struct Config {
     pub logging: LoggingConfig,
     /// 特性开关
     pub features: FeatureFlags,
+    /// 大语言模型配置
+    #[serde(default)]
+    pub llm: LlmConfig,
+    /// 属性提取配置
+    #[serde(default)]
+    pub attributes: AttributeConfig,
+    /// 记忆类型分类配置
+    #[serde(default)]
+    pub classification: ClassificationConfig,
+    /// 中文分词配置
+    #[serde(default)]
+    pub segmentation: SegmentationConfig,
+    /// 近重复检测配置
+    #[serde(default)]
+    pub deduplication: DeduplicationConfig,
+    /// 记忆演化配置
+    #[serde(default)]
+    pub evolution: EvolutionConfig,
+    /// 遗忘曲线配置
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    /// 访问统计配置
+    #[serde(default)]
+    pub access: AccessConfig,
+    /// 排序学习配置
+    #[serde(default)]
+    pub ranking: RankingConfig,
+    /// 查询时间表达式解析配置
+    #[serde(default)]
+    pub time_parsing: TimeParsingConfig,
+    /// 结果多样化配置
+    #[serde(default)]
+    pub diversity: DiversityConfig,
+    /// 重排序配置
+    #[serde(default)]
+    pub rerank: RerankConfig,
+    /// 提示词上下文组装配置
+    #[serde(default)]
+    pub context_builder: ContextBuilderConfig,
+    /// 查询扩展配置
+    #[serde(default)]
+    pub query_expansion: QueryExpansionConfig,
     /// 自定义配置
     pub custom: HashMap<String, serde_json::Value>,
 }
@@ -242,6 +284,18 @@ pub struct GraphConfig {
     pub traversal: TraversalConfig,
     /// 图压缩配置
     pub compression: CompressionConfig,
+    /// 社区检测配置
+    #[serde(default)]
+    pub community: CommunityConfig,
+    /// 实体图配置
+    #[serde(default)]
+    pub entity: EntityConfig,
+    /// 自动链接发现配置
+    #[serde(default)]
+    pub linking: LinkDiscoveryConfig,
+    /// 因果关系检测配置
+    #[serde(default)]
+    pub causal: CausalConfig,
 }
 
 /// PageRank配置
@@ -255,6 +309,24 @@ pub struct PageRankConfig {
     pub convergence_threshold: f32,
     /// 个性化向量权重
     pub personalization_weight: f32,
+    /// 增量PageRank配置
+    #[serde(default)]
+    pub incremental: IncrementalPageRankConfig,
+}
+
+/// 增量PageRank配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IncrementalPageRankConfig {
+    /// 是否在边变化时增量更新持久化的全局PageRank
+    pub enabled: bool,
+    /// 残差推送阈值
+    pub push_tolerance: f32,
+    /// 单次更新的最大推送次数
+    pub max_pushes: usize,
+    /// 是否把边视为无向边
+    pub undirected: bool,
+    /// 个性化PageRank缓存的种子集合数量
+    pub cache_size: usize,
 }
 
 /// 图遍历配置
@@ -283,6 +355,439 @@ pub struct CompressionConfig {
     pub min_connections_to_keep: usize,
 }
 
+/// 社区检测配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommunityConfig {
+    /// 检测方法
+    pub method: CommunityMethod,
+    /// 最大迭代次数
+    pub max_iterations: usize,
+    /// 最小社区规模
+    pub min_community_size: usize,
+    /// K-means聚类数（0表示自动估计）
+    pub kmeans_clusters: usize,
+    /// 社区标签关键词数量
+    pub label_keywords: usize,
+    /// 是否为社区成员创建主题连接
+    pub create_thematic_links: bool,
+}
+
+/// 社区检测方法
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CommunityMethod {
+    /// 基于图边的标签传播
+    LabelPropagation,
+    /// 基于嵌入向量的K-means聚类
+    KMeans,
+    /// 标签传播后按嵌入相似度归并孤立节点
+    Hybrid,
+}
+
+/// 实体图配置
+///
+/// 控制从记忆内容中抽取实体、建立“提及”与“同义”边的行为。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntityConfig {
+    /// 是否在创建记忆时抽取实体
+    pub enabled: bool,
+    /// 每条记忆最多抽取的实体数
+    pub max_entities_per_memory: usize,
+    /// 同义边的嵌入相似度阈值
+    pub synonym_threshold: f32,
+    /// 查询实体作为PageRank种子的权重
+    pub query_seed_weight: f32,
+}
+
+/// 自动链接发现配置
+///
+/// A-Mem风格的链接生成：新记忆与近邻记忆建立语义、时间和主题连接。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LinkDiscoveryConfig {
+    /// 是否启用自动链接发现
+    pub enabled: bool,
+    /// 语义近邻搜索的数量
+    pub top_k: usize,
+    /// 语义连接的相似度阈值
+    pub semantic_threshold: f32,
+    /// 时间窗口（小时）
+    pub temporal_window_hours: u64,
+    /// 时间连接的最大强度（随时间间隔线性衰减）
+    pub temporal_strength: f32,
+    /// 最多建立的时间连接数
+    pub max_temporal_links: usize,
+    /// 建立主题连接所需的最少共享标签或关键词数
+    pub min_shared_terms: usize,
+    /// 最多建立的主题连接数
+    pub max_thematic_links: usize,
+    /// 主题词表：主题名称到关键词列表，命中同一主题的记忆视为共享该主题
+    pub themes: HashMap<String, Vec<String>>,
+}
+
+/// 因果关系检测配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CausalConfig {
+    /// 是否在创建记忆时检测因果关系
+    pub enabled: bool,
+    /// 建立因果连接的最低置信度
+    pub min_confidence: f32,
+    /// 每条新记忆最多分析的候选记忆数
+    pub max_candidates: usize,
+    /// 规则检测器判断原因或结果所需的最低词汇重叠度
+    pub min_overlap: f32,
+}
+
+/// 大语言模型配置
+///
+/// 使用OpenAI兼容的聊天补全接口。未启用或调用失败时回退到内置的启发式实现。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LlmConfig {
+    /// 是否启用大语言模型
+    pub enabled: bool,
+    /// 接口基础URL（例如 https://api.openai.com/v1）
+    pub base_url: String,
+    /// API密钥
+    pub api_key: Option<String>,
+    /// 未配置API密钥时读取的环境变量
+    pub api_key_env: String,
+    /// 模型名称
+    pub model: String,
+    /// 采样温度
+    pub temperature: f32,
+    /// 最大生成token数
+    pub max_tokens: u32,
+    /// 请求超时（秒）
+    pub timeout_seconds: u64,
+    /// 调用失败或输出无效时的最大重试次数
+    pub max_retries: usize,
+    /// 重试退避基准时间（毫秒），按重试次数线性增长
+    pub retry_backoff_ms: u64,
+    /// 是否要求模型以JSON对象格式输出
+    pub json_mode: bool,
+}
+
+/// 属性提取配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AttributeConfig {
+    /// 每条记忆最多提取的关键词数
+    pub max_keywords: usize,
+    /// 是否基于情感词典标注情感
+    pub detect_emotion: bool,
+    /// 自动打标签所需命中的最少词数
+    pub min_tag_matches: usize,
+    /// 标签体系：标签名 -> 触发词（英文按整词匹配，中文按子串匹配）
+    pub taxonomy: HashMap<String, Vec<String>>,
+}
+
+/// 记忆类型分类配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClassificationConfig {
+    /// 是否根据内容自动推断记忆类型（关闭时默认为知识型记忆）
+    pub enabled: bool,
+    /// 规则分类器的最低置信度，低于该值时归为知识型记忆
+    pub min_confidence: f32,
+}
+
+/// 中文分词配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SegmentationConfig {
+    /// 是否使用内置词典（关闭且无自定义词典时退化为相邻字二元组切分）
+    pub builtin_dictionary: bool,
+    /// 自定义词典
+    pub custom_dictionary: Vec<String>,
+    /// 自定义词典文件路径，每行一个词，行内空白之后的内容（如词频）被忽略
+    pub dictionary_path: Option<String>,
+    /// 额外的停用词
+    pub stop_words: Vec<String>,
+    /// 最大匹配的词长（字数）
+    pub max_word_length: usize,
+}
+
+/// 近重复检测配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeduplicationConfig {
+    /// 是否在创建记忆时检测近重复
+    pub enabled: bool,
+    /// 默认处理策略，可在单次创建请求中覆盖
+    pub strategy: DuplicateStrategy,
+    /// 嵌入相似度阈值
+    pub embedding_threshold: f32,
+    /// SimHash指纹的最大汉明距离
+    pub simhash_max_distance: u32,
+    /// MinHash估计的Jaccard相似度阈值
+    pub minhash_threshold: f32,
+    /// MinHash签名长度
+    pub num_hashes: usize,
+    /// 每次检测比较的最多候选记忆数
+    pub max_candidates: usize,
+    /// 合并时对重要性的提升
+    pub importance_boost: f32,
+}
+
+/// 近重复记忆的处理策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateStrategy {
+    /// 不检测，始终创建新记忆
+    Allow,
+    /// 拒绝创建
+    Reject,
+    /// 合并到已有记忆
+    Merge,
+    /// 创建新记忆并链接到已有记忆
+    Link,
+}
+
+/// 记忆演化配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EvolutionConfig {
+    /// 创建记忆后是否演化其近邻记忆
+    pub on_insert: bool,
+    /// 参与演化的最多近邻数
+    pub max_neighbours: usize,
+    /// 近邻的最低嵌入相似度
+    pub similarity_threshold: f32,
+    /// 向近邻传播标签所需的最低相似度
+    pub tag_similarity: f32,
+    /// 每条记忆最多的标签数
+    pub max_tags: usize,
+    /// 主题一致时连接强度的增量（乘以相似度）
+    pub link_reinforcement: f32,
+}
+
+/// 遗忘曲线配置
+///
+/// 每条记忆的保持率按 `exp(-t/S)` 随时间衰减，稳定度 `S` 随每次强化增长。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetentionConfig {
+    /// 是否启用遗忘曲线
+    pub enabled: bool,
+    /// 新记忆的初始稳定度（天）
+    pub initial_stability_days: f32,
+    /// 一次完全强化后稳定度的增长倍数
+    pub stability_growth: f32,
+    /// 稳定度上限（天）
+    pub max_stability_days: f32,
+    /// 完全遗忘时保留的基础重要性比例
+    pub importance_floor: f32,
+    /// 检索时保持率的权重，为0时不影响排序
+    pub retrieval_weight: f32,
+}
+
+/// 访问统计配置
+///
+/// 访问记录先在内存中累积，待记录的记忆数达到阈值时批量写回数据库。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccessConfig {
+    /// 是否记录访问统计
+    pub enabled: bool,
+    /// 计为一次访问的时机
+    pub count_mode: AccessCountMode,
+    /// 待写回的记忆数达到该值时批量写回
+    pub flush_threshold: usize,
+    /// 写回访问记录时强化记忆的强度
+    pub reinforcement_strength: f32,
+}
+
+/// 计为一次访问的时机
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessCountMode {
+    /// 检索返回即计为访问
+    Retrieval,
+    /// 只有通过交互记录明确使用时才计为访问
+    ExplicitUse,
+}
+
+/// 排序学习配置
+///
+/// 检索展示和用户反馈被记录下来，学习周期中据此训练 `LearnedFusion` 使用的排序模型。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RankingConfig {
+    /// 是否记录检索展示日志
+    pub log_impressions: bool,
+    /// 学习周期中是否训练排序模型
+    pub training_enabled: bool,
+    /// 训练使用最近多少天的日志，更早的检索展示在学习周期中被删除
+    pub training_window_days: u32,
+    /// 训练使用的最大展示数，学习周期中只保留最近的这么多条检索展示
+    pub max_impressions: usize,
+    /// 训练所需的最少标注样本数
+    pub min_samples: usize,
+    /// 学习率
+    pub learning_rate: f32,
+    /// 训练轮数
+    pub epochs: usize,
+    /// L2正则系数
+    pub l2_penalty: f32,
+    /// 评分类反馈（显式、隐式、停留时间）视为正例的阈值
+    pub positive_threshold: f32,
+    /// 离线评估NDCG的截断位置
+    pub eval_k: usize,
+}
+
+/// 查询时间表达式解析配置
+///
+/// 从查询文本中解析“昨天”“last month”等时间表达式，得到的时间范围用于过滤或时间评分。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimeParsingConfig {
+    /// 是否解析查询中的时间表达式
+    pub enabled: bool,
+    /// 解析相对时间使用的时区，相对UTC的分钟数
+    pub utc_offset_minutes: i32,
+    /// 解析出的时间范围的用法
+    pub mode: TimeRangeMode,
+    /// “最近”“recently”等不带数量的表达式对应的天数
+    pub recent_days: u32,
+}
+
+/// 查询时间范围的用法
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeRangeMode {
+    /// 作为过滤条件，只返回范围内的记忆
+    Filter,
+    /// 作为时间评分的窗口，范围内的记忆排序靠前
+    Score,
+}
+
+/// 结果多样化配置
+///
+/// 融合之后按最大边际相关性或行列式点过程重排，压低与前面结果近重复的记忆。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiversityConfig {
+    /// 是否启用结果多样化
+    pub enabled: bool,
+    /// 多样化方法
+    pub method: DiversityMethod,
+    /// 相关性与多样性的权衡，1.0只看相关性，0.0只看多样性
+    pub lambda: f32,
+    /// 记忆相似度中社区归属所占的权重，其余为嵌入的余弦相似度
+    pub community_weight: f32,
+    /// 参与重排的最大结果数，之后的结果保持原顺序
+    pub max_candidates: usize,
+}
+
+/// 结果多样化方法
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiversityMethod {
+    /// 最大边际相关性（MMR）
+    Mmr,
+    /// 行列式点过程（DPP）贪心推断
+    Dpp,
+}
+
+/// 重排序配置
+///
+/// 融合后的前若干个候选交给重排序器逐条打分，与融合分数加权得到最终排序。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RerankConfig {
+    /// 是否启用重排序
+    pub enabled: bool,
+    /// 重排序器
+    pub method: RerankMethod,
+    /// 参与重排序的候选数
+    pub top_n: usize,
+    /// 重排序分数的权重，1.0表示完全按重排序分数排序
+    pub weight: f32,
+    /// 交叉编码器服务配置
+    pub cross_encoder: CrossEncoderConfig,
+}
+
+/// 重排序器
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RerankMethod {
+    /// 查询词覆盖率基线
+    Keyword,
+    /// 由大语言模型判断相关性，未配置模型时使用查询词覆盖率
+    Llm,
+    /// 通过HTTP调用交叉编码器服务
+    CrossEncoder,
+}
+
+/// 交叉编码器服务配置
+///
+/// 服务接收 `{"model", "query", "documents"}`，返回Cohere/Jina风格的
+/// `{"results": [{"index", "relevance_score"}]}` 或TEI风格的 `[{"index", "score"}]`。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CrossEncoderConfig {
+    /// 重排序接口URL（例如 http://localhost:8080/rerank）
+    pub endpoint: String,
+    /// 模型名称
+    pub model: String,
+    /// API密钥
+    pub api_key: Option<String>,
+    /// 未配置API密钥时读取的环境变量
+    pub api_key_env: String,
+    /// 请求超时（秒）
+    pub timeout_seconds: u64,
+}
+
+/// 提示词上下文组装配置
+///
+/// 把检索结果在token预算内组装成放进提示词的记忆块。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextBuilderConfig {
+    /// token预算
+    pub token_budget: usize,
+    /// 记忆块中的排列顺序
+    pub order: ContextOrder,
+    /// 是否按记忆类型分组
+    pub group_by_type: bool,
+    /// 是否标注记忆的创建时间
+    pub include_timestamps: bool,
+    /// 是否标注记忆来源
+    pub include_provenance: bool,
+    /// 是否附上记忆ID供模型引用
+    pub cite_ids: bool,
+    /// 单条记忆最多占用的token数
+    pub max_memory_tokens: usize,
+    /// 预算不足时，剩余预算低于该值就不再截短放入
+    pub min_memory_tokens: usize,
+    /// 过长记忆的缩短方式
+    pub long_memory: LongMemoryStrategy,
+}
+
+/// 记忆块中的排列顺序
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextOrder {
+    /// 按相关性降序
+    Relevance,
+    /// 按创建时间升序
+    Chronological,
+}
+
+/// 过长记忆的缩短方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LongMemoryStrategy {
+    /// 保留开头的完整句子
+    Summarize,
+    /// 按token数截断
+    Truncate,
+}
+
+/// 查询扩展配置
+///
+/// 查询通过 `Query::expansion` 启用扩展，这里设置各类变体的数量和融合权重。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryExpansionConfig {
+    /// 同义词变体的最大数量
+    pub max_synonyms: usize,
+    /// 伪相关反馈使用的前几条结果数
+    pub feedback_docs: usize,
+    /// Rocchio更新中原查询嵌入的权重
+    pub rocchio_alpha: f32,
+    /// Rocchio更新中反馈结果平均嵌入的权重
+    pub rocchio_beta: f32,
+    /// 大语言模型改写的最大数量
+    pub max_paraphrases: usize,
+    /// 融合时各变体结果相对原查询结果的权重
+    pub variant_weight: f32,
+}
+
 /// 性能配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PerformanceConfig {
@@ -317,6 +822,52 @@ pub struct RetrievalConfig {
     pub enable_cache: bool,
     /// 缓存TTL（秒）
     pub cache_ttl_seconds: u64,
+    /// 结果融合配置
+    #[serde(default)]
+    pub fusion: FusionConfig,
+    /// 检索流水线配置
+    #[serde(default)]
+    pub pipeline: PipelineConfig,
+}
+
+/// 结果融合配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FusionConfig {
+    /// 倒数排名融合（RRF）的平滑常数k
+    pub rrf_k: f32,
+    /// 分数融合前对各检索器分数的归一化方式
+    pub normalization: ScoreNormalization,
+}
+
+/// 检索流水线配置
+///
+/// 记忆管理器和HippoRAG检索器共用的候选生成器与PageRank重排序参数。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PipelineConfig {
+    /// 未指定候选数时每个候选生成器最多返回的候选数
+    pub candidate_limit: usize,
+    /// 图遍历以语义候选为起点时取的种子数
+    pub graph_seeds: usize,
+    /// 因果检索沿因果边扩展的最大深度
+    pub causal_depth: usize,
+    /// 未指定时间窗口时时间评分使用的最近天数
+    pub time_window_days: i64,
+    /// PageRank重排序时PageRank分数的权重
+    pub pagerank_weight: f32,
+    /// 个性化PageRank检索在未指定结果数时额外召回的图可达记忆数
+    pub max_reached: usize,
+}
+
+/// 分数归一化方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreNormalization {
+    /// 不归一化
+    None,
+    /// 按最小值和最大值线性缩放到[0, 1]
+    MinMax,
+    /// 标准分数，再经logistic函数映射到(0, 1)
+    ZScore,
 }
 
 /// 融合权重配置
@@ -591,6 +1142,16 @@ impl Config {
             config.performance.worker_threads = workers.parse().unwrap_or(4);
         }
 
+        // 大语言模型配置
+        if let Ok(base_url) = std::env::var("AI00_MEM_LLM_BASE_URL") {
+            config.llm.enabled = true;
+            config.llm.base_url = base_url;
+        }
+
+        if let Ok(model) = std::env::var("AI00_MEM_LLM_MODEL") {
+            config.llm.model = model;
+        }
+
         // 日志级别
         if let Ok(log_level) = std::env::var("AI00_MEM_LOG_LEVEL") {
             config.logging.level = match log_level.to_lowercase().as_str() {
@@ -675,6 +1236,189 @@ impl Config {
             ));
         }
 
+        if self.graph.pagerank.incremental.push_tolerance <= 0.0 {
+            return Err(MemoryError::validation_error(
+                "PageRank push tolerance must be greater than 0".to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.graph.entity.synonym_threshold) {
+            return Err(MemoryError::validation_error(
+                "Entity synonym threshold must be between 0 and 1".to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.graph.linking.semantic_threshold) {
+            return Err(MemoryError::validation_error(
+                "Link semantic threshold must be between 0 and 1".to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.graph.causal.min_confidence) {
+            return Err(MemoryError::validation_error(
+                "Causal min confidence must be between 0 and 1".to_string(),
+            ));
+        }
+
+        if self.graph.community.min_community_size == 0 {
+            return Err(MemoryError::validation_error(
+                "Min community size must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.llm.enabled && self.llm.base_url.is_empty() {
+            return Err(MemoryError::validation_error(
+                "LLM base URL cannot be empty".to_string(),
+            ));
+        }
+
+        if self.attributes.max_keywords == 0 {
+            return Err(MemoryError::validation_error(
+                "Attribute max keywords must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.attributes.min_tag_matches == 0 {
+            return Err(MemoryError::validation_error(
+                "Attribute min tag matches must be greater than 0".to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.classification.min_confidence) {
+            return Err(MemoryError::validation_error(
+                "Classification min confidence must be between 0 and 1".to_string(),
+            ));
+        }
+
+        if self.segmentation.max_word_length < 2 {
+            return Err(MemoryError::validation_error(
+                "Segmentation max word length must be at least 2".to_string(),
+            ));
+        }
+
+        let dedup = &self.deduplication;
+        if !(0.0..=1.0).contains(&dedup.embedding_threshold)
+            || !(0.0..=1.0).contains(&dedup.minhash_threshold)
+        {
+            return Err(MemoryError::validation_error(
+                "Deduplication thresholds must be between 0 and 1".to_string(),
+            ));
+        }
+
+        if dedup.num_hashes == 0 || dedup.max_candidates == 0 {
+            return Err(MemoryError::validation_error(
+                "Deduplication hash count and candidate count must be greater than 0".to_string(),
+            ));
+        }
+
+        let evolution = &self.evolution;
+        if !(0.0..=1.0).contains(&evolution.similarity_threshold)
+            || !(0.0..=1.0).contains(&evolution.tag_similarity)
+        {
+            return Err(MemoryError::validation_error(
+                "Evolution similarity thresholds must be between 0 and 1".to_string(),
+            ));
+        }
+
+        let retention = &self.retention;
+        if retention.initial_stability_days <= 0.0
+            || retention.stability_growth < 1.0
+            || retention.max_stability_days < retention.initial_stability_days
+        {
+            return Err(MemoryError::validation_error(
+                "Retention stability must be positive, growth at least 1 and max not below initial"
+                    .to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&retention.importance_floor)
+            || !(0.0..=1.0).contains(&retention.retrieval_weight)
+        {
+            return Err(MemoryError::validation_error(
+                "Retention importance floor and retrieval weight must be between 0 and 1"
+                    .to_string(),
+            ));
+        }
+
+        if self.access.flush_threshold == 0
+            || !(0.0..=1.0).contains(&self.access.reinforcement_strength)
+        {
+            return Err(MemoryError::validation_error(
+                "Access flush threshold must be greater than 0 and reinforcement between 0 and 1"
+                    .to_string(),
+            ));
+        }
+
+        if self.retrieval.fusion.rrf_k < 0.0 {
+            return Err(MemoryError::validation_error(
+                "RRF constant must not be negative".to_string(),
+            ));
+        }
+
+        let pipeline = &self.retrieval.pipeline;
+        if pipeline.candidate_limit == 0
+            || pipeline.time_window_days <= 0
+            || !(0.0..=1.0).contains(&pipeline.pagerank_weight)
+        {
+            return Err(MemoryError::validation_error(
+                "Pipeline candidate limit and time window must be positive and PageRank weight between 0 and 1"
+                    .to_string(),
+            ));
+        }
+
+        let ranking = &self.ranking;
+        if ranking.learning_rate <= 0.0
+            || ranking.l2_penalty < 0.0
+            || ranking.eval_k == 0
+            || !(0.0..=1.0).contains(&ranking.positive_threshold)
+        {
+            return Err(MemoryError::validation_error(
+                "Ranking learning rate must be positive, L2 penalty non-negative, eval k positive \
+                 and positive threshold between 0 and 1"
+                    .to_string(),
+            ));
+        }
+
+        if self.time_parsing.utc_offset_minutes.abs() >= 24 * 60
+            || self.time_parsing.recent_days == 0
+        {
+            return Err(MemoryError::validation_error(
+                "Time parsing UTC offset must be within a day and recent days greater than 0"
+                    .to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.diversity.lambda)
+            || !(0.0..=1.0).contains(&self.diversity.community_weight)
+        {
+            return Err(MemoryError::validation_error(
+                "Diversity lambda and community weight must be between 0 and 1".to_string(),
+            ));
+        }
+
+        if self.rerank.top_n == 0 || !(0.0..=1.0).contains(&self.rerank.weight) {
+            return Err(MemoryError::validation_error(
+                "Rerank top N must be greater than 0 and weight between 0 and 1".to_string(),
+            ));
+        }
+
+        if self.context_builder.token_budget == 0 || self.context_builder.max_memory_tokens == 0 {
+            return Err(MemoryError::validation_error(
+                "Context token budget and max memory tokens must be greater than 0".to_string(),
+            ));
+        }
+
+        let expansion = &self.query_expansion;
+        if expansion.rocchio_alpha < 0.0
+            || expansion.rocchio_beta < 0.0
+            || !(0.0..=1.0).contains(&expansion.variant_weight)
+        {
+            return Err(MemoryError::validation_error(
+                "Rocchio weights must be non-negative and variant weight between 0 and 1"
+                    .to_string(),
+            ));
+        }
+
         // 验证性能配置
         if self.performance.worker_threads == 0 {
             return Err(MemoryError::validation_error(
@@ -809,6 +1553,10 @@ impl Default for GraphConfig {
             pagerank: PageRankConfig::default(),
             traversal: TraversalConfig::default(),
             compression: CompressionConfig::default(),
+            community: CommunityConfig::default(),
+            entity: EntityConfig::default(),
+            linking: LinkDiscoveryConfig::default(),
+            causal: CausalConfig::default(),
         }
     }
 }
@@ -820,6 +1568,19 @@ impl Default for PageRankConfig {
             max_iterations: 100,
             convergence_threshold: 1e-6,
             personalization_weight: 0.15,
+            incremental: IncrementalPageRankConfig::default(),
+        }
+    }
+}
+
+impl Default for IncrementalPageRankConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            push_tolerance: 1e-4,
+            max_pushes: 50_000,
+            undirected: true,
+            cache_size: 256,
         }
     }
 }
@@ -846,6 +1607,268 @@ impl Default for CompressionConfig {
     }
 }
 
+impl Default for CommunityConfig {
+    fn default() -> Self {
+        Self {
+            method: CommunityMethod::Hybrid,
+            max_iterations: 20,
+            min_community_size: 2,
+            kmeans_clusters: 0,
+            label_keywords: 3,
+            create_thematic_links: true,
+        }
+    }
+}
+
+impl Default for EntityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entities_per_memory: 8,
+            synonym_threshold: 0.85,
+            query_seed_weight: 1.0,
+        }
+    }
+}
+
+impl Default for LinkDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            top_k: 10,
+            semantic_threshold: 0.75,
+            temporal_window_hours: 24,
+            temporal_strength: 0.6,
+            max_temporal_links: 5,
+            min_shared_terms: 1,
+            max_thematic_links: 5,
+            themes: HashMap::new(),
+        }
+    }
+}
+
+impl Default for CausalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_confidence: 0.5,
+            max_candidates: 10,
+            min_overlap: 0.3,
+        }
+    }
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: "https://api.openai.com/v1".to_string(),
+            api_key: None,
+            api_key_env: "OPENAI_API_KEY".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            temperature: 0.2,
+            max_tokens: 1024,
+            timeout_seconds: 30,
+            max_retries: 2,
+            retry_backoff_ms: 500,
+            json_mode: true,
+        }
+    }
+}
+
+impl Default for AttributeConfig {
+    fn default() -> Self {
+        // 每个标签的触发词以空格分隔
+        let taxonomy = [
+            ("技术", "编程 代码 数据库 算法 服务器 rust python api bug"),
+            ("人工智能", "人工智能 机器学习 深度学习 神经网络 llm ai"),
+            ("学习", "学习 课程 读书 笔记 考试 教程 learn study"),
+            ("工作", "会议 项目 客户 汇报 需求 上线 meeting project"),
+            ("健康", "运动 跑步 睡眠 健身 饮食 医院 exercise health"),
+            ("生活", "吃饭 购物 旅行 电影 家人 朋友 travel family"),
+        ]
+        .into_iter()
+        .map(|(tag, terms)| {
+            let terms = terms.split_whitespace().map(str::to_string).collect();
+            (tag.to_string(), terms)
+        })
+        .collect();
+
+        Self {
+            max_keywords: 8,
+            detect_emotion: true,
+            min_tag_matches: 1,
+            taxonomy,
+        }
+    }
+}
+
+impl Default for ClassificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_confidence: 0.5,
+        }
+    }
+}
+
+impl Default for SegmentationConfig {
+    fn default() -> Self {
+        Self {
+            builtin_dictionary: true,
+            custom_dictionary: Vec::new(),
+            dictionary_path: None,
+            stop_words: Vec::new(),
+            max_word_length: 6,
+        }
+    }
+}
+
+impl Default for DeduplicationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            strategy: DuplicateStrategy::Merge,
+            embedding_threshold: 0.95,
+            simhash_max_distance: 3,
+            minhash_threshold: 0.8,
+            num_hashes: 64,
+            max_candidates: 5,
+            importance_boost: 0.05,
+        }
+    }
+}
+
+impl Default for EvolutionConfig {
+    fn default() -> Self {
+        Self {
+            on_insert: true,
+            max_neighbours: 5,
+            similarity_threshold: 0.7,
+            tag_similarity: 0.85,
+            max_tags: 10,
+            link_reinforcement: 0.1,
+        }
+    }
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            initial_stability_days: 1.0,
+            stability_growth: 2.0,
+            max_stability_days: 3650.0,
+            importance_floor: 0.2,
+            retrieval_weight: 0.0,
+        }
+    }
+}
+
+impl Default for AccessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            count_mode: AccessCountMode::Retrieval,
+            flush_threshold: 64,
+            reinforcement_strength: 0.5,
+        }
+    }
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            log_impressions: true,
+            training_enabled: true,
+            training_window_days: 30,
+            max_impressions: 5000,
+            min_samples: 20,
+            learning_rate: 0.5,
+            epochs: 300,
+            l2_penalty: 0.001,
+            positive_threshold: 0.5,
+            eval_k: 10,
+        }
+    }
+}
+
+impl Default for TimeParsingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            utc_offset_minutes: 0,
+            mode: TimeRangeMode::Score,
+            recent_days: 7,
+        }
+    }
+}
+
+impl Default for DiversityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            method: DiversityMethod::Mmr,
+            lambda: 0.7,
+            community_weight: 0.3,
+            max_candidates: 100,
+        }
+    }
+}
+
+impl Default for RerankConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            method: RerankMethod::Keyword,
+            top_n: 20,
+            weight: 0.5,
+            cross_encoder: CrossEncoderConfig::default(),
+        }
+    }
+}
+
+impl Default for CrossEncoderConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://localhost:8080/rerank".to_string(),
+            model: "bge-reranker-v2-m3".to_string(),
+            api_key: None,
+            api_key_env: "RERANK_API_KEY".to_string(),
+            timeout_seconds: 30,
+        }
+    }
+}
+
+impl Default for ContextBuilderConfig {
+    fn default() -> Self {
+        Self {
+            token_budget: 2000,
+            order: ContextOrder::Relevance,
+            group_by_type: true,
+            include_timestamps: true,
+            include_provenance: true,
+            cite_ids: false,
+            max_memory_tokens: 256,
+            min_memory_tokens: 16,
+            long_memory: LongMemoryStrategy::Summarize,
+        }
+    }
+}
+
+impl Default for QueryExpansionConfig {
+    fn default() -> Self {
+        Self {
+            max_synonyms: 3,
+            feedback_docs: 5,
+            rocchio_alpha: 1.0,
+            rocchio_beta: 0.75,
+            max_paraphrases: 2,
+            variant_weight: 0.5,
+        }
+    }
+}
+
 impl Default for PerformanceConfig {
     fn default() -> Self {
         Self {
@@ -884,6 +1907,30 @@ impl Default for RetrievalConfig {
             timeout_ms: 5000,
             enable_cache: true,
             cache_ttl_seconds: 3600,
+            fusion: FusionConfig::default(),
+            pipeline: PipelineConfig::default(),
+        }
+    }
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            candidate_limit: 50,
+            graph_seeds: 5,
+            causal_depth: 3,
+            time_window_days: 30,
+            pagerank_weight: 0.3,
+            max_reached: 10,
+        }
+    }
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self {
+            rrf_k: 60.0,
+            normalization: ScoreNormalization::MinMax,
         }
     }
 }
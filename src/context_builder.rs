@@ -0,0 +1,450 @@
+//! 提示词上下文组装模块
+//!
+//! 检索之后把 `Vec<RetrievalResult>` 在token预算内组装成放进提示词的记忆块：
+//! 按相关性挑选放入的记忆，按相关性或时间排列、按记忆类型分组，
+//! 标注时间、来源和可引用的记忆ID，过长的记忆先缩短再放入。
+
+use crate::config::{Config, ContextBuilderConfig, ContextOrder, LongMemoryStrategy};
+use crate::core::{Memory, MemoryId, MemoryType};
+use crate::memory::RetrievalResult;
+use crate::utils::TextUtils;
+use chrono::FixedOffset;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// 缩短记忆时追加的省略号
+const ELLIPSIS: &str = "…";
+
+/// token数估计
+pub trait TokenEstimator: Send + Sync + std::fmt::Debug {
+    fn estimate(&self, text: &str) -> usize;
+}
+
+/// 按字符数估计token数：每个汉字计1个token，其余字符按平均每token字符数折算
+#[derive(Debug, Clone)]
+pub struct HeuristicTokenEstimator {
+    chars_per_token: f32,
+}
+
+impl HeuristicTokenEstimator {
+    pub fn new(chars_per_token: f32) -> Self {
+        Self {
+            chars_per_token: chars_per_token.max(1.0),
+        }
+    }
+}
+
+impl Default for HeuristicTokenEstimator {
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+impl TokenEstimator for HeuristicTokenEstimator {
+    fn estimate(&self, text: &str) -> usize {
+        let cjk = text.chars().filter(|c| TextUtils::is_cjk(*c)).count();
+        let other = text.chars().count() - cjk;
+        cjk + (other as f32 / self.chars_per_token).ceil() as usize
+    }
+}
+
+/// 放入记忆块的记忆
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncludedMemory {
+    pub memory_id: MemoryId,
+    pub memory_type: MemoryType,
+    pub relevance_score: f32,
+    /// 该条目占用的token数
+    pub tokens: usize,
+    /// 内容是否被缩短
+    pub shortened: bool,
+}
+
+/// 组装好的上下文
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuiltContext {
+    /// 记忆块文本
+    pub text: String,
+    /// 按记忆块中的顺序排列
+    pub included: Vec<IncludedMemory>,
+    /// 因预算不足未放入的记忆
+    pub omitted: Vec<MemoryId>,
+    /// 记忆块的token数
+    pub token_count: usize,
+}
+
+/// 提示词上下文组装器
+#[derive(Debug, Clone)]
+pub struct ContextBuilder {
+    token_budget: usize,
+    order: ContextOrder,
+    group_by_type: bool,
+    include_timestamps: bool,
+    include_provenance: bool,
+    cite_ids: bool,
+    max_memory_tokens: usize,
+    min_memory_tokens: usize,
+    long_memory: LongMemoryStrategy,
+    offset: FixedOffset,
+    tokenizer: Arc<dyn TokenEstimator>,
+}
+
+/// 挑选阶段的一个条目
+struct Entry<'a> {
+    result: &'a RetrievalResult,
+    line: String,
+    tokens: usize,
+    shortened: bool,
+}
+
+impl ContextBuilder {
+    /// 时间按 `time_parsing.utc_offset_minutes` 指定的时区显示
+    pub fn new(config: &Config) -> Self {
+        let ContextBuilderConfig {
+            token_budget,
+            order,
+            group_by_type,
+            include_timestamps,
+            include_provenance,
+            cite_ids,
+            max_memory_tokens,
+            min_memory_tokens,
+            long_memory,
+        } = config.context_builder;
+
+        Self {
+            token_budget,
+            order,
+            group_by_type,
+            include_timestamps,
+            include_provenance,
+            cite_ids,
+            max_memory_tokens: max_memory_tokens.max(1),
+            min_memory_tokens,
+            long_memory,
+            offset: FixedOffset::east_opt(config.time_parsing.utc_offset_minutes * 60)
+                .unwrap_or(FixedOffset::east_opt(0).unwrap()),
+            tokenizer: Arc::new(HeuristicTokenEstimator::default()),
+        }
+    }
+
+    pub fn with_token_budget(mut self, token_budget: usize) -> Self {
+        self.token_budget = token_budget;
+        self
+    }
+
+    pub fn with_order(mut self, order: ContextOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// 是否附上记忆ID供模型引用
+    pub fn with_citations(mut self, cite_ids: bool) -> Self {
+        self.cite_ids = cite_ids;
+        self
+    }
+
+    /// 使用与目标模型一致的token计数
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn TokenEstimator>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// 组装记忆块
+    ///
+    /// 按相关性从高到低放入，放不下的记忆在剩余预算足够时缩短后放入，否则跳过。
+    pub fn build(&self, results: &[RetrievalResult]) -> BuiltContext {
+        let mut candidates: Vec<&RetrievalResult> = results.iter().collect();
+        candidates.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+
+        let mut used = 0;
+        let mut groups: Vec<MemoryType> = Vec::new();
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut omitted = Vec::new();
+        for result in candidates {
+            let memory_type = &result.memory.memory_type;
+            let header_tokens = if self.group_by_type && !groups.contains(memory_type) {
+                self.tokenizer.estimate(&Self::header(memory_type))
+            } else {
+                0
+            };
+            let available = self.token_budget.saturating_sub(used + header_tokens);
+
+            let mut entry = self.entry(result, self.max_memory_tokens);
+            if entry.as_ref().is_some_and(|e| e.tokens > available)
+                && available >= self.min_memory_tokens
+            {
+                entry = self.entry(result, available);
+            }
+            match entry.filter(|e| e.tokens <= available) {
+                Some(entry) => {
+                    used += header_tokens + entry.tokens;
+                    if header_tokens > 0 {
+                        groups.push(memory_type.clone());
+                    }
+                    entries.push(entry);
+                }
+                None => omitted.push(result.memory.id.clone()),
+            }
+        }
+
+        if self.order == ContextOrder::Chronological {
+            entries.sort_by_key(|e| e.result.memory.metadata.created_at);
+        }
+        if self.group_by_type {
+            // 分组按各类型在排列顺序中首次出现的位置
+            let mut order: Vec<MemoryType> = Vec::new();
+            for entry in &entries {
+                if !order.contains(&entry.result.memory.memory_type) {
+                    order.push(entry.result.memory.memory_type.clone());
+                }
+            }
+            entries.sort_by_key(|e| order.iter().position(|t| *t == e.result.memory.memory_type));
+        }
+
+        let mut lines: Vec<String> = Vec::new();
+        let mut current: Option<&MemoryType> = None;
+        for entry in &entries {
+            let memory_type = &entry.result.memory.memory_type;
+            if self.group_by_type && current != Some(memory_type) {
+                // 各组之间空一行
+                if !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                lines.push(Self::header(memory_type));
+                current = Some(memory_type);
+            }
+            lines.push(entry.line.clone());
+        }
+        let text = lines.join("\n");
+
+        BuiltContext {
+            token_count: self.tokenizer.estimate(&text),
+            text,
+            included: entries
+                .iter()
+                .map(|e| IncludedMemory {
+                    memory_id: e.result.memory.id.clone(),
+                    memory_type: e.result.memory.memory_type.clone(),
+                    relevance_score: e.result.relevance_score,
+                    tokens: e.tokens,
+                    shortened: e.shortened,
+                })
+                .collect(),
+            omitted,
+        }
+    }
+
+    fn header(memory_type: &MemoryType) -> String {
+        format!("## {:?}", memory_type)
+    }
+
+    /// 生成不超过 `max_tokens` 的条目，内容放不下时返回空
+    fn entry<'a>(&self, result: &'a RetrievalResult, max_tokens: usize) -> Option<Entry<'a>> {
+        let memory = &result.memory;
+        let content = memory
+            .content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let overhead = self.tokenizer.estimate(&self.line(memory, ""));
+        let content_budget = max_tokens.checked_sub(overhead).filter(|b| *b > 0)?;
+
+        let shortened = self.tokenizer.estimate(&content) > content_budget;
+        let content = if shortened {
+            let short = match self.long_memory {
+                LongMemoryStrategy::Summarize => self.summarize(&content, content_budget),
+                LongMemoryStrategy::Truncate => self.truncate(&content, content_budget),
+            };
+            if short.is_empty() {
+                return None;
+            }
+            short
+        } else {
+            content
+        };
+
+        let line = self.line(memory, &content);
+        Some(Entry {
+            result,
+            tokens: self.tokenizer.estimate(&line),
+            line,
+            shortened,
+        })
+    }
+
+    fn line(&self, memory: &Memory, content: &str) -> String {
+        let mut line = String::from("- ");
+        if self.include_timestamps {
+            let time = memory.metadata.created_at.with_timezone(&self.offset);
+            line.push_str(&format!("[{}] ", time.format("%Y-%m-%d %H:%M")));
+        }
+        line.push_str(content);
+        if self.include_provenance {
+            if let Some(source) = memory
+                .attributes
+                .source
+                .as_deref()
+                .filter(|s| !s.is_empty())
+            {
+                line.push_str(&format!(" (source: {})", source));
+            }
+        }
+        if self.cite_ids {
+            line.push_str(&format!(" [{}]", memory.id));
+        }
+        line
+    }
+
+    /// 保留开头能放下的完整句子，一句都放不下时按token数截断
+    fn summarize(&self, content: &str, max_tokens: usize) -> String {
+        let mut summary = String::new();
+        let mut sentence = String::new();
+        for c in content.chars() {
+            sentence.push(c);
+            if matches!(c, '。' | '！' | '？' | '；' | '.' | '!' | '?' | ';') {
+                let candidate = format!("{}{}{}", summary, sentence, ELLIPSIS);
+                if self.tokenizer.estimate(candidate.trim()) > max_tokens {
+                    break;
+                }
+                summary.push_str(&sentence);
+                sentence.clear();
+            }
+        }
+
+        if summary.trim().is_empty() {
+            self.truncate(content, max_tokens)
+        } else {
+            format!("{}{}", summary.trim(), ELLIPSIS)
+        }
+    }
+
+    /// 截断到不超过 `max_tokens` 的最长前缀
+    fn truncate(&self, content: &str, max_tokens: usize) -> String {
+        let chars: Vec<char> = content.chars().collect();
+        let prefix = |n: usize| -> String {
+            let text: String = chars[..n].iter().collect();
+            format!("{}{}", text.trim_end(), ELLIPSIS)
+        };
+
+        // 二分查找能放下的最长前缀
+        let (mut low, mut high) = (0, chars.len());
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if self.tokenizer.estimate(&prefix(mid)) <= max_tokens {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+
+        if low == 0 {
+            String::new()
+        } else {
+            prefix(low)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn result(
+        id: &str,
+        memory_type: MemoryType,
+        content: &str,
+        score: f32,
+        day: u32,
+    ) -> RetrievalResult {
+        let mut result = RetrievalResult::for_test(id, score).with_content(content);
+        result.memory.memory_type = memory_type;
+        result.memory.metadata.created_at = Utc.with_ymd_and_hms(2025, 6, day, 1, 30, 0).unwrap();
+        result
+    }
+
+    fn results() -> Vec<RetrievalResult> {
+        let mut meeting = result(
+            "e1",
+            MemoryType::Event,
+            "周三和Alice讨论了发布计划。",
+            0.7,
+            18,
+        );
+        meeting.memory.attributes.source = Some("chat".to_string());
+        vec![
+            result("k1", MemoryType::Knowledge, "Rust的所有权规则。", 0.9, 10),
+            meeting,
+            result(
+                "k2",
+                MemoryType::Knowledge,
+                "借用检查器在编译期运行。",
+                0.5,
+                1,
+            ),
+        ]
+    }
+
+    fn builder() -> ContextBuilder {
+        let mut config = Config::default();
+        config.time_parsing.utc_offset_minutes = 480;
+        ContextBuilder::new(&config)
+    }
+
+    fn ids(context: &BuiltContext) -> Vec<&str> {
+        context
+            .included
+            .iter()
+            .map(|m| m.memory_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_build_context() {
+        let context = builder().with_citations(true).build(&results());
+        assert_eq!(ids(&context), vec!["k1", "k2", "e1"]);
+        assert_eq!(
+            context.text,
+            "## Knowledge\n\
+             - [2025-06-10 09:30] Rust的所有权规则。 [k1]\n\
+             - [2025-06-01 09:30] 借用检查器在编译期运行。 [k2]\n\n\
+             ## Event\n\
+             - [2025-06-18 09:30] 周三和Alice讨论了发布计划。 (source: chat) [e1]"
+        );
+        assert!(context.omitted.is_empty());
+
+        // 按时间排列时分组顺序随之变化
+        let context = builder()
+            .with_order(ContextOrder::Chronological)
+            .build(&results());
+        assert_eq!(ids(&context), vec!["k2", "k1", "e1"]);
+
+        // 预算只够放下最相关的记忆
+        let context = builder().with_token_budget(30).build(&results());
+        assert_eq!(ids(&context), vec!["k1"]);
+        assert_eq!(context.omitted, vec!["e1", "k2"]);
+        assert!(context.token_count <= 30);
+    }
+
+    #[test]
+    fn test_long_memory() {
+        let long = "第一句话说明背景。第二句话给出结论。第三句话补充了很多很多的细节内容。";
+        let results = vec![result("k1", MemoryType::Knowledge, long, 0.9, 10)];
+        let mut config = Config::default();
+        config.context_builder.include_timestamps = false;
+        config.context_builder.max_memory_tokens = 22;
+
+        let context = ContextBuilder::new(&config).build(&results);
+        assert_eq!(
+            context.text,
+            "## Knowledge\n- 第一句话说明背景。第二句话给出结论。…"
+        );
+        assert!(context.included[0].shortened);
+
+        config.context_builder.long_memory = LongMemoryStrategy::Truncate;
+        let context = ContextBuilder::new(&config).build(&results);
+        assert_eq!(context.included[0].tokens, 22);
+        assert!(context.text.ends_with("第三句话…"));
+    }
+}
@@ -184,9 +184,10 @@ pub struct Context {
 }
 
 /// 优先级枚举
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum Priority {
     Low,
+    #[default]
     Normal,
     High,
     Critical,
@@ -211,6 +212,9 @@ pub struct Query {
     pub sort_by: Option<SortBy>,
     /// 查询权重
     pub weights: QueryWeights,
+    /// 查询扩展选项，为空时不扩展
+    #[serde(default)]
+    pub expansion: Option<QueryExpansion>,
 }
 
 /// 查询类型枚举
@@ -272,6 +276,19 @@ pub enum SortBy {
     Custom(String),
 }
 
+/// 查询扩展选项
+///
+/// 启用的每种扩展生成若干查询变体，各变体并行检索后与原查询的结果融合。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QueryExpansion {
+    /// 用实体库中的别名和同义实体替换查询中的实体
+    pub synonyms: bool,
+    /// 伪相关反馈：用原查询前几条结果的嵌入做Rocchio更新
+    pub pseudo_relevance_feedback: bool,
+    /// 由大语言模型改写查询（未配置模型时忽略）
+    pub paraphrases: bool,
+}
+
 /// 查询权重
 ///
 /// 定义不同检索维度的权重。
@@ -536,9 +553,13 @@ impl Default for QueryWeights {
     }
 }
 
-impl Default for Priority {
+impl Default for QueryExpansion {
     fn default() -> Self {
-        Self::Normal
+        Self {
+            synonyms: true,
+            pseudo_relevance_feedback: true,
+            paraphrases: true,
+        }
     }
 }
 
@@ -605,8 +626,13 @@ impl Memory {
 
     /// 更新访问信息
     pub fn update_access(&mut self) {
-        self.metadata.access_count += 1;
-        self.metadata.last_accessed = Utc::now();
+        self.record_accesses(1, Utc::now());
+    }
+
+    /// 记录多次访问，最后访问时间只会前移
+    pub fn record_accesses(&mut self, count: u64, last_accessed: DateTime<Utc>) {
+        self.metadata.access_count += count;
+        self.metadata.last_accessed = self.metadata.last_accessed.max(last_accessed);
     }
 
     /// 检查是否匹配过滤器
@@ -663,10 +689,41 @@ impl Memory {
             }
         }
 
+        // 自定义过滤：与自定义属性逐项相等（如社区ID）
+        for (key, expected) in &filters.custom_filters {
+            if self.attributes.custom_attributes.get(key) != Some(expected) {
+                return false;
+            }
+        }
+
         true
     }
 }
 
+impl ConnectionType {
+    /// 从图边类型解析连接类型
+    ///
+    /// 边类型以 `format!("{:?}", connection_type)` 的形式存储，未知类型解析为自定义连接。
+    pub fn from_edge_type(edge_type: &str) -> Self {
+        match edge_type.to_lowercase().as_str() {
+            "semantic" => Self::Semantic,
+            "temporal" => Self::Temporal,
+            "causal" => Self::Causal,
+            "thematic" => Self::Thematic,
+            "hierarchical" => Self::Hierarchical,
+            "reference" => Self::Reference,
+            _ => {
+                let name = edge_type
+                    .strip_prefix("Custom(")
+                    .and_then(|s| s.strip_suffix(')'))
+                    .and_then(|s| serde_json::from_str::<String>(s).ok())
+                    .unwrap_or_else(|| edge_type.to_string());
+                Self::Custom(name)
+            }
+        }
+    }
+}
+
 impl Connection {
     /// 创建新的连接
     pub fn new(
@@ -696,10 +753,49 @@ impl Connection {
     }
 }
 
+impl Update {
+    /// 创建更新记录
+    pub fn new(memory_id: MemoryId, update_type: UpdateType) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            memory_id,
+            update_type,
+            timestamp: Utc::now(),
+            reason: None,
+            updater_info: None,
+        }
+    }
+
+    /// 设置更新原因和更新者
+    pub fn with_reason(mut self, reason: impl Into<String>, updater: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self.updater_info = Some(updater.into());
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_connection_type_from_edge_type() {
+        for connection_type in [
+            ConnectionType::Causal,
+            ConnectionType::Thematic,
+            ConnectionType::Custom("forced".to_string()),
+            ConnectionType::Custom("带\"引号\"".to_string()),
+        ] {
+            let edge_type = format!("{:?}", connection_type);
+            assert_eq!(ConnectionType::from_edge_type(&edge_type), connection_type);
+        }
+        assert_eq!(ConnectionType::from_edge_type("causal"), ConnectionType::Causal);
+        assert_eq!(
+            ConnectionType::from_edge_type("mentions"),
+            ConnectionType::Custom("mentions".to_string())
+        );
+    }
+
     #[test]
     fn test_memory_creation() {
         let memory = Memory::new(
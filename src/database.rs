@@ -1,17 +1,27 @@
 //! 数据库模�?//!
 //! 本模块提供向量数据库和图数据库的统一接口，支持SQLite、PostgreSQL和MySQL�?//! 包含向量存储、图节点和边的管理、以及高效的查询功能�?
 use crate::config::{Config, DatabaseType};
+use crate::core::Update;
 use crate::error::{MemoryError, Result};
+use crate::graph_query::{QueryExecutor, QueryPlan, QueryRow};
+use crate::learning::FeedbackRecord;
+use crate::pagerank::{IncrementalPageRank, PageRankEntry};
+use crate::ranking::{LearnedRanker, RetrievalImpression};
 use chrono::{DateTime, Utc};
+use log::debug;
 use lru::LruCache;
 use serde::{Deserialize, Serialize};
 
 use sqlx::{Pool, Row, Sqlite};
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// 排除软删除向量的SQL条件
+const NOT_DELETED: &str = "COALESCE(json_extract(metadata, '$.is_deleted'), 0) = 0";
+
 /// 数据库连接池枚举
 #[derive(Debug, Clone)]
 pub enum DatabasePool {
@@ -87,6 +97,19 @@ pub struct GraphQueryResult {
     pub paths: Vec<Vec<String>>,
 }
 
+/// 社区数据结构
+///
+/// 记忆图上检测出的主题社区，成员为记忆节点ID。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Community {
+    pub id: String,
+    pub label: String,
+    pub keywords: Vec<String>,
+    pub members: Vec<String>,
+    pub cohesion: f32,
+    pub created_at: DateTime<Utc>,
+}
+
 /// 内存缓存
 #[derive(Debug)]
 pub struct MemoryCache {
@@ -133,6 +156,10 @@ impl MemoryCache {
         self.edges.write().await.put(id, edge);
     }
 
+    pub async fn remove_edge(&self, id: &str) {
+        self.edges.write().await.pop(id);
+    }
+
     pub async fn clear(&self) {
         self.vectors.write().await.clear();
         self.nodes.write().await.clear();
@@ -155,6 +182,10 @@ pub struct VectorGraphDB {
     pool: DatabasePool,
     cache: MemoryCache,
     config: Config,
+    /// 增量PageRank
+    pagerank: IncrementalPageRank,
+    /// 图版本号，每次图结构变化时递增
+    graph_version: AtomicU64,
 }
 
 impl VectorGraphDB {
@@ -166,12 +197,19 @@ impl VectorGraphDB {
         let db = Self {
             pool,
             cache,
+            pagerank: IncrementalPageRank::new(&config),
             config,
+            graph_version: AtomicU64::new(0),
         };
 
         // 初始化数据库表
         db.initialize_tables().await?;
 
+        // 已有图数据但尚未计算PageRank时先全量构建一次
+        if db.pagerank.is_enabled() && db.pagerank_needs_rebuild().await? {
+            db.rebuild_pagerank().await?;
+        }
+
         Ok(db)
     }
 
@@ -259,6 +297,106 @@ impl VectorGraphDB {
         .await
         .map_err(MemoryError::Database)?;
 
+        // 社区表
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}communities (
+                id TEXT PRIMARY KEY,
+                label TEXT NOT NULL,
+                keywords TEXT NOT NULL,
+                members TEXT NOT NULL,
+                cohesion REAL NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
+        // PageRank表
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}pagerank (
+                node_id TEXT PRIMARY KEY,
+                score REAL NOT NULL,
+                residual REAL NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
+        // 记忆更新历史表
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}memory_updates (
+                id TEXT PRIMARY KEY,
+                memory_id TEXT NOT NULL,
+                update_type TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                reason TEXT,
+                updater_info TEXT
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
+        // 检索展示日志表
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}retrieval_impressions (
+                id TEXT PRIMARY KEY,
+                query TEXT NOT NULL,
+                session_id TEXT,
+                items TEXT NOT NULL,
+                timestamp TEXT NOT NULL
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
+        // 检索反馈日志表
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}retrieval_feedback (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                memory_id TEXT NOT NULL,
+                record TEXT NOT NULL,
+                timestamp TEXT NOT NULL
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
+        // 排序模型表
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}ranking_models (
+                name TEXT PRIMARY KEY,
+                model TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
         // 创建索引
         self.create_sqlite_indexes(pool).await?;
 
@@ -277,6 +415,9 @@ impl VectorGraphDB {
             format!("CREATE INDEX IF NOT EXISTS idx_{prefix}edges_to_node ON {prefix}graph_edges(to_node)"),
             format!("CREATE INDEX IF NOT EXISTS idx_{prefix}edges_type ON {prefix}graph_edges(edge_type)"),
             format!("CREATE INDEX IF NOT EXISTS idx_{prefix}edges_weight ON {prefix}graph_edges(weight)"),
+            format!("CREATE INDEX IF NOT EXISTS idx_{prefix}updates_memory ON {prefix}memory_updates(memory_id)"),
+            format!("CREATE INDEX IF NOT EXISTS idx_{prefix}impressions_timestamp ON {prefix}retrieval_impressions(timestamp)"),
+            format!("CREATE INDEX IF NOT EXISTS idx_{prefix}feedback_timestamp ON {prefix}retrieval_feedback(timestamp)"),
         ];
 
         for index_sql in indexes {
@@ -414,6 +555,94 @@ impl VectorGraphDB {
         Ok(vectors)
     }
 
+    /// 创建时间与 `center` 相差不超过 `window` 的未删除向量，按时间间隔从近到远
+    ///
+    /// 在 `created_at` 索引上向前、向后各取最近的 `limit` 条，不扫描整张表。
+    pub async fn get_vectors_near_time(
+        &self,
+        center: DateTime<Utc>,
+        window: chrono::Duration,
+        limit: usize,
+    ) -> Result<Vec<Vector>> {
+        let mut vectors = Vec::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                // 中心之前（含）和之后各一侧
+                for (near, far, order, bound) in [
+                    ("<=", ">=", "DESC", center - window),
+                    (">", "<=", "ASC", center + window),
+                ] {
+                    let rows = sqlx::query(&format!(
+                        "SELECT id, embedding, metadata, created_at, updated_at FROM {}vectors WHERE created_at {} ? AND created_at {} ? AND {} ORDER BY created_at {} LIMIT ?",
+                        self.config.database.table_prefix, near, far, NOT_DELETED, order
+                    ))
+                    .bind(center.to_rfc3339())
+                    .bind(bound.to_rfc3339())
+                    .bind(limit as i64)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(MemoryError::Database)?;
+
+                    for row in rows {
+                        vectors.push(self.row_to_vector(&row)?);
+                    }
+                }
+            }
+        }
+
+        vectors.sort_by_key(|v| (v.created_at - center).num_milliseconds().abs());
+        vectors.truncate(limit);
+        Ok(vectors)
+    }
+
+    /// 标签或关键词包含任一给定词（ASCII不区分大小写）的未删除向量，按创建时间从新到旧
+    pub async fn get_vectors_by_terms(
+        &self,
+        terms: &[String],
+        limit: usize,
+    ) -> Result<Vec<Vector>> {
+        let mut vectors = Vec::new();
+        if terms.is_empty() {
+            return Ok(vectors);
+        }
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.config.database.table_prefix;
+                let placeholders = vec!["?"; terms.len()].join(", ");
+                let contains = |key: &str| {
+                    format!(
+                        "EXISTS (SELECT 1 FROM json_each({prefix}vectors.metadata, '$.{key}') WHERE lower(trim(json_each.value)) IN ({placeholders}))"
+                    )
+                };
+                let sql = format!(
+                    "SELECT id, embedding, metadata, created_at, updated_at FROM {}vectors WHERE ({} OR {}) AND {} ORDER BY created_at DESC LIMIT ?",
+                    prefix,
+                    contains("tags"),
+                    contains("keywords"),
+                    NOT_DELETED
+                );
+
+                let mut query = sqlx::query(&sql);
+                for term in terms.iter().chain(terms) {
+                    query = query.bind(term.trim().to_lowercase());
+                }
+                let rows = query
+                    .bind(limit as i64)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(MemoryError::Database)?;
+
+                for row in rows {
+                    vectors.push(self.row_to_vector(&row)?);
+                }
+            }
+        }
+
+        Ok(vectors)
+    }
+
     /// 插入图节点
     pub async fn insert_node(&self, node: &GraphNode) -> Result<()> {
         let properties_json =
@@ -440,6 +669,12 @@ impl VectorGraphDB {
         // 更新缓存
         self.cache.put_node(node.id.clone(), node.clone()).await;
 
+        if self.pagerank.is_enabled() {
+            let _guard = self.pagerank.lock().await;
+            self.pagerank.apply_node_insert(self, &node.id).await?;
+        }
+        self.graph_version.fetch_add(1, Ordering::SeqCst);
+
         Ok(())
     }
 
@@ -448,6 +683,9 @@ impl VectorGraphDB {
         let properties_json =
             serde_json::to_string(&edge.properties).map_err(MemoryError::Serialization)?;
 
+        let _guard = self.pagerank_guard().await;
+        let before = self.pagerank_snapshot(&[edge]).await?;
+
         match &self.pool {
             DatabasePool::Sqlite(pool) => {
                 sqlx::query(&format!(
@@ -472,6 +710,129 @@ impl VectorGraphDB {
         // 更新缓存
         self.cache.put_edge(edge.id.clone(), edge.clone()).await;
 
+        self.after_edge_change(before).await
+    }
+
+    /// 更新图边（端点、类型、权重或属性）
+    pub async fn update_edge(&self, edge: &GraphEdge) -> Result<()> {
+        let properties_json =
+            serde_json::to_string(&edge.properties).map_err(MemoryError::Serialization)?;
+
+        let _guard = self.pagerank_guard().await;
+        let old = self.get_edge(&edge.id).await?;
+        let before = self.pagerank_snapshot(&[&old, edge]).await?;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "UPDATE {}graph_edges SET from_node = ?, to_node = ?, edge_type = ?, weight = ?, properties = ?, updated_at = ? WHERE id = ?",
+                    self.config.database.table_prefix
+                ))
+                .bind(&edge.from_node)
+                .bind(&edge.to_node)
+                .bind(&edge.edge_type)
+                .bind(edge.weight)
+                .bind(&properties_json)
+                .bind(edge.updated_at.to_rfc3339())
+                .bind(&edge.id)
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
+
+        self.cache.put_edge(edge.id.clone(), edge.clone()).await;
+
+        self.after_edge_change(before).await
+    }
+
+    /// 删除图边
+    pub async fn delete_edge(&self, edge_id: &str) -> Result<()> {
+        let _guard = self.pagerank_guard().await;
+        let old = self.get_edge(edge_id).await?;
+        let before = self.pagerank_snapshot(&[&old]).await?;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "DELETE FROM {}graph_edges WHERE id = ?",
+                    self.config.database.table_prefix
+                ))
+                .bind(edge_id)
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
+
+        self.cache.remove_edge(edge_id).await;
+
+        self.after_edge_change(before).await
+    }
+
+    /// 获取图边
+    pub async fn get_edge(&self, edge_id: &str) -> Result<GraphEdge> {
+        if let Some(edge) = self.cache.get_edge(edge_id).await {
+            return Ok(edge);
+        }
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let row = sqlx::query(&format!(
+                    "SELECT id, from_node, to_node, edge_type, weight, properties, created_at, updated_at FROM {}graph_edges WHERE id = ?",
+                    self.config.database.table_prefix
+                ))
+                .bind(edge_id)
+                .fetch_one(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                let edge = self.row_to_edge(&row)?;
+                self.cache.put_edge(edge.id.clone(), edge.clone()).await;
+                Ok(edge)
+            }
+        }
+    }
+
+    /// 当前图版本号
+    pub fn graph_version(&self) -> u64 {
+        self.graph_version.load(Ordering::SeqCst)
+    }
+
+    /// 启用增量PageRank时获取更新锁，未启用时边写入无需串行化
+    async fn pagerank_guard(&self) -> Option<tokio::sync::MutexGuard<'_, ()>> {
+        if self.pagerank.is_enabled() {
+            Some(self.pagerank.lock().await)
+        } else {
+            None
+        }
+    }
+
+    /// 边变化前记录受影响节点的转移分布
+    async fn pagerank_snapshot(
+        &self,
+        edges: &[&GraphEdge],
+    ) -> Result<Option<HashMap<String, crate::pagerank::Transitions>>> {
+        if !self.pagerank.is_enabled() {
+            return Ok(None);
+        }
+        let nodes: Vec<String> = edges
+            .iter()
+            .flat_map(|edge| self.pagerank.affected_nodes(edge))
+            .collect();
+        Ok(Some(self.pagerank.snapshot(self, &nodes).await?))
+    }
+
+    /// 边变化后增量更新PageRank并递增图版本
+    async fn after_edge_change(
+        &self,
+        before: Option<HashMap<String, crate::pagerank::Transitions>>,
+    ) -> Result<()> {
+        if let Some(before) = before {
+            let pushes = self.pagerank.apply_edge_change(self, before).await?;
+            debug!("Incremental PageRank update took {} pushes", pushes);
+        }
+        self.graph_version.fetch_add(1, Ordering::SeqCst);
         Ok(())
     }
 
@@ -579,6 +940,11 @@ impl VectorGraphDB {
         Ok((result_nodes, result_edges, paths))
     }
 
+    /// 执行图查询语言的查询计划
+    pub async fn execute_query(&self, plan: &QueryPlan) -> Result<Vec<QueryRow>> {
+        QueryExecutor::new(self).run(plan).await
+    }
+
     /// 更新向量
     pub async fn update_vector(&self, vector: &Vector) -> Result<()> {
         let embedding_bytes = self.vector_to_bytes(&vector.embedding)?;
@@ -689,7 +1055,7 @@ impl VectorGraphDB {
     }
 
     /// 获取节点
-    async fn get_node(&self, node_id: &str) -> Result<GraphNode> {
+    pub async fn get_node(&self, node_id: &str) -> Result<GraphNode> {
         // 先检查缓存
         if let Some(node) = self.cache.get_node(node_id).await {
             return Ok(node);
@@ -742,7 +1108,7 @@ impl VectorGraphDB {
     }
 
     /// 获取相邻边
-    async fn get_adjacent_edges(
+    pub(crate) async fn get_adjacent_edges(
         &self,
         node_id: &str,
         edge_types: &Option<Vec<String>>,
@@ -816,6 +1182,526 @@ impl VectorGraphDB {
         Ok(edges)
     }
 
+    /// 获取所有节点（可按节点类型过滤）
+    pub async fn get_all_nodes(&self, node_type: Option<&str>) -> Result<Vec<GraphNode>> {
+        let mut nodes = Vec::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let type_filter = if node_type.is_some() {
+                    " WHERE node_type = ?"
+                } else {
+                    ""
+                };
+                let sql = format!(
+                    "SELECT id, node_type, properties, created_at, updated_at FROM {}graph_nodes{} ORDER BY id",
+                    self.config.database.table_prefix, type_filter
+                );
+
+                let mut query = sqlx::query(&sql);
+                if let Some(node_type) = node_type {
+                    query = query.bind(node_type);
+                }
+
+                let rows = query.fetch_all(pool).await.map_err(MemoryError::Database)?;
+                for row in rows {
+                    nodes.push(self.row_to_node(&row)?);
+                }
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    /// 获取所有边（可按边类型过滤）
+    pub async fn get_all_edges(&self, edge_types: &Option<Vec<String>>) -> Result<Vec<GraphEdge>> {
+        let mut edges = Vec::new();
+
+        let type_filter = if let Some(types) = edge_types {
+            format!(
+                " WHERE edge_type IN ({})",
+                types.iter().map(|_| "?").collect::<Vec<_>>().join(", ")
+            )
+        } else {
+            String::new()
+        };
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = format!(
+                    "SELECT id, from_node, to_node, edge_type, weight, properties, created_at, updated_at FROM {}graph_edges{} ORDER BY id",
+                    self.config.database.table_prefix, type_filter
+                );
+
+                let mut query = sqlx::query(&sql);
+                if let Some(types) = edge_types {
+                    for edge_type in types {
+                        query = query.bind(edge_type);
+                    }
+                }
+
+                let rows = query.fetch_all(pool).await.map_err(MemoryError::Database)?;
+                for row in rows {
+                    edges.push(self.row_to_edge(&row)?);
+                }
+            }
+        }
+
+        Ok(edges)
+    }
+
+    /// 保存社区（整体替换旧的社区划分）
+    pub async fn save_communities(&self, communities: &[Community]) -> Result<()> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.config.database.table_prefix;
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                sqlx::query(&format!("DELETE FROM {}communities", prefix))
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(MemoryError::Database)?;
+
+                for community in communities {
+                    let keywords_json = serde_json::to_string(&community.keywords)
+                        .map_err(MemoryError::Serialization)?;
+                    let members_json = serde_json::to_string(&community.members)
+                        .map_err(MemoryError::Serialization)?;
+
+                    sqlx::query(&format!(
+                        "INSERT INTO {}communities (id, label, keywords, members, cohesion, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+                        prefix
+                    ))
+                    .bind(&community.id)
+                    .bind(&community.label)
+                    .bind(&keywords_json)
+                    .bind(&members_json)
+                    .bind(community.cohesion)
+                    .bind(community.created_at.to_rfc3339())
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(MemoryError::Database)?;
+                }
+
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 获取所有社区（按成员数量降序）
+    pub async fn get_communities(&self) -> Result<Vec<Community>> {
+        let mut communities = Vec::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT id, label, keywords, members, cohesion, created_at FROM {}communities",
+                    self.config.database.table_prefix
+                ))
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                for row in rows {
+                    let keywords_str: String = row.get("keywords");
+                    let members_str: String = row.get("members");
+                    let created_at_str: String = row.get("created_at");
+
+                    communities.push(Community {
+                        id: row.get("id"),
+                        label: row.get("label"),
+                        keywords: serde_json::from_str(&keywords_str)
+                            .map_err(MemoryError::Serialization)?,
+                        members: serde_json::from_str(&members_str)
+                            .map_err(MemoryError::Serialization)?,
+                        cohesion: row.get("cohesion"),
+                        created_at: Self::parse_timestamp(&created_at_str)?,
+                    });
+                }
+            }
+        }
+
+        communities.sort_by(|a, b| {
+            b.members
+                .len()
+                .cmp(&a.members.len())
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        Ok(communities)
+    }
+
+    /// 记录记忆更新历史
+    pub async fn insert_update(&self, update: &Update) -> Result<()> {
+        let update_type_json =
+            serde_json::to_string(&update.update_type).map_err(MemoryError::Serialization)?;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "INSERT INTO {}memory_updates (id, memory_id, update_type, timestamp, reason, updater_info) VALUES (?, ?, ?, ?, ?, ?)",
+                    self.config.database.table_prefix
+                ))
+                .bind(&update.id)
+                .bind(&update.memory_id)
+                .bind(&update_type_json)
+                .bind(update.timestamp.to_rfc3339())
+                .bind(&update.reason)
+                .bind(&update.updater_info)
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 获取记忆的更新历史（按时间升序）
+    pub async fn get_updates(&self, memory_id: &str) -> Result<Vec<Update>> {
+        let mut updates = Vec::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT id, memory_id, update_type, timestamp, reason, updater_info FROM {}memory_updates WHERE memory_id = ? ORDER BY timestamp, rowid",
+                    self.config.database.table_prefix
+                ))
+                .bind(memory_id)
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                for row in rows {
+                    let update_type_str: String = row.get("update_type");
+                    let timestamp_str: String = row.get("timestamp");
+
+                    updates.push(Update {
+                        id: row.get("id"),
+                        memory_id: row.get("memory_id"),
+                        update_type: serde_json::from_str(&update_type_str)
+                            .map_err(MemoryError::Serialization)?,
+                        timestamp: Self::parse_timestamp(&timestamp_str)?,
+                        reason: row.get("reason"),
+                        updater_info: row.get("updater_info"),
+                    });
+                }
+            }
+        }
+
+        Ok(updates)
+    }
+
+    /// 记录一次检索展示
+    pub async fn insert_impression(&self, impression: &RetrievalImpression) -> Result<()> {
+        let items_json =
+            serde_json::to_string(&impression.items).map_err(MemoryError::Serialization)?;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "INSERT INTO {}retrieval_impressions (id, query, session_id, items, timestamp) VALUES (?, ?, ?, ?, ?)",
+                    self.config.database.table_prefix
+                ))
+                .bind(&impression.id)
+                .bind(&impression.query)
+                .bind(&impression.session_id)
+                .bind(&items_json)
+                .bind(impression.timestamp.to_rfc3339())
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 删除早于 `before` 的检索展示，并只保留最近的 `keep` 条，返回删除的条数
+    pub async fn prune_impressions(&self, before: DateTime<Utc>, keep: usize) -> Result<u64> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.config.database.table_prefix;
+                let result = sqlx::query(&format!(
+                    "DELETE FROM {prefix}retrieval_impressions WHERE timestamp < ? OR id NOT IN (SELECT id FROM {prefix}retrieval_impressions ORDER BY timestamp DESC LIMIT ?)"
+                ))
+                .bind(before.to_rfc3339())
+                .bind(keep as i64)
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+                Ok(result.rows_affected())
+            }
+        }
+    }
+
+    /// 获取时间窗口内最近的检索展示（按时间升序）
+    pub async fn get_impressions(
+        &self,
+        window: (DateTime<Utc>, DateTime<Utc>),
+        limit: usize,
+    ) -> Result<Vec<RetrievalImpression>> {
+        let mut impressions = Vec::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT id, query, session_id, items, timestamp FROM {}retrieval_impressions WHERE timestamp >= ? AND timestamp <= ? ORDER BY timestamp DESC LIMIT ?",
+                    self.config.database.table_prefix
+                ))
+                .bind(window.0.to_rfc3339())
+                .bind(window.1.to_rfc3339())
+                .bind(limit as i64)
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                for row in rows {
+                    let items_str: String = row.get("items");
+                    let timestamp_str: String = row.get("timestamp");
+
+                    impressions.push(RetrievalImpression {
+                        id: row.get("id"),
+                        query: row.get("query"),
+                        session_id: row.get("session_id"),
+                        items: serde_json::from_str(&items_str)
+                            .map_err(MemoryError::Serialization)?,
+                        timestamp: Self::parse_timestamp(&timestamp_str)?,
+                    });
+                }
+            }
+        }
+
+        impressions.reverse();
+        Ok(impressions)
+    }
+
+    /// 记录一条检索反馈
+    pub async fn insert_feedback(&self, feedback: &FeedbackRecord) -> Result<()> {
+        let record_json = serde_json::to_string(feedback).map_err(MemoryError::Serialization)?;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "INSERT INTO {}retrieval_feedback (memory_id, record, timestamp) VALUES (?, ?, ?)",
+                    self.config.database.table_prefix
+                ))
+                .bind(&feedback.memory_id)
+                .bind(&record_json)
+                .bind(feedback.timestamp.to_rfc3339())
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 获取时间窗口内的检索反馈（按时间升序）
+    pub async fn get_feedback(
+        &self,
+        window: (DateTime<Utc>, DateTime<Utc>),
+    ) -> Result<Vec<FeedbackRecord>> {
+        let mut feedback = Vec::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT record FROM {}retrieval_feedback WHERE timestamp >= ? AND timestamp <= ? ORDER BY timestamp, id",
+                    self.config.database.table_prefix
+                ))
+                .bind(window.0.to_rfc3339())
+                .bind(window.1.to_rfc3339())
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                for row in rows {
+                    let record_str: String = row.get("record");
+                    feedback.push(
+                        serde_json::from_str(&record_str).map_err(MemoryError::Serialization)?,
+                    );
+                }
+            }
+        }
+
+        Ok(feedback)
+    }
+
+    /// 保存排序模型，同名模型被覆盖
+    pub async fn save_ranking_model(&self, name: &str, model: &LearnedRanker) -> Result<()> {
+        let model_json = serde_json::to_string(model).map_err(MemoryError::Serialization)?;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "INSERT OR REPLACE INTO {}ranking_models (name, model, updated_at) VALUES (?, ?, ?)",
+                    self.config.database.table_prefix
+                ))
+                .bind(name)
+                .bind(&model_json)
+                .bind(Utc::now().to_rfc3339())
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 加载排序模型
+    pub async fn load_ranking_model(&self, name: &str) -> Result<Option<LearnedRanker>> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let row = sqlx::query(&format!(
+                    "SELECT model FROM {}ranking_models WHERE name = ?",
+                    self.config.database.table_prefix
+                ))
+                .bind(name)
+                .fetch_optional(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                row.map(|row| {
+                    let model_str: String = row.get("model");
+                    serde_json::from_str(&model_str).map_err(MemoryError::Serialization)
+                })
+                .transpose()
+            }
+        }
+    }
+
+    /// 全量重建全局PageRank
+    pub async fn rebuild_pagerank(&self) -> Result<usize> {
+        let _guard = self.pagerank.lock().await;
+        let count = self.pagerank.rebuild(self).await?;
+        self.graph_version.fetch_add(1, Ordering::SeqCst);
+        Ok(count)
+    }
+
+    /// 获取归一化的全局PageRank分数（总和为1）
+    ///
+    /// 全局分数不在检索路径上，检索只使用按种子计算并缓存的个性化PageRank。
+    pub async fn get_pagerank_scores(&self) -> Result<HashMap<String, f32>> {
+        let mut scores = HashMap::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT node_id, score FROM {}pagerank",
+                    self.config.database.table_prefix
+                ))
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                for row in rows {
+                    let score: f64 = row.get("score");
+                    scores.insert(row.get::<String, _>("node_id"), score);
+                }
+            }
+        }
+
+        let total: f64 = scores.values().sum();
+        Ok(scores
+            .into_iter()
+            .map(|(id, score)| {
+                let normalized = if total > 0.0 { score / total } else { 0.0 };
+                (id, normalized as f32)
+            })
+            .collect())
+    }
+
+    /// 个性化PageRank
+    pub async fn personalized_pagerank(
+        &self,
+        seeds: &HashMap<String, f32>,
+    ) -> Result<HashMap<String, f32>> {
+        self.pagerank.personalized(self, seeds).await
+    }
+
+    /// 读取单个节点的PageRank状态
+    pub(crate) async fn get_pagerank_entry(&self, node_id: &str) -> Result<Option<PageRankEntry>> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let row = sqlx::query(&format!(
+                    "SELECT score, residual FROM {}pagerank WHERE node_id = ?",
+                    self.config.database.table_prefix
+                ))
+                .bind(node_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                Ok(row.map(|row| (row.get("score"), row.get("residual"))))
+            }
+        }
+    }
+
+    /// 保存PageRank状态，`replace_all` 为真时整体替换
+    pub(crate) async fn save_pagerank_entries(
+        &self,
+        entries: &HashMap<String, PageRankEntry>,
+        replace_all: bool,
+    ) -> Result<()> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.config.database.table_prefix;
+                let now = Utc::now().to_rfc3339();
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                if replace_all {
+                    sqlx::query(&format!("DELETE FROM {}pagerank", prefix))
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                }
+
+                for (node_id, (score, residual)) in entries {
+                    sqlx::query(&format!(
+                        "INSERT OR REPLACE INTO {}pagerank (node_id, score, residual, updated_at) VALUES (?, ?, ?, ?)",
+                        prefix
+                    ))
+                    .bind(node_id)
+                    .bind(score)
+                    .bind(residual)
+                    .bind(&now)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(MemoryError::Database)?;
+                }
+
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 图中有节点但PageRank表为空
+    async fn pagerank_needs_rebuild(&self) -> Result<bool> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.config.database.table_prefix;
+                let scored: i64 =
+                    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}pagerank", prefix))
+                        .fetch_one(pool)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                let nodes: i64 =
+                    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}graph_nodes", prefix))
+                        .fetch_one(pool)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                Ok(scored == 0 && nodes > 0)
+            }
+        }
+    }
+
     /// 清除缓存
     pub async fn clear_cache(&self) {
         self.cache.clear().await;
@@ -856,6 +1742,64 @@ impl VectorGraphDB {
 
     // 辅助函数
 
+    /// 解析RFC3339时间戳
+    fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+        Ok(DateTime::parse_from_rfc3339(value)
+            .map_err(|e| MemoryError::Internal {
+                message: format!("DateTime parse failed: {}", e),
+            })?
+            .with_timezone(&Utc))
+    }
+
+    /// 行数据转向量
+    fn row_to_vector(&self, row: &sqlx::sqlite::SqliteRow) -> Result<Vector> {
+        let embedding_bytes: Vec<u8> = row.get("embedding");
+        let metadata_str: String = row.get("metadata");
+        let created_at_str: String = row.get("created_at");
+        let updated_at_str: String = row.get("updated_at");
+
+        Ok(Vector {
+            id: row.get("id"),
+            embedding: self.bytes_to_vector(&embedding_bytes)?,
+            metadata: serde_json::from_str(&metadata_str).map_err(MemoryError::Serialization)?,
+            created_at: Self::parse_timestamp(&created_at_str)?,
+            updated_at: Self::parse_timestamp(&updated_at_str)?,
+        })
+    }
+
+    /// 行数据转节点
+    fn row_to_node(&self, row: &sqlx::sqlite::SqliteRow) -> Result<GraphNode> {
+        let properties_str: String = row.get("properties");
+        let created_at_str: String = row.get("created_at");
+        let updated_at_str: String = row.get("updated_at");
+
+        Ok(GraphNode {
+            id: row.get("id"),
+            node_type: row.get("node_type"),
+            properties: serde_json::from_str(&properties_str).map_err(MemoryError::Serialization)?,
+            created_at: Self::parse_timestamp(&created_at_str)?,
+            updated_at: Self::parse_timestamp(&updated_at_str)?,
+        })
+    }
+
+    /// 行数据转边
+    fn row_to_edge(&self, row: &sqlx::sqlite::SqliteRow) -> Result<GraphEdge> {
+        let properties_str: String = row.get("properties");
+        let created_at_str: String = row.get("created_at");
+        let updated_at_str: String = row.get("updated_at");
+
+        Ok(GraphEdge {
+            id: row.get("id"),
+            from_node: row.get("from_node"),
+            to_node: row.get("to_node"),
+            edge_type: row.get("edge_type"),
+            weight: row.get("weight"),
+            properties: serde_json::from_str(&properties_str).map_err(MemoryError::Serialization)?,
+            created_at: Self::parse_timestamp(&created_at_str)?,
+            updated_at: Self::parse_timestamp(&updated_at_str)?,
+        })
+    }
+
     /// 计算余弦相似度
     fn cosine_similarity(&self, a: &[f32], b: &[f32]) -> f32 {
         if a.len() != b.len() {
@@ -884,7 +1828,7 @@ impl VectorGraphDB {
 
     /// 字节转向量
     fn bytes_to_vector(&self, bytes: &[u8]) -> Result<Vec<f32>> {
-        if bytes.len() % 4 != 0 {
+        if !bytes.len().is_multiple_of(4) {
             return Err(MemoryError::InvalidVectorDimension {
                 expected: bytes.len() - (bytes.len() % 4),
                 actual: bytes.len(),
@@ -1043,4 +1987,80 @@ mod tests {
 
         assert_eq!(original_vector, restored_vector);
     }
+
+    #[tokio::test]
+    async fn test_time_and_term_lookups() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = VectorGraphDB::new(config).await.unwrap();
+
+        let center = Utc::now();
+        for (id, minutes, tags, deleted) in [
+            ("a", -50, vec!["咖啡"], false),
+            ("b", -10, vec!["Coffee"], false),
+            ("c", 5, vec![], false),
+            ("d", 20, vec!["跑步"], false),
+            ("e", 2, vec!["咖啡"], true),
+            ("f", 300, vec!["咖啡"], false),
+        ] {
+            let mut metadata = HashMap::new();
+            metadata.insert("tags".to_string(), serde_json::json!(tags));
+            metadata.insert("is_deleted".to_string(), serde_json::json!(deleted));
+            let created_at = center + chrono::Duration::minutes(minutes);
+            db.insert_vector(&Vector {
+                id: id.to_string(),
+                embedding: vec![1.0, 0.0],
+                metadata,
+                created_at,
+                updated_at: created_at,
+            })
+            .await
+            .unwrap();
+        }
+        let ids = |vectors: Vec<Vector>| vectors.into_iter().map(|v| v.id).collect::<Vec<_>>();
+
+        // 窗口内按时间间隔从近到远，跳过已删除的和窗口外的
+        let window = chrono::Duration::hours(1);
+        let nearby = db.get_vectors_near_time(center, window, 10).await.unwrap();
+        assert_eq!(ids(nearby), vec!["c", "b", "d", "a"]);
+        let nearby = db.get_vectors_near_time(center, window, 2).await.unwrap();
+        assert_eq!(ids(nearby), vec!["c", "b"]);
+
+        // 标签匹配不区分ASCII大小写，按创建时间从新到旧
+        let terms = ["咖啡".to_string(), "coffee".to_string()];
+        let related = db.get_vectors_by_terms(&terms, 10).await.unwrap();
+        assert_eq!(ids(related), vec!["f", "b", "a"]);
+        assert!(db.get_vectors_by_terms(&[], 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prune_impressions() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = VectorGraphDB::new(config).await.unwrap();
+
+        let now = Utc::now();
+        for (id, days) in [("old", 40), ("a", 3), ("b", 2), ("c", 1)] {
+            let mut impression = RetrievalImpression::new("查询", None, &[]);
+            impression.id = id.to_string();
+            impression.timestamp = now - chrono::Duration::days(days);
+            db.insert_impression(&impression).await.unwrap();
+        }
+
+        // 删除窗口之外的展示，并只保留最近的两条
+        let pruned = db
+            .prune_impressions(now - chrono::Duration::days(30), 2)
+            .await
+            .unwrap();
+        assert_eq!(pruned, 2);
+        let window = (now - chrono::Duration::days(100), now);
+        let kept: Vec<String> = db
+            .get_impressions(window, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|i| i.id)
+            .collect();
+        assert_eq!(kept, vec!["b", "c"]);
+    }
 }
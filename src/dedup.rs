@@ -0,0 +1,400 @@
+//! 近重复检测模块
+//!
+//! 写入新记忆时结合嵌入相似度和内容指纹（SimHash、MinHash）判断近重复，
+//! 按策略拒绝、合并或链接；也可离线扫描已有记忆，找出重复簇并合并。
+
+use crate::config::{Config, DeduplicationConfig};
+use crate::core::{Memory, MemoryId};
+use crate::database::{VectorGraphDB, VectorQueryRequest};
+use crate::error::Result;
+use crate::segment::Segmenter;
+use crate::utils::{HashUtils, VectorUtils};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 合并记录在自定义属性中的键，记录被合并记忆的来源
+pub const DUPLICATE_SOURCES_ATTRIBUTE: &str = "duplicate_sources";
+
+/// 重复项连接的自定义连接类型名
+pub const DUPLICATE_CONNECTION: &str = "duplicate";
+
+/// 内容指纹
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentFingerprint {
+    /// 归一化后的内容（分词后以空格连接）
+    pub normalized: String,
+    pub simhash: u64,
+    pub minhash: Vec<u64>,
+}
+
+/// 近重复匹配结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateMatch {
+    pub memory_id: MemoryId,
+    pub embedding_similarity: f32,
+    /// MinHash估计的内容相似度
+    pub content_similarity: f32,
+}
+
+/// 离线去重结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeduplicationReport {
+    /// 重复簇，每个簇的第一个记忆为保留的记忆
+    pub clusters: Vec<Vec<MemoryId>>,
+    /// 被合并（软删除）的记忆数
+    pub merged: usize,
+}
+
+/// 近重复检测器
+#[derive(Debug, Clone)]
+pub struct DuplicateDetector {
+    enabled: bool,
+    embedding_threshold: f32,
+    simhash_max_distance: u32,
+    minhash_threshold: f32,
+    num_hashes: usize,
+    max_candidates: usize,
+    importance_boost: f32,
+    segmenter: Arc<Segmenter>,
+}
+
+impl DuplicateDetector {
+    pub fn new(config: &Config) -> Self {
+        let DeduplicationConfig {
+            enabled,
+            embedding_threshold,
+            simhash_max_distance,
+            minhash_threshold,
+            num_hashes,
+            max_candidates,
+            importance_boost,
+            ..
+        } = config.deduplication;
+
+        Self {
+            enabled,
+            embedding_threshold,
+            simhash_max_distance,
+            minhash_threshold,
+            num_hashes: num_hashes.max(1),
+            max_candidates: max_candidates.max(1),
+            importance_boost,
+            segmenter: Segmenter::shared(),
+        }
+    }
+
+    /// 使用指定的分词器计算内容指纹
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.segmenter = segmenter;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 计算内容指纹，特征为相邻词对（只有一个词时为该词本身）
+    pub fn fingerprint(&self, content: &str) -> ContentFingerprint {
+        let tokens: Vec<String> = self
+            .segmenter
+            .segment(content)
+            .into_iter()
+            .map(|token| token.text)
+            .collect();
+        let features: Vec<String> = if tokens.len() < 2 {
+            tokens.clone()
+        } else {
+            tokens.windows(2).map(|pair| pair.join(" ")).collect()
+        };
+
+        ContentFingerprint {
+            normalized: tokens.join(" "),
+            simhash: HashUtils::simhash(&features),
+            minhash: HashUtils::minhash(&features, self.num_hashes),
+        }
+    }
+
+    /// 判断两条内容是否为近重复，返回内容相似度
+    ///
+    /// 归一化后内容相同即为重复；否则要求嵌入相似度达到阈值，
+    /// 且MinHash相似度达到阈值或SimHash汉明距离足够小。
+    pub fn matches(
+        &self,
+        embedding_similarity: f32,
+        a: &ContentFingerprint,
+        b: &ContentFingerprint,
+    ) -> Option<f32> {
+        if !a.normalized.is_empty() && a.normalized == b.normalized {
+            return Some(1.0);
+        }
+        if embedding_similarity < self.embedding_threshold {
+            return None;
+        }
+
+        let content_similarity = HashUtils::minhash_similarity(&a.minhash, &b.minhash);
+        let near = content_similarity >= self.minhash_threshold
+            || HashUtils::hamming_distance(a.simhash, b.simhash) <= self.simhash_max_distance;
+        near.then_some(content_similarity)
+    }
+
+    /// 在已有记忆中查找新记忆的近重复项，返回嵌入最相似的一个
+    pub async fn find_duplicate(
+        &self,
+        memory: &Memory,
+        db: &VectorGraphDB,
+    ) -> Result<Option<DuplicateMatch>> {
+        if !self.enabled || memory.embedding.is_empty() {
+            return Ok(None);
+        }
+
+        let request = VectorQueryRequest {
+            query_vector: memory.embedding.clone(),
+            limit: Some(self.max_candidates),
+            threshold: Some(self.embedding_threshold),
+            // 在查询中排除已删除的记忆，以免占用候选名额
+            filters: Some(HashMap::from([(
+                "is_deleted".to_string(),
+                serde_json::Value::Bool(false),
+            )])),
+        };
+        let fingerprint = self.fingerprint(&memory.content);
+        for candidate in db.query_vectors(&request).await? {
+            let metadata = &candidate.vector.metadata;
+            let Some(content) = metadata.get("content").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if candidate.vector.id == memory.id {
+                continue;
+            }
+
+            let other = self.fingerprint(content);
+            if let Some(content_similarity) =
+                self.matches(candidate.similarity, &fingerprint, &other)
+            {
+                return Ok(Some(DuplicateMatch {
+                    memory_id: candidate.vector.id,
+                    embedding_similarity: candidate.similarity,
+                    content_similarity,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 找出记忆中的重复簇
+    ///
+    /// 两两比较后用并查集聚类；簇内按创建时间排序，最早的记忆排在第一位。
+    pub fn find_clusters(&self, memories: &[Memory]) -> Vec<Vec<MemoryId>> {
+        let fingerprints: Vec<ContentFingerprint> = memories
+            .iter()
+            .map(|m| self.fingerprint(&m.content))
+            .collect();
+        let mut parent: Vec<usize> = (0..memories.len()).collect();
+        fn find(parent: &mut [usize], i: usize) -> usize {
+            let mut root = i;
+            while parent[root] != root {
+                root = parent[root];
+            }
+            parent[i] = root;
+            root
+        }
+
+        for i in 0..memories.len() {
+            for j in i + 1..memories.len() {
+                let similarity =
+                    VectorUtils::cosine_similarity(&memories[i].embedding, &memories[j].embedding)
+                        .unwrap_or(0.0);
+                if self
+                    .matches(similarity, &fingerprints[i], &fingerprints[j])
+                    .is_some()
+                {
+                    let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+
+        let mut clusters: HashMap<usize, Vec<&Memory>> = HashMap::new();
+        for (i, memory) in memories.iter().enumerate() {
+            clusters
+                .entry(find(&mut parent, i))
+                .or_default()
+                .push(memory);
+        }
+
+        let mut clusters: Vec<Vec<MemoryId>> = clusters
+            .into_values()
+            .filter(|members| members.len() > 1)
+            .map(|mut members| {
+                members.sort_by(|a, b| {
+                    a.metadata
+                        .created_at
+                        .cmp(&b.metadata.created_at)
+                        .then_with(|| a.id.cmp(&b.id))
+                });
+                members.into_iter().map(|m| m.id.clone()).collect()
+            })
+            .collect();
+        clusters.sort();
+        clusters
+    }
+
+    /// 把重复记忆合并到目标记忆
+    ///
+    /// 累加访问次数并提升重要性，合并标签和关键词，补全缺失的属性，
+    /// 并在自定义属性中记录被合并记忆的来源。
+    pub fn merge_into(&self, target: &mut Memory, duplicate: &Memory) {
+        let now = Utc::now();
+        target.metadata.access_count += duplicate.metadata.access_count + 1;
+        target.metadata.last_accessed = now;
+        target.metadata.updated_at = now;
+        target.metadata.version += 1;
+
+        let attributes = &mut target.attributes;
+        let incoming = &duplicate.attributes;
+        attributes.importance =
+            (attributes.importance.max(incoming.importance) + self.importance_boost).min(1.0);
+        attributes.confidence = attributes.confidence.max(incoming.confidence);
+        for (merged, extra) in [
+            (&mut attributes.tags, &incoming.tags),
+            (&mut attributes.keywords, &incoming.keywords),
+        ] {
+            for item in extra {
+                if !merged.contains(item) {
+                    merged.push(item.clone());
+                }
+            }
+        }
+        if attributes.emotion.is_none() {
+            attributes.emotion = incoming.emotion.clone();
+        }
+        if attributes.language.is_none() {
+            attributes.language = incoming.language.clone();
+        }
+
+        let mut sources: Vec<serde_json::Value> = attributes
+            .custom_attributes
+            .get(DUPLICATE_SOURCES_ATTRIBUTE)
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        if let Some(earlier) = incoming
+            .custom_attributes
+            .get(DUPLICATE_SOURCES_ATTRIBUTE)
+            .and_then(|v| v.as_array())
+        {
+            sources.extend(earlier.iter().cloned());
+        }
+        sources.push(serde_json::json!({
+            "memory_id": duplicate.id,
+            "content": duplicate.content,
+            "source": incoming.source,
+            "created_at": duplicate.metadata.created_at.to_rfc3339(),
+        }));
+        attributes.custom_attributes.insert(
+            DUPLICATE_SOURCES_ATTRIBUTE.to_string(),
+            serde_json::Value::Array(sources),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemoryAttributes, MemoryType};
+
+    fn memory(content: &str, embedding: Vec<f32>) -> Memory {
+        Memory::new(
+            content.to_string(),
+            MemoryType::Knowledge,
+            embedding,
+            MemoryAttributes::default(),
+        )
+    }
+
+    #[test]
+    fn test_near_duplicate_matching() {
+        let detector = DuplicateDetector::new(&Config::default());
+        let a = detector.fingerprint("I like coffee");
+        let b = detector.fingerprint("i like coffee!");
+        assert_eq!(detector.matches(0.0, &a, &b), Some(1.0));
+
+        // 内容不同，即便嵌入很相似也不算重复
+        let c = detector.fingerprint("I don't like coffee");
+        assert_eq!(detector.matches(0.99, &a, &c), None);
+
+        let long = "今天下午和团队讨论了新版本的发布计划，决定下周三上线搜索功能并同步更新文档";
+        let edited = "今天下午和团队讨论了新版本的发布计划，决定下周三上线搜索功能并同步更新文档。";
+        let (x, y) = (detector.fingerprint(long), detector.fingerprint(edited));
+        assert_eq!(detector.matches(0.99, &x, &y), Some(1.0));
+        let reworded = detector.fingerprint(&long.replace("下周三", "下周四"));
+        assert!(detector.matches(0.99, &x, &reworded).is_some());
+        assert!(detector.matches(0.5, &x, &reworded).is_none());
+    }
+
+    #[test]
+    fn test_clusters_and_merge() {
+        let detector = DuplicateDetector::new(&Config::default());
+        let mut first = memory("I like coffee", vec![1.0, 0.0]);
+        first.attributes.tags = vec!["饮食".to_string()];
+        first.attributes.importance = 0.5;
+        let mut second = memory("I like coffee.", vec![1.0, 0.0]);
+        second.metadata.created_at = first.metadata.created_at + chrono::Duration::seconds(1);
+        second.attributes.tags = vec!["饮食".to_string(), "习惯".to_string()];
+        second.attributes.source = Some("wechat".to_string());
+        let other = memory("The meeting moved to Friday", vec![0.0, 1.0]);
+
+        let clusters = detector.find_clusters(&[second.clone(), other, first.clone()]);
+        assert_eq!(clusters, vec![vec![first.id.clone(), second.id.clone()]]);
+
+        detector.merge_into(&mut first, &second);
+        assert_eq!(first.metadata.access_count, 1);
+        assert!((first.attributes.importance - 0.55).abs() < 1e-6);
+        assert_eq!(first.attributes.tags, vec!["饮食", "习惯"]);
+        let sources = first.attributes.custom_attributes[DUPLICATE_SOURCES_ATTRIBUTE]
+            .as_array()
+            .unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0]["memory_id"], serde_json::json!(second.id));
+        assert_eq!(sources[0]["source"], serde_json::json!("wechat"));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_memories_do_not_use_candidate_slots() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.deduplication.max_candidates = 1;
+        let db = VectorGraphDB::new(config.clone()).await.unwrap();
+        let detector = DuplicateDetector::new(&config);
+
+        // 已删除的副本与新记忆的嵌入完全相同
+        for (id, embedding, deleted) in [
+            ("deleted", vec![1.0, 0.0], true),
+            ("kept", vec![0.99, 0.14], false),
+        ] {
+            let metadata = HashMap::from([
+                ("content".to_string(), serde_json::json!("I like coffee")),
+                ("is_deleted".to_string(), serde_json::json!(deleted)),
+            ]);
+            db.insert_vector(&crate::database::Vector {
+                id: id.to_string(),
+                embedding,
+                metadata,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let found = detector
+            .find_duplicate(&memory("I like coffee!", vec![1.0, 0.0]), &db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.memory_id, "kept");
+    }
+}
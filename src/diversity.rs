@@ -0,0 +1,340 @@
+//! 结果多样化模块
+//!
+//! 检索结果的前几名经常是几条几乎相同的记忆，放进LLM提示词时浪费上下文。
+//! 融合之后按最大边际相关性（MMR）或行列式点过程（DPP）重排，
+//! 记忆间的相似度由存储的嵌入和所属的图社区共同给出。
+
+use crate::config::{Config, DiversityConfig, DiversityMethod};
+use crate::core::MemoryId;
+use crate::database::VectorGraphDB;
+use crate::error::Result;
+use crate::memory::RetrievalResult;
+use crate::utils::VectorUtils;
+use std::collections::HashMap;
+
+/// DPP贪心推断中边际增益低于该值时停止选择
+const DPP_EPSILON: f64 = 1e-9;
+
+/// 结果多样化器
+#[derive(Debug, Clone)]
+pub struct Diversifier {
+    enabled: bool,
+    method: DiversityMethod,
+    lambda: f32,
+    community_weight: f32,
+    max_candidates: usize,
+}
+
+/// 参与重排的记忆的嵌入和社区归属
+#[derive(Debug, Clone, Default)]
+pub struct DiversitySignals {
+    pub embeddings: HashMap<MemoryId, Vec<f32>>,
+    /// 记忆ID到社区ID
+    pub communities: HashMap<MemoryId, String>,
+}
+
+impl Diversifier {
+    pub fn new(config: &Config) -> Self {
+        let DiversityConfig {
+            enabled,
+            method,
+            lambda,
+            community_weight,
+            max_candidates,
+        } = config.diversity;
+
+        Self {
+            enabled,
+            method,
+            lambda: lambda.clamp(0.0, 1.0),
+            community_weight: community_weight.clamp(0.0, 1.0),
+            max_candidates,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn method(&self) -> DiversityMethod {
+        self.method
+    }
+
+    pub fn lambda(&self) -> f32 {
+        self.lambda
+    }
+
+    /// 加载结果的嵌入和社区归属后重排
+    ///
+    /// 结果中没有嵌入的记忆从向量表读取，读取失败的按没有嵌入处理。
+    pub async fn diversify(
+        &self,
+        db: &VectorGraphDB,
+        results: Vec<RetrievalResult>,
+    ) -> Result<Vec<RetrievalResult>> {
+        if !self.enabled || results.len() < 2 {
+            return Ok(results);
+        }
+
+        let mut signals = DiversitySignals::default();
+        for result in results.iter().take(self.candidate_count(results.len())) {
+            let memory = &result.memory;
+            let embedding = if memory.embedding.is_empty() {
+                db.get_vector(&memory.id)
+                    .await
+                    .map(|v| v.embedding)
+                    .unwrap_or_default()
+            } else {
+                memory.embedding.clone()
+            };
+            signals.embeddings.insert(memory.id.clone(), embedding);
+        }
+        if self.community_weight > 0.0 {
+            for community in db.get_communities().await? {
+                for member in community.members {
+                    signals.communities.insert(member, community.id.clone());
+                }
+            }
+        }
+
+        Ok(self.rerank(results, &signals))
+    }
+
+    /// 按给定的嵌入和社区归属重排，原有的相关性分数不变
+    pub fn rerank(
+        &self,
+        results: Vec<RetrievalResult>,
+        signals: &DiversitySignals,
+    ) -> Vec<RetrievalResult> {
+        if !self.enabled || results.len() < 2 {
+            return results;
+        }
+
+        let n = self.candidate_count(results.len());
+        let max_score = results[..n]
+            .iter()
+            .map(|r| r.relevance_score)
+            .fold(0.0f32, f32::max);
+        let relevance: Vec<f32> = results[..n]
+            .iter()
+            .map(|r| {
+                if max_score > 0.0 {
+                    r.relevance_score.max(0.0) / max_score
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let similarity = self.similarity_matrix(&results[..n], signals);
+
+        let order = match self.method {
+            DiversityMethod::Mmr => self.mmr(&relevance, &similarity),
+            DiversityMethod::Dpp => self.dpp(&relevance, &similarity),
+        };
+
+        let mut slots: Vec<Option<RetrievalResult>> = results.into_iter().map(Some).collect();
+        let mut reranked: Vec<RetrievalResult> =
+            order.iter().filter_map(|&i| slots[i].take()).collect();
+        reranked.extend(slots.into_iter().flatten());
+        reranked
+    }
+
+    fn candidate_count(&self, len: usize) -> usize {
+        if self.max_candidates == 0 {
+            len
+        } else {
+            len.min(self.max_candidates)
+        }
+    }
+
+    /// 记忆两两之间的相似度，自身相似度为1
+    fn similarity_matrix(
+        &self,
+        results: &[RetrievalResult],
+        signals: &DiversitySignals,
+    ) -> Vec<Vec<f32>> {
+        let n = results.len();
+        let embeddings: Vec<&[f32]> = results
+            .iter()
+            .map(|r| {
+                signals
+                    .embeddings
+                    .get(&r.memory.id)
+                    .map(|e| e.as_slice())
+                    .unwrap_or(r.memory.embedding.as_slice())
+            })
+            .collect();
+
+        let mut matrix = vec![vec![1.0; n]; n];
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let (a, b) = (&results[i], &results[j]);
+                let cosine =
+                    VectorUtils::cosine_similarity(embeddings[i], embeddings[j]).unwrap_or(0.0);
+                let same_community = match (
+                    signals.communities.get(&a.memory.id),
+                    signals.communities.get(&b.memory.id),
+                ) {
+                    (Some(x), Some(y)) if x == y => 1.0,
+                    _ => 0.0,
+                };
+                let sim =
+                    (1.0 - self.community_weight) * cosine + self.community_weight * same_community;
+                matrix[i][j] = sim;
+                matrix[j][i] = sim;
+            }
+        }
+        matrix
+    }
+
+    /// 最大边际相关性：每次选 `λ·相关性 - (1-λ)·与已选结果的最大相似度` 最大的结果
+    fn mmr(&self, relevance: &[f32], similarity: &[Vec<f32>]) -> Vec<usize> {
+        let n = relevance.len();
+        let mut selected: Vec<usize> = Vec::with_capacity(n);
+        let mut max_sim = vec![f32::NEG_INFINITY; n];
+        let mut remaining: Vec<usize> = (0..n).collect();
+
+        while !remaining.is_empty() {
+            let mut best = 0;
+            let mut best_score = f32::NEG_INFINITY;
+            for (pos, &i) in remaining.iter().enumerate() {
+                let redundancy = if selected.is_empty() { 0.0 } else { max_sim[i] };
+                let score = self.lambda * relevance[i] - (1.0 - self.lambda) * redundancy;
+                if score > best_score {
+                    best = pos;
+                    best_score = score;
+                }
+            }
+
+            let chosen = remaining.remove(best);
+            for &i in &remaining {
+                max_sim[i] = max_sim[i].max(similarity[chosen][i]);
+            }
+            selected.push(chosen);
+        }
+        selected
+    }
+
+    /// DPP贪心MAP推断
+    ///
+    /// 核矩阵 `L = diag(q)·S·diag(q)`，质量 `q = exp(α·相关性)`，`α = λ / (2(1-λ))`。
+    /// 用增量Cholesky分解每次选边际增益最大的结果，增益耗尽后其余结果按相关性排在后面。
+    fn dpp(&self, relevance: &[f32], similarity: &[Vec<f32>]) -> Vec<usize> {
+        let n = relevance.len();
+        if self.lambda >= 1.0 {
+            return self.mmr(relevance, similarity);
+        }
+
+        let alpha = self.lambda as f64 / (2.0 * (1.0 - self.lambda as f64));
+        let quality: Vec<f64> = relevance
+            .iter()
+            .map(|r| (alpha * *r as f64).exp())
+            .collect();
+        let kernel = |i: usize, j: usize| quality[i] * similarity[i][j] as f64 * quality[j];
+
+        let mut gains: Vec<f64> = (0..n).map(|i| kernel(i, i)).collect();
+        let mut factors: Vec<Vec<f64>> = vec![Vec::new(); n];
+        let mut selected: Vec<usize> = Vec::with_capacity(n);
+        let mut remaining: Vec<usize> = (0..n).collect();
+
+        while !remaining.is_empty() {
+            let (pos, &chosen) = remaining
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| gains[**a].total_cmp(&gains[**b]).then(b.cmp(a)))
+                .unwrap();
+            if gains[chosen] < DPP_EPSILON {
+                break;
+            }
+            remaining.remove(pos);
+            selected.push(chosen);
+
+            let d = gains[chosen].sqrt();
+            for &i in &remaining {
+                let dot: f64 = factors[chosen]
+                    .iter()
+                    .zip(&factors[i])
+                    .map(|(a, b)| a * b)
+                    .sum();
+                let e = (kernel(chosen, i) - dot) / d;
+                factors[i].push(e);
+                gains[i] -= e * e;
+            }
+        }
+
+        // 与已选结果线性相关的剩余结果按相关性排序
+        remaining.sort_by(|a, b| relevance[*b].total_cmp(&relevance[*a]).then(a.cmp(b)));
+        selected.extend(remaining);
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a和a2几乎相同，b相关性较低但内容不同
+    fn results() -> Vec<RetrievalResult> {
+        vec![
+            RetrievalResult::for_test("a", 0.90).with_embedding(vec![1.0, 0.0, 0.0]),
+            RetrievalResult::for_test("a2", 0.88).with_embedding(vec![1.0, 0.0, 0.02]),
+            RetrievalResult::for_test("b", 0.70).with_embedding(vec![0.0, 1.0, 0.0]),
+        ]
+    }
+
+    fn diversifier(method: DiversityMethod, lambda: f32, community_weight: f32) -> Diversifier {
+        let mut config = Config::default();
+        config.diversity.method = method;
+        config.diversity.lambda = lambda;
+        config.diversity.community_weight = community_weight;
+        Diversifier::new(&config)
+    }
+
+    fn ids(results: &[RetrievalResult]) -> Vec<&str> {
+        results.iter().map(|r| r.memory.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_mmr() {
+        let signals = DiversitySignals::default();
+
+        let reranked = diversifier(DiversityMethod::Mmr, 0.7, 0.0).rerank(results(), &signals);
+        assert_eq!(ids(&reranked), vec!["a", "b", "a2"]);
+        assert_eq!(reranked[1].relevance_score, 0.70);
+
+        // λ=1只看相关性
+        let unchanged = diversifier(DiversityMethod::Mmr, 1.0, 0.0).rerank(results(), &signals);
+        assert_eq!(ids(&unchanged), vec!["a", "a2", "b"]);
+
+        // 没有嵌入时按社区归属判断重复
+        let mut no_embeddings = results();
+        for r in &mut no_embeddings {
+            r.memory.embedding.clear();
+        }
+        let mut signals = DiversitySignals::default();
+        signals
+            .communities
+            .insert("a".to_string(), "c1".to_string());
+        signals
+            .communities
+            .insert("a2".to_string(), "c1".to_string());
+        signals
+            .communities
+            .insert("b".to_string(), "c2".to_string());
+        let reranked = diversifier(DiversityMethod::Mmr, 0.7, 1.0).rerank(no_embeddings, &signals);
+        assert_eq!(ids(&reranked), vec!["a", "b", "a2"]);
+    }
+
+    #[test]
+    fn test_dpp() {
+        let signals = DiversitySignals::default();
+        let reranked = diversifier(DiversityMethod::Dpp, 0.7, 0.0).rerank(results(), &signals);
+        assert_eq!(ids(&reranked), vec!["a", "b", "a2"]);
+
+        // 完全重复的结果增益为0，排在最后
+        let mut duplicated = results();
+        duplicated.push(RetrievalResult::for_test("a3", 0.89).with_embedding(vec![1.0, 0.0, 0.0]));
+        let reranked = diversifier(DiversityMethod::Dpp, 0.7, 0.0).rerank(duplicated, &signals);
+        assert_eq!(ids(&reranked), vec!["a", "b", "a2", "a3"]);
+    }
+}
@@ -0,0 +1,805 @@
+//! 实体图模块
+//!
+//! 参考HippoRAG，从记忆内容中抽取实体（命名实体和关键名词短语），
+//! 为每个实体建立实体节点，并用“提及”边把记忆连接到实体，用“同义”边
+//! 把嵌入相似的实体连接起来。查询时从查询文本中抽取实体作为个性化
+//! PageRank的种子，使多跳回忆可以经由共享实体在记忆之间传播。
+//!
+//! 实体抽取器是可插拔的：默认使用基于规则和关键词的抽取器，
+//! 也可以换成基于大语言模型的抽取器。
+
+use crate::config::{Config, EntityConfig};
+use crate::core::MemoryId;
+use crate::database::{GraphEdge, GraphNode, VectorGraphDB};
+use crate::error::{MemoryError, Result};
+use crate::llm::LlmProvider;
+use crate::segment::Segmenter;
+use crate::utils::{HashUtils, VectorUtils};
+use async_trait::async_trait;
+use chrono::Utc;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// 实体节点类型
+pub const ENTITY_NODE_TYPE: &str = "entity";
+/// 记忆到实体的提及边类型
+pub const MENTIONS_EDGE_TYPE: &str = "Mentions";
+/// 实体之间的同义边类型
+pub const SYNONYM_EDGE_TYPE: &str = "Synonym";
+
+/// 抽取出的实体
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractedEntity {
+    /// 实体名称（原文形式）
+    pub name: String,
+    /// 实体类型
+    pub entity_type: String,
+}
+
+impl ExtractedEntity {
+    pub fn new(name: impl Into<String>, entity_type: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            entity_type: entity_type.into(),
+        }
+    }
+
+    /// 归一化后的名称
+    pub fn normalized(&self) -> String {
+        normalize_entity_name(&self.name)
+    }
+
+    /// 对应实体节点的ID
+    pub fn node_id(&self) -> String {
+        entity_node_id(&self.normalized())
+    }
+}
+
+/// 实体图中的实体
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entity {
+    pub id: String,
+    pub name: String,
+    pub entity_type: String,
+    /// 出现过的不同写法
+    pub aliases: Vec<String>,
+    /// 提及该实体的记忆数量
+    pub mention_count: u64,
+}
+
+impl Entity {
+    /// 从实体节点构造
+    pub fn from_node(node: &GraphNode) -> Option<Self> {
+        if node.node_type != ENTITY_NODE_TYPE {
+            return None;
+        }
+
+        let text = |key: &str| {
+            node.properties
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        Some(Self {
+            id: node.id.clone(),
+            name: text("name"),
+            entity_type: text("entity_type"),
+            aliases: node
+                .properties
+                .get("aliases")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+            mention_count: node
+                .properties
+                .get("mention_count")
+                .and_then(Value::as_u64)
+                .unwrap_or(0),
+        })
+    }
+}
+
+/// 实体抽取器
+#[async_trait]
+pub trait EntityExtractor: Send + Sync + std::fmt::Debug {
+    /// 从文本中抽取实体
+    async fn extract(&self, text: &str) -> Result<Vec<ExtractedEntity>>;
+}
+
+/// 基于规则和关键词的实体抽取器
+///
+/// 识别引号和书名号中的内容、#标签、@提及、英文大写开头的专有名词，
+/// 再以关键词补充名词短语。
+#[derive(Debug, Clone)]
+pub struct KeywordEntityExtractor {
+    max_keywords: usize,
+    segmenter: Arc<Segmenter>,
+}
+
+impl KeywordEntityExtractor {
+    pub fn new(max_keywords: usize) -> Self {
+        Self {
+            max_keywords,
+            segmenter: Segmenter::shared(),
+        }
+    }
+
+    /// 使用指定的分词器提取关键词
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.segmenter = segmenter;
+        self
+    }
+
+    /// 同步抽取实体
+    pub fn extract_entities(&self, text: &str) -> Vec<ExtractedEntity> {
+        let mut entities = Vec::new();
+
+        for (open, close, entity_type) in [
+            ('《', '》', "work"),
+            ('“', '”', "quoted"),
+            ('「', '」', "quoted"),
+            ('"', '"', "quoted"),
+        ] {
+            for span in Self::delimited(text, open, close) {
+                entities.push(ExtractedEntity::new(span, entity_type));
+            }
+        }
+
+        for word in text.split_whitespace() {
+            let (entity_type, rest) = match word.chars().next() {
+                Some('#') => ("tag", &word[1..]),
+                Some('@') => ("person", &word[1..]),
+                _ => continue,
+            };
+            let name = rest.trim_end_matches(|c: char| !c.is_alphanumeric());
+            if !name.is_empty() {
+                entities.push(ExtractedEntity::new(name, entity_type));
+            }
+        }
+
+        for name in Self::proper_nouns(text) {
+            entities.push(ExtractedEntity::new(name, "name"));
+        }
+
+        for keyword in self.segmenter.extract_keywords(text, self.max_keywords) {
+            entities.push(ExtractedEntity::new(keyword, "concept"));
+        }
+
+        dedup_entities(entities)
+    }
+
+    fn delimited(text: &str, open: char, close: char) -> Vec<String> {
+        let mut spans = Vec::new();
+        let mut current: Option<String> = None;
+
+        for c in text.chars() {
+            match current.as_mut() {
+                Some(span) if c == close => {
+                    let span = span.trim().to_string();
+                    if !span.is_empty() && span.chars().count() <= 32 {
+                        spans.push(span);
+                    }
+                    current = None;
+                }
+                Some(span) => span.push(c),
+                None if c == open => current = Some(String::new()),
+                None => {}
+            }
+        }
+
+        spans
+    }
+
+    fn proper_nouns(text: &str) -> Vec<String> {
+        const COMMON: &[&str] = &[
+            "The", "A", "An", "I", "We", "You", "He", "She", "It", "They", "This", "That", "My",
+            "Our", "Today",
+        ];
+
+        let mut names = Vec::new();
+        let mut run: Vec<&str> = Vec::new();
+
+        let mut flush = |run: &mut Vec<&str>| {
+            while run.first().is_some_and(|w| COMMON.contains(w)) {
+                run.remove(0);
+            }
+            if !run.is_empty() {
+                names.push(run.join(" "));
+            }
+            run.clear();
+        };
+
+        for token in text.split_whitespace() {
+            let word = token.trim_matches(|c: char| !c.is_alphanumeric());
+            let capitalized = word.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+                && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+
+            if capitalized {
+                run.push(word);
+            } else {
+                flush(&mut run);
+            }
+            // 句末标点打断专有名词序列
+            if capitalized && token.ends_with(|c: char| ".,;:!?".contains(c)) {
+                flush(&mut run);
+            }
+        }
+        flush(&mut run);
+
+        names
+    }
+}
+
+impl Default for KeywordEntityExtractor {
+    fn default() -> Self {
+        Self::new(5)
+    }
+}
+
+#[async_trait]
+impl EntityExtractor for KeywordEntityExtractor {
+    async fn extract(&self, text: &str) -> Result<Vec<ExtractedEntity>> {
+        Ok(self.extract_entities(text))
+    }
+}
+
+/// 基于大语言模型的实体抽取器
+///
+/// 模型调用失败或返回无法解析的结果时回退到关键词抽取器。
+#[derive(Debug, Clone)]
+pub struct LlmEntityExtractor {
+    provider: Arc<dyn LlmProvider>,
+    fallback: KeywordEntityExtractor,
+}
+
+#[derive(Debug, Deserialize)]
+struct LlmEntity {
+    name: String,
+    #[serde(default, rename = "type")]
+    entity_type: Option<String>,
+}
+
+impl LlmEntityExtractor {
+    pub fn new(provider: Arc<dyn LlmProvider>) -> Self {
+        Self {
+            provider,
+            fallback: KeywordEntityExtractor::default(),
+        }
+    }
+
+    /// 回退的关键词抽取器使用指定的分词器
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.fallback = self.fallback.with_segmenter(segmenter);
+        self
+    }
+
+    fn prompt(text: &str) -> String {
+        format!(
+            "从下面的文本中抽取命名实体和关键名词短语（人物、组织、地点、作品、概念等）。\n\
+             只返回JSON数组，每个元素形如 {{\"name\": \"实体\", \"type\": \"类型\"}}。\n\n\
+             文本：{}",
+            text
+        )
+    }
+
+    fn parse_response(response: &str) -> Result<Vec<ExtractedEntity>> {
+        let start = response.find('[');
+        let end = response.rfind(']');
+        let json = match (start, end) {
+            (Some(start), Some(end)) if start < end => &response[start..=end],
+            _ => {
+                return Err(MemoryError::validation_error(
+                    "LLM response does not contain a JSON array".to_string(),
+                ))
+            }
+        };
+
+        let entities: Vec<LlmEntity> =
+            serde_json::from_str(json).map_err(MemoryError::Serialization)?;
+
+        Ok(dedup_entities(
+            entities
+                .into_iter()
+                .map(|e| {
+                    ExtractedEntity::new(
+                        e.name.trim(),
+                        e.entity_type.unwrap_or_else(|| "concept".to_string()),
+                    )
+                })
+                .collect(),
+        ))
+    }
+}
+
+#[async_trait]
+impl EntityExtractor for LlmEntityExtractor {
+    async fn extract(&self, text: &str) -> Result<Vec<ExtractedEntity>> {
+        let parsed = match self.provider.complete(&Self::prompt(text)).await {
+            Ok(response) => Self::parse_response(&response),
+            Err(e) => Err(e),
+        };
+
+        match parsed {
+            Ok(entities) if !entities.is_empty() => Ok(entities),
+            Ok(_) => Ok(self.fallback.extract_entities(text)),
+            Err(e) => {
+                warn!(
+                    "LLM entity extraction via {} failed, falling back to keywords: {}",
+                    self.provider.name(),
+                    e
+                );
+                Ok(self.fallback.extract_entities(text))
+            }
+        }
+    }
+}
+
+/// 实体链接器
+///
+/// 维护实体节点、提及边和同义边，并为查询生成实体种子。
+#[derive(Debug, Clone)]
+pub struct EntityLinker {
+    extractor: Arc<dyn EntityExtractor>,
+    config: EntityConfig,
+}
+
+impl EntityLinker {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            extractor: Arc::new(KeywordEntityExtractor::default()),
+            config: config.graph.entity.clone(),
+        }
+    }
+
+    /// 替换实体抽取器
+    pub fn with_extractor(mut self, extractor: Arc<dyn EntityExtractor>) -> Self {
+        self.extractor = extractor;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 抽取实体（数量受配置限制）
+    pub async fn extract(&self, text: &str) -> Result<Vec<ExtractedEntity>> {
+        let mut entities = self.extractor.extract(text).await?;
+        entities.retain(|e| !e.normalized().is_empty());
+        entities.truncate(self.config.max_entities_per_memory);
+        Ok(entities)
+    }
+
+    /// 把记忆链接到其提及的实体
+    ///
+    /// 每个实体附带可选的名称嵌入，用于建立同义边；重复链接同一记忆是幂等的。
+    pub async fn link_memory(
+        &self,
+        db: &VectorGraphDB,
+        memory_id: &MemoryId,
+        entities: &[(ExtractedEntity, Option<Vec<f32>>)],
+    ) -> Result<Vec<Entity>> {
+        let mut known = Self::entity_embeddings(db).await?;
+        let mut linked = Vec::new();
+        let now = Utc::now();
+
+        for (extracted, embedding) in entities {
+            let normalized = extracted.normalized();
+            if normalized.is_empty() {
+                continue;
+            }
+            let entity_id = entity_node_id(&normalized);
+            let mention_id = edge_id("mention", memory_id, &entity_id);
+            let newly_mentioned = match db.get_edge(&mention_id).await {
+                Ok(_) => false,
+                Err(e) if is_not_found(&e) => true,
+                Err(e) => return Err(e),
+            };
+
+            let node = match db.get_node(&entity_id).await {
+                Ok(mut node) => {
+                    let mut entity = Entity::from_node(&node).ok_or_else(|| {
+                        MemoryError::graph_error(format!("Node {} is not an entity", entity_id))
+                    })?;
+                    if newly_mentioned {
+                        entity.mention_count += 1;
+                    }
+                    if !entity.aliases.contains(&extracted.name) {
+                        entity.aliases.push(extracted.name.clone());
+                    }
+                    node.properties
+                        .insert("aliases".to_string(), json!(entity.aliases));
+                    node.properties
+                        .insert("mention_count".to_string(), json!(entity.mention_count));
+                    node.updated_at = now;
+                    db.update_node(&node).await?;
+                    node
+                }
+                Err(e) if is_not_found(&e) => {
+                    let mut properties = HashMap::new();
+                    properties.insert("name".to_string(), json!(extracted.name));
+                    properties.insert("normalized".to_string(), json!(normalized));
+                    properties.insert("entity_type".to_string(), json!(extracted.entity_type));
+                    properties.insert("aliases".to_string(), json!([extracted.name]));
+                    properties.insert("mention_count".to_string(), json!(1));
+                    if let Some(embedding) = embedding {
+                        properties.insert("embedding".to_string(), json!(embedding));
+                    }
+                    let node = GraphNode {
+                        id: entity_id.clone(),
+                        node_type: ENTITY_NODE_TYPE.to_string(),
+                        properties,
+                        created_at: now,
+                        updated_at: now,
+                    };
+                    db.insert_node(&node).await?;
+
+                    if let Some(embedding) = embedding {
+                        self.link_synonyms(db, &entity_id, embedding, &known)
+                            .await?;
+                        known.push((entity_id.clone(), embedding.clone()));
+                    }
+                    node
+                }
+                Err(e) => return Err(e),
+            };
+
+            if newly_mentioned {
+                let mut properties = HashMap::new();
+                properties.insert("mention".to_string(), json!(extracted.name));
+                db.insert_edge(&GraphEdge {
+                    id: mention_id,
+                    from_node: memory_id.clone(),
+                    to_node: entity_id.clone(),
+                    edge_type: MENTIONS_EDGE_TYPE.to_string(),
+                    weight: 1.0,
+                    properties,
+                    created_at: now,
+                    updated_at: now,
+                })
+                .await?;
+            }
+
+            if let Some(entity) = Entity::from_node(&node) {
+                linked.push(entity);
+            }
+        }
+
+        debug!("Linked memory {} to {} entities", memory_id, linked.len());
+        Ok(linked)
+    }
+
+    /// 根据查询中的实体生成PageRank种子
+    ///
+    /// 名称完全匹配的实体权重为1，否则取嵌入最相似且超过同义阈值的实体。
+    /// 权重按实体被提及的次数做逆频率衰减，越具体的实体权重越高。
+    pub async fn query_seeds(
+        &self,
+        db: &VectorGraphDB,
+        entities: &[(ExtractedEntity, Option<Vec<f32>>)],
+    ) -> Result<HashMap<String, f32>> {
+        let mut seeds = HashMap::new();
+        if entities.is_empty() {
+            return Ok(seeds);
+        }
+
+        let known = Self::entity_embeddings(db).await?;
+        for (extracted, embedding) in entities {
+            let exact_id = extracted.node_id();
+            let matched = match db.get_node(&exact_id).await {
+                Ok(node) => Some((node, 1.0)),
+                Err(e) if is_not_found(&e) => match embedding {
+                    Some(embedding) => match self.most_similar(embedding, &known)? {
+                        Some((id, similarity)) => Some((db.get_node(&id).await?, similarity)),
+                        None => None,
+                    },
+                    None => None,
+                },
+                Err(e) => return Err(e),
+            };
+
+            let Some((node, similarity)) = matched else {
+                continue;
+            };
+            let Some(entity) = Entity::from_node(&node) else {
+                continue;
+            };
+
+            let specificity = 1.0 / (1.0 + (entity.mention_count.max(1) as f32).ln());
+            let weight = similarity * specificity * self.config.query_seed_weight;
+            *seeds.entry(entity.id).or_insert(0.0) += weight;
+        }
+
+        Ok(seeds)
+    }
+
+    /// 查询实体的同义写法：实体节点记录的别名，以及经同义边相连的实体的名称和别名
+    ///
+    /// 实体库中没有该实体时返回空列表，结果不含与实体本身归一化后相同的写法。
+    pub async fn synonyms(
+        &self,
+        db: &VectorGraphDB,
+        extracted: &ExtractedEntity,
+    ) -> Result<Vec<String>> {
+        let entity_id = extracted.node_id();
+        let node = match db.get_node(&entity_id).await {
+            Ok(node) => node,
+            Err(e) if is_not_found(&e) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let Some(entity) = Entity::from_node(&node) else {
+            return Ok(Vec::new());
+        };
+
+        let mut names = entity.aliases.clone();
+        names.push(entity.name.clone());
+        let edges = db
+            .get_adjacent_edges(&entity_id, &Some(vec![SYNONYM_EDGE_TYPE.to_string()]))
+            .await?;
+        for edge in edges {
+            let other_id = if edge.from_node == entity_id {
+                &edge.to_node
+            } else {
+                &edge.from_node
+            };
+            if let Some(other) = Entity::from_node(&db.get_node(other_id).await?) {
+                names.push(other.name);
+                names.extend(other.aliases);
+            }
+        }
+
+        let mut seen: HashSet<String> = HashSet::from([extracted.normalized()]);
+        names.retain(|name| seen.insert(normalize_entity_name(name)));
+        Ok(names)
+    }
+
+    /// 获取记忆提及的实体
+    pub async fn memory_entities(
+        &self,
+        db: &VectorGraphDB,
+        memory_id: &MemoryId,
+    ) -> Result<Vec<Entity>> {
+        let edges = db
+            .get_adjacent_edges(memory_id, &Some(vec![MENTIONS_EDGE_TYPE.to_string()]))
+            .await?;
+
+        let mut entities = Vec::new();
+        for edge in edges.iter().filter(|e| &e.from_node == memory_id) {
+            if let Some(entity) = Entity::from_node(&db.get_node(&edge.to_node).await?) {
+                entities.push(entity);
+            }
+        }
+        entities.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(entities)
+    }
+
+    async fn link_synonyms(
+        &self,
+        db: &VectorGraphDB,
+        entity_id: &str,
+        embedding: &[f32],
+        known: &[(String, Vec<f32>)],
+    ) -> Result<()> {
+        let now = Utc::now();
+        for (other_id, other_embedding) in known {
+            if other_id == entity_id || other_embedding.len() != embedding.len() {
+                continue;
+            }
+            let similarity = VectorUtils::cosine_similarity(embedding, other_embedding)?;
+            if similarity < self.config.synonym_threshold {
+                continue;
+            }
+
+            db.insert_edge(&GraphEdge {
+                id: edge_id("synonym", entity_id, other_id),
+                from_node: entity_id.to_string(),
+                to_node: other_id.clone(),
+                edge_type: SYNONYM_EDGE_TYPE.to_string(),
+                weight: similarity,
+                properties: HashMap::new(),
+                created_at: now,
+                updated_at: now,
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    fn most_similar(
+        &self,
+        embedding: &[f32],
+        known: &[(String, Vec<f32>)],
+    ) -> Result<Option<(String, f32)>> {
+        let mut best: Option<(String, f32)> = None;
+        for (id, other) in known {
+            if other.len() != embedding.len() {
+                continue;
+            }
+            let similarity = VectorUtils::cosine_similarity(embedding, other)?;
+            if similarity >= self.config.synonym_threshold
+                && best.as_ref().is_none_or(|(_, s)| similarity > *s)
+            {
+                best = Some((id.clone(), similarity));
+            }
+        }
+
+        Ok(best)
+    }
+
+    async fn entity_embeddings(db: &VectorGraphDB) -> Result<Vec<(String, Vec<f32>)>> {
+        Ok(db
+            .get_all_nodes(Some(ENTITY_NODE_TYPE))
+            .await?
+            .into_iter()
+            .filter_map(|node| {
+                let embedding = node
+                    .properties
+                    .get("embedding")
+                    .and_then(|v| serde_json::from_value::<Vec<f32>>(v.clone()).ok())?;
+                Some((node.id, embedding))
+            })
+            .collect())
+    }
+}
+
+/// 是否为实体图专用的边类型
+pub fn is_entity_edge(edge_type: &str) -> bool {
+    edge_type == MENTIONS_EDGE_TYPE || edge_type == SYNONYM_EDGE_TYPE
+}
+
+/// 归一化实体名称：去除首尾标点、合并空白并转为小写
+pub fn normalize_entity_name(name: &str) -> String {
+    name.trim_matches(|c: char| !c.is_alphanumeric())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn entity_node_id(normalized: &str) -> String {
+    format!("entity_{}", HashUtils::short_hash(normalized, 16))
+}
+
+fn edge_id(kind: &str, a: &str, b: &str) -> String {
+    // 同义边与方向无关，对端点排序以保证ID唯一
+    let key = if kind == "synonym" && b < a {
+        format!("{}|{}", b, a)
+    } else {
+        format!("{}|{}", a, b)
+    };
+    format!("{}_{}", kind, HashUtils::short_hash(&key, 16))
+}
+
+fn dedup_entities(entities: Vec<ExtractedEntity>) -> Vec<ExtractedEntity> {
+    let mut seen = HashSet::new();
+    entities
+        .into_iter()
+        .filter(|e| {
+            let normalized = e.normalized();
+            !normalized.is_empty() && seen.insert(normalized)
+        })
+        .collect()
+}
+
+fn is_not_found(error: &MemoryError) -> bool {
+    matches!(error, MemoryError::Database(sqlx::Error::RowNotFound))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ScriptedLlmProvider;
+
+    async fn create_db() -> VectorGraphDB {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        VectorGraphDB::new(config).await.unwrap()
+    }
+
+    async fn add_memory(db: &VectorGraphDB, id: &str) {
+        db.insert_node(&GraphNode {
+            id: id.to_string(),
+            node_type: "memory".to_string(),
+            properties: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_keyword_extractor_rules() {
+        let extractor = KeywordEntityExtractor::default();
+        let entities = extractor.extract_entities(
+            "I met Alice Chen at the RustConf keynote. She recommended 《深入理解计算机系统》 #reading",
+        );
+        let names: Vec<&str> = entities.iter().map(|e| e.name.as_str()).collect();
+
+        assert!(names.contains(&"深入理解计算机系统"));
+        assert!(names.contains(&"reading"));
+        assert!(names.contains(&"Alice Chen"));
+        assert!(names.contains(&"RustConf"));
+        assert!(!names.contains(&"She"));
+        assert_eq!(normalize_entity_name("  Alice   Chen. "), "alice chen");
+    }
+
+    #[tokio::test]
+    async fn test_llm_extractor_parses_and_falls_back() {
+        let provider = ScriptedLlmProvider::new([
+            "实体如下：[{\"name\": \"Alice\", \"type\": \"person\"}, {\"name\": \"alice\"}]",
+        ]);
+        let extractor = LlmEntityExtractor::new(Arc::new(provider));
+        let entities = extractor.extract("whatever").await.unwrap();
+        assert_eq!(entities, vec![ExtractedEntity::new("Alice", "person")]);
+
+        let broken = LlmEntityExtractor::new(Arc::new(ScriptedLlmProvider::new(["no json"])));
+        let entities = broken.extract("#coffee time").await.unwrap();
+        assert!(entities
+            .iter()
+            .any(|e| e.name == "coffee" && e.entity_type == "tag"));
+    }
+
+    #[tokio::test]
+    async fn test_link_memories_through_entities() {
+        let db = create_db().await;
+        let linker = EntityLinker::new(&Config::default());
+        add_memory(&db, "m1").await;
+        add_memory(&db, "m2").await;
+
+        let alice = (
+            ExtractedEntity::new("Alice", "person"),
+            Some(vec![1.0, 0.0]),
+        );
+        let alice_chen = (
+            ExtractedEntity::new("Alice Chen", "person"),
+            Some(vec![0.95, 0.1]),
+        );
+        let book = (ExtractedEntity::new("Dune", "work"), Some(vec![0.0, 1.0]));
+
+        linker
+            .link_memory(&db, &"m1".to_string(), &[alice.clone(), book])
+            .await
+            .unwrap();
+        linker
+            .link_memory(&db, &"m2".to_string(), &[alice.clone(), alice_chen])
+            .await
+            .unwrap();
+        // 重复链接是幂等的
+        let linked = linker
+            .link_memory(&db, &"m2".to_string(), &[alice])
+            .await
+            .unwrap();
+        assert_eq!(linked[0].mention_count, 2);
+
+        let entities = linker
+            .memory_entities(&db, &"m2".to_string())
+            .await
+            .unwrap();
+        assert_eq!(entities.len(), 2);
+
+        let synonyms = db
+            .get_all_edges(&Some(vec![SYNONYM_EDGE_TYPE.to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(synonyms.len(), 1);
+        let names = linker
+            .synonyms(&db, &ExtractedEntity::new("alice", "person"))
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["Alice Chen"]);
+
+        // 查询实体种子经由实体节点把两条记忆连接起来
+        let query = (ExtractedEntity::new("alice chen.", "person"), None);
+        let seeds = linker.query_seeds(&db, &[query]).await.unwrap();
+        assert_eq!(seeds.len(), 1);
+        let scores = db.personalized_pagerank(&seeds).await.unwrap();
+        assert!(scores.get("m2").copied().unwrap_or(0.0) > 0.0);
+        assert!(scores.get("m1").copied().unwrap_or(0.0) > 0.0);
+    }
+}
@@ -82,6 +82,10 @@ pub enum MemoryError {
     #[error("Operation timed out: {operation}")]
     Timeout { operation: String },
 
+    /// 近重复记忆错误
+    #[error("Memory duplicates existing memory '{id}'")]
+    DuplicateMemory { id: String },
+
     /// 验证错误
     #[error("Validation error: {message}")]
     ValidationError { message: String },
@@ -90,6 +94,10 @@ pub enum MemoryError {
     #[error("Permission denied: {message}")]
     PermissionDenied { message: String },
 
+    /// 大语言模型调用错误
+    #[error("LLM error: {message}")]
+    Llm { message: String },
+
     /// 内部错误
     #[error("Internal error: {message}")]
     Internal { message: String },
@@ -105,6 +113,11 @@ impl MemoryError {
         Self::MemoryNotFound { id: id.into() }
     }
 
+    /// 创建近重复记忆错误
+    pub fn duplicate_memory(id: impl Into<String>) -> Self {
+        Self::DuplicateMemory { id: id.into() }
+    }
+
     /// 创建无效向量维度错误
     pub fn invalid_vector_dimension(expected: usize, actual: usize) -> Self {
         Self::InvalidVectorDimension { expected, actual }
@@ -207,6 +220,13 @@ impl MemoryError {
         }
     }
 
+    /// 创建大语言模型错误
+    pub fn llm_error(message: impl Into<String>) -> Self {
+        Self::Llm {
+            message: message.into(),
+        }
+    }
+
     /// 创建内部错误
     pub fn internal(message: impl Into<String>) -> Self {
         Self::Internal {
@@ -229,17 +249,28 @@ impl MemoryError {
         matches!(self, Self::MemoryNotFound { .. })
     }
 
+    /// 检查是否为近重复记忆错误
+    pub fn is_duplicate_memory(&self) -> bool {
+        matches!(self, Self::DuplicateMemory { .. })
+    }
+
     /// 检查是否为超时错误
     pub fn is_timeout(&self) -> bool {
         matches!(self, Self::Timeout { .. })
     }
 
+    /// 检查是否为大语言模型错误
+    pub fn is_llm_error(&self) -> bool {
+        matches!(self, Self::Llm { .. })
+    }
+
     /// 获取错误的严重程度
     pub fn severity(&self) -> ErrorSeverity {
         match self {
             Self::Database(_) | Self::Io(_) => ErrorSeverity::Critical,
             Self::MemoryNotFound { .. }
             | Self::InvalidQuery { .. }
+            | Self::DuplicateMemory { .. }
             | Self::ValidationError { .. } => ErrorSeverity::Warning,
             Self::Timeout { .. } | Self::InsufficientResources { .. } => ErrorSeverity::Error,
             Self::Internal { .. } => ErrorSeverity::Critical,
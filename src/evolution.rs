@@ -0,0 +1,406 @@
+//! 记忆演化模块
+//!
+//! A-Mem风格的写入时演化：新记忆到来时检查它的语义近邻，决定加强或新增连接，
+//! 并更新近邻的上下文描述和标签，让记忆网络随时间变得更有条理，而不只是变大。
+
+use crate::config::{Config, EvolutionConfig};
+use crate::core::{Memory, MemoryId};
+use crate::error::Result;
+use crate::llm::{LlmProvider, LlmTaskRunner};
+use crate::llm_tasks::MemoryEvolutionTask;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// 规则演化器生成的上下文描述前缀，带此前缀的上下文会随新记忆更新
+pub const CONTEXT_TOPICS_PREFIX: &str = "相关主题：";
+
+/// 上下文描述中最多列出的主题数
+const MAX_CONTEXT_TOPICS: usize = 8;
+
+/// 参与演化的近邻记忆
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvolutionNeighbour {
+    pub memory: Memory,
+    /// 与新记忆的嵌入相似度
+    pub similarity: f32,
+    /// 与新记忆之间已有连接的强度
+    pub link_strength: Option<f32>,
+}
+
+/// 演化请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvolutionRequest {
+    /// 新记忆
+    pub memory: Memory,
+    pub neighbours: Vec<EvolutionNeighbour>,
+}
+
+/// 对一个近邻的演化决定
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct NeighbourEvolution {
+    pub memory_id: MemoryId,
+    /// 与新记忆之间连接的目标强度，为空时不调整连接
+    #[serde(default)]
+    pub link_strength: Option<f32>,
+    /// 要添加的标签
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    /// 新的上下文描述，为空时保持不变
+    #[serde(default)]
+    pub context: Option<String>,
+}
+
+/// 演化结果
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct EvolutionResponse {
+    #[serde(default)]
+    pub evolutions: Vec<NeighbourEvolution>,
+}
+
+/// 记忆演化决策器
+#[async_trait]
+pub trait EvolutionAdvisor: Send + Sync + std::fmt::Debug {
+    /// 决策器名称，记录在更新历史中
+    fn name(&self) -> &str;
+
+    /// 根据新记忆和近邻决定如何更新近邻
+    async fn advise(&self, request: &EvolutionRequest) -> Result<EvolutionResponse>;
+}
+
+/// 基于规则的演化决策器
+///
+/// 缺少连接时按相似度新增连接，已有连接在主题一致时加强；相似度足够高且共享
+/// 关键词或标签的近邻继承新记忆的标签；空白或由本决策器生成的上下文描述
+/// 会补充共享的主题词。
+#[derive(Debug, Clone)]
+pub struct RuleEvolutionAdvisor {
+    tag_similarity: f32,
+    max_tags: usize,
+    link_reinforcement: f32,
+}
+
+impl RuleEvolutionAdvisor {
+    pub fn new(config: &Config) -> Self {
+        let EvolutionConfig {
+            tag_similarity,
+            max_tags,
+            link_reinforcement,
+            ..
+        } = config.evolution;
+
+        Self {
+            tag_similarity,
+            max_tags,
+            link_reinforcement,
+        }
+    }
+
+    /// 新记忆与近邻共享的关键词和标签，按新记忆中的顺序
+    fn shared_terms(memory: &Memory, neighbour: &Memory) -> Vec<String> {
+        let theirs: HashSet<String> = neighbour
+            .attributes
+            .keywords
+            .iter()
+            .chain(&neighbour.attributes.tags)
+            .map(|t| t.to_lowercase())
+            .collect();
+        let mut seen = HashSet::new();
+        memory
+            .attributes
+            .keywords
+            .iter()
+            .chain(&memory.attributes.tags)
+            .filter(|t| theirs.contains(&t.to_lowercase()) && seen.insert(t.to_lowercase()))
+            .cloned()
+            .collect()
+    }
+
+    fn evolve_neighbour(
+        &self,
+        memory: &Memory,
+        neighbour: &EvolutionNeighbour,
+    ) -> NeighbourEvolution {
+        let other = &neighbour.memory;
+        let similarity = neighbour.similarity.clamp(0.0, 1.0);
+        let shared = Self::shared_terms(memory, other);
+
+        let link_strength = match neighbour.link_strength {
+            None => Some(similarity),
+            Some(current) => {
+                let mut strength = current.max(similarity);
+                if !shared.is_empty() {
+                    strength += self.link_reinforcement * similarity;
+                }
+                let strength = strength.min(1.0);
+                (strength > current + 1e-6).then_some(strength)
+            }
+        };
+
+        let mut add_tags = Vec::new();
+        if similarity >= self.tag_similarity && !shared.is_empty() {
+            let room = self.max_tags.saturating_sub(other.attributes.tags.len());
+            add_tags = memory
+                .attributes
+                .tags
+                .iter()
+                .filter(|tag| !other.attributes.tags.contains(tag))
+                .take(room)
+                .cloned()
+                .collect();
+        }
+
+        let current = &other.attributes.context;
+        let context = if shared.is_empty() {
+            None
+        } else if current.is_empty() || current.starts_with(CONTEXT_TOPICS_PREFIX) {
+            let mut topics: Vec<String> = current
+                .strip_prefix(CONTEXT_TOPICS_PREFIX)
+                .map(|listed| listed.split('、').map(str::to_string).collect())
+                .unwrap_or_default();
+            for term in shared {
+                if !topics.contains(&term) {
+                    topics.push(term);
+                }
+            }
+            topics.retain(|t| !t.is_empty());
+            topics.truncate(MAX_CONTEXT_TOPICS);
+            let context = format!("{}{}", CONTEXT_TOPICS_PREFIX, topics.join("、"));
+            (&context != current).then_some(context)
+        } else {
+            None
+        };
+
+        NeighbourEvolution {
+            memory_id: other.id.clone(),
+            link_strength,
+            add_tags,
+            context,
+        }
+    }
+}
+
+#[async_trait]
+impl EvolutionAdvisor for RuleEvolutionAdvisor {
+    fn name(&self) -> &str {
+        "rule"
+    }
+
+    async fn advise(&self, request: &EvolutionRequest) -> Result<EvolutionResponse> {
+        Ok(EvolutionResponse {
+            evolutions: request
+                .neighbours
+                .iter()
+                .map(|neighbour| self.evolve_neighbour(&request.memory, neighbour))
+                .collect(),
+        })
+    }
+}
+
+/// 基于大语言模型的演化决策器
+///
+/// 通过 [`MemoryEvolutionTask`] 调用模型，失败时回退到规则决策器。
+#[derive(Debug, Clone)]
+pub struct LlmEvolutionAdvisor {
+    runner: LlmTaskRunner,
+    task: MemoryEvolutionTask,
+}
+
+impl LlmEvolutionAdvisor {
+    pub fn new(provider: Arc<dyn LlmProvider>, config: &Config) -> Self {
+        Self {
+            runner: LlmTaskRunner::new(provider, &config.llm),
+            task: MemoryEvolutionTask::new(config),
+        }
+    }
+}
+
+#[async_trait]
+impl EvolutionAdvisor for LlmEvolutionAdvisor {
+    fn name(&self) -> &str {
+        self.runner.provider().name()
+    }
+
+    async fn advise(&self, request: &EvolutionRequest) -> Result<EvolutionResponse> {
+        self.runner.run(&self.task, request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemoryAttributes, MemoryType};
+    use crate::llm::ScriptedLlmProvider;
+
+    fn memory(tags: &[&str], keywords: &[&str], context: &str) -> Memory {
+        Memory::new(
+            "记忆".to_string(),
+            MemoryType::Knowledge,
+            vec![1.0, 0.0],
+            MemoryAttributes {
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                keywords: keywords.iter().map(|k| k.to_string()).collect(),
+                context: context.to_string(),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rule_evolution() {
+        let advisor = RuleEvolutionAdvisor::new(&Config::default());
+        let new = memory(&["学习", "编程"], &["rust", "所有权"], "");
+        let neighbours = vec![
+            // 高相似度、共享关键词：继承标签并补充上下文
+            EvolutionNeighbour {
+                memory: memory(&["学习"], &["rust"], ""),
+                similarity: 0.9,
+                link_strength: None,
+            },
+            // 已有连接且主题一致：加强连接，保留用户写的上下文
+            EvolutionNeighbour {
+                memory: memory(&[], &["所有权"], "读书笔记"),
+                similarity: 0.75,
+                link_strength: Some(0.75),
+            },
+            // 没有共享主题的已有连接不变
+            EvolutionNeighbour {
+                memory: memory(&["生活"], &["咖啡"], ""),
+                similarity: 0.72,
+                link_strength: Some(0.8),
+            },
+        ];
+        let request = EvolutionRequest {
+            memory: new,
+            neighbours,
+        };
+        let evolutions = advisor.advise(&request).await.unwrap().evolutions;
+
+        assert_eq!(evolutions[0].link_strength, Some(0.9));
+        assert_eq!(evolutions[0].add_tags, vec!["编程"]);
+        assert_eq!(
+            evolutions[0].context.as_deref(),
+            Some("相关主题：rust、学习")
+        );
+
+        assert!((evolutions[1].link_strength.unwrap() - 0.825).abs() < 1e-6);
+        assert!(evolutions[1].add_tags.is_empty());
+        assert_eq!(evolutions[1].context, None);
+
+        assert_eq!(
+            evolutions[2],
+            NeighbourEvolution {
+                memory_id: request.neighbours[2].memory.id.clone(),
+                ..Default::default()
+            }
+        );
+    }
+
+    fn neighbour(
+        memory: Memory,
+        similarity: f32,
+        link_strength: Option<f32>,
+    ) -> EvolutionNeighbour {
+        EvolutionNeighbour {
+            memory,
+            similarity,
+            link_strength,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rule_evolution_boundaries() {
+        let advisor = RuleEvolutionAdvisor::new(&Config::default());
+        let many_tags: Vec<String> = (0..9).map(|i| format!("t{}", i)).collect();
+        let many_tags: Vec<&str> = many_tags.iter().map(String::as_str).collect();
+        let request = EvolutionRequest {
+            memory: memory(&["学习", "编程", "rust"], &["Rust", "所有权"], ""),
+            neighbours: vec![
+                // 相似度低于阈值不继承标签；共享词不区分大小写且只列一次
+                neighbour(memory(&["学习"], &["rust"], ""), 0.84, None),
+                // 正好达到阈值时继承标签，但不超过标签上限
+                neighbour(memory(&many_tags, &["所有权"], ""), 0.85, None),
+                // 超出范围的相似度被截断，加强后的强度不超过1
+                neighbour(memory(&[], &["所有权"], ""), 1.5, Some(0.95)),
+                neighbour(memory(&[], &["所有权"], ""), 0.9, Some(1.0)),
+                neighbour(memory(&[], &[], ""), -0.2, None),
+                // 已列出的主题和已满的主题列表不再改写
+                neighbour(
+                    memory(&[], &["rust"], "相关主题：Rust、学习"),
+                    0.8,
+                    Some(0.8),
+                ),
+                neighbour(
+                    memory(&[], &["所有权"], "相关主题：a、b、c、d、e、f、g、h"),
+                    0.8,
+                    Some(0.8),
+                ),
+            ],
+        };
+        let evolutions = advisor.advise(&request).await.unwrap().evolutions;
+        assert_eq!(evolutions.len(), request.neighbours.len());
+
+        assert_eq!(evolutions[0].link_strength, Some(0.84));
+        assert!(evolutions[0].add_tags.is_empty());
+        assert_eq!(
+            evolutions[0].context.as_deref(),
+            Some("相关主题：Rust、学习")
+        );
+
+        assert_eq!(evolutions[1].add_tags, vec!["学习"]);
+        assert_eq!(evolutions[1].context.as_deref(), Some("相关主题：所有权"));
+
+        assert_eq!(evolutions[2].link_strength, Some(1.0));
+        assert_eq!(evolutions[3].link_strength, None);
+        assert_eq!(evolutions[4].link_strength, Some(0.0));
+        assert_eq!(evolutions[4].context, None);
+
+        assert_eq!(evolutions[5].context, None);
+        assert_eq!(evolutions[6].context, None);
+
+        let empty = EvolutionRequest {
+            memory: request.memory.clone(),
+            neighbours: Vec::new(),
+        };
+        assert!(advisor.advise(&empty).await.unwrap().evolutions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_llm_evolution_post_processes_and_falls_back() {
+        let mut config = Config::default();
+        config.llm.retry_backoff_ms = 0;
+        let request = EvolutionRequest {
+            memory: memory(&["编程"], &["rust"], ""),
+            neighbours: vec![neighbour(memory(&[], &["rust"], ""), 0.9, None)],
+        };
+        let id = &request.neighbours[0].memory.id;
+
+        // 模型输出的强度被截断，标签去空白去重，空白上下文视为不改写
+        let provider = ScriptedLlmProvider::new([format!(
+            r#"{{"evolutions": [{{"memory_id": "{}", "link_strength": 1.4,
+                "add_tags": [" 编程 ", "编程", ""], "context": "  "}}]}}"#,
+            id
+        )]);
+        let advisor = LlmEvolutionAdvisor::new(Arc::new(provider), &config);
+        let evolutions = advisor.advise(&request).await.unwrap().evolutions;
+        assert_eq!(
+            evolutions,
+            vec![NeighbourEvolution {
+                memory_id: id.clone(),
+                link_strength: Some(1.0),
+                add_tags: vec!["编程".to_string()],
+                context: None,
+            }]
+        );
+
+        // 输出无效时回退到规则决策器
+        let broken =
+            LlmEvolutionAdvisor::new(Arc::new(ScriptedLlmProvider::new(["not json"])), &config);
+        let rule = RuleEvolutionAdvisor::new(&config);
+        assert_eq!(
+            broken.advise(&request).await.unwrap(),
+            rule.advise(&request).await.unwrap()
+        );
+    }
+}
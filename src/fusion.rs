@@ -0,0 +1,325 @@
+//! 结果融合模块
+//!
+//! 把多个检索器的候选列表融合为一个排序。各检索器的原始分数尺度不同
+//! （余弦相似度、图遍历位置分、PageRank等），分数融合前先按检索器分别归一化；
+//! 也可以只看排名，用倒数排名融合（RRF）避开分数尺度问题。
+
+use crate::config::{Config, FusionConfig, ScoreNormalization};
+use crate::core::MemoryId;
+use crate::memory::RetrievalResult;
+use crate::retrieval::{FusionMethod, ScoreBreakdown};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 候选列表对应的分数分量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreComponent {
+    Semantic,
+    Temporal,
+    Structural,
+    Importance,
+    Personalization,
+}
+
+/// 一个检索器的候选列表
+#[derive(Debug, Clone)]
+pub struct RankedList {
+    pub component: ScoreComponent,
+    /// 融合权重
+    pub weight: f32,
+    /// 按相关性降序排列的候选
+    pub results: Vec<RetrievalResult>,
+}
+
+impl ScoreComponent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoreComponent::Semantic => "semantic",
+            ScoreComponent::Temporal => "temporal",
+            ScoreComponent::Structural => "structural",
+            ScoreComponent::Importance => "importance",
+            ScoreComponent::Personalization => "personalization",
+        }
+    }
+}
+
+impl RankedList {
+    pub fn new(component: ScoreComponent, weight: f32, results: Vec<RetrievalResult>) -> Self {
+        Self {
+            component,
+            weight,
+            results,
+        }
+    }
+}
+
+/// 融合结果及其分数分解
+#[derive(Debug, Clone)]
+pub struct FusedResult {
+    pub result: RetrievalResult,
+    /// 各分量为对应检索器归一化后的分数，`final_score` 为融合分数
+    pub breakdown: ScoreBreakdown,
+}
+
+/// 结果融合器
+#[derive(Debug, Clone)]
+pub struct ResultFuser {
+    rrf_k: f32,
+    normalization: ScoreNormalization,
+}
+
+/// 融合过程中一个记忆的累积状态
+struct Accumulator {
+    result: RetrievalResult,
+    breakdown: ScoreBreakdown,
+    score: f32,
+    hits: usize,
+}
+
+impl ResultFuser {
+    pub fn new(config: &Config) -> Self {
+        let FusionConfig {
+            rrf_k,
+            normalization,
+        } = config.retrieval.fusion;
+
+        Self {
+            rrf_k: rrf_k.max(0.0),
+            normalization,
+        }
+    }
+
+    /// 按指定方式归一化一组分数
+    pub fn normalize(scores: &[f32], normalization: ScoreNormalization) -> Vec<f32> {
+        match normalization {
+            ScoreNormalization::None => scores.to_vec(),
+            ScoreNormalization::MinMax => {
+                let min = scores.iter().copied().fold(f32::INFINITY, f32::min);
+                let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                let range = max - min;
+                scores
+                    .iter()
+                    .map(|s| {
+                        if range > f32::EPSILON {
+                            (s - min) / range
+                        } else {
+                            1.0
+                        }
+                    })
+                    .collect()
+            }
+            ScoreNormalization::ZScore => {
+                let n = scores.len().max(1) as f32;
+                let mean = scores.iter().sum::<f32>() / n;
+                let std = (scores.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / n).sqrt();
+                scores
+                    .iter()
+                    .map(|s| {
+                        let z = if std > f32::EPSILON {
+                            (s - mean) / std
+                        } else {
+                            0.0
+                        };
+                        1.0 / (1.0 + (-z).exp())
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// 融合多个候选列表，结果按融合分数降序排列
+    ///
+    /// 分数融合（线性加权、CombSUM、CombMNZ）使用配置的归一化方式，RRF只使用排名；
+    /// 融合分数再乘以 `normalization_factor` 缩放到[0, 1]。学习融合和动态融合
+    /// 在这里按线性加权处理，动态融合的权重由检索计划给出。
+    pub fn fuse(&self, lists: Vec<RankedList>, method: &FusionMethod) -> Vec<FusedResult> {
+        let mut fused: HashMap<MemoryId, Accumulator> = HashMap::new();
+        let mut max_score = 0.0;
+
+        for list in lists {
+            let weight = list.weight.max(0.0);
+            max_score += match method {
+                FusionMethod::RankFusion => weight / (self.rrf_k + 1.0),
+                FusionMethod::CombSum | FusionMethod::CombMnz => 1.0,
+                _ => weight,
+            };
+
+            let raw: Vec<f32> = list.results.iter().map(|r| r.relevance_score).collect();
+            let normalized = Self::normalize(&raw, self.normalization);
+            for (rank, (result, score)) in list.results.into_iter().zip(normalized).enumerate() {
+                let contribution = match method {
+                    FusionMethod::RankFusion => weight / (self.rrf_k + rank as f32 + 1.0),
+                    FusionMethod::CombSum | FusionMethod::CombMnz => score,
+                    _ => weight * score,
+                };
+
+                let entry = fused
+                    .entry(result.memory.id.clone())
+                    .or_insert_with(|| Accumulator {
+                        breakdown: ScoreBreakdown::default(),
+                        score: 0.0,
+                        hits: 0,
+                        result: result.clone(),
+                    });
+                entry.score += contribution;
+                entry.hits += 1;
+                let component = component_score(&mut entry.breakdown, list.component);
+                *component = component.max(score);
+                merge_explanation(&mut entry.result, &result);
+            }
+        }
+
+        // CombMNZ按命中列表数放大，最大值相应为列表数的平方
+        if let FusionMethod::CombMnz = method {
+            max_score *= max_score;
+        }
+        let factor = if max_score > 0.0 {
+            1.0 / max_score
+        } else {
+            1.0
+        };
+
+        let mut results: Vec<FusedResult> = fused
+            .into_values()
+            .map(|mut acc| {
+                if let FusionMethod::CombMnz = method {
+                    acc.score *= acc.hits as f32;
+                }
+                let final_score = acc.score * factor;
+                acc.result.relevance_score = final_score;
+                acc.breakdown.final_score = final_score;
+                acc.breakdown.normalization_factor = factor;
+                let b = &acc.breakdown;
+                acc.result.explanation.reasoning = format!(
+                    "{:?}: semantic={:.3}, temporal={:.3}, structural={:.3}, importance={:.3}, \
+                     personalization={:.3}, fused={:.3}",
+                    method,
+                    b.semantic_score,
+                    b.temporal_score,
+                    b.structural_score,
+                    b.importance_score,
+                    b.personalization_score,
+                    final_score
+                );
+                FusedResult {
+                    result: acc.result,
+                    breakdown: acc.breakdown,
+                }
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            b.breakdown
+                .final_score
+                .total_cmp(&a.breakdown.final_score)
+                .then_with(|| a.result.memory.id.cmp(&b.result.memory.id))
+        });
+        results
+    }
+}
+
+fn component_score(breakdown: &mut ScoreBreakdown, component: ScoreComponent) -> &mut f32 {
+    match component {
+        ScoreComponent::Semantic => &mut breakdown.semantic_score,
+        ScoreComponent::Temporal => &mut breakdown.temporal_score,
+        ScoreComponent::Structural => &mut breakdown.structural_score,
+        ScoreComponent::Importance => &mut breakdown.importance_score,
+        ScoreComponent::Personalization => &mut breakdown.personalization_score,
+    }
+}
+
+/// 合并同一记忆在不同列表中的解释，各分数取较大值，连接路径去重合并
+fn merge_explanation(target: &mut RetrievalResult, other: &RetrievalResult) {
+    let (target, other) = (&mut target.explanation, &other.explanation);
+    target.semantic_score = target.semantic_score.max(other.semantic_score);
+    target.temporal_score = target.temporal_score.max(other.temporal_score);
+    target.importance_score = target.importance_score.max(other.importance_score);
+    target.personalization_score = target
+        .personalization_score
+        .max(other.personalization_score);
+    for path in &other.connection_paths {
+        if !target.connection_paths.contains(path) {
+            target.connection_paths.push(path.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lists() -> Vec<RankedList> {
+        vec![
+            // 余弦相似度
+            RankedList::new(
+                ScoreComponent::Semantic,
+                0.5,
+                vec![
+                    RetrievalResult::for_test("a", 0.92),
+                    RetrievalResult::for_test("b", 0.90),
+                    RetrievalResult::for_test("c", 0.60),
+                ],
+            ),
+            // 图遍历位置分
+            RankedList::new(
+                ScoreComponent::Structural,
+                0.5,
+                vec![
+                    RetrievalResult::for_test("b", 1.0),
+                    RetrievalResult::for_test("c", 0.5),
+                    RetrievalResult::for_test("d", 1.0 / 3.0),
+                ],
+            ),
+        ]
+    }
+
+    fn ids(results: &[FusedResult]) -> Vec<&str> {
+        results
+            .iter()
+            .map(|r| r.result.memory.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_normalization() {
+        let scores = [0.6, 0.9, 0.75];
+        assert_eq!(
+            ResultFuser::normalize(&scores, ScoreNormalization::MinMax),
+            vec![0.0, 1.0, 0.5]
+        );
+        assert_eq!(
+            ResultFuser::normalize(&[0.3], ScoreNormalization::MinMax),
+            vec![1.0]
+        );
+        let z = ResultFuser::normalize(&scores, ScoreNormalization::ZScore);
+        assert!(z[0] < 0.5 && z[1] > 0.5 && (z[2] - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fusion_methods() {
+        let fuser = ResultFuser::new(&Config::default());
+
+        // 线性加权：各检索器分数先归一化，且不再叠加在第一个结果的原始分数上
+        let linear = fuser.fuse(lists(), &FusionMethod::LinearWeighted);
+        assert_eq!(ids(&linear), vec!["b", "a", "c", "d"]);
+        let b = &linear[0].breakdown;
+        assert!((b.semantic_score - 0.9375).abs() < 1e-4);
+        assert_eq!(b.structural_score, 1.0);
+        assert!((b.final_score - 0.96875).abs() < 1e-4);
+        assert_eq!(linear[0].result.relevance_score, b.final_score);
+
+        // RRF只看排名，同时出现在两个列表中的c排在只出现一次的a之前
+        let rrf = fuser.fuse(lists(), &FusionMethod::RankFusion);
+        assert_eq!(ids(&rrf), vec!["b", "c", "a", "d"]);
+        let expected = (1.0 / 62.0 + 1.0 / 61.0) / (2.0 / 61.0);
+        assert!((rrf[0].breakdown.final_score - expected).abs() < 1e-5);
+
+        // CombMNZ按命中的检索器数放大，只被一个检索器召回的a被压低
+        let sum = fuser.fuse(lists(), &FusionMethod::CombSum);
+        assert_eq!(ids(&sum), vec!["b", "a", "c", "d"]);
+        assert!((sum[1].breakdown.final_score - 0.5).abs() < 1e-6);
+        let mnz = fuser.fuse(lists(), &FusionMethod::CombMnz);
+        assert!((mnz[0].breakdown.final_score - 0.96875).abs() < 1e-6);
+        assert!((mnz[1].breakdown.final_score - 0.25).abs() < 1e-6);
+    }
+}
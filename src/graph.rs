@@ -0,0 +1,1140 @@
+//! 图分析模块
+//!
+//! 在记忆图上进行社区检测与主题聚类：基于图边的标签传播、基于嵌入向量的
+//! K-means聚类，以及两者结合的混合模式。检测结果以社区形式持久化，
+//! 可用作检索过滤条件和主题连接的来源。
+//!
+//! 另外提供图导出功能，可将整个图或指定记忆周围的k跳子图导出为
+//! GraphML、Graphviz DOT或JSON node-link格式，便于在Gephi等工具中查看。
+
+use crate::config::{CommunityConfig, CommunityMethod, Config};
+use crate::core::MemoryId;
+use crate::database::{Community, GraphEdge, GraphNode, GraphQueryRequest, VectorGraphDB};
+use crate::error::{MemoryError, Result};
+use crate::segment::Segmenter;
+use crate::utils::{HashUtils, VectorUtils};
+use chrono::Utc;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 社区ID在记忆自定义属性中的键名
+pub const COMMUNITY_ATTRIBUTE: &str = "community_id";
+
+/// 社区检测器
+///
+/// 从数据库加载记忆节点和边，划分主题社区并生成社区标签。
+#[derive(Debug, Clone)]
+pub struct CommunityDetector {
+    /// 社区检测配置
+    config: CommunityConfig,
+    /// 混合模式下归并孤立节点的相似度阈值
+    similarity_threshold: f32,
+    /// 提取社区关键词的分词器
+    segmenter: Arc<Segmenter>,
+}
+
+/// 参与社区检测的记忆节点
+#[derive(Debug, Clone)]
+struct MemoryPoint {
+    id: MemoryId,
+    content: String,
+    keywords: Vec<String>,
+    embedding: Vec<f32>,
+}
+
+impl CommunityDetector {
+    /// 创建新的社区检测器
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.graph.community.clone(),
+            similarity_threshold: config.vector.similarity_threshold,
+            segmenter: Segmenter::shared(),
+        }
+    }
+
+    /// 使用指定的分词器提取社区关键词
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.segmenter = segmenter;
+        self
+    }
+
+    /// 检测社区
+    pub async fn detect(&self, db: &VectorGraphDB) -> Result<Vec<Community>> {
+        let points: Vec<MemoryPoint> = db
+            .get_all_nodes(Some("memory"))
+            .await?
+            .iter()
+            .filter(|node| {
+                !node
+                    .properties
+                    .get("is_deleted")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false)
+            })
+            .map(Self::node_to_point)
+            .collect();
+
+        if points.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids: HashSet<&str> = points.iter().map(|p| p.id.as_str()).collect();
+        let edges: Vec<(MemoryId, MemoryId, f32)> = db
+            .get_all_edges(&None)
+            .await?
+            .into_iter()
+            .filter(|e| {
+                e.from_node != e.to_node
+                    && ids.contains(e.from_node.as_str())
+                    && ids.contains(e.to_node.as_str())
+            })
+            .map(|e| (e.from_node, e.to_node, e.weight))
+            .collect();
+
+        debug!(
+            "Detecting communities over {} memories and {} edges ({:?})",
+            points.len(),
+            edges.len(),
+            self.config.method
+        );
+
+        let groups = match self.config.method {
+            CommunityMethod::LabelPropagation => {
+                let node_ids: Vec<MemoryId> = points.iter().map(|p| p.id.clone()).collect();
+                Self::group_by_label(&self.label_propagation(&node_ids, &edges))
+            }
+            CommunityMethod::KMeans => {
+                let clusters = self.kmeans(&points, self.cluster_count(points.len()));
+                Self::group_by_label(&clusters)
+            }
+            CommunityMethod::Hybrid => self.hybrid(&points, &edges),
+        };
+
+        let by_id: HashMap<&str, &MemoryPoint> =
+            points.iter().map(|p| (p.id.as_str(), p)).collect();
+        let mut communities: Vec<Community> = groups
+            .into_iter()
+            .filter(|members| members.len() >= self.config.min_community_size.max(1))
+            .map(|members| {
+                let members: Vec<&MemoryPoint> = members
+                    .iter()
+                    .filter_map(|id| by_id.get(id.as_str()).copied())
+                    .collect();
+                self.build_community(&members)
+            })
+            .collect();
+
+        communities.sort_by(|a, b| {
+            b.members
+                .len()
+                .cmp(&a.members.len())
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        info!("Detected {} communities", communities.len());
+        Ok(communities)
+    }
+
+    /// 持久化社区划分，并把成员关系写入记忆的自定义属性
+    ///
+    /// 返回成员关系发生变化的记忆数量。
+    pub async fn store(&self, db: &VectorGraphDB, communities: &[Community]) -> Result<usize> {
+        db.save_communities(communities).await?;
+
+        let membership: HashMap<&str, &str> = communities
+            .iter()
+            .flat_map(|c| c.members.iter().map(move |m| (m.as_str(), c.id.as_str())))
+            .collect();
+
+        let mut changed = 0;
+        for mut node in db.get_all_nodes(Some("memory")).await? {
+            let community_id = membership.get(node.id.as_str()).copied();
+            if !Self::set_membership(&mut node.properties, community_id) {
+                continue;
+            }
+            db.update_node(&node).await?;
+
+            if let Ok(mut vector) = db.get_vector(&node.id).await {
+                Self::set_membership(&mut vector.metadata, community_id);
+                db.update_vector(&vector).await?;
+            }
+            changed += 1;
+        }
+
+        Ok(changed)
+    }
+
+    /// 加权标签传播
+    ///
+    /// 节点按ID顺序异步更新，选择邻居中权重和最大的标签，平局取字典序最小者，
+    /// 因此结果是确定的。
+    fn label_propagation(
+        &self,
+        nodes: &[MemoryId],
+        edges: &[(MemoryId, MemoryId, f32)],
+    ) -> HashMap<MemoryId, String> {
+        let mut adjacency: HashMap<&str, Vec<(&str, f32)>> = HashMap::new();
+        for (from, to, weight) in edges {
+            adjacency
+                .entry(from.as_str())
+                .or_default()
+                .push((to.as_str(), *weight));
+            adjacency
+                .entry(to.as_str())
+                .or_default()
+                .push((from.as_str(), *weight));
+        }
+
+        let mut order: Vec<&str> = nodes.iter().map(|n| n.as_str()).collect();
+        order.sort_unstable();
+        order.dedup();
+
+        let mut labels: HashMap<MemoryId, String> = order
+            .iter()
+            .map(|n| (n.to_string(), n.to_string()))
+            .collect();
+
+        for _ in 0..self.config.max_iterations.max(1) {
+            let mut changed = false;
+
+            for node in &order {
+                let Some(neighbors) = adjacency.get(node) else {
+                    continue;
+                };
+
+                let mut weights: BTreeMap<&str, f32> = BTreeMap::new();
+                for (neighbor, weight) in neighbors {
+                    if let Some(label) = labels.get(*neighbor) {
+                        *weights.entry(label.as_str()).or_insert(0.0) += weight.max(0.0);
+                    }
+                }
+
+                // BTreeMap按字典序遍历，严格大于保证平局时取最小标签
+                let mut best: Option<(&str, f32)> = None;
+                for (label, weight) in weights {
+                    if best.is_none_or(|(_, w)| weight > w) {
+                        best = Some((label, weight));
+                    }
+                }
+
+                if let Some((label, _)) = best {
+                    let label = label.to_string();
+                    if labels.get(*node) != Some(&label) {
+                        labels.insert(node.to_string(), label);
+                        changed = true;
+                    }
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        labels
+    }
+
+    /// 基于余弦相似度的K-means聚类（确定性最远点初始化）
+    fn kmeans(&self, points: &[MemoryPoint], k: usize) -> HashMap<MemoryId, String> {
+        let points: Vec<&MemoryPoint> = points.iter().filter(|p| !p.embedding.is_empty()).collect();
+        let mut assignment = HashMap::new();
+        if points.is_empty() {
+            return assignment;
+        }
+
+        let k = k.clamp(1, points.len());
+        let similarity = |a: &[f32], b: &[f32]| VectorUtils::cosine_similarity(a, b).unwrap_or(0.0);
+
+        // 最远点初始化：从第一个点开始，每次选择与已有中心最不相似的点
+        let mut centroids: Vec<Vec<f32>> = vec![points[0].embedding.clone()];
+        while centroids.len() < k {
+            let next = points
+                .iter()
+                .map(|p| {
+                    let closest = centroids
+                        .iter()
+                        .map(|c| similarity(&p.embedding, c))
+                        .fold(f32::MIN, f32::max);
+                    (p, closest)
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(p, _)| p.embedding.clone());
+            match next {
+                Some(embedding) => centroids.push(embedding),
+                None => break,
+            }
+        }
+
+        let mut clusters = vec![0usize; points.len()];
+        for _ in 0..self.config.max_iterations.max(1) {
+            let mut changed = false;
+            for (i, point) in points.iter().enumerate() {
+                let best = centroids
+                    .iter()
+                    .enumerate()
+                    .map(|(c, centroid)| (c, similarity(&point.embedding, centroid)))
+                    .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                    .map(|(c, _)| c)
+                    .unwrap_or(0);
+                if clusters[i] != best {
+                    clusters[i] = best;
+                    changed = true;
+                }
+            }
+
+            for (c, centroid) in centroids.iter_mut().enumerate() {
+                let members: Vec<Vec<f32>> = points
+                    .iter()
+                    .zip(&clusters)
+                    .filter(|(_, cluster)| **cluster == c)
+                    .map(|(p, _)| p.embedding.clone())
+                    .collect();
+                if let Ok(new_centroid) = VectorUtils::centroid(&members) {
+                    *centroid = new_centroid;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        for (point, cluster) in points.iter().zip(clusters) {
+            assignment.insert(point.id.clone(), format!("cluster_{}", cluster));
+        }
+        assignment
+    }
+
+    /// 混合模式：标签传播后，将过小社区的成员按嵌入相似度归入已有社区，
+    /// 剩余节点再用K-means聚类并只保留足够紧密的簇
+    fn hybrid(
+        &self,
+        points: &[MemoryPoint],
+        edges: &[(MemoryId, MemoryId, f32)],
+    ) -> Vec<Vec<MemoryId>> {
+        let node_ids: Vec<MemoryId> = points.iter().map(|p| p.id.clone()).collect();
+        let min_size = self.config.min_community_size.max(1);
+        let by_id: HashMap<&str, &MemoryPoint> =
+            points.iter().map(|p| (p.id.as_str(), p)).collect();
+
+        let (mut groups, outliers): (Vec<Vec<MemoryId>>, Vec<Vec<MemoryId>>) =
+            Self::group_by_label(&self.label_propagation(&node_ids, edges))
+                .into_iter()
+                .partition(|members| members.len() >= min_size);
+
+        let centroids: Vec<Option<Vec<f32>>> = groups
+            .iter()
+            .map(|members| {
+                let embeddings: Vec<Vec<f32>> = members
+                    .iter()
+                    .filter_map(|id| by_id.get(id.as_str()))
+                    .filter(|p| !p.embedding.is_empty())
+                    .map(|p| p.embedding.clone())
+                    .collect();
+                VectorUtils::centroid(&embeddings).ok()
+            })
+            .collect();
+
+        let mut remaining = Vec::new();
+        for id in outliers.into_iter().flatten() {
+            let Some(point) = by_id.get(id.as_str()) else {
+                continue;
+            };
+            let best = centroids
+                .iter()
+                .enumerate()
+                .filter_map(|(i, c)| {
+                    c.as_ref().map(|c| {
+                        (
+                            i,
+                            VectorUtils::cosine_similarity(&point.embedding, c).unwrap_or(0.0),
+                        )
+                    })
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+
+            match best {
+                Some((i, sim)) if sim >= self.similarity_threshold => groups[i].push(id),
+                _ => remaining.push((*point).clone()),
+            }
+        }
+
+        if remaining.len() >= min_size {
+            let clusters = self.kmeans(&remaining, self.cluster_count(remaining.len()));
+            let remaining_by_id: HashMap<&str, &MemoryPoint> =
+                remaining.iter().map(|p| (p.id.as_str(), p)).collect();
+            for members in Self::group_by_label(&clusters) {
+                let cluster: Vec<&MemoryPoint> = members
+                    .iter()
+                    .filter_map(|id| remaining_by_id.get(id.as_str()).copied())
+                    .collect();
+                if cluster.len() >= min_size
+                    && Self::cohesion(&cluster) >= self.similarity_threshold
+                {
+                    groups.push(members);
+                }
+            }
+        }
+
+        for members in &mut groups {
+            members.sort();
+        }
+        groups
+    }
+
+    /// 自动估计聚类数
+    fn cluster_count(&self, n: usize) -> usize {
+        if self.config.kmeans_clusters > 0 {
+            self.config.kmeans_clusters
+        } else {
+            ((n as f32 / 2.0).sqrt().ceil() as usize).max(1)
+        }
+    }
+
+    /// 构建社区：生成稳定ID、关键词标签和凝聚度
+    fn build_community(&self, members: &[&MemoryPoint]) -> Community {
+        let mut member_ids: Vec<MemoryId> = members.iter().map(|p| p.id.clone()).collect();
+        member_ids.sort();
+
+        let keywords = self.label_keywords(members, self.config.label_keywords.max(1));
+        let label = if keywords.is_empty() {
+            "未命名主题".to_string()
+        } else {
+            keywords.join(" / ")
+        };
+
+        Community {
+            id: format!(
+                "community_{}",
+                HashUtils::short_hash(&member_ids.join(","), 12)
+            ),
+            label,
+            keywords,
+            cohesion: Self::cohesion(members),
+            members: member_ids,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// 从成员关键词和内容中提取社区关键词
+    fn label_keywords(&self, members: &[&MemoryPoint], max_keywords: usize) -> Vec<String> {
+        let text = members
+            .iter()
+            .map(|p| format!("{} {}", p.keywords.join(" "), p.content))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut keywords = self.segmenter.extract_keywords(&text, max_keywords);
+        if keywords.len() < max_keywords {
+            // 文本中提取不足时，补充成员中最常见的关键词
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for keyword in members.iter().flat_map(|p| p.keywords.iter()) {
+                *counts.entry(keyword.as_str()).or_insert(0) += 1;
+            }
+            let mut frequent: Vec<(&str, usize)> = counts.into_iter().collect();
+            frequent.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+            for (keyword, _) in frequent {
+                if keywords.len() >= max_keywords {
+                    break;
+                }
+                if !keywords.iter().any(|k| k == keyword) {
+                    keywords.push(keyword.to_string());
+                }
+            }
+        }
+        keywords
+    }
+
+    /// 凝聚度：成员与社区中心的平均余弦相似度
+    fn cohesion(members: &[&MemoryPoint]) -> f32 {
+        let embeddings: Vec<Vec<f32>> = members
+            .iter()
+            .filter(|p| !p.embedding.is_empty())
+            .map(|p| p.embedding.clone())
+            .collect();
+        let Ok(centroid) = VectorUtils::centroid(&embeddings) else {
+            return 0.0;
+        };
+
+        let total: f32 = embeddings
+            .iter()
+            .map(|e| VectorUtils::cosine_similarity(e, &centroid).unwrap_or(0.0))
+            .sum();
+        (total / embeddings.len() as f32).clamp(0.0, 1.0)
+    }
+
+    /// 按标签分组
+    fn group_by_label(labels: &HashMap<MemoryId, String>) -> Vec<Vec<MemoryId>> {
+        let mut groups: BTreeMap<&str, Vec<MemoryId>> = BTreeMap::new();
+        for (id, label) in labels {
+            groups.entry(label.as_str()).or_default().push(id.clone());
+        }
+        groups
+            .into_values()
+            .map(|mut members| {
+                members.sort();
+                members
+            })
+            .collect()
+    }
+
+    /// 写入或清除记忆属性中的社区ID，返回是否发生变化
+    fn set_membership(properties: &mut HashMap<String, Value>, community_id: Option<&str>) -> bool {
+        let custom = properties
+            .entry("custom_attributes".to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        if !custom.is_object() {
+            *custom = Value::Object(serde_json::Map::new());
+        }
+        let Some(custom) = custom.as_object_mut() else {
+            return false;
+        };
+
+        match community_id {
+            Some(id) => {
+                if custom.get(COMMUNITY_ATTRIBUTE).and_then(|v| v.as_str()) == Some(id) {
+                    return false;
+                }
+                custom.insert(
+                    COMMUNITY_ATTRIBUTE.to_string(),
+                    Value::String(id.to_string()),
+                );
+                true
+            }
+            None => custom.remove(COMMUNITY_ATTRIBUTE).is_some(),
+        }
+    }
+
+    fn node_to_point(node: &GraphNode) -> MemoryPoint {
+        let strings = |key: &str| -> Vec<String> {
+            node.properties
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        MemoryPoint {
+            id: node.id.clone(),
+            content: node
+                .properties
+                .get("content")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            keywords: strings("keywords"),
+            embedding: node
+                .properties
+                .get("embedding")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_f64().map(|f| f as f32))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 图导出格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// GraphML（Gephi、yEd等工具可直接加载）
+    GraphML,
+    /// Graphviz DOT
+    Dot,
+    /// JSON node-link格式（与networkx/d3兼容）
+    JsonNodeLink,
+}
+
+impl ExportFormat {
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::GraphML => "graphml",
+            ExportFormat::Dot => "dot",
+            ExportFormat::JsonNodeLink => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = MemoryError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "graphml" => Ok(ExportFormat::GraphML),
+            "dot" | "graphviz" => Ok(ExportFormat::Dot),
+            "json" | "node-link" | "nodelink" => Ok(ExportFormat::JsonNodeLink),
+            other => Err(MemoryError::validation_error(format!(
+                "Unknown export format: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// 图导出范围
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ExportScope {
+    /// 整个图
+    Full,
+    /// 指定记忆周围的k跳子图
+    Neighborhood {
+        memory_ids: Vec<MemoryId>,
+        hops: usize,
+    },
+}
+
+/// 导出用的子图
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// 图导出器
+#[derive(Debug, Clone)]
+pub struct GraphExporter {
+    /// 节点标签的最大字符数
+    label_length: usize,
+}
+
+impl Default for GraphExporter {
+    fn default() -> Self {
+        Self { label_length: 40 }
+    }
+}
+
+impl GraphExporter {
+    /// 创建新的图导出器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置节点标签的最大字符数
+    pub fn with_label_length(mut self, label_length: usize) -> Self {
+        self.label_length = label_length;
+        self
+    }
+
+    /// 按范围从数据库收集子图
+    pub async fn collect(&self, db: &VectorGraphDB, scope: &ExportScope) -> Result<GraphSnapshot> {
+        let (mut nodes, edges) = match scope {
+            ExportScope::Full => (
+                db.get_all_nodes(None).await?,
+                db.get_all_edges(&None).await?,
+            ),
+            ExportScope::Neighborhood { memory_ids, hops } => {
+                let result = db
+                    .query_graph(&GraphQueryRequest {
+                        start_nodes: memory_ids.clone(),
+                        edge_types: None,
+                        max_depth: Some(*hops),
+                        limit: None,
+                        filters: None,
+                    })
+                    .await?;
+                (result.nodes, result.edges)
+            }
+        };
+
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        nodes.dedup_by(|a, b| a.id == b.id);
+
+        // 只保留两端都在子图中的边
+        let ids: HashSet<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+        let mut edges: Vec<GraphEdge> = edges
+            .into_iter()
+            .filter(|e| ids.contains(e.from_node.as_str()) && ids.contains(e.to_node.as_str()))
+            .collect();
+        edges.sort_by(|a, b| a.id.cmp(&b.id));
+        edges.dedup_by(|a, b| a.id == b.id);
+
+        Ok(GraphSnapshot { nodes, edges })
+    }
+
+    /// 导出为字符串
+    pub async fn export(
+        &self,
+        db: &VectorGraphDB,
+        scope: &ExportScope,
+        format: ExportFormat,
+    ) -> Result<String> {
+        let snapshot = self.collect(db, scope).await?;
+        self.render(&snapshot, format)
+    }
+
+    /// 导出到文件
+    pub async fn export_to_file(
+        &self,
+        db: &VectorGraphDB,
+        scope: &ExportScope,
+        format: ExportFormat,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        let content = self.export(db, scope, format).await?;
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+
+    /// 按格式渲染子图
+    pub fn render(&self, snapshot: &GraphSnapshot, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::GraphML => Ok(self.render_graphml(snapshot)),
+            ExportFormat::Dot => Ok(self.render_dot(snapshot)),
+            ExportFormat::JsonNodeLink => self.render_node_link(snapshot),
+        }
+    }
+
+    fn render_graphml(&self, snapshot: &GraphSnapshot) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (id, domain, name, ty) in [
+            ("label", "node", "label", "string"),
+            ("type", "node", "type", "string"),
+            ("importance", "node", "importance", "double"),
+            ("tags", "node", "tags", "string"),
+            ("community", "node", "community", "string"),
+            ("edge_type", "edge", "type", "string"),
+            ("weight", "edge", "weight", "double"),
+        ] {
+            let _ = writeln!(
+                out,
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                id, domain, name, ty
+            );
+        }
+        out.push_str("  <graph id=\"memory_graph\" edgedefault=\"directed\">\n");
+
+        for node in &snapshot.nodes {
+            let attrs = self.node_attributes(node);
+            let _ = writeln!(out, "    <node id=\"{}\">", xml_escape(&node.id));
+            let _ = writeln!(
+                out,
+                "      <data key=\"label\">{}</data>",
+                xml_escape(&attrs.label)
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"type\">{}</data>",
+                xml_escape(&attrs.node_type)
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"importance\">{}</data>",
+                attrs.importance
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"tags\">{}</data>",
+                xml_escape(&attrs.tags.join(","))
+            );
+            if let Some(community) = &attrs.community {
+                let _ = writeln!(
+                    out,
+                    "      <data key=\"community\">{}</data>",
+                    xml_escape(community)
+                );
+            }
+            out.push_str("    </node>\n");
+        }
+
+        for edge in &snapshot.edges {
+            let _ = writeln!(
+                out,
+                "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+                xml_escape(&edge.id),
+                xml_escape(&edge.from_node),
+                xml_escape(&edge.to_node)
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"edge_type\">{}</data>",
+                xml_escape(&edge.edge_type)
+            );
+            let _ = writeln!(out, "      <data key=\"weight\">{}</data>", edge.weight);
+            out.push_str("    </edge>\n");
+        }
+
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    fn render_dot(&self, snapshot: &GraphSnapshot) -> String {
+        let mut out = String::from("digraph memory_graph {\n");
+        for node in &snapshot.nodes {
+            let attrs = self.node_attributes(node);
+            let _ = write!(
+                out,
+                "  \"{}\" [label=\"{}\", type=\"{}\", importance={}, tags=\"{}\"",
+                dot_escape(&node.id),
+                dot_escape(&attrs.label),
+                dot_escape(&attrs.node_type),
+                attrs.importance,
+                dot_escape(&attrs.tags.join(","))
+            );
+            if let Some(community) = &attrs.community {
+                let _ = write!(out, ", community=\"{}\"", dot_escape(community));
+            }
+            out.push_str("];\n");
+        }
+        for edge in &snapshot.edges {
+            let _ = writeln!(
+                out,
+                "  \"{}\" -> \"{}\" [type=\"{}\", weight={}, label=\"{}\"];",
+                dot_escape(&edge.from_node),
+                dot_escape(&edge.to_node),
+                dot_escape(&edge.edge_type),
+                edge.weight,
+                dot_escape(&edge.edge_type)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn render_node_link(&self, snapshot: &GraphSnapshot) -> Result<String> {
+        let nodes: Vec<Value> = snapshot
+            .nodes
+            .iter()
+            .map(|node| {
+                let attrs = self.node_attributes(node);
+                serde_json::json!({
+                    "id": node.id,
+                    "label": attrs.label,
+                    "type": attrs.node_type,
+                    "importance": attrs.importance,
+                    "tags": attrs.tags,
+                    "community": attrs.community,
+                })
+            })
+            .collect();
+        let links: Vec<Value> = snapshot
+            .edges
+            .iter()
+            .map(|edge| {
+                serde_json::json!({
+                    "id": edge.id,
+                    "source": edge.from_node,
+                    "target": edge.to_node,
+                    "type": edge.edge_type,
+                    "weight": edge.weight,
+                })
+            })
+            .collect();
+
+        Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "directed": true,
+            "multigraph": true,
+            "graph": {},
+            "nodes": nodes,
+            "links": links,
+        }))?)
+    }
+
+    /// 提取节点的导出属性
+    fn node_attributes(&self, node: &GraphNode) -> NodeAttributes {
+        let properties = &node.properties;
+        let content = properties
+            .get("content")
+            .and_then(|v| v.as_str())
+            .or_else(|| properties.get("name").and_then(|v| v.as_str()))
+            .unwrap_or(&node.id);
+
+        NodeAttributes {
+            label: content.chars().take(self.label_length).collect(),
+            node_type: properties
+                .get("memory_type")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&node.node_type)
+                .to_string(),
+            importance: properties
+                .get("importance")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0),
+            tags: properties
+                .get("tags")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            community: properties
+                .get("custom_attributes")
+                .and_then(|v| v.get(COMMUNITY_ATTRIBUTE))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+        }
+    }
+}
+
+/// 节点导出属性
+struct NodeAttributes {
+    label: String,
+    node_type: String,
+    importance: f64,
+    tags: Vec<String>,
+    community: Option<String>,
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn dot_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Vector;
+    use serde_json::json;
+
+    fn point(id: &str, embedding: Vec<f32>) -> MemoryPoint {
+        MemoryPoint {
+            id: id.to_string(),
+            content: String::new(),
+            keywords: Vec::new(),
+            embedding,
+        }
+    }
+
+    fn edge(from: &str, to: &str, weight: f32) -> (MemoryId, MemoryId, f32) {
+        (from.to_string(), to.to_string(), weight)
+    }
+
+    #[test]
+    fn test_label_propagation_two_cliques() {
+        let detector = CommunityDetector::new(&Config::default());
+        let nodes: Vec<MemoryId> = ["a", "b", "c", "x", "y", "z"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let edges = vec![
+            edge("a", "b", 1.0),
+            edge("b", "c", 1.0),
+            edge("a", "c", 1.0),
+            edge("x", "y", 1.0),
+            edge("y", "z", 1.0),
+            edge("x", "z", 1.0),
+            edge("c", "x", 0.1),
+        ];
+
+        let groups = CommunityDetector::group_by_label(&detector.label_propagation(&nodes, &edges));
+        assert_eq!(groups.len(), 2);
+        assert!(groups.contains(&vec!["a".to_string(), "b".to_string(), "c".to_string()]));
+        assert!(groups.contains(&vec!["x".to_string(), "y".to_string(), "z".to_string()]));
+    }
+
+    #[test]
+    fn test_kmeans_separates_embeddings() {
+        let detector = CommunityDetector::new(&Config::default());
+        let points = vec![
+            point("a", vec![1.0, 0.0, 0.0]),
+            point("b", vec![0.9, 0.1, 0.0]),
+            point("c", vec![0.0, 1.0, 0.0]),
+            point("d", vec![0.1, 0.9, 0.0]),
+        ];
+
+        let groups = CommunityDetector::group_by_label(&detector.kmeans(&points, 2));
+        assert_eq!(groups.len(), 2);
+        assert!(groups.contains(&vec!["a".to_string(), "b".to_string()]));
+        assert!(groups.contains(&vec!["c".to_string(), "d".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_detect_and_store_communities() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = VectorGraphDB::new(config.clone()).await.unwrap();
+
+        let memories = [
+            ("m1", "咖啡 拿铁", vec![1.0, 0.0]),
+            ("m2", "咖啡 美式", vec![0.9, 0.1]),
+            ("m3", "跑步 马拉松", vec![0.0, 1.0]),
+            ("m4", "跑步 训练", vec![0.1, 0.9]),
+        ];
+        for (id, content, embedding) in &memories {
+            let mut properties = HashMap::new();
+            properties.insert("id".to_string(), json!(id));
+            properties.insert("content".to_string(), json!(content));
+            properties.insert("embedding".to_string(), json!(embedding));
+            properties.insert(
+                "keywords".to_string(),
+                json!(content.split_whitespace().collect::<Vec<_>>()),
+            );
+            db.insert_vector(&Vector {
+                id: id.to_string(),
+                embedding: embedding.clone(),
+                metadata: properties.clone(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+            db.insert_node(&GraphNode {
+                id: id.to_string(),
+                node_type: "memory".to_string(),
+                properties,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+        for (id, from, to) in [("e1", "m1", "m2"), ("e2", "m3", "m4")] {
+            db.insert_edge(&GraphEdge {
+                id: id.to_string(),
+                from_node: from.to_string(),
+                to_node: to.to_string(),
+                edge_type: "Semantic".to_string(),
+                weight: 0.9,
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let detector = CommunityDetector::new(&config);
+        let communities = detector.detect(&db).await.unwrap();
+        assert_eq!(communities.len(), 2);
+        let coffee = communities
+            .iter()
+            .find(|c| c.members.contains(&"m1".to_string()))
+            .unwrap();
+        assert_eq!(coffee.members, vec!["m1".to_string(), "m2".to_string()]);
+        assert!(coffee.keywords.contains(&"咖啡".to_string()));
+
+        let changed = detector.store(&db, &communities).await.unwrap();
+        assert_eq!(changed, 4);
+        assert_eq!(db.get_communities().await.unwrap().len(), 2);
+
+        let vector = db.get_vector("m2").await.unwrap();
+        assert_eq!(
+            vector.metadata["custom_attributes"][COMMUNITY_ATTRIBUTE],
+            json!(coffee.id)
+        );
+
+        // 划分未变化时不重复写入
+        assert_eq!(detector.store(&db, &communities).await.unwrap(), 0);
+    }
+
+    async fn create_export_db() -> VectorGraphDB {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = VectorGraphDB::new(config).await.unwrap();
+
+        for (id, content) in [
+            ("n1", "买了<新>键盘"),
+            ("n2", "键盘 \"很好用\""),
+            ("n3", "无关记忆"),
+        ] {
+            let mut properties = HashMap::new();
+            properties.insert("content".to_string(), json!(content));
+            properties.insert("memory_type".to_string(), json!("Event"));
+            properties.insert("importance".to_string(), json!(0.8));
+            properties.insert("tags".to_string(), json!(["购物"]));
+            db.insert_node(&GraphNode {
+                id: id.to_string(),
+                node_type: "memory".to_string(),
+                properties,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+        for (id, from, to) in [("e1", "n1", "n2"), ("e2", "n2", "n3")] {
+            db.insert_edge(&GraphEdge {
+                id: id.to_string(),
+                from_node: from.to_string(),
+                to_node: to.to_string(),
+                edge_type: "Semantic".to_string(),
+                weight: 0.75,
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+        db
+    }
+
+    #[tokio::test]
+    async fn test_export_neighborhood_node_link() {
+        let db = create_export_db().await;
+        let exporter = GraphExporter::new();
+
+        let scope = ExportScope::Neighborhood {
+            memory_ids: vec!["n1".to_string()],
+            hops: 1,
+        };
+        let json: Value = serde_json::from_str(
+            &exporter
+                .export(&db, &scope, ExportFormat::JsonNodeLink)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+
+        let nodes = json["nodes"].as_array().unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0]["type"], "Event");
+        assert_eq!(nodes[0]["tags"][0], "购物");
+        let links = json["links"].as_array().unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0]["source"], "n1");
+        assert_eq!(links[0]["weight"], 0.75);
+    }
+
+    #[tokio::test]
+    async fn test_export_graphml_and_dot_escape_content() {
+        let db = create_export_db().await;
+        let exporter = GraphExporter::new();
+
+        let graphml = exporter
+            .export(&db, &ExportScope::Full, ExportFormat::GraphML)
+            .await
+            .unwrap();
+        assert!(graphml.contains("买了&lt;新&gt;键盘"));
+        assert_eq!(graphml.matches("<edge ").count(), 2);
+        assert!(graphml.contains("<data key=\"edge_type\">Semantic</data>"));
+
+        let dot = exporter
+            .export(&db, &ExportScope::Full, ExportFormat::Dot)
+            .await
+            .unwrap();
+        assert!(dot.starts_with("digraph memory_graph {"));
+        assert!(dot.contains("键盘 \\\"很好用\\\""));
+        assert!(dot.contains("\"n2\" -> \"n3\" [type=\"Semantic\", weight=0.75"));
+
+        assert!("gexf".parse::<ExportFormat>().is_err());
+        assert_eq!("DOT".parse::<ExportFormat>().unwrap(), ExportFormat::Dot);
+    }
+}
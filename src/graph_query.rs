@@ -0,0 +1,1430 @@
+//! 图查询语言模块
+//!
+//! 提供一个类Cypher的小型图查询语言，用于在记忆图上做模式匹配：
+//!
+//! ```text
+//! MATCH (a:Event)-[:Causal*1..3]->(b) WHERE b.importance > 0.7 RETURN b LIMIT 10
+//! ```
+//!
+//! 支持的语法：
+//!
+//! - 节点模式 `(变量:标签 {属性: 值})`，标签匹配节点类型或记忆类型（不区分大小写）
+//! - 关系模式 `-[变量:类型1|类型2*最小..最大]->`，方向可为 `->`、`<-` 或无向 `-`
+//! - `WHERE` 条件：`=`、`<>`/`!=`、`<`、`<=`、`>`、`>=`、`CONTAINS`、
+//!   `STARTS WITH`、`ENDS WITH`，可用 `AND`/`OR`/`NOT` 和括号组合
+//! - `RETURN [DISTINCT] 变量或属性, ...`、`ORDER BY 属性 [ASC|DESC]`、`LIMIT n`
+//!
+//! 查询文本先被解析为 [`QueryPlan`]，再由 [`VectorGraphDB::execute_query`] 执行。
+
+use crate::core::{Connection, Memory};
+use crate::database::{GraphEdge, GraphNode, VectorGraphDB};
+use crate::error::{MemoryError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+/// 未指定上界的变长关系（如 `*` 或 `*2..`）使用的最大跳数
+pub const DEFAULT_MAX_HOPS: usize = 5;
+
+/// 关系方向
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Direction {
+    /// `-[]->`
+    Outgoing,
+    /// `<-[]-`
+    Incoming,
+    /// `-[]-`
+    Both,
+}
+
+/// 节点模式
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NodePattern {
+    pub variable: Option<String>,
+    pub label: Option<String>,
+    pub properties: Vec<(String, Value)>,
+}
+
+/// 关系模式
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RelPattern {
+    pub variable: Option<String>,
+    /// 允许的边类型，为空表示任意类型
+    pub types: Vec<String>,
+    pub direction: Direction,
+    pub min_hops: usize,
+    pub max_hops: usize,
+    /// 是否为变长关系（`*`），变长关系变量绑定为路径
+    pub variable_length: bool,
+}
+
+/// 路径模式：起始节点加若干 (关系, 节点) 步骤
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PathPattern {
+    pub start: NodePattern,
+    pub steps: Vec<(RelPattern, NodePattern)>,
+}
+
+/// 比较运算符
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+/// 操作数
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Operand {
+    /// 变量属性，如 `b.importance` 或 `b.custom_attributes.community_id`
+    Property { variable: String, path: Vec<String> },
+    /// 字面量
+    Literal(Value),
+}
+
+/// WHERE条件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Condition {
+    Compare {
+        left: Operand,
+        op: CompareOp,
+        right: Operand,
+    },
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+/// RETURN项
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReturnItem {
+    /// 列名（原始表达式文本）
+    pub name: String,
+    pub variable: String,
+    /// 属性路径，为空表示返回整个变量
+    pub path: Vec<String>,
+}
+
+/// 排序项
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderItem {
+    pub operand: Operand,
+    pub descending: bool,
+}
+
+/// 查询计划
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryPlan {
+    pub pattern: PathPattern,
+    pub condition: Option<Condition>,
+    pub returns: Vec<ReturnItem>,
+    pub distinct: bool,
+    pub order_by: Vec<OrderItem>,
+    pub limit: Option<usize>,
+}
+
+/// 查询结果中的值
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum QueryValue {
+    Node(GraphNode),
+    Edge(GraphEdge),
+    /// 变长关系匹配到的边序列
+    Path(Vec<GraphEdge>),
+    Value(Value),
+}
+
+/// 查询结果行，值的顺序与RETURN子句一致
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryRow {
+    pub columns: Vec<String>,
+    pub values: Vec<QueryValue>,
+}
+
+impl QueryRow {
+    /// 按列名取值
+    pub fn get(&self, column: &str) -> Option<&QueryValue> {
+        self.columns
+            .iter()
+            .position(|c| c == column)
+            .map(|i| &self.values[i])
+    }
+}
+
+/// 转换为记忆和连接后的查询结果项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum QueryItem {
+    Memory(Box<Memory>),
+    /// 非记忆节点
+    Node(GraphNode),
+    Connection(Connection),
+    Path(Vec<Connection>),
+    Value(Value),
+}
+
+/// 转换为记忆和连接后的查询结果行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryQueryRow {
+    pub columns: Vec<String>,
+    pub items: Vec<QueryItem>,
+}
+
+impl MemoryQueryRow {
+    /// 按列名取值
+    pub fn get(&self, column: &str) -> Option<&QueryItem> {
+        self.columns
+            .iter()
+            .position(|c| c == column)
+            .map(|i| &self.items[i])
+    }
+}
+
+impl std::str::FromStr for QueryPlan {
+    type Err = MemoryError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl QueryPlan {
+    /// 解析查询文本
+    pub fn parse(query: &str) -> Result<Self> {
+        let tokens = tokenize(query)?;
+        let plan = Parser { tokens, pos: 0 }.parse_query()?;
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    /// 模式中定义的变量
+    pub fn variables(&self) -> HashSet<&str> {
+        let mut variables = HashSet::new();
+        variables.extend(self.pattern.start.variable.as_deref());
+        for (rel, node) in &self.pattern.steps {
+            variables.extend(rel.variable.as_deref());
+            variables.extend(node.variable.as_deref());
+        }
+        variables
+    }
+
+    /// 检查引用的变量都已在模式中定义
+    fn validate(&self) -> Result<()> {
+        let variables = self.variables();
+        let check = |variable: &str| {
+            if variables.contains(variable) {
+                Ok(())
+            } else {
+                Err(MemoryError::invalid_query(format!(
+                    "Undefined variable: {}",
+                    variable
+                )))
+            }
+        };
+
+        for item in &self.returns {
+            check(&item.variable)?;
+        }
+        for item in &self.order_by {
+            if let Operand::Property { variable, .. } = &item.operand {
+                check(variable)?;
+            }
+        }
+        if let Some(condition) = &self.condition {
+            let mut stack = vec![condition];
+            while let Some(condition) = stack.pop() {
+                match condition {
+                    Condition::Compare { left, right, .. } => {
+                        for operand in [left, right] {
+                            if let Operand::Property { variable, .. } = operand {
+                                check(variable)?;
+                            }
+                        }
+                    }
+                    Condition::And(a, b) | Condition::Or(a, b) => {
+                        stack.push(a);
+                        stack.push(b);
+                    }
+                    Condition::Not(a) => stack.push(a),
+                }
+            }
+        }
+
+        let mut bound = HashSet::new();
+        for (rel, _) in &self.pattern.steps {
+            if let Some(variable) = &rel.variable {
+                if !bound.insert(variable) {
+                    return Err(MemoryError::invalid_query(format!(
+                        "Relationship variable used twice: {}",
+                        variable
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// 词法分析
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(f64),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 20] = [
+    "..", ">=", "<=", "<>", "!=", "(", ")", "[", "]", "{", "}", ":", ",", ".", "*", "|", "-", ">",
+    "<", "=",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '\'' || c == '"' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(MemoryError::invalid_query("Unterminated string literal")),
+                    Some('\\') if i + 1 < chars.len() => {
+                        value.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(&ch) if ch == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&ch) => {
+                        value.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Str(value));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            // `1..3` 中的点属于范围符号
+            if i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_ascii_digit() {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse()
+                .map_err(|_| MemoryError::invalid_query(format!("Invalid number: {}", text)))?;
+            tokens.push(Token::Number(number));
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '`' {
+            let start = i + 1;
+            let end = chars[start..]
+                .iter()
+                .position(|&ch| ch == '`')
+                .map(|p| start + p)
+                .ok_or_else(|| MemoryError::invalid_query("Unterminated quoted identifier"))?;
+            tokens.push(Token::Ident(chars[start..end].iter().collect()));
+            i = end + 1;
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|s| rest.starts_with(*s))
+                .ok_or_else(|| {
+                    MemoryError::invalid_query(format!("Unexpected character: {}", c))
+                })?;
+            tokens.push(Token::Symbol(symbol));
+            i += symbol.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+// 语法分析
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn parse_query(&mut self) -> Result<QueryPlan> {
+        self.expect_keyword("MATCH")?;
+        let pattern = self.parse_path()?;
+
+        let condition = if self.eat_keyword("WHERE") {
+            Some(self.parse_or()?)
+        } else {
+            None
+        };
+
+        self.expect_keyword("RETURN")?;
+        let distinct = self.eat_keyword("DISTINCT");
+        let mut returns = vec![self.parse_return_item()?];
+        while self.eat_symbol(",") {
+            returns.push(self.parse_return_item()?);
+        }
+
+        let mut order_by = Vec::new();
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let (variable, path) = self.parse_property_ref()?;
+                let descending = if self.eat_keyword("DESC") {
+                    true
+                } else {
+                    self.eat_keyword("ASC");
+                    false
+                };
+                order_by.push(OrderItem {
+                    operand: Operand::Property { variable, path },
+                    descending,
+                });
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        let limit = if self.eat_keyword("LIMIT") {
+            Some(self.expect_usize()?)
+        } else {
+            None
+        };
+
+        if let Some(token) = self.peek() {
+            return Err(MemoryError::invalid_query(format!(
+                "Unexpected token: {:?}",
+                token
+            )));
+        }
+
+        Ok(QueryPlan {
+            pattern,
+            condition,
+            returns,
+            distinct,
+            order_by,
+            limit,
+        })
+    }
+
+    fn parse_path(&mut self) -> Result<PathPattern> {
+        let start = self.parse_node()?;
+        let mut steps = Vec::new();
+        while matches!(self.peek(), Some(Token::Symbol("-" | "<"))) {
+            let rel = self.parse_rel()?;
+            let node = self.parse_node()?;
+            steps.push((rel, node));
+        }
+        Ok(PathPattern { start, steps })
+    }
+
+    fn parse_node(&mut self) -> Result<NodePattern> {
+        self.expect_symbol("(")?;
+        let variable = self.eat_ident();
+        let label = if self.eat_symbol(":") {
+            Some(self.expect_ident()?)
+        } else {
+            None
+        };
+
+        let mut properties = Vec::new();
+        if self.eat_symbol("{") {
+            loop {
+                let key = self.expect_ident()?;
+                self.expect_symbol(":")?;
+                properties.push((key, self.parse_literal()?));
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+            self.expect_symbol("}")?;
+        }
+
+        self.expect_symbol(")")?;
+        Ok(NodePattern {
+            variable,
+            label,
+            properties,
+        })
+    }
+
+    fn parse_rel(&mut self) -> Result<RelPattern> {
+        let incoming = self.eat_symbol("<");
+        self.expect_symbol("-")?;
+
+        let mut rel = RelPattern {
+            variable: None,
+            types: Vec::new(),
+            direction: Direction::Both,
+            min_hops: 1,
+            max_hops: 1,
+            variable_length: false,
+        };
+
+        if self.eat_symbol("[") {
+            rel.variable = self.eat_ident();
+            if self.eat_symbol(":") {
+                rel.types.push(self.expect_ident()?);
+                while self.eat_symbol("|") {
+                    self.eat_symbol(":");
+                    rel.types.push(self.expect_ident()?);
+                }
+            }
+            if self.eat_symbol("*") {
+                rel.variable_length = true;
+                rel.min_hops = 1;
+                rel.max_hops = DEFAULT_MAX_HOPS;
+                if let Some(Token::Number(_)) = self.peek() {
+                    rel.min_hops = self.expect_usize()?;
+                    rel.max_hops = rel.min_hops;
+                }
+                if self.eat_symbol("..") {
+                    rel.max_hops = match self.peek() {
+                        Some(Token::Number(_)) => self.expect_usize()?,
+                        _ => DEFAULT_MAX_HOPS.max(rel.min_hops),
+                    };
+                }
+                if rel.min_hops > rel.max_hops {
+                    return Err(MemoryError::invalid_query(format!(
+                        "Invalid hop range: {}..{}",
+                        rel.min_hops, rel.max_hops
+                    )));
+                }
+            }
+            self.expect_symbol("]")?;
+        }
+
+        self.expect_symbol("-")?;
+        let outgoing = self.eat_symbol(">");
+
+        rel.direction = match (incoming, outgoing) {
+            (true, true) => {
+                return Err(MemoryError::invalid_query(
+                    "Relationship cannot point both ways",
+                ))
+            }
+            (true, false) => Direction::Incoming,
+            (false, true) => Direction::Outgoing,
+            (false, false) => Direction::Both,
+        };
+        Ok(rel)
+    }
+
+    fn parse_or(&mut self) -> Result<Condition> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            let right = self.parse_and()?;
+            left = Condition::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition> {
+        let mut left = self.parse_not()?;
+        while self.eat_keyword("AND") {
+            let right = self.parse_not()?;
+            left = Condition::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Condition> {
+        if self.eat_keyword("NOT") {
+            return Ok(Condition::Not(Box::new(self.parse_not()?)));
+        }
+        if self.eat_symbol("(") {
+            let condition = self.parse_or()?;
+            self.expect_symbol(")")?;
+            return Ok(condition);
+        }
+
+        let left = self.parse_operand()?;
+        let op = if self.eat_symbol("=") {
+            CompareOp::Eq
+        } else if self.eat_symbol("<>") || self.eat_symbol("!=") {
+            CompareOp::Ne
+        } else if self.eat_symbol("<=") {
+            CompareOp::Le
+        } else if self.eat_symbol(">=") {
+            CompareOp::Ge
+        } else if self.eat_symbol("<") {
+            CompareOp::Lt
+        } else if self.eat_symbol(">") {
+            CompareOp::Gt
+        } else if self.eat_keyword("CONTAINS") {
+            CompareOp::Contains
+        } else if self.eat_keyword("STARTS") {
+            self.expect_keyword("WITH")?;
+            CompareOp::StartsWith
+        } else if self.eat_keyword("ENDS") {
+            self.expect_keyword("WITH")?;
+            CompareOp::EndsWith
+        } else {
+            return Err(self.unexpected("comparison operator"));
+        };
+        let right = self.parse_operand()?;
+
+        Ok(Condition::Compare { left, op, right })
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand> {
+        match self.peek() {
+            Some(Token::Ident(name)) if !is_literal_keyword(name) => {
+                let (variable, path) = self.parse_property_ref()?;
+                Ok(Operand::Property { variable, path })
+            }
+            _ => Ok(Operand::Literal(self.parse_literal()?)),
+        }
+    }
+
+    fn parse_property_ref(&mut self) -> Result<(String, Vec<String>)> {
+        let variable = self.expect_ident()?;
+        let mut path = Vec::new();
+        while self.eat_symbol(".") {
+            path.push(self.expect_ident()?);
+        }
+        if path.is_empty() {
+            return Err(MemoryError::invalid_query(format!(
+                "Expected property access on {}",
+                variable
+            )));
+        }
+        Ok((variable, path))
+    }
+
+    fn parse_return_item(&mut self) -> Result<ReturnItem> {
+        let variable = self.expect_ident()?;
+        let mut path = Vec::new();
+        while self.eat_symbol(".") {
+            path.push(self.expect_ident()?);
+        }
+        let name = std::iter::once(variable.clone())
+            .chain(path.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(".");
+        Ok(ReturnItem {
+            name,
+            variable,
+            path,
+        })
+    }
+
+    fn parse_literal(&mut self) -> Result<Value> {
+        let negative = self.eat_symbol("-");
+        match self.next() {
+            Some(Token::Number(n)) => {
+                let n = if negative { -n } else { n };
+                Ok(serde_json::Number::from_f64(n)
+                    .map(Value::Number)
+                    .unwrap_or(Value::Null))
+            }
+            Some(Token::Str(s)) if !negative => Ok(Value::String(s)),
+            Some(Token::Ident(name)) if !negative && is_literal_keyword(&name) => {
+                Ok(match name.to_lowercase().as_str() {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    _ => Value::Null,
+                })
+            }
+            _ => {
+                self.pos = self.pos.saturating_sub(1);
+                Err(self.unexpected("literal"))
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn unexpected(&self, expected: &str) -> MemoryError {
+        match self.peek() {
+            Some(token) => {
+                MemoryError::invalid_query(format!("Expected {}, found {:?}", expected, token))
+            }
+            None => {
+                MemoryError::invalid_query(format!("Expected {}, found end of query", expected))
+            }
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", symbol)))
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(s)) if s.eq_ignore_ascii_case(keyword)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(keyword))
+        }
+    }
+
+    fn eat_ident(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String> {
+        self.eat_ident()
+            .ok_or_else(|| self.unexpected("identifier"))
+    }
+
+    fn expect_usize(&mut self) -> Result<usize> {
+        match self.peek() {
+            Some(Token::Number(n)) if *n >= 0.0 && n.fract() == 0.0 => {
+                let n = *n as usize;
+                self.pos += 1;
+                Ok(n)
+            }
+            _ => Err(self.unexpected("non-negative integer")),
+        }
+    }
+}
+
+fn is_literal_keyword(name: &str) -> bool {
+    ["true", "false", "null"]
+        .iter()
+        .any(|k| name.eq_ignore_ascii_case(k))
+}
+
+// 执行
+
+/// 部分匹配状态
+#[derive(Clone)]
+struct MatchState {
+    bindings: HashMap<String, QueryValue>,
+    used_edges: HashSet<String>,
+    current: GraphNode,
+}
+
+/// 查询执行器
+///
+/// 从满足起始节点模式的节点出发逐步扩展关系模式，邻接边和节点按需加载并缓存。
+/// 与Cypher一致，同一匹配中的边不会重复使用。
+pub(crate) struct QueryExecutor<'a> {
+    db: &'a VectorGraphDB,
+    adjacency: HashMap<String, Vec<GraphEdge>>,
+    nodes: HashMap<String, Option<GraphNode>>,
+}
+
+impl<'a> QueryExecutor<'a> {
+    pub(crate) fn new(db: &'a VectorGraphDB) -> Self {
+        Self {
+            db,
+            adjacency: HashMap::new(),
+            nodes: HashMap::new(),
+        }
+    }
+
+    pub(crate) async fn run(&mut self, plan: &QueryPlan) -> Result<Vec<QueryRow>> {
+        let columns: Vec<String> = plan.returns.iter().map(|r| r.name.clone()).collect();
+        // 没有排序时可以在达到LIMIT后提前结束
+        let early_limit = plan.limit.filter(|_| plan.order_by.is_empty());
+
+        let mut start_nodes: Vec<GraphNode> = self
+            .db
+            .get_all_nodes(None)
+            .await?
+            .into_iter()
+            .filter(|node| !is_deleted(node) && node_matches(node, &plan.pattern.start))
+            .collect();
+        start_nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut matches: Vec<(HashMap<String, QueryValue>, Vec<QueryValue>)> = Vec::new();
+        let mut seen = HashSet::new();
+
+        for start in start_nodes {
+            let mut bindings = HashMap::new();
+            if let Some(variable) = &plan.pattern.start.variable {
+                bindings.insert(variable.clone(), QueryValue::Node(start.clone()));
+            }
+            let mut states = vec![MatchState {
+                bindings,
+                used_edges: HashSet::new(),
+                current: start,
+            }];
+
+            for (rel, node_pattern) in &plan.pattern.steps {
+                let mut next_states = Vec::new();
+                for state in &states {
+                    next_states.extend(self.expand(state, rel, node_pattern).await?);
+                }
+                states = next_states;
+                if states.is_empty() {
+                    break;
+                }
+            }
+
+            for state in states {
+                if let Some(condition) = &plan.condition {
+                    if !evaluate(condition, &state.bindings) {
+                        continue;
+                    }
+                }
+
+                let values: Vec<QueryValue> = plan
+                    .returns
+                    .iter()
+                    .map(|item| project(&state.bindings, item))
+                    .collect();
+                if plan.distinct && !seen.insert(row_key(&values)) {
+                    continue;
+                }
+                matches.push((state.bindings, values));
+            }
+
+            if early_limit.is_some_and(|limit| matches.len() >= limit) {
+                break;
+            }
+        }
+
+        if !plan.order_by.is_empty() {
+            matches.sort_by(|(a, _), (b, _)| {
+                for item in &plan.order_by {
+                    let ordering =
+                        compare_for_sort(&resolve(&item.operand, a), &resolve(&item.operand, b));
+                    let ordering = if item.descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    };
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                Ordering::Equal
+            });
+        }
+
+        let mut rows: Vec<QueryRow> = matches
+            .into_iter()
+            .map(|(_, values)| QueryRow {
+                columns: columns.clone(),
+                values,
+            })
+            .collect();
+        if let Some(limit) = plan.limit {
+            rows.truncate(limit);
+        }
+
+        Ok(rows)
+    }
+
+    /// 从当前节点沿关系模式扩展一步
+    async fn expand(
+        &mut self,
+        state: &MatchState,
+        rel: &RelPattern,
+        node_pattern: &NodePattern,
+    ) -> Result<Vec<MatchState>> {
+        let mut results = Vec::new();
+        let mut stack = vec![(state.current.clone(), Vec::<GraphEdge>::new())];
+
+        while let Some((node, path)) = stack.pop() {
+            if path.len() >= rel.min_hops {
+                if let Some(next) = self.bind_end(state, rel, node_pattern, &node, &path) {
+                    results.push(next);
+                }
+            }
+            if path.len() >= rel.max_hops {
+                continue;
+            }
+
+            let edges = self.adjacent_edges(&node.id).await?;
+            // 逆序入栈，使结果按边ID顺序输出
+            for edge in edges.iter().rev() {
+                if !edge_type_matches(&edge.edge_type, &rel.types)
+                    || state.used_edges.contains(&edge.id)
+                    || path.iter().any(|e| e.id == edge.id)
+                {
+                    continue;
+                }
+                let next_id = match rel.direction {
+                    Direction::Outgoing if edge.from_node == node.id => &edge.to_node,
+                    Direction::Incoming if edge.to_node == node.id => &edge.from_node,
+                    Direction::Both if edge.from_node == node.id => &edge.to_node,
+                    Direction::Both => &edge.from_node,
+                    _ => continue,
+                };
+                if let Some(next) = self.node(next_id).await? {
+                    let mut next_path = path.clone();
+                    next_path.push(edge.clone());
+                    stack.push((next, next_path));
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// 检查终点节点并生成新的匹配状态
+    fn bind_end(
+        &self,
+        state: &MatchState,
+        rel: &RelPattern,
+        node_pattern: &NodePattern,
+        node: &GraphNode,
+        path: &[GraphEdge],
+    ) -> Option<MatchState> {
+        if !node_matches(node, node_pattern) {
+            return None;
+        }
+
+        let mut next = state.clone();
+        if let Some(variable) = &node_pattern.variable {
+            match next.bindings.get(variable) {
+                Some(QueryValue::Node(bound)) if bound.id != node.id => return None,
+                Some(QueryValue::Node(_)) => {}
+                Some(_) => return None,
+                None => {
+                    next.bindings
+                        .insert(variable.clone(), QueryValue::Node(node.clone()));
+                }
+            }
+        }
+        if let Some(variable) = &rel.variable {
+            let value = if rel.variable_length {
+                QueryValue::Path(path.to_vec())
+            } else {
+                QueryValue::Edge(path.first()?.clone())
+            };
+            next.bindings.insert(variable.clone(), value);
+        }
+        next.used_edges.extend(path.iter().map(|e| e.id.clone()));
+        next.current = node.clone();
+        Some(next)
+    }
+
+    async fn adjacent_edges(&mut self, node_id: &str) -> Result<Vec<GraphEdge>> {
+        if let Some(edges) = self.adjacency.get(node_id) {
+            return Ok(edges.clone());
+        }
+        let mut edges = self.db.get_adjacent_edges(node_id, &None).await?;
+        edges.sort_by(|a, b| a.id.cmp(&b.id));
+        self.adjacency.insert(node_id.to_string(), edges.clone());
+        Ok(edges)
+    }
+
+    async fn node(&mut self, node_id: &str) -> Result<Option<GraphNode>> {
+        if let Some(node) = self.nodes.get(node_id) {
+            return Ok(node.clone());
+        }
+        let node = self
+            .db
+            .get_node(node_id)
+            .await
+            .ok()
+            .filter(|node| !is_deleted(node));
+        self.nodes.insert(node_id.to_string(), node.clone());
+        Ok(node)
+    }
+}
+
+fn is_deleted(node: &GraphNode) -> bool {
+    node.properties
+        .get("is_deleted")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn node_matches(node: &GraphNode, pattern: &NodePattern) -> bool {
+    if let Some(label) = &pattern.label {
+        let memory_type = node.properties.get("memory_type").and_then(|v| v.as_str());
+        if !label.eq_ignore_ascii_case(&node.node_type)
+            && !memory_type.is_some_and(|t| label.eq_ignore_ascii_case(t))
+        {
+            return false;
+        }
+    }
+
+    pattern.properties.iter().all(|(key, expected)| {
+        compare(
+            &node_property(node, std::slice::from_ref(key)),
+            CompareOp::Eq,
+            expected,
+        )
+    })
+}
+
+/// 边类型匹配，不区分大小写，并允许用名称直接匹配自定义连接类型
+fn edge_type_matches(edge_type: &str, types: &[String]) -> bool {
+    types.is_empty()
+        || types
+            .iter()
+            .any(|t| edge_type.eq_ignore_ascii_case(t) || edge_type == format!("Custom({:?})", t))
+}
+
+fn node_property(node: &GraphNode, path: &[String]) -> Value {
+    let (first, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Value::Null,
+    };
+    let value = match first.as_str() {
+        "id" => Value::String(node.id.clone()),
+        "node_type" => Value::String(node.node_type.clone()),
+        key => match node.properties.get(key) {
+            Some(value) => value.clone(),
+            None if key == "created_at" => Value::String(node.created_at.to_rfc3339()),
+            None if key == "updated_at" => Value::String(node.updated_at.to_rfc3339()),
+            None => Value::Null,
+        },
+    };
+    nested(value, rest)
+}
+
+fn edge_property(edge: &GraphEdge, path: &[String]) -> Value {
+    let (first, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Value::Null,
+    };
+    let value = match first.as_str() {
+        "id" => Value::String(edge.id.clone()),
+        "type" | "edge_type" => Value::String(edge.edge_type.clone()),
+        "weight" | "strength" => serde_json::Number::from_f64(edge.weight as f64)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        "from" | "source" => Value::String(edge.from_node.clone()),
+        "to" | "target" => Value::String(edge.to_node.clone()),
+        "created_at" => Value::String(edge.created_at.to_rfc3339()),
+        "updated_at" => Value::String(edge.updated_at.to_rfc3339()),
+        key => edge.properties.get(key).cloned().unwrap_or(Value::Null),
+    };
+    nested(value, rest)
+}
+
+fn nested(mut value: Value, path: &[String]) -> Value {
+    for key in path {
+        value = value.get(key).cloned().unwrap_or(Value::Null);
+    }
+    value
+}
+
+fn resolve(operand: &Operand, bindings: &HashMap<String, QueryValue>) -> Value {
+    match operand {
+        Operand::Literal(value) => value.clone(),
+        Operand::Property { variable, path } => match bindings.get(variable) {
+            Some(QueryValue::Node(node)) => node_property(node, path),
+            Some(QueryValue::Edge(edge)) => edge_property(edge, path),
+            Some(QueryValue::Path(edges)) if path.len() == 1 && path[0] == "length" => {
+                Value::from(edges.len())
+            }
+            _ => Value::Null,
+        },
+    }
+}
+
+fn evaluate(condition: &Condition, bindings: &HashMap<String, QueryValue>) -> bool {
+    match condition {
+        Condition::Compare { left, op, right } => {
+            compare(&resolve(left, bindings), *op, &resolve(right, bindings))
+        }
+        Condition::And(a, b) => evaluate(a, bindings) && evaluate(b, bindings),
+        Condition::Or(a, b) => evaluate(a, bindings) || evaluate(b, bindings),
+        Condition::Not(a) => !evaluate(a, bindings),
+    }
+}
+
+/// 比较两个值，任一侧为空时结果为假
+fn compare(left: &Value, op: CompareOp, right: &Value) -> bool {
+    if left.is_null() || right.is_null() {
+        return false;
+    }
+
+    match op {
+        CompareOp::Eq => values_equal(left, right),
+        CompareOp::Ne => !values_equal(left, right),
+        CompareOp::Lt | CompareOp::Le | CompareOp::Gt | CompareOp::Ge => {
+            let ordering = match (left, right) {
+                (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                _ => None,
+            };
+            match ordering {
+                Some(ordering) => match op {
+                    CompareOp::Lt => ordering == Ordering::Less,
+                    CompareOp::Le => ordering != Ordering::Greater,
+                    CompareOp::Gt => ordering == Ordering::Greater,
+                    _ => ordering != Ordering::Less,
+                },
+                None => false,
+            }
+        }
+        CompareOp::Contains => match (left, right) {
+            (Value::String(a), Value::String(b)) => a.contains(b.as_str()),
+            (Value::Array(items), _) => items.iter().any(|item| values_equal(item, right)),
+            _ => false,
+        },
+        CompareOp::StartsWith => match (left, right) {
+            (Value::String(a), Value::String(b)) => a.starts_with(b.as_str()),
+            _ => false,
+        },
+        CompareOp::EndsWith => match (left, right) {
+            (Value::String(a), Value::String(b)) => a.ends_with(b.as_str()),
+            _ => false,
+        },
+    }
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => left == right,
+    }
+}
+
+/// 排序比较：数值和字符串各自有序，空值排在最后
+fn compare_for_sort(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x
+            .as_f64()
+            .partial_cmp(&y.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+fn project(bindings: &HashMap<String, QueryValue>, item: &ReturnItem) -> QueryValue {
+    if item.path.is_empty() {
+        return bindings
+            .get(&item.variable)
+            .cloned()
+            .unwrap_or(QueryValue::Value(Value::Null));
+    }
+    QueryValue::Value(resolve(
+        &Operand::Property {
+            variable: item.variable.clone(),
+            path: item.path.clone(),
+        },
+        bindings,
+    ))
+}
+
+fn row_key(values: &[QueryValue]) -> String {
+    values
+        .iter()
+        .map(|value| match value {
+            QueryValue::Node(node) => format!("n:{}", node.id),
+            QueryValue::Edge(edge) => format!("e:{}", edge.id),
+            QueryValue::Path(edges) => format!(
+                "p:{}",
+                edges
+                    .iter()
+                    .map(|e| e.id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            QueryValue::Value(value) => format!("v:{}", value),
+        })
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use chrono::Utc;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_example_query() {
+        let plan = QueryPlan::parse(
+            "MATCH (a:Event)-[:Causal*1..3]->(b) WHERE b.importance > 0.7 RETURN b LIMIT 5",
+        )
+        .unwrap();
+
+        assert_eq!(plan.pattern.start.variable.as_deref(), Some("a"));
+        assert_eq!(plan.pattern.start.label.as_deref(), Some("Event"));
+        let (rel, node) = &plan.pattern.steps[0];
+        assert_eq!(rel.types, vec!["Causal".to_string()]);
+        assert_eq!((rel.min_hops, rel.max_hops), (1, 3));
+        assert_eq!(rel.direction, Direction::Outgoing);
+        assert!(rel.variable_length);
+        assert_eq!(node.variable.as_deref(), Some("b"));
+        assert_eq!(
+            plan.condition,
+            Some(Condition::Compare {
+                left: Operand::Property {
+                    variable: "b".to_string(),
+                    path: vec!["importance".to_string()],
+                },
+                op: CompareOp::Gt,
+                right: Operand::Literal(json!(0.7)),
+            })
+        );
+        assert_eq!(plan.returns[0].name, "b");
+        assert_eq!(plan.limit, Some(5));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(QueryPlan::parse("MATCH (a) RETURN b").is_err());
+        assert!(QueryPlan::parse("MATCH (a)-[*3..1]->(b) RETURN b").is_err());
+        assert!(QueryPlan::parse("MATCH (a)<-[]->(b) RETURN b").is_err());
+        assert!(QueryPlan::parse("MATCH (a) WHERE a.x > RETURN a").is_err());
+        assert!(QueryPlan::parse("MATCH (a) RETURN a LIMIT 1 extra").is_err());
+        assert!(
+            QueryPlan::parse("MATCH (a:Event {importance: 0.5})<-[r]-(b) RETURN r, b.content")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_malformed_queries() {
+        // 查询及错误信息应包含的内容
+        let cases = [
+            ("", "Expected MATCH, found end of query"),
+            ("RETURN a", "Expected MATCH"),
+            ("MATCH a RETURN a", "Expected '('"),
+            ("MATCH (a RETURN a", "Expected ')'"),
+            ("MATCH (a:) RETURN a", "Expected identifier"),
+            ("MATCH (a {x 1}) RETURN a", "Expected ':'"),
+            ("MATCH (a)", "Expected RETURN, found end of query"),
+            (
+                "MATCH (a) RETURN",
+                "Expected identifier, found end of query",
+            ),
+            (
+                "MATCH (a) WHERE a.name = 'x RETURN a",
+                "Unterminated string literal",
+            ),
+            ("MATCH (`a) RETURN a", "Unterminated quoted identifier"),
+            ("MATCH (a) RETURN a; MATCH (b)", "Unexpected character: ;"),
+            (
+                "MATCH (a) WHERE a = 1 RETURN a",
+                "Expected property access on a",
+            ),
+            (
+                "MATCH (a) WHERE a.x 1 RETURN a",
+                "Expected comparison operator",
+            ),
+            (
+                "MATCH (a) RETURN a LIMIT -1",
+                "Expected non-negative integer",
+            ),
+            (
+                "MATCH (a) RETURN a LIMIT 1.5",
+                "Expected non-negative integer",
+            ),
+            ("MATCH (a)-[*3..1]->(b) RETURN b", "Invalid hop range: 3..1"),
+            (
+                "MATCH (a)<-[]->(b) RETURN b",
+                "Relationship cannot point both ways",
+            ),
+            (
+                "MATCH (a)-[r]->(b)-[r]->(c) RETURN c",
+                "Relationship variable used twice: r",
+            ),
+            ("MATCH (a) WHERE b.x = 1 RETURN a", "Undefined variable: b"),
+            ("MATCH (a) RETURN a ORDER BY c.x", "Undefined variable: c"),
+        ];
+
+        for (query, expected) in cases {
+            match QueryPlan::parse(query) {
+                Err(MemoryError::InvalidQuery { message }) => {
+                    assert!(message.contains(expected), "{query}: {message}")
+                }
+                other => panic!("{query}: unexpected result {:?}", other),
+            }
+        }
+    }
+
+    async fn create_chain_db() -> VectorGraphDB {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = VectorGraphDB::new(config).await.unwrap();
+
+        for (id, memory_type, importance) in [
+            ("m1", "Event", 0.5),
+            ("m2", "Event", 0.9),
+            ("m3", "Knowledge", 0.8),
+            ("m4", "Event", 0.2),
+        ] {
+            let mut properties = HashMap::new();
+            properties.insert("content".to_string(), json!(format!("记忆 {}", id)));
+            properties.insert("memory_type".to_string(), json!(memory_type));
+            properties.insert("importance".to_string(), json!(importance));
+            db.insert_node(&GraphNode {
+                id: id.to_string(),
+                node_type: "memory".to_string(),
+                properties,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        for (id, from, to, edge_type) in [
+            ("e1", "m1", "m2", "Causal"),
+            ("e2", "m2", "m3", "Causal"),
+            ("e3", "m3", "m4", "Causal"),
+            ("e4", "m1", "m4", "Temporal"),
+        ] {
+            db.insert_edge(&GraphEdge {
+                id: id.to_string(),
+                from_node: from.to_string(),
+                to_node: to.to_string(),
+                edge_type: edge_type.to_string(),
+                weight: 0.6,
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+        db
+    }
+
+    fn node_ids(rows: &[QueryRow], column: &str) -> Vec<String> {
+        rows.iter()
+            .filter_map(|row| match row.get(column) {
+                Some(QueryValue::Node(node)) => Some(node.id.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_execute_variable_length_causal_query() {
+        let db = create_chain_db().await;
+
+        let plan = QueryPlan::parse(
+            "MATCH (a:Event)-[:Causal*1..3]->(b) WHERE b.importance > 0.7 RETURN DISTINCT b",
+        )
+        .unwrap();
+        let rows = db.execute_query(&plan).await.unwrap();
+        assert_eq!(node_ids(&rows, "b"), vec!["m2", "m3"]);
+
+        // 反向、单跳、带关系变量
+        let plan =
+            QueryPlan::parse("MATCH (b)<-[r:causal]-(a) WHERE a.id = 'm2' RETURN b.id, r.type")
+                .unwrap();
+        let rows = db.execute_query(&plan).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values[0], QueryValue::Value(json!("m3")));
+        assert_eq!(
+            rows[0].get("r.type"),
+            Some(&QueryValue::Value(json!("Causal")))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_order_limit_and_paths() {
+        let db = create_chain_db().await;
+
+        let plan = QueryPlan::parse(
+            "MATCH (a {id: 'm1'})-[p*..3]->(b:Event) RETURN b, p ORDER BY b.importance DESC LIMIT 2",
+        )
+        .unwrap();
+        let rows = db.execute_query(&plan).await.unwrap();
+        assert_eq!(node_ids(&rows, "b"), vec!["m2", "m4"]);
+        match rows[1].get("p") {
+            Some(QueryValue::Path(edges)) => assert_eq!(edges.len(), 3),
+            other => panic!("unexpected value: {:?}", other),
+        }
+
+        let plan = QueryPlan::parse(
+            "MATCH (a)-[:Temporal|Causal]->(b) WHERE NOT b.memory_type = 'Event' RETURN a",
+        )
+        .unwrap();
+        let rows = db.execute_query(&plan).await.unwrap();
+        assert_eq!(node_ids(&rows, "a"), vec!["m2"]);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_labels_match_nothing() {
+        let db = create_chain_db().await;
+
+        // 不存在的标签、连接类型和属性不报错，只是没有匹配
+        for query in [
+            "MATCH (a:Meeting) RETURN a",
+            "MATCH (a)-[:Unknown]->(b) RETURN b",
+            "MATCH (a:Event)-[:Causal*1..3]->(b:Unknown) RETURN b",
+            "MATCH (a {mood: 'happy'}) RETURN a",
+            "MATCH (a) WHERE a.mood = 'happy' RETURN a",
+        ] {
+            let plan = QueryPlan::parse(query).unwrap();
+            let rows = db.execute_query(&plan).await.unwrap();
+            assert!(rows.is_empty(), "{query}: {:?}", rows);
+        }
+
+        // 标签不区分大小写，也可以匹配节点类型
+        let plan = QueryPlan::parse("MATCH (a:KNOWLEDGE) RETURN a").unwrap();
+        let rows = db.execute_query(&plan).await.unwrap();
+        assert_eq!(node_ids(&rows, "a"), vec!["m3"]);
+        let plan = QueryPlan::parse("MATCH (a:Memory) RETURN a").unwrap();
+        assert_eq!(db.execute_query(&plan).await.unwrap().len(), 4);
+
+        // 返回不存在的属性得到空值
+        let plan = QueryPlan::parse("MATCH (a {id: 'm1'}) RETURN a.mood").unwrap();
+        let rows = db.execute_query(&plan).await.unwrap();
+        assert_eq!(rows[0].get("a.mood"), Some(&QueryValue::Value(Value::Null)));
+    }
+}
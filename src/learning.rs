@@ -7,6 +7,8 @@ use crate::config::Config;
 use crate::core::*;
 use crate::database::VectorGraphDB;
 use crate::error::Result;
+use crate::graph::CommunityDetector;
+use crate::ranking::{RankerTrainer, LEARNED_FUSION_MODEL};
 use chrono::{DateTime, Duration, Utc};
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
@@ -34,6 +36,8 @@ pub struct LearningEngine {
     pattern_detector: PatternDetector,
     /// 反馈处理器
     feedback_processor: FeedbackProcessor,
+    /// 排序模型训练器
+    ranker_trainer: RankerTrainer,
     /// 学习统计
     stats: Arc<RwLock<LearningStats>>,
 }
@@ -106,6 +110,8 @@ pub struct PatternDetector {
     semantic_similarity_threshold: f32,
     /// 模式缓存
     pattern_cache: Arc<RwLock<HashMap<String, DetectedPattern>>>,
+    /// 社区检测器（用于主题模式）
+    community_detector: CommunityDetector,
 }
 
 /// 反馈处理器
@@ -253,6 +259,10 @@ pub enum LearningTask {
     },
     /// 反馈处理
     FeedbackProcessing { feedback_batch: Vec<FeedbackRecord> },
+    /// 用时间窗口内的展示和反馈日志训练排序模型
+    RankerTraining {
+        data_window: (DateTime<Utc>, DateTime<Utc>),
+    },
 }
 
 /// 重要性触发器
@@ -315,6 +325,7 @@ impl LearningEngine {
         let preference_learner = PreferenceLearner::new(&config);
         let pattern_detector = PatternDetector::new(&config);
         let feedback_processor = FeedbackProcessor::new(&config);
+        let ranker_trainer = RankerTrainer::new(&config);
 
         Ok(Self {
             db,
@@ -324,6 +335,7 @@ impl LearningEngine {
             preference_learner,
             pattern_detector,
             feedback_processor,
+            ranker_trainer,
             stats: Arc::new(RwLock::new(LearningStats::default())),
         })
     }
@@ -337,6 +349,7 @@ impl LearningEngine {
             LearningTask::PreferenceUpdate { .. } => "PreferenceUpdate",
             LearningTask::PatternDetection { .. } => "PatternDetection",
             LearningTask::FeedbackProcessing { .. } => "FeedbackProcessing",
+            LearningTask::RankerTraining { .. } => "RankerTraining",
         };
         info!("Executing learning task: {}", task_type_name);
 
@@ -365,6 +378,7 @@ impl LearningEngine {
             LearningTask::FeedbackProcessing { ref feedback_batch } => {
                 self.process_feedback_batch(feedback_batch.clone()).await?
             }
+            LearningTask::RankerTraining { data_window } => self.train_ranker(data_window).await?,
         };
 
         let execution_time = Utc::now() - start_time;
@@ -592,7 +606,7 @@ impl LearningEngine {
         for pattern_type in pattern_types {
             let patterns = self
                 .pattern_detector
-                .detect_patterns_of_type(&pattern_type, &data_window)
+                .detect_patterns_of_type(&pattern_type, &data_window, &self.db)
                 .await?;
             detected_patterns.extend(patterns);
         }
@@ -676,6 +690,61 @@ impl LearningEngine {
         })
     }
 
+    /// 训练排序模型
+    ///
+    /// 训练成功时保存模型，`accuracy_improvement` 为模型排序相对原展示顺序的NDCG提升。
+    async fn train_ranker(
+        &self,
+        data_window: (DateTime<Utc>, DateTime<Utc>),
+    ) -> Result<LearningResult> {
+        let impressions = self
+            .db
+            .get_impressions(data_window, self.config.ranking.max_impressions)
+            .await?;
+        let feedback = self.db.get_feedback(data_window).await?;
+        let dataset = self.ranker_trainer.build_dataset(&impressions, &feedback);
+        let samples: usize = dataset.iter().map(|d| d.items.len()).sum();
+        debug!(
+            "Training ranker on {} labeled samples from {} impressions",
+            samples,
+            impressions.len()
+        );
+
+        let mut details = HashMap::new();
+        details.insert(
+            "impressions".to_string(),
+            serde_json::json!(impressions.len()),
+        );
+        details.insert("labeled_samples".to_string(), serde_json::json!(samples));
+
+        let Some(ranker) = self.ranker_trainer.train(&dataset) else {
+            return Ok(LearningResult {
+                task_type: "RankerTraining".to_string(),
+                success: true,
+                changes_made: 0,
+                accuracy_improvement: None,
+                execution_time: Duration::zero(),
+                details,
+            });
+        };
+
+        self.db
+            .save_ranking_model(LEARNED_FUSION_MODEL, &ranker)
+            .await?;
+        let metrics = &ranker.metrics;
+        details.insert("metrics".to_string(), serde_json::json!(metrics));
+        details.insert("weights".to_string(), serde_json::json!(ranker.weights));
+
+        Ok(LearningResult {
+            task_type: "RankerTraining".to_string(),
+            success: true,
+            changes_made: 1,
+            accuracy_improvement: Some(metrics.ndcg - metrics.baseline_ndcg),
+            execution_time: Duration::zero(),
+            details,
+        })
+    }
+
     /// 执行定期学习循环
     pub async fn run_learning_cycle(&self) -> Result<Vec<LearningResult>> {
         info!("Starting learning cycle");
@@ -715,6 +784,22 @@ impl LearningEngine {
             results.push(self.execute_learning_task(feedback_task).await?);
         }
 
+        // 5. 训练排序模型
+        let window = Duration::days(self.config.ranking.training_window_days as i64);
+        if self.config.ranking.training_enabled {
+            let ranker_task = LearningTask::RankerTraining {
+                data_window: (now - window, now),
+            };
+            results.push(self.execute_learning_task(ranker_task).await?);
+        }
+
+        // 6. 删除训练窗口之外和超出数量上限的检索展示
+        let pruned = self
+            .db
+            .prune_impressions(now - window, self.config.ranking.max_impressions)
+            .await?;
+        debug!("Pruned {} retrieval impressions", pruned);
+
         info!("Learning cycle completed with {} tasks", results.len());
         Ok(results)
     }
@@ -728,6 +813,9 @@ impl LearningEngine {
     pub async fn record_feedback(&self, feedback: FeedbackRecord) -> Result<()> {
         info!("Recording feedback for memory: {}", feedback.memory_id);
 
+        // 反馈日志用于训练排序模型
+        self.db.insert_feedback(&feedback).await?;
+
         // 立即处理显式反馈
         if matches!(feedback.feedback_type, FeedbackType::Explicit) {
             self.feedback_processor.process_feedback(&feedback).await?;
@@ -919,12 +1007,13 @@ impl PreferenceLearner {
 }
 
 impl PatternDetector {
-    fn new(_config: &Config) -> Self {
+    fn new(config: &Config) -> Self {
         Self {
             min_pattern_frequency: 3,
             temporal_window: Duration::days(7),
             semantic_similarity_threshold: 0.8,
             pattern_cache: Arc::new(RwLock::new(HashMap::new())),
+            community_detector: CommunityDetector::new(config),
         }
     }
 
@@ -932,11 +1021,12 @@ impl PatternDetector {
         &self,
         pattern_type: &PatternType,
         data_window: &(DateTime<Utc>, DateTime<Utc>),
+        db: &VectorGraphDB,
     ) -> Result<Vec<DetectedPattern>> {
         match pattern_type {
             PatternType::AccessPattern => self.detect_access_patterns(data_window).await,
             PatternType::QueryPattern => self.detect_query_patterns(data_window).await,
-            PatternType::TopicPattern => self.detect_topic_patterns(data_window).await,
+            PatternType::TopicPattern => self.detect_topic_patterns(data_window, db).await,
             PatternType::TemporalPattern => self.detect_temporal_patterns(data_window).await,
             PatternType::ConnectionPattern => self.detect_connection_patterns(data_window).await,
             PatternType::FeedbackPattern => self.detect_feedback_patterns(data_window).await,
@@ -959,12 +1049,45 @@ impl PatternDetector {
         Ok(Vec::new())
     }
 
+    /// 主题模式：在窗口内有新成员的社区
     async fn detect_topic_patterns(
         &self,
-        _data_window: &(DateTime<Utc>, DateTime<Utc>),
+        data_window: &(DateTime<Utc>, DateTime<Utc>),
+        db: &VectorGraphDB,
     ) -> Result<Vec<DetectedPattern>> {
-        // TODO: 实现主题模式检测
-        Ok(Vec::new())
+        let communities = self.community_detector.detect(db).await?;
+        let mut patterns = Vec::new();
+
+        for community in communities {
+            let mut created: Vec<DateTime<Utc>> = Vec::new();
+            for member_id in &community.members {
+                if let Ok(node) = db.get_node(member_id).await {
+                    created.push(node.created_at);
+                }
+            }
+
+            let frequency = created
+                .iter()
+                .filter(|t| **t >= data_window.0 && **t <= data_window.1)
+                .count() as u32;
+            if frequency == 0 {
+                continue;
+            }
+
+            let now = Utc::now();
+            patterns.push(DetectedPattern {
+                pattern_id: format!("topic_{}", community.id),
+                pattern_type: PatternType::TopicPattern,
+                frequency,
+                confidence: community.cohesion,
+                elements: community.keywords.clone(),
+                temporal_signature: None,
+                first_detected: created.iter().min().copied().unwrap_or(now),
+                last_seen: created.iter().max().copied().unwrap_or(now),
+            });
+        }
+
+        Ok(patterns)
     }
 
     async fn detect_temporal_patterns(
@@ -1066,15 +1189,11 @@ impl FeedbackProcessor {
 mod tests {
     use super::*;
     use crate::database::VectorGraphDB;
-    use tempfile::NamedTempFile;
 
     async fn create_test_learning_engine() -> LearningEngine {
-        let temp_file = NamedTempFile::new().unwrap();
-        let db_url = format!("sqlite://{}", temp_file.path().display());
-
         let config = Config {
             database: crate::config::DatabaseConfig {
-                url: db_url,
+                url: "sqlite::memory:".to_string(),
                 ..Default::default()
             },
             ..Default::default()
@@ -1113,6 +1232,42 @@ mod tests {
         assert!(result.success);
     }
 
+    #[tokio::test]
+    async fn test_topic_pattern_detection() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+
+        for (id, embedding) in [("t1", vec![1.0, 0.0]), ("t2", vec![0.95, 0.05])] {
+            let mut properties = HashMap::new();
+            properties.insert("content".to_string(), serde_json::json!("咖啡 拿铁"));
+            properties.insert("keywords".to_string(), serde_json::json!(["咖啡"]));
+            properties.insert("embedding".to_string(), serde_json::json!(embedding));
+            db.insert_node(&crate::database::GraphNode {
+                id: id.to_string(),
+                node_type: "memory".to_string(),
+                properties,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let engine = LearningEngine::new(db, config).await.unwrap();
+        let now = Utc::now();
+        let task = LearningTask::PatternDetection {
+            data_window: (now - Duration::days(1), now + Duration::minutes(1)),
+            pattern_types: vec![PatternType::TopicPattern],
+        };
+
+        let result = engine.execute_learning_task(task).await.unwrap();
+        assert_eq!(result.changes_made, 1);
+        let patterns = &result.details["detected_patterns"];
+        assert_eq!(patterns[0]["frequency"], 2);
+        assert_eq!(patterns[0]["elements"][0], "咖啡");
+    }
+
     #[tokio::test]
     async fn test_feedback_processing() {
         let engine = create_test_learning_engine().await;
@@ -1143,6 +1298,76 @@ mod tests {
         assert!(result.success);
     }
 
+    #[tokio::test]
+    async fn test_ranker_training_task() {
+        use crate::ranking::{ImpressionItem, RankingFeatures, RetrievalImpression};
+
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let engine = LearningEngine::new(db.clone(), config).await.unwrap();
+
+        // 按语义分数展示，用户点击的是排在第三、时间分数最高的结果
+        let start = Utc::now() - Duration::minutes(1);
+        for i in 0..10 {
+            let query = format!("查询{}", i);
+            let items = [(0.9, 0.1), (0.8, 0.2), (0.6, 0.9)]
+                .iter()
+                .enumerate()
+                .map(|(position, (semantic, temporal))| ImpressionItem {
+                    memory_id: format!("m{}", position),
+                    position,
+                    features: RankingFeatures {
+                        semantic: *semantic,
+                        temporal: *temporal,
+                        structural: 0.0,
+                        importance: 0.5,
+                        personalization: 0.0,
+                        recency: 1.0,
+                        access_frequency: 0.0,
+                    },
+                })
+                .collect();
+            let impression = RetrievalImpression {
+                id: format!("impression_{}", i),
+                query: query.clone(),
+                session_id: None,
+                items,
+                timestamp: start,
+            };
+            db.insert_impression(&impression).await.unwrap();
+
+            let context = FeedbackContext {
+                query,
+                result_position: 2,
+                session_id: "session_1".to_string(),
+                device_type: None,
+                time_of_day: 9,
+                day_of_week: 2,
+            };
+            let feedback = FeedbackRecord {
+                memory_id: "m2".to_string(),
+                feedback_type: FeedbackType::Click,
+                score: 1.0,
+                context,
+                timestamp: Utc::now(),
+            };
+            engine.record_feedback(feedback).await.unwrap();
+        }
+
+        let task = LearningTask::RankerTraining {
+            data_window: (start - Duration::minutes(1), Utc::now()),
+        };
+        let result = engine.execute_learning_task(task).await.unwrap();
+        assert_eq!(result.task_type, "RankerTraining");
+        assert_eq!(result.changes_made, 1);
+        assert!((result.accuracy_improvement.unwrap() - 0.5).abs() < 1e-6);
+        assert_eq!(result.details["labeled_samples"], 30);
+
+        let ranker = db.load_ranking_model(LEARNED_FUSION_MODEL).await.unwrap();
+        assert_eq!(ranker.unwrap().metrics.validation_samples, 6);
+    }
+
     #[tokio::test]
     async fn test_learning_cycle() {
         let engine = create_test_learning_engine().await;
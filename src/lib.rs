@@ -50,6 +50,7 @@
 //!         offset: None,
 //!         sort_by: None,
 //!         weights: QueryWeights::default(),
+//!         expansion: None,
 //!     };
 //!     
 //!     let context = Context::default();
@@ -63,10 +64,33 @@
 //! ## 模块结构
 //!
 //! - `core`: 核心数据结构和类型定义
+//! - `access`: 记忆访问统计的累积与批量写回
+//! - `attributes`: 语言检测、关键词、情感和标签等记忆属性的规则提取
+//! - `causal`: 写入时的因果关系检测
+//! - `classifier`: 根据内容和上下文推断记忆类型
+//! - `context_builder`: 在token预算内把检索结果组装成提示词中的记忆块
+//! - `dedup`: 基于嵌入相似度和SimHash/MinHash的近重复检测与合并
+//! - `diversity`: 融合后按MMR或DPP重排，压低近重复的检索结果
+//! - `evolution`: 新记忆写入时更新近邻记忆的连接、标签和上下文
+//! - `fusion`: 多检索器结果的分数归一化与融合（线性加权、RRF、CombSUM/CombMNZ）
 //! - `storage`: 存储层实现，包括向量数据库和图数据库
 //! - `memory`: 记忆管理模块，包括创建、链接和演化
+//! - `graph`: 图分析模块，包括社区检测和主题聚类
+//! - `entity`: HippoRAG风格的实体抽取与实体-记忆知识图
+//! - `graph_query`: 类Cypher的图查询语言
+//! - `pagerank`: 增量维护的全局PageRank和带缓存的个性化PageRank
+//! - `pipeline`: 记忆管理器和HippoRAG检索器共用的检索流水线（候选生成、过滤、融合、重排序、后处理）
+//! - `query_analysis`: 查询意图分析，为动态融合生成检索计划
+//! - `query_expansion`: 同义词、伪相关反馈和大语言模型改写的查询扩展
+//! - `ranking`: 检索展示与反馈日志，以及学习融合使用的排序模型训练
+//! - `rerank`: 融合后候选的重排序（查询词覆盖率、大语言模型判断、交叉编码器）
+//! - `retention`: Ebbinghaus风格的遗忘曲线与记忆强化
 //! - `retrieval`: 检索引擎，支持多种检索策略
 //! - `learning`: 学习模块，实现持续学习和适应
+//! - `llm`: 可插拔的大语言模型提供者接口
+//! - `llm_tasks`: 基于大语言模型的属性提取、情感分析、查询理解等类型化任务
+//! - `segment`: 基于词典最大匹配的中文分词
+//! - `time_expr`: 查询中中英文时间表达式的解析
 //! - `utils`: 工具函数和辅助模块
 //! - `examples`: 使用示例和演示代码
 //!
@@ -88,25 +112,71 @@
 //! decay_rate = 0.001
 //! ```
 
+pub mod access;
+pub mod attributes;
+pub mod causal;
+pub mod classifier;
 pub mod config;
+pub mod context_builder;
 pub mod core;
 pub mod database;
+pub mod dedup;
+pub mod diversity;
+pub mod entity;
 pub mod error;
+pub mod evolution;
+pub mod fusion;
+pub mod graph;
+pub mod graph_query;
 pub mod learning;
+pub mod llm;
+pub mod llm_tasks;
 pub mod memory;
+pub mod pagerank;
+pub mod pipeline;
+pub mod query_analysis;
+pub mod query_expansion;
+pub mod ranking;
+pub mod rerank;
+pub mod retention;
 pub mod retrieval;
+pub mod segment;
+pub mod time_expr;
 pub mod utils;
 
 #[cfg(feature = "examples")]
 pub mod examples;
 
 // 重新导出核心类型
+pub use access::*;
+pub use attributes::*;
+pub use causal::*;
+pub use classifier::*;
+pub use context_builder::*;
 pub use core::*;
 pub use database::*;
+pub use dedup::*;
+pub use diversity::*;
+pub use entity::*;
 pub use error::*;
+pub use evolution::*;
+pub use fusion::*;
+pub use graph::*;
+pub use graph_query::*;
 pub use learning::*;
+pub use llm::*;
+pub use llm_tasks::*;
 pub use memory::*;
+pub use pagerank::*;
+pub use pipeline::*;
+pub use query_analysis::*;
+pub use query_expansion::*;
+pub use ranking::*;
+pub use rerank::*;
+pub use retention::*;
 pub use retrieval::*;
+pub use segment::*;
+pub use time_expr::*;
 
 /// 预导入模块，包含最常用的类型和函数
 pub mod prelude {
@@ -0,0 +1,699 @@
+//! 大语言模型模块
+//!
+//! 定义可插拔的大语言模型提供者接口（聊天补全风格），并提供：
+//!
+//! - OpenAI兼容的HTTP实现 [`OpenAiCompatibleProvider`]
+//! - 按脚本返回结果的模拟实现 [`ScriptedLlmProvider`]，用于测试
+//! - 类型化任务 [`LlmTask`] 与执行器 [`LlmTaskRunner`]：按JSON Schema校验模型输出，
+//!   失败时重试，重试耗尽后回退到内置的启发式实现
+
+use crate::config::LlmConfig;
+use crate::error::{MemoryError, Result};
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 聊天消息角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+/// 聊天消息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::System,
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::User,
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+/// 聊天补全请求
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatRequest {
+    pub messages: Vec<ChatMessage>,
+    /// 采样温度（未设置时使用提供者默认值）
+    pub temperature: Option<f32>,
+    /// 最大生成token数（未设置时使用提供者默认值）
+    pub max_tokens: Option<u32>,
+    /// 是否要求输出JSON对象
+    pub json_mode: bool,
+}
+
+impl ChatRequest {
+    pub fn new(messages: Vec<ChatMessage>) -> Self {
+        Self {
+            messages,
+            temperature: None,
+            max_tokens: None,
+            json_mode: false,
+        }
+    }
+}
+
+/// 聊天补全结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatResponse {
+    pub content: String,
+    /// 实际使用的模型
+    pub model: String,
+    /// 消耗的token数
+    pub total_tokens: Option<u64>,
+}
+
+/// 大语言模型提供者
+///
+/// 实现者负责把聊天消息发送给具体的模型服务并返回补全文本。
+#[async_trait]
+pub trait LlmProvider: Send + Sync + std::fmt::Debug {
+    /// 提供者名称
+    fn name(&self) -> &str;
+
+    /// 聊天补全
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse>;
+
+    /// 根据单条提示词生成补全文本
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        let request = ChatRequest::new(vec![ChatMessage::user(prompt)]);
+        Ok(self.chat(&request).await?.content)
+    }
+}
+
+/// OpenAI兼容的聊天补全接口
+///
+/// 适用于OpenAI以及vLLM、Ollama、ai00-server等提供 `/chat/completions` 的服务。
+#[derive(Clone)]
+pub struct OpenAiCompatibleProvider {
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
+    temperature: f32,
+    max_tokens: u32,
+    timeout: Duration,
+}
+
+impl std::fmt::Debug for OpenAiCompatibleProvider {
+    // 手动实现以免API密钥出现在日志中
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenAiCompatibleProvider")
+            .field("endpoint", &self.endpoint)
+            .field("api_key", &"<redacted>")
+            .field("model", &self.model)
+            .field("temperature", &self.temperature)
+            .field("max_tokens", &self.max_tokens)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl OpenAiCompatibleProvider {
+    pub fn new(config: &LlmConfig) -> Result<Self> {
+        if config.base_url.is_empty() {
+            return Err(MemoryError::validation_error(
+                "LLM base URL cannot be empty".to_string(),
+            ));
+        }
+
+        let api_key = config
+            .api_key
+            .clone()
+            .or_else(|| std::env::var(&config.api_key_env).ok())
+            .filter(|key| !key.is_empty());
+
+        Ok(Self {
+            endpoint: format!("{}/chat/completions", config.base_url.trim_end_matches('/')),
+            api_key,
+            model: config.model.clone(),
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+            timeout: Duration::from_secs(config.timeout_seconds.max(1)),
+        })
+    }
+
+    fn request_body(&self, request: &ChatRequest) -> Value {
+        let mut body = json!({
+            "model": self.model,
+            "messages": request.messages,
+            "temperature": request.temperature.unwrap_or(self.temperature),
+            "max_tokens": request.max_tokens.unwrap_or(self.max_tokens),
+        });
+        if request.json_mode {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+        body
+    }
+
+    fn parse_body(body: &str) -> Result<ChatResponse> {
+        let value: Value = serde_json::from_str(body).map_err(MemoryError::Serialization)?;
+        let content = value["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| MemoryError::llm_error("Response contains no message content"))?;
+
+        Ok(ChatResponse {
+            content: content.to_string(),
+            model: value["model"].as_str().unwrap_or_default().to_string(),
+            total_tokens: value["usage"]["total_tokens"].as_u64(),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &str {
+        &self.model
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse> {
+        let body = serde_json::to_string(&self.request_body(request))?;
+        let response =
+            post_json(&self.endpoint, self.api_key.as_deref(), self.timeout, body).await?;
+        Self::parse_body(&response)
+    }
+}
+
+/// 向模型服务POST一个JSON请求体，返回响应文本
+pub(crate) async fn post_json(
+    endpoint: &str,
+    api_key: Option<&str>,
+    timeout: Duration,
+    body: String,
+) -> Result<String> {
+    let endpoint = endpoint.to_string();
+    let api_key = api_key.map(str::to_string);
+
+    // ureq是阻塞客户端，放到阻塞线程池中执行
+    tokio::task::spawn_blocking(move || {
+        let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+        let mut call = agent
+            .post(&endpoint)
+            .set("Content-Type", "application/json");
+        if let Some(key) = &api_key {
+            call = call.set("Authorization", &format!("Bearer {}", key));
+        }
+
+        match call.send_string(&body) {
+            Ok(response) => response.into_string().map_err(MemoryError::Io),
+            Err(ureq::Error::Status(status, response)) => {
+                let detail = response.into_string().unwrap_or_default();
+                Err(MemoryError::llm_error(format!(
+                    "HTTP {} from {}: {}",
+                    status, endpoint, detail
+                )))
+            }
+            Err(ureq::Error::Transport(e)) => Err(MemoryError::llm_error(format!(
+                "Request to {} failed: {}",
+                endpoint, e
+            ))),
+        }
+    })
+    .await
+    .map_err(|e| MemoryError::internal(format!("LLM request task failed: {}", e)))?
+}
+
+/// 按脚本返回结果的模拟提供者
+///
+/// 依次返回预设的回复或错误，并记录收到的请求，用于测试。
+#[derive(Debug, Default)]
+pub struct ScriptedLlmProvider {
+    responses: Mutex<VecDeque<std::result::Result<String, String>>>,
+    requests: Mutex<Vec<ChatRequest>>,
+}
+
+impl ScriptedLlmProvider {
+    pub fn new<I, S>(responses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let provider = Self::default();
+        for response in responses {
+            provider.push_response(response);
+        }
+        provider
+    }
+
+    /// 追加一条回复
+    pub fn push_response(&self, response: impl Into<String>) {
+        self.responses
+            .lock()
+            .unwrap()
+            .push_back(Ok(response.into()));
+    }
+
+    /// 追加一次调用失败
+    pub fn push_error(&self, message: impl Into<String>) {
+        self.responses
+            .lock()
+            .unwrap()
+            .push_back(Err(message.into()));
+    }
+
+    /// 已收到的请求
+    pub fn requests(&self) -> Vec<ChatRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// 尚未消费的脚本条数
+    pub fn remaining(&self) -> usize {
+        self.responses.lock().unwrap().len()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ScriptedLlmProvider {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    async fn chat(&self, request: &ChatRequest) -> Result<ChatResponse> {
+        self.requests.lock().unwrap().push(request.clone());
+        match self.responses.lock().unwrap().pop_front() {
+            Some(Ok(content)) => Ok(ChatResponse {
+                content,
+                model: "scripted".to_string(),
+                total_tokens: None,
+            }),
+            Some(Err(message)) => Err(MemoryError::llm_error(message)),
+            None => Err(MemoryError::llm_error("Scripted responses exhausted")),
+        }
+    }
+}
+
+/// 类型化的大语言模型任务
+///
+/// 任务以JSON形式发送请求，并要求模型返回符合 [`LlmTask::schema`] 的JSON。
+#[async_trait]
+pub trait LlmTask: Send + Sync {
+    type Request: Serialize + Send + Sync;
+    type Response: DeserializeOwned + Send;
+
+    /// 任务名称
+    fn name(&self) -> &'static str;
+
+    /// 任务说明（系统提示词）
+    fn instructions(&self) -> String;
+
+    /// 输出的JSON Schema
+    fn schema(&self) -> Value;
+
+    /// 发送给模型的输入（默认直接序列化请求）
+    fn input(&self, request: &Self::Request) -> Result<Value> {
+        Ok(serde_json::to_value(request)?)
+    }
+
+    /// 对通过校验的输出做后处理（例如把分数截断到合法范围）
+    fn post_process(&self, response: Self::Response) -> Self::Response {
+        response
+    }
+
+    /// 模型不可用时的启发式实现
+    async fn fallback(&self, request: &Self::Request) -> Result<Self::Response>;
+}
+
+/// 类型化任务执行器
+///
+/// 模型调用失败或输出未通过Schema校验时重试（把错误反馈给模型），
+/// 重试耗尽后回退到任务的启发式实现。
+#[derive(Debug, Clone)]
+pub struct LlmTaskRunner {
+    provider: Arc<dyn LlmProvider>,
+    max_retries: usize,
+    retry_backoff: Duration,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    json_mode: bool,
+}
+
+impl LlmTaskRunner {
+    pub fn new(provider: Arc<dyn LlmProvider>, config: &LlmConfig) -> Self {
+        Self {
+            provider,
+            max_retries: config.max_retries,
+            retry_backoff: Duration::from_millis(config.retry_backoff_ms),
+            temperature: Some(config.temperature),
+            max_tokens: Some(config.max_tokens),
+            json_mode: config.json_mode,
+        }
+    }
+
+    pub fn provider(&self) -> &Arc<dyn LlmProvider> {
+        &self.provider
+    }
+
+    /// 执行任务，失败时回退到启发式实现
+    pub async fn run<T: LlmTask>(&self, task: &T, request: &T::Request) -> Result<T::Response> {
+        match self.run_strict(task, request).await {
+            Ok(response) => Ok(response),
+            Err(e) => {
+                warn!(
+                    "LLM task {} via {} failed, using fallback: {}",
+                    task.name(),
+                    self.provider.name(),
+                    e
+                );
+                task.fallback(request).await
+            }
+        }
+    }
+
+    /// 执行任务，重试耗尽后返回错误而不回退
+    pub async fn run_strict<T: LlmTask>(
+        &self,
+        task: &T,
+        request: &T::Request,
+    ) -> Result<T::Response> {
+        let schema = task.schema();
+        let mut messages = vec![
+            ChatMessage::system(format!(
+                "{}\n只返回一个符合下面JSON Schema的JSON值，不要输出其他内容：\n{}",
+                task.instructions(),
+                schema
+            )),
+            ChatMessage::user(task.input(request)?.to_string()),
+        ];
+
+        let mut last_error = MemoryError::llm_error("LLM task was not attempted");
+        for attempt in 0..=self.max_retries {
+            if attempt > 0 && !self.retry_backoff.is_zero() {
+                tokio::time::sleep(self.retry_backoff * attempt as u32).await;
+            }
+
+            let chat = ChatRequest {
+                messages: messages.clone(),
+                temperature: self.temperature,
+                max_tokens: self.max_tokens,
+                json_mode: self.json_mode,
+            };
+            let content = match self.provider.chat(&chat).await {
+                Ok(response) => response.content,
+                Err(e) => {
+                    debug!("LLM task {} attempt {} failed: {}", task.name(), attempt, e);
+                    last_error = e;
+                    continue;
+                }
+            };
+
+            match Self::parse_output::<T>(&content, &schema) {
+                Ok(response) => return Ok(task.post_process(response)),
+                Err(e) => {
+                    debug!(
+                        "LLM task {} attempt {} returned invalid output: {}",
+                        task.name(),
+                        attempt,
+                        e
+                    );
+                    messages.push(ChatMessage::assistant(content));
+                    messages.push(ChatMessage::user(format!(
+                        "上一次的输出无效：{}。请只返回符合Schema的JSON。",
+                        e
+                    )));
+                    last_error = e;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    fn parse_output<T: LlmTask>(content: &str, schema: &Value) -> Result<T::Response> {
+        let json = extract_json(content)
+            .ok_or_else(|| MemoryError::llm_error("Output contains no JSON value"))?;
+        let value: Value = serde_json::from_str(json).map_err(MemoryError::Serialization)?;
+        validate_json(&value, schema).map_err(MemoryError::validation_error)?;
+        serde_json::from_value(value).map_err(MemoryError::Serialization)
+    }
+}
+
+/// 从模型输出中截取JSON对象或数组（容忍代码块和前后说明文字）
+pub fn extract_json(text: &str) -> Option<&str> {
+    let start = text.find(['{', '['])?;
+    let close = if text[start..].starts_with('{') {
+        '}'
+    } else {
+        ']'
+    };
+    let end = text.rfind(close)?;
+    (start < end).then(|| &text[start..=end])
+}
+
+/// 按JSON Schema校验值
+///
+/// 支持 `type`、`properties`、`required`、`items`、`enum`、`minimum`、`maximum`
+/// 以及 `additionalProperties` 的子集，错误信息包含出错位置。
+pub fn validate_json(value: &Value, schema: &Value) -> std::result::Result<(), String> {
+    validate_at(value, schema, "$")
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str) -> std::result::Result<(), String> {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| type_matches(value, t)) {
+            return Err(format!("{} should be {}", path, types.join(" or ")));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return Err(format!(
+                "{} should be one of {}",
+                path,
+                Value::from(allowed.clone())
+            ));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                return Err(format!("{} should be >= {}", path, minimum));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                return Err(format!("{} should be <= {}", path, maximum));
+            }
+        }
+    }
+
+    if let Value::Object(object) = value {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    return Err(format!("{} is missing required field '{}'", path, key));
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, field) in object {
+            let field_path = format!("{}.{}", path, key);
+            match properties.and_then(|p| p.get(key)) {
+                Some(field_schema) => validate_at(field, field_schema, &field_path)?,
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        return Err(format!("{} is not allowed", field_path))
+                    }
+                    Some(extra) if extra.is_object() => validate_at(field, extra, &field_path)?,
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_at(item, item_schema, &format!("{}[{}]", path, i))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn type_matches(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Score {
+        score: f32,
+    }
+
+    struct ScoreTask;
+
+    #[async_trait]
+    impl LlmTask for ScoreTask {
+        type Request = String;
+        type Response = Score;
+
+        fn name(&self) -> &'static str {
+            "score"
+        }
+
+        fn instructions(&self) -> String {
+            "给文本打分".to_string()
+        }
+
+        fn schema(&self) -> Value {
+            json!({
+                "type": "object",
+                "required": ["score"],
+                "properties": { "score": { "type": "number", "minimum": 0, "maximum": 1 } }
+            })
+        }
+
+        async fn fallback(&self, _request: &String) -> Result<Score> {
+            Ok(Score { score: 0.5 })
+        }
+    }
+
+    fn runner(provider: Arc<ScriptedLlmProvider>) -> LlmTaskRunner {
+        let config = LlmConfig {
+            retry_backoff_ms: 0,
+            ..Default::default()
+        };
+        LlmTaskRunner::new(provider, &config)
+    }
+
+    #[test]
+    fn test_validate_json() {
+        let schema = ScoreTask.schema();
+        assert!(validate_json(&json!({"score": 0.3}), &schema).is_ok());
+        assert_eq!(
+            validate_json(&json!({"score": 3}), &schema).unwrap_err(),
+            "$.score should be <= 1"
+        );
+        assert!(validate_json(&json!({}), &schema).is_err());
+        assert!(validate_json(&json!([]), &schema).is_err());
+        assert_eq!(
+            extract_json("```json\n{\"a\": [1]}\n```"),
+            Some("{\"a\": [1]}")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runner_retries_then_falls_back() {
+        let provider = Arc::new(ScriptedLlmProvider::new([
+            "{\"score\": 7}",
+            "{\"score\": 0.9}",
+        ]));
+        let score = runner(provider.clone())
+            .run(&ScoreTask, &"文本".to_string())
+            .await
+            .unwrap();
+        assert_eq!(score, Score { score: 0.9 });
+
+        // 第二次请求带上了校验错误反馈
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].messages[3]
+            .content
+            .contains("$.score should be <= 1"));
+
+        let provider = Arc::new(ScriptedLlmProvider::default());
+        provider.push_error("unavailable");
+        let score = runner(provider.clone())
+            .run(&ScoreTask, &"文本".to_string())
+            .await
+            .unwrap();
+        assert_eq!(score, Score { score: 0.5 });
+        assert_eq!(provider.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_openai_compatible_provider() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            // 读取到请求体结束（请求体是一个JSON对象）
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut buffer).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..n]);
+            }
+            let body = r#"{"model":"test-model","choices":[{"message":{"role":"assistant","content":"你好"}}],"usage":{"total_tokens":12}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let config = LlmConfig {
+            base_url: format!("http://{}/v1/", address),
+            api_key: Some("secret".to_string()),
+            ..Default::default()
+        };
+        let provider = OpenAiCompatibleProvider::new(&config).unwrap();
+        let debug = format!("{:?}", provider);
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("secret"));
+
+        let mut request = ChatRequest::new(vec![ChatMessage::user("hi")]);
+        request.json_mode = true;
+        let response = provider.chat(&request).await.unwrap();
+        assert_eq!(response.content, "你好");
+        assert_eq!(response.total_tokens, Some(12));
+
+        let raw = server.join().unwrap();
+        assert!(raw.starts_with("POST /v1/chat/completions"));
+        assert!(raw.contains("Bearer secret"));
+        assert!(raw.contains("\"response_format\":{\"type\":\"json_object\"}"));
+    }
+}
@@ -0,0 +1,1257 @@
+//! 大语言模型任务模块
+//!
+//! 按 `LLM集成接口文档.md` 定义的请求/响应格式实现各项类型化任务。
+//! 每个任务都带有启发式回退实现，模型不可用或输出无效时使用。
+
+use crate::attributes::{analyze_emotion, AttributeExtractor};
+use crate::causal::{
+    CausalAnalysisRequest, CausalAnalysisResponse, CausalAnalyzer, CausalRelationType,
+    RuleCausalDetector,
+};
+use crate::classifier::{MemoryTypeClassification, RuleMemoryClassifier};
+use crate::config::Config;
+use crate::core::{Context, Interaction, Memory, MemoryId, QueryType};
+use crate::error::Result;
+use crate::evolution::{
+    EvolutionAdvisor, EvolutionRequest, EvolutionResponse, RuleEvolutionAdvisor,
+};
+use crate::learning::FeedbackRecord;
+use crate::llm::LlmTask;
+use crate::rerank::KeywordOverlapReranker;
+use crate::segment::Segmenter;
+use crate::utils::{TextUtils, TimeUtils};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// 发送给模型的记忆摘要（不含嵌入向量）
+fn memory_input(memory: &Memory) -> Value {
+    json!({
+        "content": memory.content,
+        "memory_type": memory.memory_type,
+        "tags": memory.attributes.tags,
+        "keywords": memory.attributes.keywords,
+        "importance": memory.attributes.importance,
+        "timestamp": memory.metadata.created_at,
+    })
+}
+
+fn clamp_unit(value: f32) -> f32 {
+    if value.is_finite() {
+        value.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// 需要提取的属性类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttributeType {
+    Keywords,
+    Tags,
+    Emotion,
+    Importance,
+    Language,
+    Theme,
+}
+
+/// 记忆属性提取请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributeExtractionRequest {
+    pub content: String,
+    pub context: Context,
+    pub extraction_types: Vec<AttributeType>,
+}
+
+/// 记忆属性提取结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeExtractionResponse {
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub emotion: Option<String>,
+    pub importance: f32,
+    pub confidence: f32,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub themes: Vec<String>,
+}
+
+/// 记忆属性提取任务
+///
+/// 回退到基于规则的属性提取器。
+#[derive(Debug, Clone)]
+pub struct AttributeExtractionTask {
+    extractor: AttributeExtractor,
+}
+
+impl AttributeExtractionTask {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            extractor: AttributeExtractor::new(config),
+        }
+    }
+
+    /// 回退提取器使用指定的分词器
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.extractor = self.extractor.with_segmenter(segmenter);
+        self
+    }
+}
+
+#[async_trait]
+impl LlmTask for AttributeExtractionTask {
+    type Request = AttributeExtractionRequest;
+    type Response = AttributeExtractionResponse;
+
+    fn name(&self) -> &'static str {
+        "attribute_extraction"
+    }
+
+    fn instructions(&self) -> String {
+        "从记忆内容中提取extraction_types要求的属性：关键词、标签、情感、重要性（0到1）、\
+         语言代码（如zh、en）和主题，并给出整体置信度（0到1）。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["importance", "confidence"],
+            "properties": {
+                "keywords": { "type": "array", "items": { "type": "string" } },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "emotion": { "type": ["string", "null"] },
+                "importance": { "type": "number", "minimum": 0, "maximum": 1 },
+                "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                "language": { "type": ["string", "null"] },
+                "themes": { "type": "array", "items": { "type": "string" } }
+            }
+        })
+    }
+
+    fn input(&self, request: &AttributeExtractionRequest) -> Result<Value> {
+        Ok(json!({
+            "content": request.content,
+            "current_topic": request.context.current_topic,
+            "extraction_types": request.extraction_types,
+        }))
+    }
+
+    async fn fallback(
+        &self,
+        request: &AttributeExtractionRequest,
+    ) -> Result<AttributeExtractionResponse> {
+        Ok(self.extractor.extract(&request.content))
+    }
+}
+
+/// 记忆类型分类请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryTypeClassificationRequest {
+    pub content: String,
+    pub context: Context,
+}
+
+/// 记忆类型分类任务
+///
+/// 回退到基于规则特征的分类器。
+#[derive(Debug, Clone)]
+pub struct MemoryTypeClassificationTask {
+    classifier: RuleMemoryClassifier,
+}
+
+impl MemoryTypeClassificationTask {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            classifier: RuleMemoryClassifier::new(config),
+        }
+    }
+
+    /// 回退分类器使用指定的分词器
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.classifier = self.classifier.with_segmenter(segmenter);
+        self
+    }
+}
+
+#[async_trait]
+impl LlmTask for MemoryTypeClassificationTask {
+    type Request = MemoryTypeClassificationRequest;
+    type Response = MemoryTypeClassification;
+
+    fn name(&self) -> &'static str {
+        "memory_type_classification"
+    }
+
+    fn instructions(&self) -> String {
+        "判断记忆的类型：Knowledge（事实、概念、技能）、Event（具体发生的事情）、\
+         Task（待办事项、计划）、Conversation（聊天记录）、Reflection（总结、思考）、\
+         Goal（长期目标、愿望）、Habit（行为模式、偏好）或Emotion（情绪状态、感受），\
+         给出置信度（0到1）和简短理由。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["memory_type", "confidence"],
+            "properties": {
+                "memory_type": {
+                    "enum": [
+                        "Knowledge",
+                        "Event",
+                        "Task",
+                        "Conversation",
+                        "Reflection",
+                        "Goal",
+                        "Habit",
+                        "Emotion"
+                    ]
+                },
+                "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                "reason": { "type": "string" }
+            }
+        })
+    }
+
+    fn input(&self, request: &MemoryTypeClassificationRequest) -> Result<Value> {
+        Ok(json!({
+            "content": request.content,
+            "current_topic": request.context.current_topic,
+        }))
+    }
+
+    async fn fallback(
+        &self,
+        request: &MemoryTypeClassificationRequest,
+    ) -> Result<MemoryTypeClassification> {
+        Ok(self
+            .classifier
+            .classify_text(&request.content, &request.context))
+    }
+}
+
+/// 关键词提取请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordExtractionRequest {
+    pub text: String,
+    pub max_keywords: usize,
+    pub language: Option<String>,
+    /// 领域信息，如"技术"、"医学"等
+    pub domain: Option<String>,
+}
+
+/// 关键词信息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeywordInfo {
+    pub word: String,
+    /// 关键词重要性评分
+    pub score: f32,
+    /// 关键词类别
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// 关键词提取结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeywordExtractionResponse {
+    pub keywords: Vec<KeywordInfo>,
+}
+
+/// 关键词提取任务
+#[derive(Debug, Clone, Default)]
+pub struct KeywordExtractionTask;
+
+#[async_trait]
+impl LlmTask for KeywordExtractionTask {
+    type Request = KeywordExtractionRequest;
+    type Response = KeywordExtractionResponse;
+
+    fn name(&self) -> &'static str {
+        "keyword_extraction"
+    }
+
+    fn instructions(&self) -> String {
+        "从文本中提取最多max_keywords个关键词，按重要性从高到低排列，\
+         score为0到1的重要性评分，category为关键词类别。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["keywords"],
+            "properties": {
+                "keywords": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["word", "score"],
+                        "properties": {
+                            "word": { "type": "string" },
+                            "score": { "type": "number", "minimum": 0, "maximum": 1 },
+                            "category": { "type": ["string", "null"] }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    async fn fallback(
+        &self,
+        request: &KeywordExtractionRequest,
+    ) -> Result<KeywordExtractionResponse> {
+        let words = TextUtils::extract_keywords(&request.text, request.max_keywords);
+        let total = words.len().max(1) as f32;
+        let keywords = words
+            .into_iter()
+            .enumerate()
+            .map(|(i, word)| KeywordInfo {
+                word,
+                score: 1.0 - i as f32 / total,
+                category: None,
+            })
+            .collect();
+
+        Ok(KeywordExtractionResponse { keywords })
+    }
+}
+
+/// 因果关系分析任务
+///
+/// 回退到基于规则的因果检测器。
+#[derive(Debug, Clone)]
+pub struct CausalAnalysisTask {
+    detector: RuleCausalDetector,
+}
+
+impl CausalAnalysisTask {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            detector: RuleCausalDetector::new(config),
+        }
+    }
+
+    /// 回退检测器使用指定的分词器
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.detector = self.detector.with_segmenter(segmenter);
+        self
+    }
+}
+
+#[async_trait]
+impl LlmTask for CausalAnalysisTask {
+    type Request = CausalAnalysisRequest;
+    type Response = CausalAnalysisResponse;
+
+    fn name(&self) -> &'static str {
+        "causal_analysis"
+    }
+
+    fn instructions(&self) -> String {
+        "分析两条记忆之间的因果关系。relation_type取值为Cause（源记忆是目标记忆的原因）、\
+         Effect（源记忆是目标记忆的结果）、Correlation（相关但非因果）或None。\
+         confidence为0到1的置信度，evidence为支持判断的原文片段。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["has_causal_relation", "relation_type", "confidence"],
+            "properties": {
+                "has_causal_relation": { "type": "boolean" },
+                "relation_type": { "enum": ["Cause", "Effect", "Correlation", "None"] },
+                "confidence": { "type": "number" },
+                "explanation": { "type": "string" },
+                "causal_keywords": { "type": "array", "items": { "type": "string" } },
+                "evidence": { "type": "string" }
+            }
+        })
+    }
+
+    fn input(&self, request: &CausalAnalysisRequest) -> Result<Value> {
+        Ok(json!({
+            "source_memory": memory_input(&request.source_memory),
+            "target_memory": memory_input(&request.target_memory),
+            "context": request.context.iter().map(memory_input).collect::<Vec<_>>(),
+        }))
+    }
+
+    fn post_process(&self, mut response: CausalAnalysisResponse) -> CausalAnalysisResponse {
+        response.confidence = clamp_unit(response.confidence);
+        if response.relation_type == CausalRelationType::None {
+            response.has_causal_relation = false;
+        }
+        if response.evidence.is_empty() {
+            response.evidence = response.explanation.clone();
+        }
+        response
+    }
+
+    async fn fallback(&self, request: &CausalAnalysisRequest) -> Result<CausalAnalysisResponse> {
+        self.detector.analyze(request).await
+    }
+}
+
+/// 记忆演化任务
+///
+/// 回退到基于规则的演化决策器。
+#[derive(Debug, Clone)]
+pub struct MemoryEvolutionTask {
+    advisor: RuleEvolutionAdvisor,
+}
+
+impl MemoryEvolutionTask {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            advisor: RuleEvolutionAdvisor::new(config),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmTask for MemoryEvolutionTask {
+    type Request = EvolutionRequest;
+    type Response = EvolutionResponse;
+
+    fn name(&self) -> &'static str {
+        "memory_evolution"
+    }
+
+    fn instructions(&self) -> String {
+        "一条新记忆加入了记忆网络，判断它的每个近邻是否需要演化。对需要调整的近邻给出：\
+         link_strength为它与新记忆之间连接的新强度（0到1，不调整则省略），\
+         add_tags为要补充的标签，context为改写后的上下文描述（不改写则省略）。\
+         不需要调整的近邻可以不列出。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["evolutions"],
+            "properties": {
+                "evolutions": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["memory_id"],
+                        "properties": {
+                            "memory_id": { "type": "string" },
+                            "link_strength": { "type": ["number", "null"] },
+                            "add_tags": { "type": "array", "items": { "type": "string" } },
+                            "context": { "type": ["string", "null"] }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn input(&self, request: &EvolutionRequest) -> Result<Value> {
+        Ok(json!({
+            "memory": memory_input(&request.memory),
+            "neighbours": request
+                .neighbours
+                .iter()
+                .map(|neighbour| {
+                    json!({
+                        "memory_id": neighbour.memory.id,
+                        "similarity": neighbour.similarity,
+                        "link_strength": neighbour.link_strength,
+                        "context": neighbour.memory.attributes.context,
+                        "memory": memory_input(&neighbour.memory),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        }))
+    }
+
+    fn post_process(&self, mut response: EvolutionResponse) -> EvolutionResponse {
+        for evolution in &mut response.evolutions {
+            evolution.link_strength = evolution.link_strength.map(clamp_unit);
+            let mut seen = HashSet::new();
+            evolution.add_tags = evolution
+                .add_tags
+                .iter()
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty() && seen.insert(tag.clone()))
+                .collect();
+            evolution.context = evolution
+                .context
+                .take()
+                .map(|context| context.trim().to_string())
+                .filter(|context| !context.is_empty());
+        }
+        response
+    }
+
+    async fn fallback(&self, request: &EvolutionRequest) -> Result<EvolutionResponse> {
+        self.advisor.advise(request).await
+    }
+}
+
+/// 专业水平
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ExpertiseLevel {
+    Beginner,
+    #[default]
+    Intermediate,
+    Advanced,
+    Expert,
+}
+
+/// 用户上下文
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct UserContext {
+    pub user_id: String,
+    pub interests: Vec<String>,
+    pub expertise_level: ExpertiseLevel,
+    pub current_goals: Vec<String>,
+}
+
+/// 重要性评估请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportanceAssessmentRequest {
+    pub memory: Memory,
+    pub user_context: UserContext,
+    pub historical_interactions: Vec<Interaction>,
+}
+
+/// 重要性因素
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportanceFactor {
+    pub factor: String,
+    pub weight: f32,
+    pub contribution: f32,
+}
+
+/// 重要性评估结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportanceAssessmentResponse {
+    pub importance_score: f32,
+    #[serde(default)]
+    pub reasoning: String,
+    #[serde(default)]
+    pub factors: Vec<ImportanceFactor>,
+}
+
+/// 重要性评估任务
+#[derive(Debug, Clone, Default)]
+pub struct ImportanceAssessmentTask;
+
+impl ImportanceAssessmentTask {
+    /// 记忆内容、标签或关键词中出现的兴趣和目标所占比例
+    fn interest_match(memory: &Memory, user: &UserContext) -> f32 {
+        let topics: Vec<String> = user
+            .interests
+            .iter()
+            .chain(&user.current_goals)
+            .map(|t| t.to_lowercase())
+            .collect();
+        if topics.is_empty() {
+            return 0.0;
+        }
+
+        let haystack = format!(
+            "{} {} {}",
+            memory.content,
+            memory.attributes.tags.join(" "),
+            memory.attributes.keywords.join(" ")
+        )
+        .to_lowercase();
+        let matched = topics
+            .iter()
+            .filter(|t| haystack.contains(t.as_str()))
+            .count();
+        matched as f32 / topics.len() as f32
+    }
+}
+
+#[async_trait]
+impl LlmTask for ImportanceAssessmentTask {
+    type Request = ImportanceAssessmentRequest;
+    type Response = ImportanceAssessmentResponse;
+
+    fn name(&self) -> &'static str {
+        "importance_assessment"
+    }
+
+    fn instructions(&self) -> String {
+        "结合用户的兴趣、专业水平、当前目标和历史交互，评估记忆对该用户的重要性（0到1），\
+         说明理由并列出各项因素的权重与贡献。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["importance_score"],
+            "properties": {
+                "importance_score": { "type": "number", "minimum": 0, "maximum": 1 },
+                "reasoning": { "type": "string" },
+                "factors": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["factor", "weight", "contribution"],
+                        "properties": {
+                            "factor": { "type": "string" },
+                            "weight": { "type": "number" },
+                            "contribution": { "type": "number" }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn input(&self, request: &ImportanceAssessmentRequest) -> Result<Value> {
+        Ok(json!({
+            "memory": memory_input(&request.memory),
+            "user_context": request.user_context,
+            "historical_interactions": request
+                .historical_interactions
+                .iter()
+                .map(|i| json!({ "query": i.query, "timestamp": i.timestamp }))
+                .collect::<Vec<_>>(),
+        }))
+    }
+
+    async fn fallback(
+        &self,
+        request: &ImportanceAssessmentRequest,
+    ) -> Result<ImportanceAssessmentResponse> {
+        let memory = &request.memory;
+        let interactions = &request.historical_interactions;
+        let retrieved = interactions
+            .iter()
+            .filter(|i| i.retrieved_memories.contains(&memory.id))
+            .count();
+        let usage = if interactions.is_empty() {
+            0.0
+        } else {
+            retrieved as f32 / interactions.len() as f32
+        };
+
+        let factors = vec![
+            ImportanceFactor {
+                factor: "基础重要性".to_string(),
+                weight: 0.5,
+                contribution: clamp_unit(memory.attributes.importance),
+            },
+            ImportanceFactor {
+                factor: "与用户兴趣匹配".to_string(),
+                weight: 0.3,
+                contribution: Self::interest_match(memory, &request.user_context),
+            },
+            ImportanceFactor {
+                factor: "历史使用频率".to_string(),
+                weight: 0.2,
+                contribution: usage,
+            },
+        ];
+        let importance_score = factors.iter().map(|f| f.weight * f.contribution).sum();
+
+        Ok(ImportanceAssessmentResponse {
+            importance_score: clamp_unit(importance_score),
+            reasoning: "根据基础重要性、兴趣匹配度和历史使用频率加权估计".to_string(),
+            factors,
+        })
+    }
+}
+
+/// 情感倾向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Sentiment {
+    Positive,
+    Negative,
+    Neutral,
+}
+
+/// 情感分析请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmotionAnalysisRequest {
+    pub text: String,
+    pub context: Option<String>,
+    pub language: Option<String>,
+}
+
+/// 情感分析结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmotionAnalysisResponse {
+    pub primary_emotion: String,
+    #[serde(default)]
+    pub emotion_scores: HashMap<String, f32>,
+    pub sentiment: Sentiment,
+    pub confidence: f32,
+}
+
+/// 情感分析任务
+#[derive(Debug, Clone, Default)]
+pub struct EmotionAnalysisTask;
+
+#[async_trait]
+impl LlmTask for EmotionAnalysisTask {
+    type Request = EmotionAnalysisRequest;
+    type Response = EmotionAnalysisResponse;
+
+    fn name(&self) -> &'static str {
+        "emotion_analysis"
+    }
+
+    fn instructions(&self) -> String {
+        "分析文本的情感倾向：给出主要情感、各情感的强度（0到1）、\
+         整体倾向（Positive、Negative或Neutral）和置信度（0到1）。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["primary_emotion", "sentiment", "confidence"],
+            "properties": {
+                "primary_emotion": { "type": "string" },
+                "emotion_scores": {
+                    "type": "object",
+                    "additionalProperties": { "type": "number", "minimum": 0, "maximum": 1 }
+                },
+                "sentiment": { "enum": ["Positive", "Negative", "Neutral"] },
+                "confidence": { "type": "number", "minimum": 0, "maximum": 1 }
+            }
+        })
+    }
+
+    async fn fallback(&self, request: &EmotionAnalysisRequest) -> Result<EmotionAnalysisResponse> {
+        Ok(analyze_emotion(&request.text))
+    }
+}
+
+/// 记忆访问模式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryAccess {
+    pub memory_id: MemoryId,
+    pub access_count: u64,
+    pub last_accessed: DateTime<Utc>,
+}
+
+/// 用户偏好分析请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreferenceAnalysisRequest {
+    pub user_id: String,
+    pub interaction_history: Vec<Interaction>,
+    pub memory_access_patterns: Vec<MemoryAccess>,
+    pub feedback_records: Vec<FeedbackRecord>,
+}
+
+/// 学习风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LearningStyle {
+    Visual,
+    Auditory,
+    Reading,
+    Kinesthetic,
+    Mixed,
+}
+
+/// 交互模式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InteractionPatterns {
+    pub preferred_time: String,
+    pub session_length: String,
+    pub query_complexity: String,
+}
+
+/// 用户偏好分析结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreferenceAnalysisResponse {
+    pub topic_preferences: HashMap<String, f32>,
+    pub learning_style: LearningStyle,
+    pub interaction_patterns: InteractionPatterns,
+    #[serde(default)]
+    pub recommendations: Vec<String>,
+}
+
+/// 用户偏好分析任务
+#[derive(Debug, Clone, Default)]
+pub struct PreferenceAnalysisTask;
+
+#[async_trait]
+impl LlmTask for PreferenceAnalysisTask {
+    type Request = PreferenceAnalysisRequest;
+    type Response = PreferenceAnalysisResponse;
+
+    fn name(&self) -> &'static str {
+        "preference_analysis"
+    }
+
+    fn instructions(&self) -> String {
+        "根据用户的交互历史、记忆访问模式和反馈记录，分析其主题偏好（0到1）、学习风格\
+         （Visual、Auditory、Reading、Kinesthetic或Mixed）、交互模式，并给出推荐。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["topic_preferences", "learning_style", "interaction_patterns"],
+            "properties": {
+                "topic_preferences": {
+                    "type": "object",
+                    "additionalProperties": { "type": "number", "minimum": 0, "maximum": 1 }
+                },
+                "learning_style": {
+                    "enum": ["Visual", "Auditory", "Reading", "Kinesthetic", "Mixed"]
+                },
+                "interaction_patterns": {
+                    "type": "object",
+                    "required": ["preferred_time", "session_length", "query_complexity"],
+                    "properties": {
+                        "preferred_time": { "type": "string" },
+                        "session_length": { "type": "string" },
+                        "query_complexity": { "type": "string" }
+                    }
+                },
+                "recommendations": { "type": "array", "items": { "type": "string" } }
+            }
+        })
+    }
+
+    async fn fallback(
+        &self,
+        request: &PreferenceAnalysisRequest,
+    ) -> Result<PreferenceAnalysisResponse> {
+        let history = &request.interaction_history;
+
+        let mut topic_counts: HashMap<String, usize> = HashMap::new();
+        let mut period_counts: HashMap<String, usize> = HashMap::new();
+        for interaction in history {
+            for keyword in TextUtils::extract_keywords(&interaction.query, 5) {
+                *topic_counts.entry(keyword).or_insert(0) += 1;
+            }
+            let period = TimeUtils::get_time_of_day_period(interaction.timestamp);
+            *period_counts.entry(period).or_insert(0) += 1;
+        }
+
+        let max_count = topic_counts.values().copied().max().unwrap_or(1) as f32;
+        let topic_preferences: HashMap<String, f32> = topic_counts
+            .into_iter()
+            .map(|(topic, n)| (topic, n as f32 / max_count))
+            .collect();
+
+        let preferred_time = period_counts
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+            .map(|(period, _)| period)
+            .unwrap_or_else(|| "unknown".to_string());
+        let session_length = match history.len() {
+            0..=3 => "short",
+            4..=10 => "medium",
+            _ => "long",
+        };
+        let average_query_chars = if history.is_empty() {
+            0.0
+        } else {
+            history
+                .iter()
+                .map(|i| i.query.chars().count())
+                .sum::<usize>() as f32
+                / history.len() as f32
+        };
+        let query_complexity = if average_query_chars < 10.0 {
+            "basic"
+        } else if average_query_chars < 40.0 {
+            "intermediate"
+        } else {
+            "advanced"
+        };
+
+        let mut top_topics: Vec<(&String, &f32)> = topic_preferences.iter().collect();
+        top_topics.sort_by(|a, b| b.1.total_cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        let recommendations = top_topics
+            .into_iter()
+            .take(3)
+            .map(|(topic, _)| format!("推荐更多{}相关内容", topic))
+            .collect();
+
+        Ok(PreferenceAnalysisResponse {
+            topic_preferences,
+            learning_style: LearningStyle::Mixed,
+            interaction_patterns: InteractionPatterns {
+                preferred_time,
+                session_length: session_length.to_string(),
+                query_complexity: query_complexity.to_string(),
+            },
+            recommendations,
+        })
+    }
+}
+
+/// 查询理解请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryUnderstandingRequest {
+    pub query: String,
+    pub context: Option<Context>,
+}
+
+/// 查询理解结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryUnderstandingResponse {
+    pub query_type: QueryType,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// 改写后更适合检索的查询
+    #[serde(default)]
+    pub rewritten_query: Option<String>,
+    pub confidence: f32,
+}
+
+const CAUSAL_QUERY_MARKERS: &[&str] = &["为什么", "原因", "导致", "why", "cause", "because"];
+const TEMPORAL_QUERY_MARKERS: &[&str] = &[
+    "昨天",
+    "今天",
+    "上周",
+    "最近",
+    "什么时候",
+    "when",
+    "yesterday",
+    "last week",
+    "recently",
+];
+
+/// 查询理解任务
+#[derive(Debug, Clone, Default)]
+pub struct QueryUnderstandingTask;
+
+#[async_trait]
+impl LlmTask for QueryUnderstandingTask {
+    type Request = QueryUnderstandingRequest;
+    type Response = QueryUnderstandingResponse;
+
+    fn name(&self) -> &'static str {
+        "query_understanding"
+    }
+
+    fn instructions(&self) -> String {
+        "理解用户的记忆检索查询：判断检索类型（Semantic、Temporal、Causal、Thematic、Mixed、\
+         GraphTraversal或PersonalizedPageRank），提取关键词，必要时改写查询，并给出置信度（0到1）。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["query_type", "confidence"],
+            "properties": {
+                "query_type": {
+                    "enum": [
+                        "Semantic",
+                        "Temporal",
+                        "Causal",
+                        "Thematic",
+                        "Mixed",
+                        "GraphTraversal",
+                        "PersonalizedPageRank"
+                    ]
+                },
+                "keywords": { "type": "array", "items": { "type": "string" } },
+                "rewritten_query": { "type": ["string", "null"] },
+                "confidence": { "type": "number", "minimum": 0, "maximum": 1 }
+            }
+        })
+    }
+
+    fn input(&self, request: &QueryUnderstandingRequest) -> Result<Value> {
+        Ok(json!({
+            "query": request.query,
+            "current_topic": request.context.as_ref().and_then(|c| c.current_topic.clone()),
+        }))
+    }
+
+    async fn fallback(
+        &self,
+        request: &QueryUnderstandingRequest,
+    ) -> Result<QueryUnderstandingResponse> {
+        let query = request.query.to_lowercase();
+        let has = |markers: &[&str]| markers.iter().any(|m| query.contains(m));
+        let query_type = match (has(CAUSAL_QUERY_MARKERS), has(TEMPORAL_QUERY_MARKERS)) {
+            (true, true) => QueryType::Mixed,
+            (true, false) => QueryType::Causal,
+            (false, true) => QueryType::Temporal,
+            (false, false) => QueryType::Semantic,
+        };
+
+        Ok(QueryUnderstandingResponse {
+            query_type,
+            keywords: TextUtils::extract_keywords(&request.query, 5),
+            rewritten_query: None,
+            confidence: 0.5,
+        })
+    }
+}
+
+/// 相关性判断请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelevanceJudgmentRequest {
+    pub query: String,
+    pub candidates: Vec<Memory>,
+}
+
+/// 单条候选的相关性判断
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RelevanceJudgment {
+    pub memory_id: MemoryId,
+    pub score: f32,
+}
+
+/// 相关性判断响应
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RelevanceJudgmentResponse {
+    pub judgments: Vec<RelevanceJudgment>,
+}
+
+/// 相关性判断任务
+///
+/// 回退到查询词覆盖率。
+#[derive(Debug, Clone, Default)]
+pub struct RelevanceJudgmentTask;
+
+#[async_trait]
+impl LlmTask for RelevanceJudgmentTask {
+    type Request = RelevanceJudgmentRequest;
+    type Response = RelevanceJudgmentResponse;
+
+    fn name(&self) -> &'static str {
+        "relevance_judgment"
+    }
+
+    fn instructions(&self) -> String {
+        "判断每条候选记忆能否回答或支持查询，为每条候选给出0到1的相关性分数：         1表示直接回答查询，0表示无关。memory_id取候选的id。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["judgments"],
+            "properties": {
+                "judgments": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["memory_id", "score"],
+                        "properties": {
+                            "memory_id": { "type": "string" },
+                            "score": { "type": "number" }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn input(&self, request: &RelevanceJudgmentRequest) -> Result<Value> {
+        let candidates: Vec<Value> = request
+            .candidates
+            .iter()
+            .map(|memory| {
+                let mut input = memory_input(memory);
+                input["id"] = json!(memory.id);
+                input
+            })
+            .collect();
+        Ok(json!({ "query": request.query, "candidates": candidates }))
+    }
+
+    fn post_process(&self, mut response: RelevanceJudgmentResponse) -> RelevanceJudgmentResponse {
+        for judgment in &mut response.judgments {
+            judgment.score = clamp_unit(judgment.score);
+        }
+        response
+    }
+
+    async fn fallback(
+        &self,
+        request: &RelevanceJudgmentRequest,
+    ) -> Result<RelevanceJudgmentResponse> {
+        let judgments = request
+            .candidates
+            .iter()
+            .map(|memory| RelevanceJudgment {
+                memory_id: memory.id.clone(),
+                score: KeywordOverlapReranker::overlap(&request.query, memory),
+            })
+            .collect();
+        Ok(RelevanceJudgmentResponse { judgments })
+    }
+}
+
+/// 查询改写请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryParaphraseRequest {
+    pub query: String,
+    /// 需要的改写数量
+    pub count: usize,
+}
+
+/// 查询改写响应
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueryParaphraseResponse {
+    pub paraphrases: Vec<String>,
+}
+
+/// 查询改写任务
+///
+/// 没有可靠的启发式改写，回退时不生成改写。
+#[derive(Debug, Clone, Default)]
+pub struct QueryParaphraseTask;
+
+#[async_trait]
+impl LlmTask for QueryParaphraseTask {
+    type Request = QueryParaphraseRequest;
+    type Response = QueryParaphraseResponse;
+
+    fn name(&self) -> &'static str {
+        "query_paraphrase"
+    }
+
+    fn instructions(&self) -> String {
+        "用不同的措辞改写检索查询，保持原意，可以换用同义词或补全省略的主语。\
+         给出count条互不相同的改写，使用与查询相同的语言。"
+            .to_string()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "required": ["paraphrases"],
+            "properties": {
+                "paraphrases": { "type": "array", "items": { "type": "string" } }
+            }
+        })
+    }
+
+    fn post_process(&self, mut response: QueryParaphraseResponse) -> QueryParaphraseResponse {
+        let mut seen = HashSet::new();
+        response.paraphrases = response
+            .paraphrases
+            .into_iter()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty() && seen.insert(p.clone()))
+            .collect();
+        response
+    }
+
+    async fn fallback(&self, _request: &QueryParaphraseRequest) -> Result<QueryParaphraseResponse> {
+        Ok(QueryParaphraseResponse {
+            paraphrases: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LlmConfig;
+    use crate::core::{MemoryAttributes, MemoryType};
+    use crate::llm::{LlmTaskRunner, ScriptedLlmProvider};
+    use std::sync::Arc;
+
+    fn runner(provider: Arc<ScriptedLlmProvider>) -> LlmTaskRunner {
+        let config = LlmConfig {
+            retry_backoff_ms: 0,
+            ..Default::default()
+        };
+        LlmTaskRunner::new(provider, &config)
+    }
+
+    #[tokio::test]
+    async fn test_attribute_extraction_validates_output() {
+        let provider = Arc::new(ScriptedLlmProvider::new([
+            r#"{"keywords": ["CNN"], "importance": 1.5, "confidence": 0.9}"#,
+            r#"{"keywords": ["CNN", "卷积"], "tags": ["深度学习"], "emotion": "积极学习",
+                "importance": 0.8, "confidence": 0.9, "language": "zh", "themes": ["人工智能"]}"#,
+        ]));
+        let request = AttributeExtractionRequest {
+            content: "今天学习了卷积神经网络(CNN)的基本原理".to_string(),
+            context: Context::default(),
+            extraction_types: vec![AttributeType::Keywords, AttributeType::Importance],
+        };
+
+        let response = runner(provider.clone())
+            .run(&AttributeExtractionTask::new(&Config::default()), &request)
+            .await
+            .unwrap();
+        assert_eq!(response.keywords, vec!["CNN", "卷积"]);
+        assert_eq!(response.importance, 0.8);
+        assert_eq!(response.themes, vec!["人工智能"]);
+        assert!(provider.requests()[0].messages[1]
+            .content
+            .contains("\"extraction_types\":[\"Keywords\",\"Importance\"]"));
+    }
+
+    #[tokio::test]
+    async fn test_heuristic_fallbacks() {
+        let provider = Arc::new(ScriptedLlmProvider::default());
+        let runner = runner(provider);
+
+        let emotion = runner
+            .run(
+                &EmotionAnalysisTask,
+                &EmotionAnalysisRequest {
+                    text: "今天的项目演示非常成功，客户很满意！".to_string(),
+                    context: None,
+                    language: Some("zh".to_string()),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(emotion.sentiment, Sentiment::Positive);
+        assert_eq!(emotion.emotion_scores.len(), 2);
+
+        let query = runner
+            .run(
+                &QueryUnderstandingTask,
+                &QueryUnderstandingRequest {
+                    query: "为什么数据库变慢了".to_string(),
+                    context: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(query.query_type, QueryType::Causal);
+
+        let memory = Memory::new(
+            "学会了使用Git进行版本控制".to_string(),
+            MemoryType::Knowledge,
+            Vec::new(),
+            MemoryAttributes::default(),
+        );
+        let importance = runner
+            .run(
+                &ImportanceAssessmentTask,
+                &ImportanceAssessmentRequest {
+                    memory,
+                    user_context: UserContext {
+                        user_id: "dev_001".to_string(),
+                        interests: vec!["git".to_string(), "烹饪".to_string()],
+                        ..Default::default()
+                    },
+                    historical_interactions: Vec::new(),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(importance.factors.len(), 3);
+        assert_eq!(importance.factors[1].contribution, 0.5);
+    }
+}
@@ -3,19 +3,56 @@
 //! 本模块实现AI00-Mem的核心记忆管理功能，包括A-Mem的Zettelkasten机制
 //! 和HippoRAG的神经生物学启发的检索算法。
 
-use crate::config::Config;
+use crate::access::AccessTracker;
+use crate::attributes::AttributeExtractor;
+use crate::causal::{
+    CausalAnalysisRequest, CausalAnalyzer, CausalRelationType, LlmCausalAnalyzer,
+    RuleCausalDetector,
+};
+use crate::classifier::{
+    LlmMemoryClassifier, MemoryTypeClassification, MemoryTypeClassifier, RuleMemoryClassifier,
+    TYPE_CLASSIFIER_ATTRIBUTE, TYPE_CONFIDENCE_ATTRIBUTE,
+};
+use crate::config::{Config, DuplicateStrategy, RerankMethod, SegmentationConfig};
 use crate::core::*;
 use crate::database::{
-    GraphEdge, GraphNode, GraphQueryRequest, Vector, VectorGraphDB, VectorQueryRequest,
+    Community, GraphEdge, GraphNode, GraphQueryRequest, Vector, VectorGraphDB, VectorQueryRequest,
+};
+use crate::dedup::{DeduplicationReport, DuplicateDetector, DUPLICATE_CONNECTION};
+use crate::entity::{
+    is_entity_edge, Entity, EntityExtractor, EntityLinker, ExtractedEntity, KeywordEntityExtractor,
+    LlmEntityExtractor,
 };
 use crate::error::{MemoryError, Result};
+use crate::evolution::{
+    EvolutionAdvisor, EvolutionNeighbour, EvolutionRequest, LlmEvolutionAdvisor,
+    RuleEvolutionAdvisor,
+};
+use crate::graph::{CommunityDetector, ExportFormat, ExportScope, GraphExporter};
+use crate::graph_query::{MemoryQueryRow, QueryItem, QueryPlan, QueryValue};
+use crate::llm::{LlmProvider, LlmTask, LlmTaskRunner, OpenAiCompatibleProvider};
+use crate::llm_tasks::{AttributeExtractionRequest, AttributeExtractionTask, AttributeType};
+use crate::pipeline::{embed_text, RetrievalPipeline, RetrievalRequest};
+use crate::rerank::{LlmReranker, Reranker};
+use crate::retention::RetentionModel;
+use crate::segment::Segmenter;
+use crate::utils::TextUtils;
 use chrono::{DateTime, Duration, Utc};
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// 访问频度达到饱和的访问次数
+const ACCESS_SATURATION: f32 = 20.0;
+
+/// 遗忘曲线衰减时，重要性变化小于该值的记忆不重写
+const MIN_IMPORTANCE_CHANGE: f32 = 0.01;
+
+/// 发现主题连接时按标签、关键词最多取回的候选数（按创建时间从新到旧）
+const MAX_THEMATIC_CANDIDATES: usize = 200;
+
 /// 记忆管理器
 ///
 /// 核心记忆管理组件，整合向量数据库、图数据库和各种记忆处理算法。
@@ -27,6 +64,17 @@ pub struct MemoryManager {
     importance_calculator: ImportanceCalculator,
     evolution_engine: EvolutionEngine,
     retrieval_engine: RetrievalEngine,
+    community_detector: CommunityDetector,
+    entity_linker: EntityLinker,
+    causal_analyzer: Arc<dyn CausalAnalyzer>,
+    attribute_task: AttributeExtractionTask,
+    type_classifier: Arc<dyn MemoryTypeClassifier>,
+    duplicate_detector: DuplicateDetector,
+    retention: RetentionModel,
+    access_tracker: AccessTracker,
+    pipeline: RetrievalPipeline,
+    segmenter: Arc<Segmenter>,
+    llm: Option<LlmTaskRunner>,
     stats: Arc<RwLock<MemoryStats>>,
 }
 
@@ -46,14 +94,25 @@ pub struct MemoryStats {
 ///
 /// 实现A-Mem的Zettelkasten链接机制，自动发现和建立记忆之间的连接。
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct LinkAnalyzer {
+    /// 是否启用自动链接发现
+    enabled: bool,
+    /// 语义近邻数量
+    top_k: usize,
     /// 语义阈值
     semantic_threshold: f32,
     /// 时间窗口
     temporal_window: Duration,
-    /// 因果模式
-    causal_patterns: Vec<String>,
+    /// 时间连接最大强度
+    temporal_strength: f32,
+    /// 最多时间连接数
+    max_temporal_links: usize,
+    /// 主题连接所需的最少共享词数
+    min_shared_terms: usize,
+    /// 最多主题连接数
+    max_thematic_links: usize,
+    /// 单个记忆最多自动建立的连接数
+    max_links: usize,
     /// 主题关键词
     thematic_keywords: HashMap<String, Vec<String>>,
 }
@@ -74,6 +133,8 @@ pub struct ImportanceCalculator {
     feedback_weight: f32,
     /// 内容权重
     content_weight: f32,
+    /// 完全遗忘时保留的基础重要性比例
+    retention_floor: f32,
 }
 
 /// 演化引擎
@@ -90,6 +151,12 @@ pub struct EvolutionEngine {
     pruning_threshold: f32,
     /// 演化间隔
     evolution_interval: Duration,
+    /// 新记忆写入时的近邻演化决策器
+    advisor: Arc<dyn EvolutionAdvisor>,
+    /// 参与演化的最多近邻数
+    max_neighbours: usize,
+    /// 近邻的最低嵌入相似度
+    neighbour_threshold: f32,
 }
 
 /// 检索引擎
@@ -142,6 +209,43 @@ pub struct RetrievalResult {
     pub explanation: RetrievalExplanation,
 }
 
+#[cfg(test)]
+impl RetrievalResult {
+    /// 测试用的检索结果，内容与ID相同，语义分数等于相关性分数
+    pub(crate) fn for_test(id: &str, score: f32) -> Self {
+        let mut memory = Memory::new(
+            id.to_string(),
+            MemoryType::Knowledge,
+            Vec::new(),
+            MemoryAttributes::default(),
+        );
+        memory.id = id.to_string();
+        Self {
+            memory,
+            relevance_score: score,
+            explanation: RetrievalExplanation {
+                semantic_score: score,
+                temporal_score: 0.0,
+                importance_score: 0.0,
+                personalization_score: 0.0,
+                connection_paths: Vec::new(),
+                reasoning: String::new(),
+                rerank_score: None,
+            },
+        }
+    }
+
+    pub(crate) fn with_embedding(mut self, embedding: Vec<f32>) -> Self {
+        self.memory.embedding = embedding;
+        self
+    }
+
+    pub(crate) fn with_content(mut self, content: &str) -> Self {
+        self.memory.content = content.to_string();
+        self
+    }
+}
+
 /// 检索解释
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetrievalExplanation {
@@ -151,6 +255,9 @@ pub struct RetrievalExplanation {
     pub personalization_score: f32,
     pub connection_paths: Vec<Vec<MemoryId>>,
     pub reasoning: String,
+    /// 重排序器给出的相关性分数，未经重排序时为空
+    #[serde(default)]
+    pub rerank_score: Option<f32>,
 }
 
 /// 记忆创建请求
@@ -160,6 +267,12 @@ pub struct CreateMemoryRequest {
     pub context: Context,
     pub attributes: Option<MemoryAttributes>,
     pub force_connections: Option<Vec<MemoryId>>,
+    /// 显式指定记忆类型，未指定时根据内容自动分类
+    #[serde(default)]
+    pub memory_type: Option<MemoryType>,
+    /// 近重复记忆的处理策略，未指定时使用配置中的默认策略
+    #[serde(default)]
+    pub duplicate_strategy: Option<DuplicateStrategy>,
 }
 
 /// 记忆更新请求
@@ -173,20 +286,151 @@ pub struct UpdateMemoryRequest {
 impl MemoryManager {
     /// 创建新的记忆管理器
     pub async fn new(db: Arc<VectorGraphDB>, config: Config) -> Result<Self> {
+        // 分词器按本管理器的配置创建并传给各组件，不影响其他管理器
+        let segmenter = if config.segmentation == SegmentationConfig::default() {
+            Segmenter::shared()
+        } else {
+            Arc::new(Segmenter::new(&config.segmentation)?)
+        };
+        let keyword_extractor: Arc<dyn EntityExtractor> =
+            Arc::new(KeywordEntityExtractor::default().with_segmenter(segmenter.clone()));
         let link_analyzer = LinkAnalyzer::new(&config);
         let importance_calculator = ImportanceCalculator::new(&config);
         let evolution_engine = EvolutionEngine::new(&config);
         let retrieval_engine = RetrievalEngine::new(&config).await?;
-
-        Ok(Self {
+        let community_detector = CommunityDetector::new(&config).with_segmenter(segmenter.clone());
+        let entity_linker = EntityLinker::new(&config).with_extractor(keyword_extractor.clone());
+        let causal_analyzer =
+            Arc::new(RuleCausalDetector::new(&config).with_segmenter(segmenter.clone()));
+        let attribute_task =
+            AttributeExtractionTask::new(&config).with_segmenter(segmenter.clone());
+        let type_classifier =
+            Arc::new(RuleMemoryClassifier::new(&config).with_segmenter(segmenter.clone()));
+        let duplicate_detector = DuplicateDetector::new(&config).with_segmenter(segmenter.clone());
+        let retention = RetentionModel::new(&config);
+        let access_tracker = AccessTracker::new(&config);
+        let pipeline = RetrievalPipeline::new(db.clone(), &config)?
+            .with_segmenter(segmenter.clone())
+            .with_entity_extractor(keyword_extractor);
+        pipeline.reload_ranking_model().await?;
+        let llm_enabled = config.llm.enabled;
+
+        let manager = Self {
             db,
             config,
             link_analyzer,
             importance_calculator,
             evolution_engine,
             retrieval_engine,
+            community_detector,
+            entity_linker,
+            causal_analyzer,
+            attribute_task,
+            type_classifier,
+            duplicate_detector,
+            retention,
+            access_tracker,
+            pipeline,
+            segmenter,
+            llm: None,
             stats: Arc::new(RwLock::new(MemoryStats::default())),
-        })
+        };
+
+        if llm_enabled {
+            let provider = OpenAiCompatibleProvider::new(&manager.config.llm)?;
+            Ok(manager.with_llm_provider(Arc::new(provider)))
+        } else {
+            Ok(manager)
+        }
+    }
+
+    /// 使用大语言模型提取属性、实体和因果关系
+    ///
+    /// 模型不可用或输出无效时各项任务回退到内置的启发式实现。
+    pub fn with_llm_provider(self, provider: Arc<dyn LlmProvider>) -> Self {
+        let extractor = Arc::new(
+            LlmEntityExtractor::new(provider.clone()).with_segmenter(self.segmenter.clone()),
+        );
+        let analyzer = Arc::new(
+            LlmCausalAnalyzer::new(provider.clone(), &self.config)
+                .with_segmenter(self.segmenter.clone()),
+        );
+        let classifier = Arc::new(
+            LlmMemoryClassifier::new(provider.clone(), &self.config)
+                .with_segmenter(self.segmenter.clone()),
+        );
+        let advisor = Arc::new(LlmEvolutionAdvisor::new(provider.clone(), &self.config));
+        let use_llm_reranker = self.config.rerank.method == RerankMethod::Llm;
+        let reranker = Arc::new(LlmReranker::new(provider.clone(), &self.config));
+        let runner = LlmTaskRunner::new(provider, &self.config.llm);
+
+        let mut manager = self
+            .with_entity_extractor(extractor)
+            .with_causal_analyzer(analyzer)
+            .with_type_classifier(classifier)
+            .with_evolution_advisor(advisor);
+        if use_llm_reranker {
+            manager = manager.with_reranker(reranker);
+        }
+        manager.pipeline = manager.pipeline.with_llm_runner(runner.clone());
+        manager.llm = Some(runner);
+        manager
+    }
+
+    /// 替换重排序器（例如换成交叉编码器或自定义模型）
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.pipeline = self.pipeline.with_reranker(reranker);
+        self
+    }
+
+    /// 替换检索流水线（例如注册自定义的候选生成器、过滤器或后处理器）
+    pub fn with_pipeline(mut self, pipeline: RetrievalPipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// 检索使用的流水线，可以单独运行某个候选生成器或过滤器
+    pub fn pipeline(&self) -> &RetrievalPipeline {
+        &self.pipeline
+    }
+
+    /// 按本管理器的分词配置创建的分词器
+    pub fn segmenter(&self) -> &Arc<Segmenter> {
+        &self.segmenter
+    }
+
+    /// 替换实体抽取器（例如换成基于大语言模型的抽取器）
+    pub fn with_entity_extractor(mut self, extractor: Arc<dyn EntityExtractor>) -> Self {
+        self.pipeline = self.pipeline.with_entity_extractor(extractor.clone());
+        self.entity_linker = self.entity_linker.with_extractor(extractor);
+        self
+    }
+
+    /// 替换因果关系分析器（例如换成基于大语言模型的分析器）
+    pub fn with_causal_analyzer(mut self, analyzer: Arc<dyn CausalAnalyzer>) -> Self {
+        self.causal_analyzer = analyzer;
+        self
+    }
+
+    /// 替换记忆类型分类器（例如换成基于大语言模型的分类器）
+    pub fn with_type_classifier(mut self, classifier: Arc<dyn MemoryTypeClassifier>) -> Self {
+        self.type_classifier = classifier;
+        self
+    }
+
+    /// 替换记忆演化决策器（例如换成基于大语言模型的决策器）
+    pub fn with_evolution_advisor(mut self, advisor: Arc<dyn EvolutionAdvisor>) -> Self {
+        self.evolution_engine.advisor = advisor;
+        self
+    }
+
+    /// 根据内容和上下文推断记忆类型
+    pub async fn classify_memory_type(
+        &self,
+        content: &str,
+        context: &Context,
+    ) -> Result<MemoryTypeClassification> {
+        self.type_classifier.classify(content, context).await
     }
 
     /// 创建新记忆（从请求）
@@ -200,15 +444,48 @@ impl MemoryManager {
         let embedding = self.generate_embedding(&request.content).await?;
 
         // 提取属性
-        let attributes = if let Some(attrs) = request.attributes {
+        let mut attributes = if let Some(attrs) = request.attributes {
             attrs
         } else {
             self.extract_attributes(&request.content, &request.context)
                 .await?
         };
 
+        // 确定记忆类型：显式指定优先，否则自动分类
+        let memory_type = if let Some(memory_type) = request.memory_type {
+            attributes
+                .custom_attributes
+                .insert(TYPE_CONFIDENCE_ATTRIBUTE.to_string(), serde_json::json!(1.0));
+            attributes
+                .custom_attributes
+                .insert(TYPE_CLASSIFIER_ATTRIBUTE.to_string(), "explicit".into());
+            memory_type
+        } else if self.config.classification.enabled {
+            match self
+                .classify_memory_type(&request.content, &request.context)
+                .await
+            {
+                Ok(classification) => {
+                    attributes.custom_attributes.insert(
+                        TYPE_CONFIDENCE_ATTRIBUTE.to_string(),
+                        serde_json::json!(classification.confidence),
+                    );
+                    attributes.custom_attributes.insert(
+                        TYPE_CLASSIFIER_ATTRIBUTE.to_string(),
+                        self.type_classifier.name().into(),
+                    );
+                    classification.memory_type
+                }
+                Err(e) => {
+                    warn!("Memory type classification failed: {}", e);
+                    MemoryType::Knowledge
+                }
+            }
+        } else {
+            MemoryType::Knowledge
+        };
+
         // 创建记忆对象
-        let memory_type = MemoryType::Knowledge; // 默认类型，可以根据内容分析来确定
         let mut memory = Memory::new(request.content, memory_type, embedding, attributes);
 
         // 计算初始重要性
@@ -217,6 +494,33 @@ impl MemoryManager {
             .calculate_initial_importance(&memory, &request.context)
             .await?;
 
+        // 近重复检测：拒绝或合并时不创建新记忆
+        let strategy = request
+            .duplicate_strategy
+            .unwrap_or(self.config.deduplication.strategy);
+        let duplicate = if strategy == DuplicateStrategy::Allow {
+            None
+        } else {
+            self.duplicate_detector
+                .find_duplicate(&memory, &self.db)
+                .await?
+        };
+        if let Some(duplicate) = &duplicate {
+            info!(
+                "Memory duplicates {} (embedding similarity {:.3})",
+                duplicate.memory_id, duplicate.embedding_similarity
+            );
+            match strategy {
+                DuplicateStrategy::Reject => {
+                    return Err(MemoryError::duplicate_memory(&duplicate.memory_id));
+                }
+                DuplicateStrategy::Merge => {
+                    return self.merge_duplicate(&duplicate.memory_id, &memory).await;
+                }
+                DuplicateStrategy::Allow | DuplicateStrategy::Link => {}
+            }
+        }
+
         // 存储到向量数据库
         let vector = Vector {
             id: memory.id.clone(),
@@ -236,13 +540,31 @@ impl MemoryManager {
             updated_at: memory.metadata.updated_at,
         };
         self.db.insert_node(&node).await?;
+        self.index_entities_logged(&memory).await;
 
         // 自动发现连接
-        let connections = self
+        let mut connections = self
             .link_analyzer
-            .discover_connections(&memory, &request.context)
+            .discover_connections(&memory, &request.context, &self.db)
             .await?;
 
+        // 在近邻中检测因果关系
+        let neighbours: Vec<MemoryId> = connections.iter().map(|c| c.to_memory.clone()).collect();
+        connections.extend(
+            self.detect_causal_connections(&memory, &request.context, &neighbours)
+                .await?,
+        );
+
+        // 链接到重复的已有记忆
+        if let Some(duplicate) = duplicate {
+            connections.push(Connection::new(
+                memory.id.clone(),
+                duplicate.memory_id,
+                ConnectionType::Custom(DUPLICATE_CONNECTION.to_string()),
+                duplicate.embedding_similarity.clamp(0.0, 1.0),
+            ));
+        }
+
         // 添加强制连接
         if let Some(forced_connections) = request.force_connections {
             for target_id in forced_connections {
@@ -259,10 +581,26 @@ impl MemoryManager {
         // 创建发现的连接
         for connection in connections {
             self.create_connection(&connection).await?;
-            memory
-                .connections
-                .semantic_links
-                .push(connection.to_memory.clone());
+            let other = if connection.from_memory == memory.id {
+                connection.to_memory.clone()
+            } else {
+                connection.from_memory.clone()
+            };
+            let links = &mut memory.connections;
+            match connection.connection_type {
+                ConnectionType::Temporal => links.temporal_links.push(other),
+                ConnectionType::Causal => links.causal_links.push(other),
+                ConnectionType::Thematic => links.thematic_links.push(other),
+                _ => links.semantic_links.push(other),
+            }
+        }
+
+        // 演化近邻记忆，失败不影响新记忆的创建
+        if self.config.evolution.on_insert {
+            let trigger = EvolutionTrigger::NewMemoryAdded(memory.id.clone());
+            if let Err(e) = self.evolve_memories(trigger).await {
+                warn!("Memory evolution failed for {}: {}", memory.id, e);
+            }
         }
 
         // 更新统计信息
@@ -300,6 +638,7 @@ impl MemoryManager {
             updated_at: memory.metadata.updated_at,
         };
         self.db.insert_node(&node).await?;
+        self.index_entities_logged(memory).await;
 
         // 更新统计信息
         self.update_stats(|stats| {
@@ -321,17 +660,29 @@ impl MemoryManager {
     ) -> Result<Vec<RetrievalResult>> {
         debug!("Retrieving memories for query: {}", query.text);
 
-        let results = match query.query_type {
-            QueryType::Semantic => self.semantic_retrieval(query, context).await?,
-            QueryType::Temporal => self.temporal_retrieval(query, context).await?,
-            QueryType::Causal => self.causal_retrieval(query, context).await?,
-            QueryType::Thematic => self.thematic_retrieval(query, context).await?,
-            QueryType::Mixed => self.mixed_retrieval(query, context).await?,
-            QueryType::GraphTraversal => self.graph_traversal_retrieval(query, context).await?,
-            QueryType::PersonalizedPageRank => {
-                self.personalized_pagerank_retrieval(query, context).await?
+        let mut request = RetrievalRequest::new(query.clone());
+        request.session_id = context.session_id.clone();
+        request.time_window = context.time_window;
+        request.start_memories = context.recent_memories.clone();
+        request.preferences = self
+            .retrieval_engine
+            .get_personalization_vector()
+            .await?
+            .preferences;
+        let mut results = self.pipeline.run(request).await?.results;
+
+        // 按检索计数时，返回的记忆计为一次访问
+        if self.access_tracker.counts_retrieval() && !results.is_empty() {
+            for result in &mut results {
+                result.memory.update_access();
             }
-        };
+            let ids = results.iter().map(|r| &r.memory.id);
+            if self.access_tracker.record(ids).await {
+                if let Err(e) = self.flush_access_stats().await {
+                    warn!("Failed to flush access stats: {}", e);
+                }
+            }
+        }
 
         // 更新统计信息
         self.update_stats(|stats| {
@@ -344,310 +695,26 @@ impl MemoryManager {
         Ok(results)
     }
 
-    /// 语义检索
-    async fn semantic_retrieval(
-        &self,
-        query: &Query,
-        _context: &Context,
-    ) -> Result<Vec<RetrievalResult>> {
-        let query_embedding = self.generate_embedding(&query.text).await?;
-
-        let vector_request = VectorQueryRequest {
-            query_vector: query_embedding,
-            limit: query.limit,
-            threshold: Some(self.config.vector.similarity_threshold),
-            filters: self.query_filters_to_metadata_filters(&query.filters)?,
-        };
-
-        let vector_results = self.db.query_vectors(&vector_request).await?;
-
-        let mut results = Vec::new();
-        for vector_result in vector_results {
-            let memory = self.metadata_to_memory(&vector_result.vector.metadata)?;
-
-            // 应用查询过滤器
-            if !memory.matches_filters(&query.filters) {
-                continue;
-            }
-
-            let explanation = RetrievalExplanation {
-                semantic_score: vector_result.similarity,
-                temporal_score: 0.0,
-                importance_score: memory.attributes.importance,
-                personalization_score: 0.0,
-                connection_paths: Vec::new(),
-                reasoning: format!("Semantic similarity: {:.3}", vector_result.similarity),
-            };
-
-            results.push(RetrievalResult {
-                memory,
-                relevance_score: vector_result.similarity,
-                explanation,
-            });
-        }
-
-        Ok(results)
-    }
-
-    /// 时间检索
-    async fn temporal_retrieval(
-        &self,
-        query: &Query,
-        context: &Context,
-    ) -> Result<Vec<RetrievalResult>> {
-        // 基于时间窗口的检索实现
-        let time_window = context
-            .time_window
-            .unwrap_or((Utc::now() - Duration::days(30), Utc::now()));
-
-        // 首先进行语义检索
-        let mut semantic_results = self.semantic_retrieval(query, context).await?;
-
-        // 根据时间相关性重新评分
-        for result in &mut semantic_results {
-            let time_score = self
-                .calculate_temporal_relevance(&result.memory, &time_window, &query.text)
-                .await?;
-
-            result.explanation.temporal_score = time_score;
-            result.relevance_score = result.explanation.semantic_score
-                * query.weights.semantic_weight
-                + time_score * query.weights.temporal_weight;
-        }
-
-        // 重新排序
-        semantic_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
-
-        Ok(semantic_results)
-    }
-
-    /// 因果检索
-    async fn causal_retrieval(
-        &self,
-        query: &Query,
-        context: &Context,
-    ) -> Result<Vec<RetrievalResult>> {
-        // 基于因果关系的检索实现
-        let causal_keywords = self.extract_causal_keywords(&query.text)?;
-
-        let graph_request = GraphQueryRequest {
-            start_nodes: context.recent_memories.clone(),
-            edge_types: Some(vec!["causal".to_string()]),
-            max_depth: Some(3),
-            limit: query.limit,
-            filters: self.query_filters_to_graph_filters(&query.filters)?,
-        };
-
-        let graph_results = self.db.query_graph(&graph_request).await?;
-
-        let mut results = Vec::new();
-        for node in graph_results.nodes {
-            if let Ok(memory) = self.properties_to_memory(&node.properties) {
-                let causal_score = self
-                    .calculate_causal_relevance(&memory, &causal_keywords)
-                    .await?;
-
-                if causal_score > 0.1 {
-                    let explanation = RetrievalExplanation {
-                        semantic_score: 0.0,
-                        temporal_score: 0.0,
-                        importance_score: memory.attributes.importance,
-                        personalization_score: 0.0,
-                        connection_paths: Vec::new(),
-                        reasoning: format!("Causal relevance: {:.3}", causal_score),
-                    };
-
-                    results.push(RetrievalResult {
-                        memory,
-                        relevance_score: causal_score,
-                        explanation,
-                    });
-                }
-            }
-        }
-
-        results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
-        Ok(results)
-    }
-
-    /// 主题检索
-    async fn thematic_retrieval(
-        &self,
-        query: &Query,
-        _context: &Context,
-    ) -> Result<Vec<RetrievalResult>> {
-        let themes = self.extract_themes(&query.text)?;
-
-        let graph_request = GraphQueryRequest {
-            start_nodes: Vec::new(),
-            edge_types: Some(vec!["thematic".to_string()]),
-            max_depth: Some(2),
-            limit: query.limit,
-            filters: self.create_theme_filters(&themes)?,
-        };
-
-        let graph_results = self.db.query_graph(&graph_request).await?;
-
-        let mut results = Vec::new();
-        for node in graph_results.nodes {
-            if let Ok(memory) = self.properties_to_memory(&node.properties) {
-                let theme_score = self.calculate_thematic_relevance(&memory, &themes).await?;
-
-                let explanation = RetrievalExplanation {
-                    semantic_score: 0.0,
-                    temporal_score: 0.0,
-                    importance_score: memory.attributes.importance,
-                    personalization_score: 0.0,
-                    connection_paths: Vec::new(),
-                    reasoning: format!("Thematic relevance: {:.3}", theme_score),
-                };
-
-                results.push(RetrievalResult {
-                    memory,
-                    relevance_score: theme_score,
-                    explanation,
-                });
-            }
-        }
-
-        results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
-        Ok(results)
-    }
-
-    /// 混合检索
-    async fn mixed_retrieval(
-        &self,
-        query: &Query,
-        context: &Context,
-    ) -> Result<Vec<RetrievalResult>> {
-        // 执行多种检索方法
-        let semantic_results = self.semantic_retrieval(query, context).await?;
-        let temporal_results = self.temporal_retrieval(query, context).await?;
-        let causal_results = self.causal_retrieval(query, context).await?;
-
-        // 融合结果
-        let fused_results = self
-            .fuse_retrieval_results(
-                vec![semantic_results, temporal_results, causal_results],
-                &query.weights,
-            )
-            .await?;
-
-        Ok(fused_results)
-    }
-
-    /// 图遍历检索
-    async fn graph_traversal_retrieval(
-        &self,
-        query: &Query,
-        context: &Context,
-    ) -> Result<Vec<RetrievalResult>> {
-        let start_nodes = if context.recent_memories.is_empty() {
-            // 如果没有最近记忆，使用语义检索找到起始节点
-            let semantic_results = self.semantic_retrieval(query, context).await?;
-            semantic_results
-                .into_iter()
-                .take(3)
-                .map(|r| r.memory.id)
-                .collect()
-        } else {
-            context.recent_memories.clone()
-        };
-
-        let graph_request = GraphQueryRequest {
-            start_nodes,
-            edge_types: None,
-            max_depth: Some(self.config.graph.traversal.max_depth),
-            limit: query.limit,
-            filters: self.query_filters_to_graph_filters(&query.filters)?,
-        };
-
-        let graph_results = self.db.query_graph(&graph_request).await?;
-
-        let mut results = Vec::new();
-        for (i, node) in graph_results.nodes.iter().enumerate() {
-            if let Ok(memory) = self.properties_to_memory(&node.properties) {
-                let path_score = 1.0 / (i as f32 + 1.0); // 距离越近分数越高
-
-                let explanation = RetrievalExplanation {
-                    semantic_score: 0.0,
-                    temporal_score: 0.0,
-                    importance_score: memory.attributes.importance,
-                    personalization_score: 0.0,
-                    connection_paths: graph_results.paths.clone(),
-                    reasoning: format!("Graph traversal score: {:.3}", path_score),
-                };
-
-                results.push(RetrievalResult {
-                    memory,
-                    relevance_score: path_score,
-                    explanation,
-                });
-            }
-        }
-
-        Ok(results)
-    }
-
-    /// 个性化PageRank检索
-    async fn personalized_pagerank_retrieval(
-        &self,
-        query: &Query,
-        context: &Context,
-    ) -> Result<Vec<RetrievalResult>> {
-        // 获取个性化向量
-        let personalization_vector = self
-            .retrieval_engine
-            .get_personalization_vector()
-            .await?;
-
-        // 执行个性化PageRank
-        let pagerank_scores = self
-            .compute_personalized_pagerank(&personalization_vector, &query.text)
-            .await?;
-
-        // 结合语义检索
-        let semantic_results = self.semantic_retrieval(query, context).await?;
-
-        let mut results = Vec::new();
-        for semantic_result in semantic_results {
-            let pagerank_score = pagerank_scores
-                .get(&semantic_result.memory.id)
-                .unwrap_or(&0.0);
-
-            let combined_score = semantic_result.relevance_score * query.weights.semantic_weight
-                + pagerank_score * query.weights.personalization_weight;
-
-            let mut explanation = semantic_result.explanation;
-            explanation.personalization_score = *pagerank_score;
-            explanation.reasoning = format!(
-                "Combined score: semantic={:.3}, pagerank={:.3}",
-                semantic_result.relevance_score, pagerank_score
-            );
-
-            results.push(RetrievalResult {
-                memory: semantic_result.memory,
-                relevance_score: combined_score,
-                explanation,
-            });
-        }
-
-        results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
-        Ok(results)
-    }
-
     /// 更新记忆
     pub async fn update_memory(&self, request: UpdateMemoryRequest) -> Result<Memory> {
         info!("Updating memory: {}", request.memory_id);
 
         // 获取现有记忆
         let mut memory = self.get_memory(&request.memory_id).await?;
+        let history: Vec<Update> = request
+            .updates
+            .iter()
+            .map(|update| Update::new(request.memory_id.clone(), update.clone()))
+            .collect();
 
         // 应用更新
         for update in request.updates {
             match update {
                 UpdateType::ImportanceAdjustment(new_importance) => {
                     memory.attributes.importance = new_importance.clamp(0.0, 1.0);
+                    if self.retention.is_enabled() {
+                        self.retention.rebase(&mut memory);
+                    }
                 }
                 UpdateType::ConnectionAdded(connection) => {
                     self.create_connection(&connection).await?;
@@ -678,8 +745,11 @@ impl MemoryManager {
         memory.metadata.updated_at = Utc::now();
         memory.metadata.version += 1;
 
-        // 保存到数据库
+        // 保存到数据库并记录更新历史
         self.save_memory(&memory).await?;
+        for update in &history {
+            self.db.insert_update(update).await?;
+        }
 
         info!("Successfully updated memory: {}", request.memory_id);
         Ok(memory)
@@ -716,11 +786,65 @@ impl MemoryManager {
         Ok(())
     }
 
+    /// 把新记忆合并到已有的重复记忆，返回合并后的记忆
+    async fn merge_duplicate(&self, existing_id: &MemoryId, incoming: &Memory) -> Result<Memory> {
+        let mut existing = self.get_memory(existing_id).await?;
+        self.duplicate_detector.merge_into(&mut existing, incoming);
+        self.save_memory(&existing).await?;
+
+        info!("Merged new memory into existing memory {}", existing_id);
+        Ok(existing)
+    }
+
+    /// 离线去重：找出已有记忆中的重复簇，合并到每簇最早的记忆并软删除其余记忆
+    pub async fn merge_duplicates(&self) -> Result<DeduplicationReport> {
+        let memories: HashMap<MemoryId, Memory> = self
+            .db
+            .get_all_nodes(Some("memory"))
+            .await?
+            .iter()
+            .filter_map(|node| self.properties_to_memory(&node.properties).ok())
+            .filter(|memory| !memory.metadata.is_deleted)
+            .map(|memory| (memory.id.clone(), memory))
+            .collect();
+        let mut candidates: Vec<Memory> = memories.values().cloned().collect();
+        candidates.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let clusters = self.duplicate_detector.find_clusters(&candidates);
+        let mut merged = 0;
+        for cluster in &clusters {
+            let mut canonical = self.get_memory(&cluster[0]).await?;
+            for duplicate_id in &cluster[1..] {
+                self.duplicate_detector
+                    .merge_into(&mut canonical, &memories[duplicate_id]);
+                self.delete_memory(duplicate_id, true).await?;
+                merged += 1;
+            }
+            self.save_memory(&canonical).await?;
+        }
+
+        info!(
+            "Merged {} duplicate memories in {} clusters",
+            merged,
+            clusters.len()
+        );
+        Ok(DeduplicationReport { clusters, merged })
+    }
+
     /// 演化记忆
     pub async fn evolve_memories(&self, trigger: EvolutionTrigger) -> Result<u64> {
         info!("Starting memory evolution with trigger: {:?}", trigger);
 
-        let evolved_count = self.evolution_engine.evolve(&trigger, &self.db).await?;
+        let evolved_count = match &trigger {
+            EvolutionTrigger::NewMemoryAdded(memory_id) => {
+                self.evolve_neighbours(memory_id).await?
+            }
+            EvolutionTrigger::TimeDecay => self.decay_memories().await?,
+            EvolutionTrigger::UserFeedback(memory_id, score) => {
+                self.apply_feedback(memory_id, *score).await?
+            }
+            _ => self.evolution_engine.evolve(&trigger, &self.db).await?,
+        };
 
         // 更新统计信息
         self.update_stats(|stats| {
@@ -733,74 +857,405 @@ impl MemoryManager {
         Ok(evolved_count)
     }
 
-    /// 获取记忆统计信息
-    pub async fn get_stats(&self) -> MemoryStats {
-        self.stats.read().await.clone()
-    }
-
-    // 私有辅助方法
-
-    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        use model2vec_rs::model::StaticModel;
-        use std::sync::OnceLock;
-        use std::sync::{Arc, Mutex};
-
-        // 使用静态变量缓存模型，避免重复加载
-        static MODEL: OnceLock<Option<Arc<Mutex<StaticModel>>>> = OnceLock::new();
-
-        let model = MODEL
-            .get_or_init(|| {
-                match StaticModel::from_pretrained(
-                    "minishlab/potion-multilingual-128M", // 使用多语言模型
-                    None,                                 // 无需 HF token
-                    None,                                 // 使用模型默认的归一化设置
-                    None,                                 // 无子文件夹
-                ) {
-                    Ok(m) => Some(Arc::new(Mutex::new(m))),
-                    Err(_) => None,
-                }
-            })
-            .as_ref()
-            .ok_or_else(|| MemoryError::Internal {
-                message: "Failed to load embedding model".to_string(),
-            })?;
-
-        let model_guard = model.lock().map_err(|e| MemoryError::Internal {
-            message: format!("Failed to acquire model lock: {}", e),
-        })?;
-
-        // 生成嵌入向量
-        let sentences = vec![text.to_string()];
-        let embeddings = model_guard.encode(&sentences);
-
-        if embeddings.is_empty() {
-            return Err(MemoryError::Internal {
-                message: "Failed to generate embedding: empty result".to_string(),
-            });
+    /// 新记忆写入后演化它的语义近邻，返回发生变化的近邻数
+    ///
+    /// 按决策器的结果新增或加强与新记忆的连接、补充标签、改写上下文描述，
+    /// 变化的近邻版本号加一，每项变化记录一条更新历史。
+    async fn evolve_neighbours(&self, memory_id: &MemoryId) -> Result<u64> {
+        let memory = self.get_memory(memory_id).await?;
+        let engine = &self.evolution_engine;
+        if memory.embedding.is_empty() || engine.max_neighbours == 0 {
+            return Ok(0);
         }
 
-        Ok(embeddings[0].clone())
-    }
+        // 新记忆与每个近邻之间最强的已有连接
+        let mut links: HashMap<MemoryId, Connection> = HashMap::new();
+        for connection in self.get_connections(memory_id).await? {
+            let other = if &connection.from_memory == memory_id {
+                connection.to_memory.clone()
+            } else {
+                connection.from_memory.clone()
+            };
+            if links
+                .get(&other)
+                .is_none_or(|c| c.strength < connection.strength)
+            {
+                links.insert(other, connection);
+            }
+        }
 
-    async fn extract_attributes(
-        &self,
+        let request = VectorQueryRequest {
+            query_vector: memory.embedding.clone(),
+            limit: Some(engine.max_neighbours + 1),
+            threshold: Some(engine.neighbour_threshold),
+            filters: None,
+        };
+        let mut neighbours = Vec::new();
+        for candidate in self.db.query_vectors(&request).await? {
+            if &candidate.vector.id == memory_id {
+                continue;
+            }
+            let Ok(mut neighbour) = self.metadata_to_memory(&candidate.vector.metadata) else {
+                continue;
+            };
+            if neighbour.metadata.is_deleted {
+                continue;
+            }
+            neighbour.embedding = candidate.vector.embedding;
+            neighbours.push(EvolutionNeighbour {
+                link_strength: links.get(&neighbour.id).map(|c| c.strength),
+                memory: neighbour,
+                similarity: candidate.similarity,
+            });
+        }
+        neighbours.truncate(engine.max_neighbours);
+        if neighbours.is_empty() {
+            return Ok(0);
+        }
+
+        let request = EvolutionRequest { memory, neighbours };
+        let response = engine.advisor.advise(&request).await?;
+        let reason = format!("evolution: new memory {}", memory_id);
+
+        let mut evolved = 0;
+        for evolution in response.evolutions {
+            let Some(neighbour) = request
+                .neighbours
+                .iter()
+                .find(|n| n.memory.id == evolution.memory_id)
+            else {
+                continue;
+            };
+            let mut neighbour = neighbour.memory.clone();
+            let mut updates = Vec::new();
+
+            if let Some(strength) = evolution.link_strength {
+                match links.get(&neighbour.id) {
+                    Some(link) if (link.strength - strength).abs() > 1e-6 => {
+                        let mut edge = self.db.get_edge(&link.id).await?;
+                        edge.weight = strength.clamp(0.0, 1.0);
+                        edge.updated_at = Utc::now();
+                        self.db.update_edge(&edge).await?;
+                        updates.push(UpdateType::MetadataUpdate(HashMap::from([
+                            ("connection_id".to_string(), serde_json::json!(link.id)),
+                            ("strength".to_string(), serde_json::json!(edge.weight)),
+                        ])));
+                    }
+                    Some(_) => {}
+                    None => {
+                        let connection = Connection::new(
+                            memory_id.clone(),
+                            neighbour.id.clone(),
+                            ConnectionType::Semantic,
+                            strength,
+                        );
+                        self.create_connection(&connection).await?;
+                        updates.push(UpdateType::ConnectionAdded(connection));
+                    }
+                }
+            }
+
+            let new_tags: Vec<String> = evolution
+                .add_tags
+                .into_iter()
+                .filter(|tag| !neighbour.attributes.tags.contains(tag))
+                .collect();
+            if !new_tags.is_empty() {
+                neighbour.attributes.tags.extend(new_tags);
+                updates.push(UpdateType::TagUpdate(neighbour.attributes.tags.clone()));
+            }
+
+            if let Some(context) = evolution.context {
+                if context != neighbour.attributes.context {
+                    neighbour.attributes.context = context.clone();
+                    updates.push(UpdateType::AttributeUpdate(
+                        "context".to_string(),
+                        serde_json::Value::String(context),
+                    ));
+                }
+            }
+
+            if updates.is_empty() {
+                continue;
+            }
+            neighbour.metadata.version += 1;
+            neighbour.metadata.updated_at = Utc::now();
+            self.save_memory(&neighbour).await?;
+            for update_type in updates {
+                let update = Update::new(neighbour.id.clone(), update_type)
+                    .with_reason(reason.clone(), engine.advisor.name());
+                self.db.insert_update(&update).await?;
+            }
+            evolved += 1;
+        }
+
+        debug!("Evolved {} neighbours of memory {}", evolved, memory_id);
+        Ok(evolved)
+    }
+
+    /// 遗忘曲线衰减：按当前保持率重新计算所有记忆的重要性，返回重要性变化的记忆数
+    async fn decay_memories(&self) -> Result<u64> {
+        if !self.retention.is_enabled() {
+            return Ok(0);
+        }
+        // 先写回访问记录，让衰减基于最新的访问情况
+        self.flush_access_stats().await?;
+
+        let mut decayed = 0;
+        for node in self.db.get_all_nodes(Some("memory")).await? {
+            let Ok(memory) = self.properties_to_memory(&node.properties) else {
+                continue;
+            };
+            if memory.metadata.is_deleted {
+                continue;
+            }
+
+            let state = self.retention.state(&memory);
+            let importance = self.importance_calculator.retained_importance(
+                state.base_importance,
+                self.retention.retention_of(&state),
+                memory.metadata.access_count,
+            );
+            if (importance - memory.attributes.importance).abs() < MIN_IMPORTANCE_CHANGE {
+                continue;
+            }
+
+            let mut memory = self.get_memory(&memory.id).await?;
+            self.retention.store(&mut memory, &state);
+            memory.attributes.importance = importance;
+            self.save_memory(&memory).await?;
+            let update = Update::new(
+                memory.id.clone(),
+                UpdateType::ImportanceAdjustment(importance),
+            )
+            .with_reason("retention decay", "retention");
+            self.db.insert_update(&update).await?;
+            decayed += 1;
+        }
+
+        debug!("Retention decay adjusted {} memories", decayed);
+        Ok(decayed)
+    }
+
+    /// 处理用户反馈，`score` 取值-1到1
+    ///
+    /// 正向反馈按强度强化记忆并提升基础重要性，负向反馈降低基础重要性。
+    async fn apply_feedback(&self, memory_id: &MemoryId, score: f32) -> Result<u64> {
+        let engine = &self.evolution_engine;
+        let score = score.clamp(-1.0, 1.0);
+        let mut memory = self.get_memory(memory_id).await?;
+
+        let mut state = if score > 0.0 {
+            self.retention.reinforce(&mut memory, score)
+        } else {
+            self.retention.state(&memory)
+        };
+        let factor = if score > 0.0 {
+            1.0 + (engine.reinforcement_factor - 1.0) * score
+        } else {
+            1.0 + (engine.decay_factor - 1.0) * -score
+        };
+        state.base_importance = (state.base_importance * factor).clamp(0.0, 1.0);
+        self.retention.store(&mut memory, &state);
+        memory.attributes.importance = self.importance_calculator.retained_importance(
+            state.base_importance,
+            self.retention.retention_of(&state),
+            memory.metadata.access_count,
+        );
+        memory.metadata.updated_at = Utc::now();
+        self.save_memory(&memory).await?;
+
+        let update = Update::new(
+            memory_id.clone(),
+            UpdateType::ImportanceAdjustment(memory.attributes.importance),
+        )
+        .with_reason(format!("user feedback {:.2}", score), "user");
+        self.db.insert_update(&update).await?;
+        Ok(1)
+    }
+
+    /// 强化记忆：稳定度按强度增长，重要性恢复为未衰减的值
+    ///
+    /// 检索命中或被明确使用的记忆调用此方法，`strength` 取值0到1。
+    pub async fn reinforce_memory(&self, memory_id: &MemoryId, strength: f32) -> Result<Memory> {
+        let mut memory = self.get_memory(memory_id).await?;
+        self.reinforce(&mut memory, strength);
+        self.save_memory(&memory).await?;
+        Ok(memory)
+    }
+
+    fn reinforce(&self, memory: &mut Memory, strength: f32) {
+        let state = self.retention.reinforce(memory, strength);
+        memory.attributes.importance = self.importance_calculator.retained_importance(
+            state.base_importance,
+            self.retention.retention_of(&state),
+            memory.metadata.access_count,
+        );
+    }
+
+    /// 把累积的访问记录批量写回，返回写回的记忆数
+    ///
+    /// 更新访问次数和最后访问时间并强化记忆；同一批次内的多次访问只强化一次，
+    /// 集中重复对稳定度的增益不叠加。关闭前应调用一次，以免丢失未写回的记录。
+    pub async fn flush_access_stats(&self) -> Result<usize> {
+        let pending = self.access_tracker.take_pending().await;
+        let mut flushed = 0;
+        for (memory_id, access) in pending {
+            let mut memory = match self.get_memory(&memory_id).await {
+                Ok(memory) => memory,
+                Err(e) if e.is_memory_not_found() => continue,
+                Err(e) => return Err(e),
+            };
+            memory.record_accesses(access.count, access.last_accessed);
+            if self.retention.is_enabled() {
+                self.reinforce(&mut memory, self.access_tracker.reinforcement_strength());
+            }
+            self.save_memory(&memory).await?;
+            flushed += 1;
+        }
+
+        debug!("Flushed access stats for {} memories", flushed);
+        Ok(flushed)
+    }
+
+    /// 获取记忆的更新历史
+    pub async fn get_memory_history(&self, memory_id: &MemoryId) -> Result<Vec<Update>> {
+        self.db.get_updates(memory_id).await
+    }
+
+    /// 检测主题社区
+    ///
+    /// 在记忆图上划分社区并持久化成员关系，按配置为社区成员建立主题连接。
+    pub async fn detect_communities(&self) -> Result<Vec<Community>> {
+        let communities = self.community_detector.detect(&self.db).await?;
+        let changed = self
+            .community_detector
+            .store(&self.db, &communities)
+            .await?;
+
+        if self.config.graph.community.create_thematic_links {
+            self.link_community_members(&communities).await?;
+        }
+
+        info!(
+            "Detected {} communities, membership changed for {} memories",
+            communities.len(),
+            changed
+        );
+        Ok(communities)
+    }
+
+    /// 获取已检测的主题社区（按规模降序）
+    pub async fn get_communities(&self) -> Result<Vec<Community>> {
+        self.db.get_communities().await
+    }
+
+    /// 获取社区的成员记忆
+    pub async fn get_community_memories(&self, community_id: &str) -> Result<Vec<Memory>> {
+        let communities = self.db.get_communities().await?;
+        let community = communities
+            .iter()
+            .find(|c| c.id == community_id)
+            .ok_or_else(|| MemoryError::Internal {
+                message: format!("Community not found: {}", community_id),
+            })?;
+
+        let mut memories = Vec::new();
+        for member_id in &community.members {
+            if let Ok(memory) = self.get_memory(member_id).await {
+                memories.push(memory);
+            }
+        }
+        Ok(memories)
+    }
+
+    /// 导出记忆图（整个图或指定记忆周围的k跳子图）
+    pub async fn export_graph(&self, scope: &ExportScope, format: ExportFormat) -> Result<String> {
+        GraphExporter::new().export(&self.db, scope, format).await
+    }
+
+    /// 执行图查询语言查询，并把结果转换为记忆和连接
+    ///
+    /// 例如 `MATCH (a:Event)-[:Causal*1..3]->(b) WHERE b.importance > 0.7 RETURN b`。
+    pub async fn execute_graph_query(&self, query: &str) -> Result<Vec<MemoryQueryRow>> {
+        let plan = QueryPlan::parse(query)?;
+        let rows = self.db.execute_query(&plan).await?;
+
+        let mut results = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut items = Vec::with_capacity(row.values.len());
+            for value in row.values {
+                items.push(match value {
+                    QueryValue::Node(node) if node.node_type == "memory" => {
+                        QueryItem::Memory(Box::new(self.get_memory(&node.id).await?))
+                    }
+                    QueryValue::Node(node) => QueryItem::Node(node),
+                    QueryValue::Edge(edge) => QueryItem::Connection(Self::edge_to_connection(edge)),
+                    QueryValue::Path(edges) => QueryItem::Path(
+                        edges.into_iter().map(Self::edge_to_connection).collect(),
+                    ),
+                    QueryValue::Value(value) => QueryItem::Value(value),
+                });
+            }
+            results.push(MemoryQueryRow {
+                columns: row.columns,
+                items,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// 获取记忆统计信息
+    pub async fn get_stats(&self) -> MemoryStats {
+        self.stats.read().await.clone()
+    }
+
+    // 私有辅助方法
+
+    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        embed_text(text).await
+    }
+
+    async fn extract_attributes(
+        &self,
         content: &str,
         context: &Context,
     ) -> Result<MemoryAttributes> {
-        // 简单的属性提取实现
-        let words: Vec<&str> = content.split_whitespace().collect();
-        let keywords = words.iter().take(5).map(|s| s.to_string()).collect();
+        let request = AttributeExtractionRequest {
+            content: content.to_string(),
+            context: context.clone(),
+            extraction_types: vec![
+                AttributeType::Keywords,
+                AttributeType::Tags,
+                AttributeType::Emotion,
+                AttributeType::Importance,
+                AttributeType::Language,
+                AttributeType::Theme,
+            ],
+        };
+        let extracted = match &self.llm {
+            Some(runner) => runner.run(&self.attribute_task, &request).await?,
+            None => self.attribute_task.fallback(&request).await?,
+        };
+
+        let mut custom_attributes = HashMap::new();
+        if !extracted.themes.is_empty() {
+            custom_attributes.insert("themes".to_string(), serde_json::json!(extracted.themes));
+        }
 
         Ok(MemoryAttributes {
-            keywords,
-            tags: Vec::new(),
+            keywords: extracted.keywords,
+            tags: extracted.tags,
             context: context.current_topic.clone().unwrap_or_default(),
-            importance: 0.5,
-            emotion: None,
-            source: None,
-            confidence: 1.0,
-            language: Some("zh".to_string()),
-            custom_attributes: HashMap::new(),
+            importance: extracted.importance,
+            emotion: extracted.emotion,
+            source: AttributeExtractor::source(context),
+            confidence: extracted.confidence,
+            language: extracted
+                .language
+                .or_else(|| TextUtils::detect_language(content)),
+            custom_attributes,
         })
     }
 
@@ -828,9 +1283,72 @@ impl MemoryManager {
         Ok(())
     }
 
+    /// 以加权度最高的成员为中心，为社区其他成员建立主题连接
+    async fn link_community_members(&self, communities: &[Community]) -> Result<()> {
+        let thematic_type = format!("{:?}", ConnectionType::Thematic);
+        let edges = self.db.get_all_edges(&None).await?;
+
+        let mut degree: HashMap<&str, f32> = HashMap::new();
+        let mut linked: HashSet<(&str, &str)> = HashSet::new();
+        for edge in &edges {
+            *degree.entry(edge.from_node.as_str()).or_insert(0.0) += edge.weight;
+            *degree.entry(edge.to_node.as_str()).or_insert(0.0) += edge.weight;
+            if edge.edge_type == thematic_type {
+                linked.insert((edge.from_node.as_str(), edge.to_node.as_str()));
+                linked.insert((edge.to_node.as_str(), edge.from_node.as_str()));
+            }
+        }
+
+        for community in communities {
+            let Some(hub) = community.members.iter().max_by(|a, b| {
+                let da = degree.get(a.as_str()).unwrap_or(&0.0);
+                let db = degree.get(b.as_str()).unwrap_or(&0.0);
+                da.total_cmp(db).then_with(|| b.cmp(a))
+            }) else {
+                continue;
+            };
+
+            for member in &community.members {
+                if member == hub || linked.contains(&(hub.as_str(), member.as_str())) {
+                    continue;
+                }
+
+                let mut connection = Connection::new(
+                    hub.clone(),
+                    member.clone(),
+                    ConnectionType::Thematic,
+                    community.cohesion,
+                );
+                connection.bidirectional = true;
+                connection.properties.insert(
+                    "source".to_string(),
+                    serde_json::Value::String("community".to_string()),
+                );
+                connection.properties.insert(
+                    "community_id".to_string(),
+                    serde_json::Value::String(community.id.clone()),
+                );
+                self.create_connection(&connection).await?;
+            }
+        }
+
+        Ok(())
+    }
+
     async fn remove_connection(&self, connection_id: &ConnectionId) -> Result<()> {
-        // TODO: 实现连接删除逻辑
-        warn!("Connection removal not implemented: {}", connection_id);
+        self.db.delete_edge(connection_id).await.map_err(|e| match e {
+            MemoryError::Database(sqlx::Error::RowNotFound) => {
+                MemoryError::graph_error(format!("Connection not found: {}", connection_id))
+            }
+            e => e,
+        })?;
+
+        self.update_stats(|stats| {
+            stats.total_connections = stats.total_connections.saturating_sub(1);
+            stats.last_updated = Utc::now();
+        })
+        .await;
+
         Ok(())
     }
 
@@ -878,10 +1396,16 @@ impl MemoryManager {
     pub async fn record_interaction(&self, interaction: &Interaction) -> Result<()> {
         debug!("Recording interaction: {:?}", interaction.interaction_type);
 
-        // 更新记忆的访问统计
-        for memory_id in &interaction.retrieved_memories {
-            // TODO: 更新记忆的访问计数和最后访问时间
-            info!("Updated access stats for memory: {}", memory_id);
+        // 记录记忆的访问，达到阈值时批量写回
+        if self
+            .access_tracker
+            .counts_interaction(&interaction.interaction_type)
+            && self
+                .access_tracker
+                .record(&interaction.retrieved_memories)
+                .await
+        {
+            self.flush_access_stats().await?;
         }
 
         // 更新统计信息
@@ -907,25 +1431,30 @@ impl MemoryManager {
 
         let graph_results = self.db.query_graph(&graph_request).await?;
 
-        let mut connections = Vec::new();
-        for edge in graph_results.edges {
-            let connection = Connection {
-                id: edge.id,
-                from_memory: edge.from_node,
-                to_memory: edge.to_node,
-                connection_type: ConnectionType::Semantic, // TODO: 从edge.edge_type解析
-                strength: edge.weight,
-                created_at: edge.created_at,
-                updated_at: edge.updated_at,
-                properties: edge.properties,
-                bidirectional: false,
-            };
-            connections.push(connection);
-        }
+        let connections = graph_results
+            .edges
+            .into_iter()
+            .filter(|edge| !is_entity_edge(&edge.edge_type))
+            .map(Self::edge_to_connection)
+            .collect();
 
         Ok(connections)
     }
 
+    fn edge_to_connection(edge: GraphEdge) -> Connection {
+        Connection {
+            id: edge.id,
+            from_memory: edge.from_node,
+            to_memory: edge.to_node,
+            connection_type: ConnectionType::from_edge_type(&edge.edge_type),
+            strength: edge.weight,
+            created_at: edge.created_at,
+            updated_at: edge.updated_at,
+            properties: edge.properties,
+            bidirectional: false,
+        }
+    }
+
     async fn update_stats<F>(&self, updater: F)
     where
         F: FnOnce(&mut MemoryStats),
@@ -1018,6 +1547,20 @@ impl MemoryManager {
             );
         }
 
+        if let Some(ref emotion) = memory.attributes.emotion {
+            metadata.insert(
+                "emotion".to_string(),
+                serde_json::Value::String(emotion.clone()),
+            );
+        }
+
+        if !memory.attributes.custom_attributes.is_empty() {
+            metadata.insert(
+                "custom_attributes".to_string(),
+                serde_json::to_value(&memory.attributes.custom_attributes)?,
+            );
+        }
+
         // 元数据
         metadata.insert(
             "created_at".to_string(),
@@ -1055,156 +1598,7 @@ impl MemoryManager {
     }
 
     fn metadata_to_memory(&self, metadata: &HashMap<String, serde_json::Value>) -> Result<Memory> {
-        // 从元数据重构Memory对象
-        let id = metadata
-            .get("id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| MemoryError::Internal {
-                message: "Missing memory id in metadata".to_string(),
-            })?
-            .to_string();
-
-        let content = metadata
-            .get("content")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| MemoryError::Internal {
-                message: "Missing content in metadata".to_string(),
-            })?
-            .to_string();
-
-        let memory_type_str = metadata
-            .get("memory_type")
-            .and_then(|v| v.as_str())
-            .unwrap_or("Knowledge");
-        let memory_type = match memory_type_str {
-            "Knowledge" => MemoryType::Knowledge,
-            "Event" => MemoryType::Event,
-            "Task" => MemoryType::Task,
-            "Conversation" => MemoryType::Conversation,
-            "Reflection" => MemoryType::Reflection,
-            "Goal" => MemoryType::Goal,
-            "Habit" => MemoryType::Habit,
-            "Emotion" => MemoryType::Emotion,
-            _ => MemoryType::Knowledge,
-        };
-
-        let embedding = metadata
-            .get("embedding")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_f64().map(|f| f as f32))
-                    .collect()
-            })
-            .unwrap_or_else(|| vec![0.0; self.config.vector.dimension]);
-
-        let importance = metadata
-            .get("importance")
-            .and_then(|v| v.as_f64())
-            .unwrap_or(0.5) as f32;
-
-        let keywords = metadata
-            .get("keywords")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        let tags = metadata
-            .get("tags")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        let created_at = metadata
-            .get("created_at")
-            .and_then(|v| v.as_str())
-            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(Utc::now);
-
-        let updated_at = metadata
-            .get("updated_at")
-            .and_then(|v| v.as_str())
-            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(Utc::now);
-
-        let attributes = MemoryAttributes {
-            keywords,
-            tags,
-            context: metadata
-                .get("context")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string(),
-            importance,
-            emotion: None,
-            source: metadata
-                .get("source")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            confidence: metadata
-                .get("confidence")
-                .and_then(|v| v.as_f64())
-                .unwrap_or(1.0) as f32,
-            language: metadata
-                .get("language")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string()),
-            custom_attributes: HashMap::new(),
-        };
-
-        let metadata_obj = MemoryMetadata {
-            created_at,
-            updated_at,
-            access_count: metadata
-                .get("access_count")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0),
-            last_accessed: metadata
-                .get("last_accessed")
-                .and_then(|v| v.as_str())
-                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(Utc::now),
-            version: metadata
-                .get("version")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(1) as u32,
-
-            is_deleted: metadata
-                .get("is_deleted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false),
-            deleted_at: None,
-            custom_metadata: HashMap::new(),
-        };
-
-        let connections = MemoryConnections {
-            semantic_links: Vec::new(),
-            temporal_links: Vec::new(),
-            causal_links: Vec::new(),
-            thematic_links: Vec::new(),
-            custom_links: HashMap::new(),
-        };
-
-        Ok(Memory {
-            id,
-            content,
-            memory_type,
-            embedding,
-            attributes,
-            connections,
-            metadata: metadata_obj,
-        })
+        memory_from_metadata(metadata, self.config.vector.dimension)
     }
 
     fn properties_to_memory(
@@ -1214,197 +1608,584 @@ impl MemoryManager {
         self.metadata_to_memory(properties)
     }
 
-    fn query_filters_to_metadata_filters(
+    /// 为已有记忆检测因果关系并建立因果连接
+    ///
+    /// 候选为上下文中的最近记忆以及该记忆已连接的记忆。
+    pub async fn discover_causal_links(
         &self,
-        _filters: &QueryFilters,
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        // TODO: 实现查询过滤器转换
-        Ok(None)
-    }
+        memory_id: &MemoryId,
+        context: &Context,
+    ) -> Result<Vec<Connection>> {
+        let memory = self.get_memory(memory_id).await?;
+        let existing = self.get_connections(memory_id).await?;
+        let neighbours: Vec<MemoryId> = existing
+            .iter()
+            .filter(|c| c.connection_type != ConnectionType::Causal)
+            .map(|c| {
+                if &c.from_memory == memory_id {
+                    c.to_memory.clone()
+                } else {
+                    c.from_memory.clone()
+                }
+            })
+            .collect();
+        let linked: HashSet<(MemoryId, MemoryId)> = existing
+            .iter()
+            .filter(|c| c.connection_type == ConnectionType::Causal)
+            .map(|c| (c.from_memory.clone(), c.to_memory.clone()))
+            .collect();
 
-    fn query_filters_to_graph_filters(
-        &self,
-        filters: &QueryFilters,
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        self.query_filters_to_metadata_filters(filters)
+        let mut created = Vec::new();
+        for connection in self
+            .detect_causal_connections(&memory, context, &neighbours)
+            .await?
+        {
+            let key = (connection.from_memory.clone(), connection.to_memory.clone());
+            if linked.contains(&key) {
+                continue;
+            }
+            self.create_connection(&connection).await?;
+            created.push(connection);
+        }
+
+        Ok(created)
     }
 
-    async fn calculate_temporal_relevance(
+    /// 分析新记忆与候选记忆之间的因果关系，返回有向的因果连接
+    async fn detect_causal_connections(
         &self,
         memory: &Memory,
-        time_window: &(DateTime<Utc>, DateTime<Utc>),
-        _query: &str,
-    ) -> Result<f32> {
-        // 简单的时间相关性计算
-        let memory_time = memory.metadata.created_at;
-        if memory_time >= time_window.0 && memory_time <= time_window.1 {
-            let window_duration = time_window.1 - time_window.0;
-            let memory_offset = memory_time - time_window.0;
-            Ok(1.0 - (memory_offset.num_seconds() as f32 / window_duration.num_seconds() as f32))
-        } else {
-            Ok(0.0)
+        context: &Context,
+        neighbours: &[MemoryId],
+    ) -> Result<Vec<Connection>> {
+        let causal = &self.config.graph.causal;
+        if !causal.enabled {
+            return Ok(Vec::new());
         }
-    }
 
-    fn extract_causal_keywords(&self, text: &str) -> Result<Vec<String>> {
-        let causal_words = vec!["因为", "所以", "导致", "引起", "造成", "由于", "结果"];
-        let keywords = causal_words
-            .into_iter()
-            .filter(|word| text.contains(word))
-            .map(|s| s.to_string())
-            .collect();
-        Ok(keywords)
-    }
+        let mut seen = HashSet::new();
+        let mut candidates = Vec::new();
+        for id in context.recent_memories.iter().chain(neighbours) {
+            if candidates.len() >= causal.max_candidates {
+                break;
+            }
+            if id == &memory.id || !seen.insert(id.clone()) {
+                continue;
+            }
+            match self.get_memory(id).await {
+                Ok(candidate) if !candidate.metadata.is_deleted => candidates.push(candidate),
+                Ok(_) => {}
+                Err(e) => debug!("Skipping causal candidate {}: {}", id, e),
+            }
+        }
 
-    async fn calculate_causal_relevance(
-        &self,
-        memory: &Memory,
-        keywords: &[String],
-    ) -> Result<f32> {
-        let content_lower = memory.content.to_lowercase();
-        let matches = keywords
+        let recent: Vec<Memory> = candidates
             .iter()
-            .filter(|k| content_lower.contains(&k.to_lowercase()))
-            .count();
-        Ok(matches as f32 / keywords.len().max(1) as f32)
-    }
-
-    fn extract_themes(&self, text: &str) -> Result<Vec<String>> {
-        // 简单的主题提取
-        let words: Vec<String> = text
-            .split_whitespace()
-            .filter(|w| w.len() > 3)
-            .take(10)
-            .map(|s| s.to_string())
+            .filter(|m| context.recent_memories.contains(&m.id))
+            .cloned()
             .collect();
-        Ok(words)
-    }
 
-    fn create_theme_filters(
-        &self,
-        themes: &[String],
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        let mut filters = HashMap::new();
-        filters.insert(
-            "themes".to_string(),
-            serde_json::Value::Array(
-                themes
-                    .iter()
-                    .map(|t| serde_json::Value::String(t.clone()))
-                    .collect(),
-            ),
-        );
-        Ok(Some(filters))
-    }
-
-    async fn calculate_thematic_relevance(
-        &self,
-        memory: &Memory,
-        themes: &[String],
-    ) -> Result<f32> {
-        let content_lower = memory.content.to_lowercase();
-        let matches = themes
-            .iter()
-            .filter(|theme| content_lower.contains(&theme.to_lowercase()))
-            .count();
-        Ok(matches as f32 / themes.len().max(1) as f32)
-    }
+        let mut connections = Vec::new();
+        for candidate in candidates {
+            let request = CausalAnalysisRequest {
+                source_memory: candidate,
+                target_memory: memory.clone(),
+                context: recent.clone(),
+            };
+            let response = match self.causal_analyzer.analyze(&request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    warn!("Causal analysis failed for {}: {}", request.source_memory.id, e);
+                    continue;
+                }
+            };
+            if !response.has_causal_relation || response.confidence < causal.min_confidence {
+                continue;
+            }
 
-    async fn fuse_retrieval_results(
-        &self,
-        results_list: Vec<Vec<RetrievalResult>>,
-        weights: &QueryWeights,
-    ) -> Result<Vec<RetrievalResult>> {
-        let mut fused_results = HashMap::new();
-
-        for (i, results) in results_list.iter().enumerate() {
-            let weight = match i {
-                0 => weights.semantic_weight,
-                1 => weights.temporal_weight,
-                2 => weights.importance_weight,
-                _ => 0.1,
+            let candidate_id = request.source_memory.id;
+            let (from, to) = match response.relation_type {
+                CausalRelationType::Cause => (candidate_id, memory.id.clone()),
+                CausalRelationType::Effect => (memory.id.clone(), candidate_id),
+                _ => continue,
             };
 
-            for result in results {
-                let entry = fused_results
-                    .entry(result.memory.id.clone())
-                    .or_insert_with(|| result.clone());
-                entry.relevance_score += result.relevance_score * weight;
-            }
+            let mut connection =
+                Connection::new(from, to, ConnectionType::Causal, response.confidence);
+            let properties = &mut connection.properties;
+            properties.insert("confidence".to_string(), serde_json::json!(response.confidence));
+            properties.insert("evidence".to_string(), serde_json::json!(response.evidence));
+            properties.insert(
+                "explanation".to_string(),
+                serde_json::json!(response.explanation),
+            );
+            properties.insert(
+                "causal_keywords".to_string(),
+                serde_json::json!(response.causal_keywords),
+            );
+            properties.insert(
+                "analyzer".to_string(),
+                serde_json::json!(self.causal_analyzer.name()),
+            );
+            connections.push(connection);
         }
 
-        let mut final_results: Vec<RetrievalResult> = fused_results.into_values().collect();
-        final_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
+        Ok(connections)
+    }
+
+    /// 抽取记忆中的实体并链接到实体图
+    pub async fn index_entities(&self, memory: &Memory) -> Result<Vec<Entity>> {
+        let entities = self.embed_entities(&memory.content).await?;
+        self.entity_linker
+            .link_memory(&self.db, &memory.id, &entities)
+            .await
+    }
 
-        Ok(final_results)
+    /// 获取记忆提及的实体
+    pub async fn get_memory_entities(&self, memory_id: &MemoryId) -> Result<Vec<Entity>> {
+        self.entity_linker.memory_entities(&self.db, memory_id).await
     }
 
-    async fn compute_personalized_pagerank(
-        &self,
-        personalization: &PersonalizationVector,
-        query: &str,
-    ) -> Result<HashMap<MemoryId, f32>> {
-        // 简化的PageRank实现
-        let mut scores = HashMap::new();
-
-        // 基于用户偏好计算分数
-        for (preference, weight) in &personalization.preferences {
-            if query.contains(preference) {
-                scores.insert(format!("memory_{}", preference), *weight);
-            }
+    /// 实体索引失败不影响记忆本身的创建
+    async fn index_entities_logged(&self, memory: &Memory) {
+        if !self.entity_linker.is_enabled() {
+            return;
         }
+        if let Err(e) = self.index_entities(memory).await {
+            warn!("Failed to index entities for memory {}: {}", memory.id, e);
+        }
+    }
 
-        Ok(scores)
+    /// 抽取实体并为实体名称生成嵌入（嵌入不可用时只按名称匹配）
+    async fn embed_entities(
+        &self,
+        text: &str,
+    ) -> Result<Vec<(ExtractedEntity, Option<Vec<f32>>)>> {
+        let mut entities = Vec::new();
+        for entity in self.entity_linker.extract(text).await? {
+            let embedding = self.generate_embedding(&entity.name).await.ok();
+            entities.push((entity, embedding));
+        }
+        Ok(entities)
     }
 }
 
+/// 从向量元数据或图节点属性重构记忆，元数据中没有嵌入时使用给定维度的零向量
+pub(crate) fn memory_from_metadata(
+    metadata: &HashMap<String, serde_json::Value>,
+    dimension: usize,
+) -> Result<Memory> {
+    // 从元数据重构Memory对象
+    let id = metadata
+        .get("id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| MemoryError::Internal {
+            message: "Missing memory id in metadata".to_string(),
+        })?
+        .to_string();
+
+    let content = metadata
+        .get("content")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| MemoryError::Internal {
+            message: "Missing content in metadata".to_string(),
+        })?
+        .to_string();
+
+    let memory_type_str = metadata
+        .get("memory_type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("Knowledge");
+    let memory_type = match memory_type_str {
+        "Knowledge" => MemoryType::Knowledge,
+        "Event" => MemoryType::Event,
+        "Task" => MemoryType::Task,
+        "Conversation" => MemoryType::Conversation,
+        "Reflection" => MemoryType::Reflection,
+        "Goal" => MemoryType::Goal,
+        "Habit" => MemoryType::Habit,
+        "Emotion" => MemoryType::Emotion,
+        _ => MemoryType::Knowledge,
+    };
+
+    let embedding = metadata
+        .get("embedding")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_f64().map(|f| f as f32))
+                .collect()
+        })
+        .unwrap_or_else(|| vec![0.0; dimension]);
+
+    let importance = metadata
+        .get("importance")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.5) as f32;
+
+    let keywords = metadata
+        .get("keywords")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let tags = metadata
+        .get("tags")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let created_at = metadata
+        .get("created_at")
+        .and_then(|v| v.as_str())
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+
+    let updated_at = metadata
+        .get("updated_at")
+        .and_then(|v| v.as_str())
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+
+    let attributes = MemoryAttributes {
+        keywords,
+        tags,
+        context: metadata
+            .get("context")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        importance,
+        emotion: metadata
+            .get("emotion")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        source: metadata
+            .get("source")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        confidence: metadata
+            .get("confidence")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(1.0) as f32,
+        language: metadata
+            .get("language")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        custom_attributes: metadata
+            .get("custom_attributes")
+            .and_then(|v| v.as_object())
+            .map(|obj| obj.clone().into_iter().collect())
+            .unwrap_or_default(),
+    };
+
+    let metadata_obj = MemoryMetadata {
+        created_at,
+        updated_at,
+        access_count: metadata
+            .get("access_count")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0),
+        last_accessed: metadata
+            .get("last_accessed")
+            .and_then(|v| v.as_str())
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now),
+        version: metadata
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1) as u32,
+
+        is_deleted: metadata
+            .get("is_deleted")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        deleted_at: None,
+        custom_metadata: HashMap::new(),
+    };
+
+    let connections = MemoryConnections {
+        semantic_links: Vec::new(),
+        temporal_links: Vec::new(),
+        causal_links: Vec::new(),
+        thematic_links: Vec::new(),
+        custom_links: HashMap::new(),
+    };
+
+    Ok(Memory {
+        id,
+        content,
+        memory_type,
+        embedding,
+        attributes,
+        connections,
+        metadata: metadata_obj,
+    })
+}
+
 // 实现各个组件
 
 impl LinkAnalyzer {
     fn new(config: &Config) -> Self {
+        let linking = &config.graph.linking;
         Self {
-            semantic_threshold: config.vector.similarity_threshold,
-            temporal_window: Duration::hours(24),
-            causal_patterns: vec!["因为".to_string(), "所以".to_string(), "导致".to_string()],
-            thematic_keywords: HashMap::new(),
+            enabled: linking.enabled,
+            top_k: linking.top_k,
+            semantic_threshold: linking.semantic_threshold,
+            temporal_window: Duration::hours(linking.temporal_window_hours as i64),
+            temporal_strength: linking.temporal_strength,
+            max_temporal_links: linking.max_temporal_links,
+            min_shared_terms: linking.min_shared_terms.max(1),
+            max_thematic_links: linking.max_thematic_links,
+            max_links: config.graph.max_connections_per_node,
+            thematic_keywords: linking.themes.clone(),
         }
     }
 
+    /// 为新记忆发现连接
+    ///
+    /// 与上下文中的最近记忆建立时间连接；启用自动发现时，再从数据库中
+    /// 找出语义近邻、时间窗口内的记忆以及共享标签或关键词的记忆。
     async fn discover_connections(
         &self,
         memory: &Memory,
         context: &Context,
+        db: &VectorGraphDB,
     ) -> Result<Vec<Connection>> {
         let mut connections = Vec::new();
 
         // 基于最近记忆发现连接
         for recent_memory_id in &context.recent_memories {
-            if recent_memory_id != &memory.id {
-                let connection = Connection::new(
-                    memory.id.clone(),
-                    recent_memory_id.clone(),
+            Self::push_link(
+                &mut connections,
+                memory,
+                recent_memory_id,
+                ConnectionType::Temporal,
+                0.7,
+            );
+        }
+
+        if !self.enabled || memory.embedding.is_empty() {
+            return Ok(connections);
+        }
+
+        // 语义连接：相似度超过阈值的前k个近邻，强度即相似度。
+        // 新记忆已写入数据库，多取一个以跳过自身。
+        let request = VectorQueryRequest {
+            query_vector: memory.embedding.clone(),
+            limit: Some(self.top_k + 1),
+            threshold: Some(self.semantic_threshold),
+            filters: Some(HashMap::from([(
+                "is_deleted".to_string(),
+                serde_json::Value::Bool(false),
+            )])),
+        };
+        let neighbours: Vec<Vector> = db
+            .query_vectors(&request)
+            .await?
+            .into_iter()
+            .filter(|c| c.vector.id != memory.id)
+            .take(self.top_k)
+            .map(|candidate| {
+                Self::push_link(
+                    &mut connections,
+                    memory,
+                    &candidate.vector.id,
+                    ConnectionType::Semantic,
+                    candidate.similarity.clamp(0.0, 1.0),
+                );
+                candidate.vector
+            })
+            .collect();
+
+        // 时间连接：按创建时间取窗口内最近的记忆，强度随时间间隔衰减
+        if self.max_temporal_links > 0 {
+            let nearby = db
+                .get_vectors_near_time(
+                    memory.metadata.created_at,
+                    self.temporal_window,
+                    self.max_temporal_links + 1,
+                )
+                .await?;
+            let window = self.temporal_window.num_seconds().max(1) as f32;
+            let mut temporal: Vec<(&MemoryId, f32)> = nearby
+                .iter()
+                .filter(|v| v.id != memory.id)
+                .filter_map(|v| {
+                    let gap = (memory.metadata.created_at - v.created_at)
+                        .num_seconds()
+                        .abs() as f32;
+                    let strength = self.temporal_strength * (1.0 - gap / window);
+                    (gap <= window).then_some((&v.id, strength))
+                })
+                .filter(|(_, strength)| *strength > 0.0)
+                .collect();
+            temporal.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then_with(|| a.0.cmp(b.0)));
+            for (id, strength) in temporal.into_iter().take(self.max_temporal_links) {
+                Self::push_link(
+                    &mut connections,
+                    memory,
+                    id,
                     ConnectionType::Temporal,
-                    0.7,
+                    strength,
+                );
+            }
+        }
+
+        // 主题连接：语义近邻以及按标签、关键词查到的记忆中共享足够多词的，
+        // 强度为词集合的Jaccard相似度
+        let terms = self.thematic_terms(&memory.attributes.tags, &memory.attributes.keywords);
+        if !terms.is_empty() && self.max_thematic_links > 0 {
+            let related = db
+                .get_vectors_by_terms(&self.lookup_terms(&terms), MAX_THEMATIC_CANDIDATES)
+                .await?;
+            let mut seen = HashSet::new();
+            let mut thematic: Vec<(&MemoryId, f32)> = neighbours
+                .iter()
+                .chain(&related)
+                .filter(|v| v.id != memory.id && seen.insert(&v.id))
+                .filter_map(|v| {
+                    let other = self.thematic_terms(
+                        &Self::string_list(&v.metadata, "tags"),
+                        &Self::string_list(&v.metadata, "keywords"),
+                    );
+                    let shared = terms.intersection(&other).count();
+                    (shared >= self.min_shared_terms)
+                        .then(|| (&v.id, shared as f32 / terms.union(&other).count() as f32))
+                })
+                .collect();
+            thematic.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then_with(|| a.0.cmp(b.0)));
+            for (id, strength) in thematic.into_iter().take(self.max_thematic_links) {
+                Self::push_link(
+                    &mut connections,
+                    memory,
+                    id,
+                    ConnectionType::Thematic,
+                    strength,
                 );
-                connections.push(connection);
             }
         }
 
+        connections.truncate(self.max_links.max(context.recent_memories.len()));
+        debug!(
+            "Discovered {} connections for memory {}",
+            connections.len(),
+            memory.id
+        );
+
         Ok(connections)
     }
+
+    /// 添加连接，跳过自身以及同一目标、同一类型的重复连接
+    fn push_link(
+        connections: &mut Vec<Connection>,
+        memory: &Memory,
+        target: &MemoryId,
+        connection_type: ConnectionType,
+        strength: f32,
+    ) {
+        let duplicate = connections
+            .iter()
+            .any(|c| &c.to_memory == target && c.connection_type == connection_type);
+        if target != &memory.id && !duplicate {
+            connections.push(Connection::new(
+                memory.id.clone(),
+                target.clone(),
+                connection_type,
+                strength,
+            ));
+        }
+    }
+
+    /// 标签和关键词（小写），以及它们所属的主题
+    fn thematic_terms(&self, tags: &[String], keywords: &[String]) -> HashSet<String> {
+        let mut terms: HashSet<String> = tags
+            .iter()
+            .chain(keywords)
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        for (theme, theme_keywords) in &self.thematic_keywords {
+            if theme_keywords
+                .iter()
+                .any(|k| terms.contains(&k.trim().to_lowercase()))
+            {
+                terms.insert(format!("theme:{}", theme.to_lowercase()));
+            }
+        }
+
+        terms
+    }
+
+    fn string_list(metadata: &HashMap<String, serde_json::Value>, key: &str) -> Vec<String> {
+        metadata
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 查找主题候选用的词：自身的标签和关键词，以及所属主题的全部关键词
+    fn lookup_terms(&self, terms: &HashSet<String>) -> Vec<String> {
+        let mut lookup: HashSet<String> = terms
+            .iter()
+            .filter(|t| !t.starts_with("theme:"))
+            .cloned()
+            .collect();
+        for (theme, theme_keywords) in &self.thematic_keywords {
+            if terms.contains(&format!("theme:{}", theme.to_lowercase())) {
+                lookup.extend(theme_keywords.iter().map(|k| k.trim().to_lowercase()));
+            }
+        }
+        lookup.into_iter().collect()
+    }
 }
 
 impl ImportanceCalculator {
-    fn new(_config: &Config) -> Self {
+    fn new(config: &Config) -> Self {
         Self {
             access_weight: 0.3,
             recency_weight: 0.2,
             connection_weight: 0.2,
             feedback_weight: 0.2,
             content_weight: 0.1,
+            retention_floor: config.retention.importance_floor,
         }
     }
 
+    /// 按保持率和访问次数计算衰减后的重要性
+    ///
+    /// 活跃度取保持率与（时间权重×保持率 + 访问权重×访问频度）加权平均中的较大者，
+    /// 重要性在基础重要性的下限比例和全部之间按活跃度插值。
+    fn retained_importance(&self, base: f32, retention: f32, access_count: u64) -> f32 {
+        let frequency = ((access_count as f32).ln_1p() / ACCESS_SATURATION.ln_1p()).min(1.0);
+        let weight = self.recency_weight + self.access_weight;
+        let activity = if weight > 0.0 {
+            (self.recency_weight * retention + self.access_weight * frequency) / weight
+        } else {
+            retention
+        };
+        let vitality = activity.max(retention);
+        (base * (self.retention_floor + (1.0 - self.retention_floor) * vitality)).clamp(0.0, 1.0)
+    }
+
     async fn calculate_initial_importance(
         &self,
         memory: &Memory,
@@ -1435,21 +2216,15 @@ impl EvolutionEngine {
             reinforcement_factor: 1.05,
             pruning_threshold: 0.1,
             evolution_interval: Duration::hours(config.learning.learning_interval_hours as i64),
+            advisor: Arc::new(RuleEvolutionAdvisor::new(config)),
+            max_neighbours: config.evolution.max_neighbours,
+            neighbour_threshold: config.evolution.similarity_threshold,
         }
     }
 
-    async fn evolve(&self, trigger: &EvolutionTrigger, _db: &VectorGraphDB) -> Result<u64> {
-        match trigger {
-            EvolutionTrigger::TimeDecay => {
-                // 实现时间衰减逻辑
-                Ok(0)
-            }
-            EvolutionTrigger::UserFeedback(_memory_id, _score) => {
-                // 实现用户反馈处理逻辑
-                Ok(1)
-            }
-            _ => Ok(0),
-        }
+    async fn evolve(&self, _trigger: &EvolutionTrigger, _db: &VectorGraphDB) -> Result<u64> {
+        // 访问模式、连接强度变化和批量更新触发的演化尚未实现
+        Ok(0)
     }
 }
 
@@ -1477,6 +2252,8 @@ impl RetrievalEngine {
 mod tests {
     use super::*;
     use crate::database::VectorGraphDB;
+    use crate::dedup::DUPLICATE_SOURCES_ATTRIBUTE;
+    use crate::llm::ScriptedLlmProvider;
     use tempfile::NamedTempFile;
 
     async fn create_test_memory_manager() -> MemoryManager {
@@ -1508,6 +2285,8 @@ mod tests {
             context: Context::default(),
             attributes: None,
             force_connections: None,
+            memory_type: None,
+            duplicate_strategy: None,
         };
 
         let memory = manager.create_memory_from_request(request).await.unwrap();
@@ -1526,6 +2305,8 @@ mod tests {
             context: Context::default(),
             attributes: None,
             force_connections: None,
+            memory_type: None,
+            duplicate_strategy: None,
         };
         manager.create_memory_from_request(request).await.unwrap();
 
@@ -1538,6 +2319,7 @@ mod tests {
             offset: None,
             sort_by: None,
             weights: QueryWeights::default(),
+            expansion: None,
         };
 
         let results = manager
@@ -1547,6 +2329,639 @@ mod tests {
         assert!(!results.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_community_detection_and_thematic_retrieval() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for (content, keywords, embedding) in [
+            ("早上喝了一杯拿铁", vec!["咖啡", "拿铁"], vec![1.0, 0.0, 0.0]),
+            ("下午又点了美式", vec!["咖啡", "美式"], vec![0.9, 0.1, 0.0]),
+            ("周末跑了十公里", vec!["跑步"], vec![0.0, 0.0, 1.0]),
+        ] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Event,
+                embedding,
+                MemoryAttributes {
+                    keywords: keywords.into_iter().map(String::from).collect(),
+                    ..Default::default()
+                },
+            );
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let communities = manager.detect_communities().await.unwrap();
+        assert_eq!(communities.len(), 1);
+        assert_eq!(communities[0].keywords[0], "咖啡");
+        assert_eq!(manager.get_communities().await.unwrap(), communities);
+        assert_eq!(manager.get_stats().await.total_connections, 1);
+
+        let mut filters = QueryFilters::default();
+        filters.custom_filters.insert(
+            crate::graph::COMMUNITY_ATTRIBUTE.to_string(),
+            serde_json::Value::String(communities[0].id.clone()),
+        );
+        let query = Query {
+            text: "我喝的咖啡".to_string(),
+            query_type: QueryType::Thematic,
+            filters,
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+            expansion: None,
+        };
+
+        let results = manager
+            .retrieve_memories(&query, &Context::default())
+            .await
+            .unwrap();
+        let mut found: Vec<_> = results.iter().map(|r| r.memory.id.clone()).collect();
+        found.sort();
+        let mut expected = vec![ids[0].clone(), ids[1].clone()];
+        expected.sort();
+        assert_eq!(found, expected);
+    }
+
+    #[tokio::test]
+    async fn test_personalized_pagerank_cache_and_connection_removal() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        // 只考察记忆之间的连接，不引入实体节点
+        config.graph.entity.enabled = false;
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for content in ["开会", "写周报", "提交周报"] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Event,
+                vec![1.0, 0.0],
+                MemoryAttributes::default(),
+            );
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+        let first = Connection::new(ids[0].clone(), ids[1].clone(), ConnectionType::Temporal, 0.9);
+        let second = Connection::new(ids[1].clone(), ids[2].clone(), ConnectionType::Causal, 0.6);
+        manager.create_connection(&first).await.unwrap();
+        manager.create_connection(&second).await.unwrap();
+
+        let seeds = HashMap::from([(ids[0].clone(), 1.0)]);
+        let pipeline = manager.pipeline();
+        let scores = pipeline.personalized_pagerank(&seeds).await.unwrap();
+        assert!(scores[&ids[1]] > scores[&ids[2]]);
+        assert_eq!(pipeline.pagerank_cache().read().await.len(), 1);
+        assert_eq!(pipeline.personalized_pagerank(&seeds).await.unwrap(), scores);
+
+        manager.remove_connection(&second.id).await.unwrap();
+        let scores = pipeline.personalized_pagerank(&seeds).await.unwrap();
+        assert!(!scores.contains_key(&ids[2]));
+        assert_eq!(manager.get_stats().await.total_connections, 1);
+        assert!(manager.remove_connection(&second.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_entity_graph_links_memories() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for content in ["I met Alice at RustConf", "Alice recommended 《沙丘》"] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Event,
+                vec![1.0, 0.0],
+                MemoryAttributes::default(),
+            );
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let entities = manager.get_memory_entities(&ids[1]).await.unwrap();
+        let names: Vec<&str> = entities.iter().map(|e| e.name.as_str()).collect();
+        assert!(names.contains(&"Alice"));
+        assert!(names.contains(&"沙丘"));
+        assert!(manager.get_connections(&ids[0]).await.unwrap().is_empty());
+
+        // 从“RustConf”出发，经由共享实体Alice到达第二条记忆
+        let pipeline = manager.pipeline();
+        let seeds = pipeline.entity_seeds("RustConf").await.unwrap();
+        let scores = pipeline.personalized_pagerank(&seeds).await.unwrap();
+        assert!(scores.get(&ids[1]).copied().unwrap_or(0.0) > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_discover_connections() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.graph.entity.enabled = false;
+        config.graph.linking.themes =
+            HashMap::from([("出行".to_string(), vec!["旅行".to_string(), "机票".to_string()])]);
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for (embedding, tags, keywords, hours_ago) in [
+            (vec![1.0, 0.0], vec!["旅行"], vec![], 2),
+            (vec![0.0, 1.0], vec![], vec!["旅行"], 240),
+            (vec![0.96, 0.28], vec![], vec![], 720),
+            (vec![0.0, 1.0], vec![], vec!["机票"], 240),
+        ] {
+            let mut memory = Memory::new(
+                "记忆".to_string(),
+                MemoryType::Event,
+                embedding,
+                MemoryAttributes {
+                    tags: tags.into_iter().map(String::from).collect(),
+                    keywords: keywords.into_iter().map(String::from).collect(),
+                    ..Default::default()
+                },
+            );
+            memory.metadata.created_at = Utc::now() - Duration::hours(hours_ago);
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let memory = Memory::new(
+            "新记忆".to_string(),
+            MemoryType::Event,
+            vec![1.0, 0.0],
+            MemoryAttributes {
+                tags: vec!["旅行".to_string()],
+                ..Default::default()
+            },
+        );
+        let connections = manager
+            .link_analyzer
+            .discover_connections(&memory, &Context::default(), &manager.db)
+            .await
+            .unwrap();
+        let targets = |kind: ConnectionType| -> HashSet<MemoryId> {
+            connections
+                .iter()
+                .filter(|c| c.connection_type == kind)
+                .map(|c| c.to_memory.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            targets(ConnectionType::Semantic),
+            HashSet::from([ids[0].clone(), ids[2].clone()])
+        );
+        assert_eq!(targets(ConnectionType::Temporal), HashSet::from([ids[0].clone()]));
+        assert_eq!(
+            targets(ConnectionType::Thematic),
+            HashSet::from([ids[0].clone(), ids[1].clone(), ids[3].clone()])
+        );
+        let semantic = connections
+            .iter()
+            .find(|c| c.to_memory == ids[2] && c.connection_type == ConnectionType::Semantic)
+            .unwrap();
+        assert!((semantic.strength - 0.96).abs() < 1e-3);
+
+        // 语义近邻数受top_k限制
+        let mut config = manager.config.clone();
+        config.graph.linking.top_k = 1;
+        let connections = LinkAnalyzer::new(&config)
+            .discover_connections(&memory, &Context::default(), &manager.db)
+            .await
+            .unwrap();
+        let semantic: Vec<&MemoryId> = connections
+            .iter()
+            .filter(|c| c.connection_type == ConnectionType::Semantic)
+            .map(|c| &c.to_memory)
+            .collect();
+        assert_eq!(semantic, vec![&ids[0]]);
+    }
+
+    #[tokio::test]
+    async fn test_segmenter_is_per_manager() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let plain = MemoryManager::new(db.clone(), config.clone())
+            .await
+            .unwrap();
+
+        config.segmentation.custom_dictionary = vec!["量子纠缠".to_string()];
+        let custom = MemoryManager::new(db, config).await.unwrap();
+
+        assert_eq!(custom.segmenter().tokenize("量子纠缠"), vec!["量子纠缠"]);
+        assert_eq!(
+            custom.duplicate_detector.fingerprint("量子纠缠").normalized,
+            "量子纠缠"
+        );
+        // 自定义词典不影响其他管理器和默认分词器
+        assert_eq!(
+            plain.segmenter().tokenize("量子纠缠"),
+            vec!["量子", "子纠", "纠缠"]
+        );
+        assert_eq!(
+            TextUtils::tokenize("量子纠缠"),
+            vec!["量子", "子纠", "纠缠"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_causal_links_and_retrieval() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.graph.entity.enabled = false;
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for content in ["用户反馈系统响应速度太慢", "因为系统响应速度太慢，我们给数据库添加了索引"] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Event,
+                vec![1.0, 0.0],
+                MemoryAttributes::default(),
+            );
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let context = Context {
+            recent_memories: vec![ids[0].clone()],
+            ..Default::default()
+        };
+        let links = manager.discover_causal_links(&ids[1], &context).await.unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].from_memory, ids[0]);
+        assert_eq!(links[0].to_memory, ids[1]);
+        // 重复检测不会创建重复的因果边
+        assert!(manager
+            .discover_causal_links(&ids[1], &context)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let stored = manager.get_connections(&ids[1]).await.unwrap();
+        assert_eq!(stored[0].connection_type, ConnectionType::Causal);
+        assert_eq!(stored[0].properties["analyzer"], "rule");
+        assert!(stored[0].properties["evidence"]
+            .as_str()
+            .unwrap()
+            .starts_with("因为"));
+
+        let query = Query {
+            text: "后来做了什么".to_string(),
+            query_type: QueryType::Causal,
+            filters: QueryFilters::default(),
+            limit: Some(5),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+            expansion: None,
+        };
+        let results = manager.retrieve_memories(&query, &context).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].memory.id, ids[1]);
+    }
+
+    #[tokio::test]
+    async fn test_llm_attribute_extraction() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.llm.retry_backoff_ms = 0;
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let provider = Arc::new(ScriptedLlmProvider::new([
+            r#"{"keywords": ["Transformer", "自注意力"], "tags": ["深度学习"],
+                "emotion": "好奇", "importance": 0.9, "confidence": 0.8,
+                "language": "zh", "themes": ["人工智能"]}"#,
+        ]));
+        let manager = MemoryManager::new(db, config)
+            .await
+            .unwrap()
+            .with_llm_provider(provider.clone());
+
+        let context = Context {
+            current_topic: Some("NLP".to_string()),
+            ..Default::default()
+        };
+        let content = "Transformer 的自注意力机制能够捕捉长距离依赖";
+        let attributes = manager.extract_attributes(content, &context).await.unwrap();
+        assert_eq!(attributes.keywords, vec!["Transformer", "自注意力"]);
+        assert_eq!(attributes.importance, 0.9);
+        assert_eq!(attributes.emotion.as_deref(), Some("好奇"));
+        assert_eq!(attributes.context, "NLP");
+        assert_eq!(attributes.custom_attributes["themes"], serde_json::json!(["人工智能"]));
+
+        // 模型不可用时回退到规则提取
+        let context = Context {
+            session_id: Some("s1".to_string()),
+            ..context
+        };
+        let attributes = manager.extract_attributes(content, &context).await.unwrap();
+        assert!(attributes.keywords.contains(&"transformer".to_string()));
+        assert!(attributes.keywords.iter().all(|k| k.chars().count() <= 11));
+        assert_eq!(attributes.language.as_deref(), Some("zh"));
+        assert_eq!(attributes.source.as_deref(), Some("session:s1"));
+        assert_eq!(attributes.importance, 0.5);
+        assert_eq!(provider.remaining(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_merge_duplicates() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.graph.entity.enabled = false;
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for (i, (content, embedding, tag)) in [
+            ("我喜欢喝咖啡", vec![1.0, 0.0], "饮食"),
+            ("我喜欢喝咖啡！", vec![0.99, 0.14], "习惯"),
+            ("明天上午开会", vec![0.0, 1.0], "工作"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut memory = Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                embedding,
+                MemoryAttributes {
+                    tags: vec![tag.to_string()],
+                    importance: 0.5,
+                    ..Default::default()
+                },
+            );
+            memory.metadata.created_at = Utc::now() - Duration::hours(3 - i as i64);
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let report = manager.merge_duplicates().await.unwrap();
+        assert_eq!(report.clusters, vec![vec![ids[0].clone(), ids[1].clone()]]);
+        assert_eq!(report.merged, 1);
+
+        let kept = manager.get_memory(&ids[0]).await.unwrap();
+        assert_eq!(kept.attributes.tags, vec!["饮食", "习惯"]);
+        assert_eq!(kept.metadata.access_count, 1);
+        assert!(kept.attributes.importance > 0.5);
+        assert!(kept.attributes.custom_attributes[DUPLICATE_SOURCES_ATTRIBUTE].is_array());
+        assert!(manager.get_memory(&ids[1]).await.unwrap().metadata.is_deleted);
+        assert!(!manager.get_memory(&ids[2]).await.unwrap().metadata.is_deleted);
+
+        // 再次运行时不再有重复
+        assert_eq!(manager.merge_duplicates().await.unwrap().merged, 0);
+    }
+
+    #[tokio::test]
+    async fn test_evolve_neighbours_on_new_memory() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.graph.entity.enabled = false;
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for (embedding, tags, keywords) in [
+            (vec![1.0, 0.0, 0.0], vec!["学习"], vec!["rust"]),
+            (vec![0.0, 0.0, 1.0], vec!["生活"], vec!["咖啡"]),
+            (vec![0.0, 1.0, 0.0], vec!["学习"], vec!["rust"]),
+        ] {
+            let memory = Memory::new(
+                "记忆".to_string(),
+                MemoryType::Knowledge,
+                embedding,
+                MemoryAttributes {
+                    tags: tags.into_iter().map(String::from).collect(),
+                    keywords: keywords.into_iter().map(String::from).collect(),
+                    ..Default::default()
+                },
+            );
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let new = Memory::new(
+            "新记忆".to_string(),
+            MemoryType::Knowledge,
+            vec![0.95, 0.0, 0.31],
+            MemoryAttributes {
+                tags: vec!["学习".to_string(), "编程".to_string()],
+                keywords: vec!["rust".to_string()],
+                ..Default::default()
+            },
+        );
+        manager.create_memory(&new).await.unwrap();
+
+        let evolved = manager
+            .evolve_memories(EvolutionTrigger::NewMemoryAdded(new.id.clone()))
+            .await
+            .unwrap();
+        assert_eq!(evolved, 1);
+
+        // 只有相似度达到阈值的近邻被演化
+        let neighbour = manager.get_memory(&ids[0]).await.unwrap();
+        assert_eq!(neighbour.attributes.tags, vec!["学习", "编程"]);
+        assert_eq!(neighbour.attributes.context, "相关主题：rust、学习");
+        assert_eq!(neighbour.metadata.version, 2);
+        let connections = manager.get_connections(&new.id).await.unwrap();
+        assert!(connections.iter().any(|c| c.to_memory == ids[0]));
+
+        let history = manager.get_memory_history(&ids[0]).await.unwrap();
+        assert_eq!(history.len(), 3);
+        assert!(matches!(history[0].update_type, UpdateType::ConnectionAdded(_)));
+        assert_eq!(history[0].updater_info.as_deref(), Some("rule"));
+        assert!(manager.get_memory_history(&ids[1]).await.unwrap().is_empty());
+        assert_eq!(manager.get_memory(&ids[2]).await.unwrap().metadata.version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retention_decay_and_feedback() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.graph.entity.enabled = false;
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for days in [10, 0] {
+            let mut memory = Memory::new(
+                "记忆".to_string(),
+                MemoryType::Knowledge,
+                vec![1.0, 0.0],
+                MemoryAttributes {
+                    importance: 0.8,
+                    ..Default::default()
+                },
+            );
+            memory.metadata.last_accessed = Utc::now() - Duration::days(days);
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        // 十天未强化的记忆衰减到基础重要性的下限比例，新记忆不变
+        let decayed = manager
+            .evolve_memories(EvolutionTrigger::TimeDecay)
+            .await
+            .unwrap();
+        assert_eq!(decayed, 1);
+        let stale = manager.get_memory(&ids[0]).await.unwrap();
+        assert!((stale.attributes.importance - 0.16).abs() < 1e-3);
+        let fresh = manager.get_memory(&ids[1]).await.unwrap();
+        assert_eq!(fresh.attributes.importance, 0.8);
+
+        // 正向反馈强化记忆，重要性恢复并略有提升
+        manager
+            .evolve_memories(EvolutionTrigger::UserFeedback(ids[0].clone(), 1.0))
+            .await
+            .unwrap();
+        let reinforced = manager.get_memory(&ids[0]).await.unwrap();
+        assert!((reinforced.attributes.importance - 0.84).abs() < 1e-3);
+        assert_eq!(manager.retention.state(&reinforced).stability_days, 2.0);
+
+        let history = manager.get_memory_history(&ids[0]).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].updater_info.as_deref(), Some("retention"));
+        assert_eq!(
+            manager
+                .evolve_memories(EvolutionTrigger::TimeDecay)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_retained_importance_bounds() {
+        let calculator = ImportanceCalculator::new(&Config::default());
+
+        // 完全遗忘且从未访问时降到下限比例，刚强化时保持基础重要性
+        assert!((calculator.retained_importance(0.8, 0.0, 0) - 0.16).abs() < 1e-6);
+        assert!((calculator.retained_importance(0.8, 1.0, 0) - 0.8).abs() < 1e-6);
+
+        // 访问次数达到饱和后不再提升重要性
+        let saturated = calculator.retained_importance(0.8, 0.0, 20);
+        assert!((saturated - 0.544).abs() < 1e-6);
+        assert_eq!(calculator.retained_importance(0.8, 0.0, 10_000), saturated);
+
+        assert_eq!(calculator.retained_importance(2.0, 1.0, 0), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_access_stats_write_behind() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.graph.entity.enabled = false;
+        config.access.flush_threshold = 2;
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for embedding in [vec![1.0, 0.0], vec![0.0, 1.0]] {
+            let memory = Memory::new(
+                "记忆".to_string(),
+                MemoryType::Knowledge,
+                embedding,
+                MemoryAttributes::default(),
+            );
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let interaction = |interaction_type, retrieved_memories| Interaction {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: "user".to_string(),
+            session_id: None,
+            query: String::new(),
+            retrieved_memories,
+            user_feedback: None,
+            interaction_type,
+            timestamp: Utc::now(),
+            response_time_ms: None,
+            additional_info: HashMap::new(),
+        };
+
+        // 按检索计数时查询交互不重复计数；浏览计数但未达到阈值前不写回
+        let query = interaction(InteractionType::Query, vec![ids[0].clone(), ids[1].clone()]);
+        manager.record_interaction(&query).await.unwrap();
+        let browse = interaction(InteractionType::Browse, vec![ids[0].clone(); 2]);
+        manager.record_interaction(&browse).await.unwrap();
+        let memory = manager.get_memory(&ids[0]).await.unwrap();
+        assert_eq!(memory.metadata.access_count, 0);
+
+        // 待写回的记忆数达到阈值后批量写回，并强化记忆
+        let browse = interaction(InteractionType::Browse, vec![ids[1].clone()]);
+        manager.record_interaction(&browse).await.unwrap();
+        let memory = manager.get_memory(&ids[0]).await.unwrap();
+        assert_eq!(memory.metadata.access_count, 2);
+        assert_eq!(manager.retention.state(&memory).stability_days, 1.5);
+        let other = manager.get_memory(&ids[1]).await.unwrap();
+        assert_eq!(other.metadata.access_count, 1);
+        assert_eq!(manager.flush_access_stats().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_execute_graph_query() {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let mut ids = Vec::new();
+        for (content, importance) in [("下雨了", 0.5), ("航班延误", 0.9)] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Event,
+                vec![1.0, 0.0],
+                MemoryAttributes {
+                    importance,
+                    ..Default::default()
+                },
+            );
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+        manager
+            .create_connection(&Connection::new(
+                ids[0].clone(),
+                ids[1].clone(),
+                ConnectionType::Causal,
+                0.8,
+            ))
+            .await
+            .unwrap();
+
+        let rows = manager
+            .execute_graph_query(
+                "MATCH (a:Event)-[r:Causal]->(b) WHERE b.importance > 0.7 RETURN b, r",
+            )
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        match rows[0].get("b") {
+            Some(QueryItem::Memory(memory)) => {
+                assert_eq!(memory.id, ids[1]);
+                assert_eq!(memory.content, "航班延误");
+            }
+            other => panic!("unexpected item: {:?}", other),
+        }
+        match rows[0].get("r") {
+            Some(QueryItem::Connection(connection)) => {
+                assert_eq!(connection.connection_type, ConnectionType::Causal);
+                assert_eq!(connection.from_memory, ids[0]);
+            }
+            other => panic!("unexpected item: {:?}", other),
+        }
+
+        assert!(manager.execute_graph_query("MATCH (a RETURN a").await.is_err());
+    }
+
     #[tokio::test]
     async fn test_memory_stats() {
         let manager = create_test_memory_manager().await;
@@ -1560,6 +2975,8 @@ mod tests {
             context: Context::default(),
             attributes: None,
             force_connections: None,
+            memory_type: None,
+            duplicate_strategy: None,
         };
         manager.create_memory_from_request(request).await.unwrap();
 
@@ -0,0 +1,561 @@
+//! PageRank模块
+//!
+//! 维护持久化的全局PageRank分数，并在边插入、更新或删除时通过残差推送（delta-push）
+//! 增量更新；另外提供基于局部推送的个性化PageRank，以及按种子集合缓存的结果。
+//! 检索只使用个性化PageRank及其缓存；持久化的全局分数供
+//! [`VectorGraphDB::get_pagerank_scores`] 读取，目前不参与检索排序。
+//!
+//! 全局分数采用未归一化形式 `π = (1-d)·1 + d·Wᵀπ`。每个节点维护估计值 `p` 和残差 `r`，
+//! 始终满足 `r = (1-d)·1 - p + d·Wᵀp`。某个节点的出边变化时只需按转移概率的差值修正
+//! 邻居残差，再从残差较大的节点局部推送，无需全量重算。
+
+use crate::config::{Config, IncrementalPageRankConfig};
+use crate::core::MemoryId;
+use crate::database::{GraphEdge, VectorGraphDB};
+use crate::error::Result;
+use crate::utils::HashUtils;
+use log::{debug, info, warn};
+use lru::LruCache;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::num::NonZeroUsize;
+use tokio::sync::{Mutex, MutexGuard};
+
+/// 节点的转移分布（邻居 -> 转移概率）
+pub type Transitions = HashMap<String, f64>;
+
+/// 节点的PageRank状态：(估计值, 残差)
+pub type PageRankEntry = (f64, f64);
+
+/// 增量PageRank
+#[derive(Debug)]
+pub struct IncrementalPageRank {
+    config: IncrementalPageRankConfig,
+    damping: f64,
+    max_iterations: usize,
+    convergence_threshold: f64,
+    /// 串行化边变化，保证残差不变式
+    update_lock: Mutex<()>,
+}
+
+impl IncrementalPageRank {
+    /// 创建新的增量PageRank
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.graph.pagerank.incremental.clone(),
+            damping: config.graph.pagerank.damping_factor as f64,
+            max_iterations: config.graph.pagerank.max_iterations,
+            convergence_threshold: config.graph.pagerank.convergence_threshold as f64,
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    /// 是否启用增量更新
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 获取更新锁，在边写入前后持有
+    pub async fn lock(&self) -> MutexGuard<'_, ()> {
+        self.update_lock.lock().await
+    }
+
+    /// 边变化会影响其转移分布的节点
+    pub fn affected_nodes(&self, edge: &GraphEdge) -> Vec<String> {
+        let mut nodes = vec![edge.from_node.clone()];
+        if self.config.undirected && edge.to_node != edge.from_node {
+            nodes.push(edge.to_node.clone());
+        }
+        nodes
+    }
+
+    /// 读取节点当前的转移分布，用于边变化前的快照
+    pub async fn snapshot(
+        &self,
+        db: &VectorGraphDB,
+        nodes: &[String],
+    ) -> Result<HashMap<String, Transitions>> {
+        let mut snapshot = HashMap::new();
+        for node in nodes {
+            if !snapshot.contains_key(node) {
+                snapshot.insert(node.clone(), self.transitions(db, node).await?);
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// 边变化后修正残差并局部推送，返回推送次数
+    pub async fn apply_edge_change(
+        &self,
+        db: &VectorGraphDB,
+        before: HashMap<String, Transitions>,
+    ) -> Result<usize> {
+        let mut entries = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        for (node, old) in before {
+            let new = self.transitions(db, &node).await?;
+            let (score, _) = *self.load(db, &mut entries, &node).await?;
+            if score == 0.0 {
+                continue;
+            }
+
+            let neighbors: HashSet<&String> = old.keys().chain(new.keys()).collect();
+            for neighbor in neighbors {
+                let delta = self.damping
+                    * score
+                    * (new.get(neighbor).unwrap_or(&0.0) - old.get(neighbor).unwrap_or(&0.0));
+                if delta != 0.0 {
+                    self.load(db, &mut entries, neighbor).await?.1 += delta;
+                    queue.push_back(neighbor.clone());
+                }
+            }
+        }
+
+        let pushes = self.push(db, &mut entries, queue, true).await?;
+        db.save_pagerank_entries(&entries, false).await?;
+        Ok(pushes)
+    }
+
+    /// 新节点加入时注入初始残差
+    pub async fn apply_node_insert(&self, db: &VectorGraphDB, node_id: &str) -> Result<()> {
+        let mut entries = HashMap::new();
+        self.load(db, &mut entries, node_id).await?.1 += 1.0 - self.damping;
+
+        let queue = VecDeque::from([node_id.to_string()]);
+        self.push(db, &mut entries, queue, true).await?;
+        db.save_pagerank_entries(&entries, false).await
+    }
+
+    /// 用幂迭代全量重建全局PageRank，返回节点数
+    pub async fn rebuild(&self, db: &VectorGraphDB) -> Result<usize> {
+        let nodes: HashSet<String> = db
+            .get_all_nodes(None)
+            .await?
+            .into_iter()
+            .map(|n| n.id)
+            .collect();
+        let edges = db.get_all_edges(&None).await?;
+
+        let mut out: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+        for edge in &edges {
+            self.add_transition(&mut out, edge);
+        }
+        let transitions: HashMap<String, Transitions> = out
+            .into_iter()
+            .map(|(node, neighbors)| (node, Self::normalize(neighbors)))
+            .collect();
+
+        let mut ids: HashSet<String> = nodes.clone();
+        for (node, neighbors) in &transitions {
+            ids.insert(node.clone());
+            ids.extend(neighbors.keys().cloned());
+        }
+
+        let teleport = |id: &str| {
+            if nodes.contains(id) {
+                1.0 - self.damping
+            } else {
+                0.0
+            }
+        };
+        let spread = |scores: &HashMap<String, f64>| {
+            let mut next: HashMap<String, f64> =
+                ids.iter().map(|id| (id.clone(), teleport(id))).collect();
+            for (node, neighbors) in &transitions {
+                let score = scores.get(node).copied().unwrap_or(0.0);
+                for (neighbor, probability) in neighbors {
+                    *next.entry(neighbor.clone()).or_insert(0.0) +=
+                        self.damping * score * probability;
+                }
+            }
+            next
+        };
+
+        let mut scores: HashMap<String, f64> =
+            ids.iter().map(|id| (id.clone(), teleport(id))).collect();
+        for iteration in 0..self.max_iterations.max(1) {
+            let next = spread(&scores);
+            let delta = next
+                .iter()
+                .map(|(id, score)| (score - scores.get(id).unwrap_or(&0.0)).abs())
+                .fold(0.0, f64::max);
+            scores = next;
+            if delta < self.convergence_threshold {
+                debug!("PageRank converged after {} iterations", iteration + 1);
+                break;
+            }
+        }
+
+        // r = (1-d)·1 - p + d·Wᵀp
+        let propagated = spread(&scores);
+        let entries: HashMap<String, PageRankEntry> = scores
+            .iter()
+            .map(|(id, score)| (id.clone(), (*score, propagated[id] - score)))
+            .collect();
+
+        db.save_pagerank_entries(&entries, true).await?;
+        info!("Rebuilt PageRank for {} nodes", entries.len());
+        Ok(entries.len())
+    }
+
+    /// 个性化PageRank（局部前向推送）
+    ///
+    /// 种子权重会被归一化，返回的分数之和不超过1，只包含推送到达的节点。
+    pub async fn personalized(
+        &self,
+        db: &VectorGraphDB,
+        seeds: &HashMap<MemoryId, f32>,
+    ) -> Result<HashMap<MemoryId, f32>> {
+        let total: f64 = seeds
+            .values()
+            .filter(|w| **w > 0.0)
+            .map(|w| *w as f64)
+            .sum();
+        if total <= 0.0 {
+            return Ok(HashMap::new());
+        }
+
+        let mut entries: HashMap<String, PageRankEntry> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut seed_ids: Vec<&MemoryId> = seeds.keys().collect();
+        seed_ids.sort();
+        for id in seed_ids {
+            let weight = seeds[id] as f64;
+            if weight > 0.0 {
+                entries.insert(id.clone(), (0.0, (1.0 - self.damping) * weight / total));
+                queue.push_back(id.clone());
+            }
+        }
+
+        let pushes = self.push(db, &mut entries, queue, false).await?;
+        debug!("Personalized PageRank finished after {} pushes", pushes);
+
+        Ok(entries
+            .into_iter()
+            .filter(|(_, (score, _))| *score > 0.0)
+            .map(|(id, (score, _))| (id, score as f32))
+            .collect())
+    }
+
+    /// 残差推送直到所有残差低于阈值
+    async fn push(
+        &self,
+        db: &VectorGraphDB,
+        entries: &mut HashMap<String, PageRankEntry>,
+        mut queue: VecDeque<String>,
+        persisted: bool,
+    ) -> Result<usize> {
+        let tolerance = self.config.push_tolerance as f64;
+        let mut transitions: HashMap<String, Transitions> = HashMap::new();
+        let mut queued: HashSet<String> = queue.iter().cloned().collect();
+        let mut pushes = 0;
+
+        while let Some(node) = queue.pop_front() {
+            queued.remove(&node);
+
+            let residual = self.load_or_default(db, entries, &node, persisted).await?.1;
+            if residual.abs() <= tolerance {
+                continue;
+            }
+            if pushes >= self.config.max_pushes {
+                warn!(
+                    "PageRank push limit reached ({}), {} nodes left in queue",
+                    self.config.max_pushes,
+                    queue.len() + 1
+                );
+                break;
+            }
+            pushes += 1;
+
+            if let Some(entry) = entries.get_mut(&node) {
+                entry.0 += residual;
+                entry.1 = 0.0;
+            }
+
+            if !transitions.contains_key(&node) {
+                let node_transitions = self.transitions(db, &node).await?;
+                transitions.insert(node.clone(), node_transitions);
+            }
+            for (neighbor, probability) in &transitions[&node] {
+                let entry = self
+                    .load_or_default(db, entries, neighbor, persisted)
+                    .await?;
+                entry.1 += self.damping * residual * probability;
+                if entry.1.abs() > tolerance && queued.insert(neighbor.clone()) {
+                    queue.push_back(neighbor.clone());
+                }
+            }
+        }
+
+        Ok(pushes)
+    }
+
+    /// 读取节点的转移分布
+    async fn transitions(&self, db: &VectorGraphDB, node: &str) -> Result<Transitions> {
+        let mut out = HashMap::new();
+        for edge in db.get_adjacent_edges(node, &None).await? {
+            if edge.from_node == node || (self.config.undirected && edge.to_node == node) {
+                self.add_transition(&mut out, &edge);
+            }
+        }
+        Ok(out.remove(node).map(Self::normalize).unwrap_or_default())
+    }
+
+    fn add_transition(&self, out: &mut HashMap<String, Vec<(String, f64)>>, edge: &GraphEdge) {
+        let weight = edge.weight.max(0.0) as f64;
+        out.entry(edge.from_node.clone())
+            .or_default()
+            .push((edge.to_node.clone(), weight));
+        if self.config.undirected && edge.to_node != edge.from_node {
+            out.entry(edge.to_node.clone())
+                .or_default()
+                .push((edge.from_node.clone(), weight));
+        }
+    }
+
+    fn normalize(neighbors: Vec<(String, f64)>) -> Transitions {
+        let total: f64 = neighbors.iter().map(|(_, w)| w).sum();
+        let mut transitions = HashMap::new();
+        if total > 0.0 {
+            for (neighbor, weight) in neighbors {
+                *transitions.entry(neighbor).or_insert(0.0) += weight / total;
+            }
+        }
+        transitions
+    }
+
+    async fn load<'e>(
+        &self,
+        db: &VectorGraphDB,
+        entries: &'e mut HashMap<String, PageRankEntry>,
+        node: &str,
+    ) -> Result<&'e mut PageRankEntry> {
+        self.load_or_default(db, entries, node, true).await
+    }
+
+    async fn load_or_default<'e>(
+        &self,
+        db: &VectorGraphDB,
+        entries: &'e mut HashMap<String, PageRankEntry>,
+        node: &str,
+        persisted: bool,
+    ) -> Result<&'e mut PageRankEntry> {
+        if !entries.contains_key(node) {
+            let entry = if persisted {
+                db.get_pagerank_entry(node).await?.unwrap_or((0.0, 0.0))
+            } else {
+                (0.0, 0.0)
+            };
+            entries.insert(node.to_string(), entry);
+        }
+        Ok(entries.get_mut(node).expect("entry inserted above"))
+    }
+}
+
+/// 按最大值把分数归一化到[0, 1]
+pub fn normalize_by_max(scores: HashMap<MemoryId, f32>) -> HashMap<MemoryId, f32> {
+    let max = scores.values().copied().fold(0.0f32, f32::max);
+    if max <= 0.0 {
+        return scores;
+    }
+    scores
+        .into_iter()
+        .map(|(id, score)| (id, score / max))
+        .collect()
+}
+
+/// 缓存的个性化PageRank结果
+#[derive(Debug, Clone)]
+struct CachedScores {
+    graph_version: u64,
+    scores: HashMap<MemoryId, f32>,
+}
+
+/// 个性化PageRank缓存
+///
+/// 以种子集合为键缓存结果，图版本变化后对应条目自动失效。
+#[derive(Debug)]
+pub struct PageRankCache {
+    entries: LruCache<String, CachedScores>,
+}
+
+impl Default for PageRankCache {
+    fn default() -> Self {
+        Self::new(IncrementalPageRankConfig::default().cache_size)
+    }
+}
+
+impl PageRankCache {
+    /// 创建新的缓存
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: LruCache::new(capacity),
+        }
+    }
+
+    /// 种子集合的缓存键
+    pub fn seed_key(seeds: &HashMap<MemoryId, f32>) -> String {
+        let mut parts: Vec<String> = seeds
+            .iter()
+            .map(|(id, weight)| format!("{}:{:.4}", id, weight))
+            .collect();
+        parts.sort();
+        HashUtils::hash_string(&parts.join("|"))
+    }
+
+    /// 查询缓存，版本不一致的条目会被移除
+    pub fn get(&mut self, key: &str, graph_version: u64) -> Option<HashMap<MemoryId, f32>> {
+        match self.entries.get(key) {
+            Some(cached) if cached.graph_version == graph_version => Some(cached.scores.clone()),
+            Some(_) => {
+                self.entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// 写入缓存
+    pub fn insert(&mut self, key: String, graph_version: u64, scores: HashMap<MemoryId, f32>) {
+        self.entries.put(
+            key,
+            CachedScores {
+                graph_version,
+                scores,
+            },
+        );
+    }
+
+    /// 缓存条目数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 缓存是否为空
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 清空缓存
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::GraphNode;
+    use chrono::Utc;
+
+    async fn create_db() -> VectorGraphDB {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.graph.pagerank.incremental.push_tolerance = 1e-7;
+        VectorGraphDB::new(config).await.unwrap()
+    }
+
+    async fn add_node(db: &VectorGraphDB, id: &str) {
+        db.insert_node(&GraphNode {
+            id: id.to_string(),
+            node_type: "memory".to_string(),
+            properties: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+    }
+
+    fn edge(id: &str, from: &str, to: &str, weight: f32) -> GraphEdge {
+        GraphEdge {
+            id: id.to_string(),
+            from_node: from.to_string(),
+            to_node: to.to_string(),
+            edge_type: "Semantic".to_string(),
+            weight,
+            properties: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn assert_close(a: &HashMap<String, f32>, b: &HashMap<String, f32>) {
+        assert_eq!(a.len(), b.len());
+        for (id, score) in a {
+            assert!(
+                (score - b[id]).abs() < 1e-4,
+                "{}: {} vs {}",
+                id,
+                score,
+                b[id]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_incremental_updates_match_rebuild() {
+        let db = create_db().await;
+        for id in ["a", "b", "c", "d", "e"] {
+            add_node(&db, id).await;
+        }
+        db.insert_edge(&edge("e1", "a", "b", 1.0)).await.unwrap();
+        db.insert_edge(&edge("e2", "b", "c", 0.5)).await.unwrap();
+        db.insert_edge(&edge("e3", "c", "a", 0.8)).await.unwrap();
+        db.insert_edge(&edge("e4", "d", "a", 1.0)).await.unwrap();
+        db.insert_edge(&edge("e5", "d", "e", 0.3)).await.unwrap();
+
+        let version = db.graph_version();
+        db.update_edge(&edge("e2", "b", "c", 0.9)).await.unwrap();
+        db.delete_edge("e5").await.unwrap();
+        assert_eq!(db.graph_version(), version + 2);
+
+        let incremental = db.get_pagerank_scores().await.unwrap();
+        assert!(incremental["a"] > incremental["e"]);
+
+        db.rebuild_pagerank().await.unwrap();
+        let rebuilt = db.get_pagerank_scores().await.unwrap();
+        assert_close(&incremental, &rebuilt);
+        assert!((rebuilt.values().sum::<f32>() - 1.0).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_personalized_pagerank_favors_seed_neighborhood() {
+        let db = create_db().await;
+        for id in ["a", "b", "c", "d"] {
+            add_node(&db, id).await;
+        }
+        db.insert_edge(&edge("e1", "a", "b", 1.0)).await.unwrap();
+        db.insert_edge(&edge("e2", "b", "c", 1.0)).await.unwrap();
+        db.insert_edge(&edge("e3", "c", "d", 1.0)).await.unwrap();
+
+        let seeds = HashMap::from([("a".to_string(), 1.0)]);
+        let scores = db.personalized_pagerank(&seeds).await.unwrap();
+        assert!(scores["a"] > scores["c"]);
+        assert!(scores["b"] > scores["c"]);
+        assert!(scores["c"] > scores["d"]);
+        assert!(scores.values().sum::<f32>() <= 1.0 + 1e-4);
+
+        assert!(db
+            .personalized_pagerank(&HashMap::new())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_cache_invalidated_by_graph_version() {
+        let mut cache = PageRankCache::new(2);
+        let seeds = HashMap::from([("a".to_string(), 0.5), ("b".to_string(), 1.0)]);
+        let key = PageRankCache::seed_key(&seeds);
+        cache.insert(key.clone(), 3, HashMap::from([("a".to_string(), 0.4)]));
+
+        assert!(cache.get(&key, 3).is_some());
+        assert!(cache.get(&key, 4).is_none());
+        assert!(cache.is_empty());
+
+        let reordered = HashMap::from([("b".to_string(), 1.0), ("a".to_string(), 0.5)]);
+        assert_eq!(PageRankCache::seed_key(&reordered), key);
+    }
+}
@@ -0,0 +1,1756 @@
+//! 检索流水线模块
+//!
+//! 记忆管理器和HippoRAG检索器共用的检索流水线：
+//! 候选生成 → 过滤 → 融合 → PageRank重排序与查询扩展 → 重排序 → 后处理。
+//! 两个入口只负责把各自的查询形式转换为 [`RetrievalRequest`]，
+//! 候选生成器、过滤器和后处理器都可以单独替换和测试。
+
+use crate::causal::RuleCausalDetector;
+use crate::config::{Config, PipelineConfig, ScoreNormalization, TimeParsingConfig, TimeRangeMode};
+use crate::core::*;
+use crate::database::{GraphQueryRequest, VectorGraphDB, VectorQueryRequest};
+use crate::diversity::Diversifier;
+use crate::entity::{EntityExtractor, EntityLinker};
+use crate::error::{MemoryError, Result};
+use crate::fusion::{FusedResult, RankedList, ResultFuser, ScoreComponent};
+use crate::llm::LlmTaskRunner;
+use crate::memory::{memory_from_metadata, RetrievalExplanation, RetrievalResult};
+use crate::pagerank::{normalize_by_max, PageRankCache};
+use crate::query_expansion::QueryExpander;
+use crate::ranking::{LearnedRanker, RetrievalImpression, LEARNED_FUSION_MODEL};
+use crate::rerank::{RerankStage, Reranker};
+use crate::retention::RetentionModel;
+use crate::retrieval::{
+    rank_snapshot, AlternativeResult, FusionMethod, ReasoningStep, RetrievalTrace,
+};
+use crate::segment::Segmenter;
+use crate::time_expr::{window_relevance, TimeExpressionParser};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use futures::future::join_all;
+use log::{debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{OnceCell, RwLock};
+
+/// 语义候选生成器
+pub const SEMANTIC_GENERATOR: &str = "semantic";
+/// 时间感知候选生成器
+pub const TEMPORAL_GENERATOR: &str = "temporal";
+/// 因果候选生成器
+pub const CAUSAL_GENERATOR: &str = "causal";
+/// 主题社区候选生成器
+pub const THEMATIC_GENERATOR: &str = "thematic";
+/// 图遍历候选生成器
+pub const GRAPH_GENERATOR: &str = "graph";
+/// 个性化PageRank候选生成器
+pub const PAGERANK_GENERATOR: &str = "pagerank";
+
+/// 一次检索的请求
+#[derive(Debug, Clone)]
+pub struct RetrievalRequest {
+    pub query: Query,
+    /// 运行的候选生成器及其融合权重
+    pub generators: Vec<(String, f32)>,
+    /// 多个生成器的候选的融合方法
+    pub fusion: FusionMethod,
+    /// 是否对融合结果做PageRank重排序
+    pub pagerank: bool,
+    /// 时间评分窗口，为空时使用最近若干天
+    pub time_window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// 图检索的起始记忆，为空时以语义候选为起点
+    pub start_memories: Vec<MemoryId>,
+    /// 每个生成器最多返回的候选数，为空时使用配置值
+    ///
+    /// 实际取值不小于查询的结果数上限，以免过滤和去重后结果不足。
+    pub candidate_limit: Option<usize>,
+    /// 语义候选的最低相似度，为空时使用向量配置的阈值
+    pub min_similarity: Option<f32>,
+    /// 必须全部带有的标签
+    pub required_tags: Vec<String>,
+    /// 带有其中任一标签的记忆被排除
+    pub excluded_tags: Vec<String>,
+    /// 只保留这些来源的记忆
+    pub sources: Option<Vec<String>>,
+    /// 用户偏好及权重，命中的记忆获得更高的PageRank种子权重和个性化分数
+    pub preferences: HashMap<String, f32>,
+    /// 用户感兴趣的主题及权重
+    pub topic_interests: HashMap<String, f32>,
+    /// 检索所属的会话，记录检索展示时使用
+    pub session_id: Option<String>,
+}
+
+/// 流水线的输出
+#[derive(Debug, Clone, Default)]
+pub struct PipelineOutput {
+    pub results: Vec<RetrievalResult>,
+    /// 按最终顺序排列的融合结果，用于记录检索展示；只运行单个生成器时为空
+    pub shown: Vec<FusedResult>,
+    pub steps: Vec<ReasoningStep>,
+    /// 被过滤或在某个阶段被丢弃的结果
+    pub alternatives: Vec<AlternativeResult>,
+    pub trace: RetrievalTrace,
+}
+
+/// 候选生成器
+#[async_trait]
+pub trait CandidateGenerator: Send + Sync + std::fmt::Debug {
+    /// 生成器名称，请求按名称选择生成器
+    fn name(&self) -> &str;
+
+    /// 候选在融合和分数分解中对应的分量
+    fn component(&self) -> ScoreComponent;
+
+    /// 按相关性降序返回候选
+    async fn generate(&self, ctx: &GenerationContext<'_>) -> Result<Vec<RetrievalResult>>;
+}
+
+/// 候选过滤器
+///
+/// 融合之前作用于每个生成器的候选，被排除的候选记为替代结果。
+pub trait CandidateFilter: Send + Sync + std::fmt::Debug {
+    fn name(&self) -> &str;
+
+    /// 返回排除原因，保留时为空
+    fn exclude(&self, request: &RetrievalRequest, memory: &Memory) -> Option<String>;
+}
+
+/// 后处理器，重排序之后依次调整结果
+#[async_trait]
+pub trait PostProcessor: Send + Sync + std::fmt::Debug {
+    fn name(&self) -> &str;
+
+    fn is_enabled(&self) -> bool;
+
+    /// 推理步骤中对处理方式的说明
+    fn describe(&self) -> String {
+        self.name().to_string()
+    }
+
+    async fn process(
+        &self,
+        db: &VectorGraphDB,
+        results: Vec<RetrievalResult>,
+    ) -> Result<Vec<RetrievalResult>>;
+}
+
+/// 检索流水线
+#[derive(Debug)]
+pub struct RetrievalPipeline {
+    db: Arc<VectorGraphDB>,
+    config: PipelineConfig,
+    similarity_threshold: f32,
+    dimension: usize,
+    normalization: ScoreNormalization,
+    time_parsing: TimeParsingConfig,
+    time_parser: TimeExpressionParser,
+    generators: Vec<Arc<dyn CandidateGenerator>>,
+    filters: Vec<Arc<dyn CandidateFilter>>,
+    fuser: ResultFuser,
+    /// 学习融合使用的排序模型，未训练时按线性加权融合
+    ranker: Arc<RwLock<Option<LearnedRanker>>>,
+    /// 是否记录检索展示，用于训练排序模型
+    log_impressions: bool,
+    entity_linker: EntityLinker,
+    pagerank_cache: RwLock<PageRankCache>,
+    query_expander: QueryExpander,
+    reranker: RerankStage,
+    post_processors: Vec<Arc<dyn PostProcessor>>,
+    segmenter: Arc<Segmenter>,
+}
+
+/// 一次检索中候选生成器共享的上下文
+///
+/// 查询嵌入和语义候选只在第一次使用时计算，多个生成器并行运行时共用。
+pub struct GenerationContext<'a> {
+    pipeline: &'a RetrievalPipeline,
+    request: &'a RetrievalRequest,
+    query_embedding: OnceCell<Vec<f32>>,
+    semantic: OnceCell<Vec<RetrievalResult>>,
+}
+
+impl RetrievalRequest {
+    /// 按查询类型选择候选生成器
+    pub fn new(query: Query) -> Self {
+        let weights = &query.weights;
+        let generators = match query.query_type {
+            QueryType::Semantic => vec![(SEMANTIC_GENERATOR, 1.0)],
+            QueryType::Temporal => vec![(TEMPORAL_GENERATOR, 1.0)],
+            QueryType::Causal => vec![(CAUSAL_GENERATOR, 1.0)],
+            QueryType::Thematic => vec![(THEMATIC_GENERATOR, 1.0)],
+            QueryType::Mixed => vec![
+                (SEMANTIC_GENERATOR, weights.semantic_weight),
+                (TEMPORAL_GENERATOR, weights.temporal_weight),
+                (CAUSAL_GENERATOR, weights.importance_weight),
+            ],
+            QueryType::GraphTraversal => vec![(GRAPH_GENERATOR, 1.0)],
+            QueryType::PersonalizedPageRank => vec![(PAGERANK_GENERATOR, 1.0)],
+        };
+
+        Self {
+            generators: generators
+                .into_iter()
+                .map(|(name, weight)| (name.to_string(), weight))
+                .collect(),
+            fusion: FusionMethod::LinearWeighted,
+            pagerank: false,
+            time_window: None,
+            start_memories: Vec::new(),
+            candidate_limit: None,
+            min_similarity: None,
+            required_tags: Vec::new(),
+            excluded_tags: Vec::new(),
+            sources: None,
+            preferences: HashMap::new(),
+            topic_interests: HashMap::new(),
+            session_id: None,
+            query,
+        }
+    }
+
+    /// 替换候选生成器
+    pub fn with_generators(mut self, generators: Vec<(String, f32)>) -> Self {
+        self.generators = generators;
+        self
+    }
+
+    /// 只运行单个生成器
+    fn is_single(&self, name: &str) -> bool {
+        self.generators.len() == 1 && self.generators[0].0 == name
+    }
+}
+
+impl RetrievalPipeline {
+    /// 创建带内置生成器、过滤器和后处理器的流水线
+    pub fn new(db: Arc<VectorGraphDB>, config: &Config) -> Result<Self> {
+        let generators: Vec<Arc<dyn CandidateGenerator>> = vec![
+            Arc::new(SemanticGenerator),
+            Arc::new(TemporalGenerator),
+            Arc::new(CausalGenerator),
+            Arc::new(ThematicGenerator),
+            Arc::new(GraphTraversalGenerator {
+                max_depth: config.graph.traversal.max_depth,
+            }),
+            Arc::new(PersonalizedPageRankGenerator),
+        ];
+        let filters: Vec<Arc<dyn CandidateFilter>> = vec![
+            Arc::new(DeletedFilter),
+            Arc::new(TimeRangeFilter),
+            Arc::new(QueryFilter),
+            Arc::new(ConstraintFilter),
+        ];
+        let post_processors: Vec<Arc<dyn PostProcessor>> = vec![
+            Arc::new(RetentionWeighting {
+                model: RetentionModel::new(config),
+                weight: config.retention.retrieval_weight,
+            }),
+            Arc::new(Diversify {
+                diversifier: Diversifier::new(config),
+            }),
+        ];
+
+        Ok(Self {
+            db,
+            config: config.retrieval.pipeline.clone(),
+            similarity_threshold: config.vector.similarity_threshold,
+            dimension: config.vector.dimension,
+            normalization: config.retrieval.fusion.normalization,
+            time_parsing: config.time_parsing.clone(),
+            time_parser: TimeExpressionParser::new(config),
+            generators,
+            filters,
+            fuser: ResultFuser::new(config),
+            ranker: Arc::new(RwLock::new(None)),
+            log_impressions: config.ranking.log_impressions,
+            entity_linker: EntityLinker::new(config),
+            pagerank_cache: RwLock::new(PageRankCache::new(
+                config.graph.pagerank.incremental.cache_size,
+            )),
+            query_expander: QueryExpander::new(config),
+            reranker: RerankStage::new(config)?,
+            post_processors,
+            segmenter: Segmenter::shared(),
+        })
+    }
+
+    /// 使用指定的分词器提取查询主题
+    pub fn with_segmenter(mut self, segmenter: Arc<Segmenter>) -> Self {
+        self.segmenter = segmenter;
+        self
+    }
+
+    /// 注册候选生成器，同名的生成器被替换
+    pub fn with_generator(mut self, generator: Arc<dyn CandidateGenerator>) -> Self {
+        self.generators.retain(|g| g.name() != generator.name());
+        self.generators.push(generator);
+        self
+    }
+
+    /// 追加候选过滤器
+    pub fn with_filter(mut self, filter: Arc<dyn CandidateFilter>) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// 追加后处理器，同名的后处理器被替换
+    pub fn with_post_processor(mut self, processor: Arc<dyn PostProcessor>) -> Self {
+        match self
+            .post_processors
+            .iter_mut()
+            .find(|p| p.name() == processor.name())
+        {
+            Some(existing) => *existing = processor,
+            None => self.post_processors.push(processor),
+        }
+        self
+    }
+
+    /// 替换重排序器
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = self.reranker.with_reranker(reranker);
+        self
+    }
+
+    /// 使用大语言模型生成查询改写
+    pub fn with_llm_runner(mut self, runner: LlmTaskRunner) -> Self {
+        self.query_expander = self.query_expander.with_llm_runner(runner);
+        self
+    }
+
+    /// 替换从查询中抽取PageRank实体种子的抽取器
+    pub fn with_entity_extractor(mut self, extractor: Arc<dyn EntityExtractor>) -> Self {
+        self.entity_linker = self.entity_linker.with_extractor(extractor);
+        self
+    }
+
+    /// 设置学习融合使用的排序模型
+    pub async fn set_learned_ranker(&self, ranker: Option<LearnedRanker>) {
+        *self.ranker.write().await = ranker;
+    }
+
+    /// 从数据库重新加载学习融合使用的排序模型，返回是否存在已训练的模型
+    pub async fn reload_ranking_model(&self) -> Result<bool> {
+        let ranker = self.db.load_ranking_model(LEARNED_FUSION_MODEL).await?;
+        let loaded = ranker.is_some();
+        self.set_learned_ranker(ranker).await;
+        Ok(loaded)
+    }
+
+    pub fn pagerank_cache(&self) -> &RwLock<PageRankCache> {
+        &self.pagerank_cache
+    }
+
+    /// 运行完整的检索流水线
+    pub async fn run(&self, mut request: RetrievalRequest) -> Result<PipelineOutput> {
+        let mut output = PipelineOutput::default();
+        // 时间短语会从查询中去掉，检索展示记录原始查询
+        let query_text = request.query.text.clone();
+        output
+            .steps
+            .extend(self.resolve_time_expression(&mut request));
+
+        // 候选生成：各生成器并行运行，共用查询嵌入和语义候选
+        let generators = request
+            .generators
+            .iter()
+            .map(|(name, weight)| Ok((self.generator(name)?, *weight)))
+            .collect::<Result<Vec<_>>>()?;
+        let ctx = GenerationContext::new(self, &request);
+        let generated = join_all(generators.iter().map(|(g, _)| g.generate(&ctx))).await;
+
+        // 过滤：被排除的候选记为替代结果，同一记忆只记一次
+        let mut lists = Vec::with_capacity(generators.len());
+        let mut excluded = HashSet::new();
+        for ((generator, weight), results) in generators.iter().zip(generated) {
+            let results = results?;
+            output.trace.record_retriever(
+                generator.component(),
+                *weight,
+                &results,
+                self.normalization,
+            );
+            output.steps.push(ReasoningStep {
+                step_type: "retriever".to_string(),
+                description: format!(
+                    "{} retrieval: {} candidates, weight {:.3}",
+                    generator.name(),
+                    results.len(),
+                    weight
+                ),
+                score_impact: *weight,
+                confidence: 1.0,
+            });
+            let (kept, alternatives) = self.filter(&request, results);
+            output.alternatives.extend(
+                alternatives
+                    .into_iter()
+                    .filter(|a| excluded.insert(a.memory_id.clone())),
+            );
+            lists.push(RankedList::new(generator.component(), *weight, kept));
+        }
+
+        // 融合：只有一个生成器时保持其顺序
+        let mut fused = Vec::new();
+        let mut results = if lists.len() == 1 {
+            lists.pop().map(|list| list.results).unwrap_or_default()
+        } else {
+            fused = self.fuse(lists, &request.fusion).await;
+            output.trace.record_fused(&fused);
+            output.steps.push(ReasoningStep {
+                step_type: "fusion".to_string(),
+                description: format!("{:?} fused {} candidates", request.fusion, fused.len()),
+                score_impact: fused.first().map_or(0.0, |f| f.breakdown.final_score),
+                confidence: 1.0,
+            });
+            fused.iter().map(|f| f.result.clone()).collect()
+        };
+
+        if request.pagerank && !results.is_empty() {
+            let before = rank_snapshot(&results);
+            results = self.pagerank_rerank(&request, results).await?;
+            output.trace.record_stage("pagerank", &before, &results);
+            output.steps.push(ReasoningStep {
+                step_type: "pagerank".to_string(),
+                description: "Reranked by personalized PageRank".to_string(),
+                score_impact: 0.0,
+                confidence: 1.0,
+            });
+        }
+
+        // 扩展查询，各变体并行检索后与原查询的结果融合
+        if let Some(options) = &request.query.expansion {
+            let before = rank_snapshot(&results);
+            let query_embedding = if options.pseudo_relevance_feedback {
+                Some(ctx.query_embedding().await?)
+            } else {
+                None
+            };
+            let variants = self
+                .query_expander
+                .expand(
+                    &self.db,
+                    &request.query.text,
+                    options,
+                    query_embedding,
+                    &results,
+                )
+                .await?;
+            if !variants.is_empty() {
+                debug!("Expanded query into {} variants", variants.len());
+                let descriptions: Vec<String> = variants
+                    .iter()
+                    .map(|v| format!("{:?} \"{}\" ({})", v.kind, v.text, v.description))
+                    .collect();
+                let retrieved = join_all(variants.iter().map(|variant| async {
+                    let embedding = match &variant.embedding {
+                        Some(embedding) => embedding.clone(),
+                        None => embed_text(&variant.text).await?,
+                    };
+                    self.vector_candidates(&request, embedding).await
+                }))
+                .await;
+                let mut variant_lists = Vec::with_capacity(variants.len());
+                for (variant, variant_results) in variants.into_iter().zip(retrieved) {
+                    let (kept, alternatives) = self.filter(&request, variant_results?);
+                    output.alternatives.extend(
+                        alternatives
+                            .into_iter()
+                            .filter(|a| excluded.insert(a.memory_id.clone())),
+                    );
+                    variant_lists.push((variant, kept));
+                }
+                results = self
+                    .query_expander
+                    .fuse(results, variant_lists, request.query.limit);
+                output.steps.push(ReasoningStep {
+                    step_type: "query_expansion".to_string(),
+                    description: format!(
+                        "Fused {} results from {} query variants: {}",
+                        results.len(),
+                        descriptions.len(),
+                        descriptions.join("; ")
+                    ),
+                    score_impact: 0.0,
+                    confidence: 1.0,
+                });
+            }
+            let dropped = output
+                .trace
+                .record_stage("query_expansion", &before, &results);
+            output.alternatives.extend(dropped);
+        }
+
+        // 重排序前若干个候选
+        if self.reranker.is_enabled() && !results.is_empty() {
+            let before = rank_snapshot(&results);
+            results = self.reranker.rerank(&request.query.text, results).await;
+            output.trace.record_stage("rerank", &before, &results);
+            let scores: Vec<String> = results
+                .iter()
+                .filter_map(|r| {
+                    r.explanation
+                        .rerank_score
+                        .map(|score| format!("{}={:.3}", r.memory.id, score))
+                })
+                .collect();
+            output.steps.push(ReasoningStep {
+                step_type: "rerank".to_string(),
+                description: if scores.is_empty() {
+                    format!("Reranker {} failed, kept fused order", self.reranker.name())
+                } else {
+                    format!(
+                        "Reranked top {} results with {}: {}",
+                        scores.len(),
+                        self.reranker.name(),
+                        scores.join(", ")
+                    )
+                },
+                score_impact: 0.0,
+                confidence: 1.0,
+            });
+        }
+
+        // 后处理
+        for processor in &self.post_processors {
+            if !processor.is_enabled() || results.len() < 2 {
+                continue;
+            }
+            let before = rank_snapshot(&results);
+            results = processor.process(&self.db, results).await?;
+            output
+                .trace
+                .record_stage(processor.name(), &before, &results);
+            let moved = results
+                .iter()
+                .zip(&before)
+                .filter(|(r, (id, _))| &r.memory.id != id)
+                .count();
+            output.steps.push(ReasoningStep {
+                step_type: processor.name().to_string(),
+                description: format!(
+                    "Reordered {} results with {}, {} changed position",
+                    results.len(),
+                    processor.describe(),
+                    moved
+                ),
+                score_impact: 0.0,
+                confidence: 1.0,
+            });
+        }
+
+        if let Some(limit) = request.query.limit {
+            results.truncate(limit);
+        }
+        output.trace.record_final(&results);
+
+        let mut by_id: HashMap<&str, &FusedResult> = fused
+            .iter()
+            .map(|f| (f.result.memory.id.as_str(), f))
+            .collect();
+        output.shown = results
+            .iter()
+            .filter_map(|r| by_id.remove(r.memory.id.as_str()).cloned())
+            .collect();
+        self.log_impression(&query_text, &request, &output.shown)
+            .await;
+        output.results = results;
+        Ok(output)
+    }
+
+    /// 记录检索展示，用于训练学习融合的排序模型
+    async fn log_impression(
+        &self,
+        query_text: &str,
+        request: &RetrievalRequest,
+        shown: &[FusedResult],
+    ) {
+        if !self.log_impressions || shown.is_empty() {
+            return;
+        }
+        let impression = RetrievalImpression::new(query_text, request.session_id.clone(), shown);
+        if let Err(e) = self.db.insert_impression(&impression).await {
+            warn!("Failed to log retrieval impression: {}", e);
+        }
+    }
+
+    /// 只运行一个候选生成器，不经过滤和后续阶段
+    pub async fn generate(
+        &self,
+        name: &str,
+        request: &RetrievalRequest,
+    ) -> Result<Vec<RetrievalResult>> {
+        let generator = self.generator(name)?;
+        generator
+            .generate(&GenerationContext::new(self, request))
+            .await
+    }
+
+    /// 依次应用各过滤器，返回保留的候选和被排除的替代结果
+    pub fn filter(
+        &self,
+        request: &RetrievalRequest,
+        results: Vec<RetrievalResult>,
+    ) -> (Vec<RetrievalResult>, Vec<AlternativeResult>) {
+        apply_filters(&self.filters, request, results)
+    }
+
+    /// 计算个性化PageRank（按最大值归一化），结果按种子集合缓存，图变化后缓存失效
+    pub async fn personalized_pagerank(
+        &self,
+        seeds: &HashMap<MemoryId, f32>,
+    ) -> Result<HashMap<MemoryId, f32>> {
+        let key = PageRankCache::seed_key(seeds);
+        let graph_version = self.db.graph_version();
+        if let Some(scores) = self.pagerank_cache.write().await.get(&key, graph_version) {
+            debug!("PageRank cache hit for {} seeds", seeds.len());
+            return Ok(scores);
+        }
+
+        let scores = normalize_by_max(self.db.personalized_pagerank(seeds).await?);
+        self.pagerank_cache
+            .write()
+            .await
+            .insert(key, graph_version, scores.clone());
+
+        Ok(scores)
+    }
+
+    /// 文本中提到的实体作为PageRank种子，实体图未启用时为空
+    pub async fn entity_seeds(&self, text: &str) -> Result<HashMap<String, f32>> {
+        let linker = &self.entity_linker;
+        if !linker.is_enabled() {
+            return Ok(HashMap::new());
+        }
+        let mut entities = Vec::new();
+        for entity in linker.extract(text).await? {
+            let embedding = embed_text(&entity.name).await.ok();
+            entities.push((entity, embedding));
+        }
+        linker.query_seeds(&self.db, &entities).await
+    }
+
+    fn generator(&self, name: &str) -> Result<&Arc<dyn CandidateGenerator>> {
+        self.generators
+            .iter()
+            .find(|g| g.name() == name)
+            .ok_or_else(|| {
+                MemoryError::validation_error(format!("Unknown candidate generator: {}", name))
+            })
+    }
+
+    /// 解析查询中的时间表达式
+    ///
+    /// 去掉时间短语后的文本用于检索；解析出的时间范围按配置作为过滤条件，
+    /// 或作为时间评分的窗口，此时单独的语义检索改为时间感知检索。已显式设置的范围不被覆盖。
+    fn resolve_time_expression(&self, request: &mut RetrievalRequest) -> Option<ReasoningStep> {
+        if !self.time_parsing.enabled {
+            return None;
+        }
+        let parsed = self.time_parser.parse(&request.query.text, Utc::now())?;
+        let expression = parsed.expression;
+        if !parsed.remaining.is_empty() {
+            request.query.text = parsed.remaining;
+        }
+
+        let mode = self.time_parsing.mode;
+        let range = match mode {
+            TimeRangeMode::Filter => *request
+                .query
+                .filters
+                .time_range
+                .get_or_insert(expression.range()),
+            TimeRangeMode::Score => {
+                if request.is_single(SEMANTIC_GENERATOR) {
+                    request.generators[0].0 = TEMPORAL_GENERATOR.to_string();
+                }
+                *request.time_window.get_or_insert(expression.range())
+            }
+        };
+
+        Some(ReasoningStep {
+            step_type: "time_expression".to_string(),
+            description: format!(
+                "Resolved '{}' to {} - {} ({:?}), used as {:?}; semantic query: '{}'",
+                expression.text,
+                range.0.to_rfc3339(),
+                range.1.to_rfc3339(),
+                expression.granularity,
+                mode,
+                request.query.text
+            ),
+            score_impact: 0.0,
+            confidence: 0.9,
+        })
+    }
+
+    /// 融合各生成器的候选列表
+    ///
+    /// 学习融合先按线性加权得到各分量分数，有已训练的排序模型时再用模型分数重新排序。
+    async fn fuse(&self, lists: Vec<RankedList>, method: &FusionMethod) -> Vec<FusedResult> {
+        let mut fused = self.fuser.fuse(lists, method);
+        if let FusionMethod::LearnedFusion = method {
+            if let Some(ranker) = self.ranker.read().await.as_ref() {
+                ranker.rerank(&mut fused);
+            }
+        }
+        fused
+    }
+
+    /// 以融合结果为种子计算个性化PageRank，按配置权重与融合分数混合后重新排序
+    async fn pagerank_rerank(
+        &self,
+        request: &RetrievalRequest,
+        mut results: Vec<RetrievalResult>,
+    ) -> Result<Vec<RetrievalResult>> {
+        let seeds = pagerank_seeds(&results, &request.preferences);
+        let scores = self.personalized_pagerank(&seeds).await?;
+
+        let weight = self.config.pagerank_weight;
+        for result in &mut results {
+            if let Some(pagerank_score) = scores.get(&result.memory.id) {
+                result.relevance_score =
+                    result.relevance_score * (1.0 - weight) + pagerank_score * weight;
+            }
+        }
+        results.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+        Ok(results)
+    }
+
+    fn candidate_limit(&self, request: &RetrievalRequest) -> usize {
+        // 结果数上限在后处理之后才截断
+        request
+            .candidate_limit
+            .unwrap_or(self.config.candidate_limit)
+            .max(request.query.limit.unwrap_or(0))
+    }
+
+    /// 按查询嵌入做向量检索
+    async fn vector_candidates(
+        &self,
+        request: &RetrievalRequest,
+        embedding: Vec<f32>,
+    ) -> Result<Vec<RetrievalResult>> {
+        let vector_request = VectorQueryRequest {
+            query_vector: embedding,
+            limit: Some(self.candidate_limit(request)),
+            threshold: Some(request.min_similarity.unwrap_or(self.similarity_threshold)),
+            filters: None,
+        };
+
+        let mut results = Vec::new();
+        for vector_result in self.db.query_vectors(&vector_request).await? {
+            let mut memory = memory_from_metadata(&vector_result.vector.metadata, self.dimension)?;
+            memory.embedding = vector_result.vector.embedding;
+            let similarity = vector_result.similarity;
+            results.push(RetrievalResult {
+                explanation: explanation(
+                    &memory,
+                    format!("Semantic similarity: {:.3}", similarity),
+                )
+                .with_semantic(similarity),
+                relevance_score: similarity,
+                memory,
+            });
+        }
+        Ok(results)
+    }
+
+    /// 读取记忆，实体等非记忆节点读取失败
+    async fn load_memory(&self, memory_id: &MemoryId) -> Result<Memory> {
+        let vector = self.db.get_vector(memory_id).await?;
+        let mut memory = memory_from_metadata(&vector.metadata, self.dimension)?;
+        memory.embedding = vector.embedding;
+        Ok(memory)
+    }
+}
+
+impl<'a> GenerationContext<'a> {
+    fn new(pipeline: &'a RetrievalPipeline, request: &'a RetrievalRequest) -> Self {
+        Self {
+            pipeline,
+            request,
+            query_embedding: OnceCell::new(),
+            semantic: OnceCell::new(),
+        }
+    }
+
+    pub fn request(&self) -> &RetrievalRequest {
+        self.request
+    }
+
+    pub fn db(&self) -> &VectorGraphDB {
+        &self.pipeline.db
+    }
+
+    pub fn config(&self) -> &PipelineConfig {
+        &self.pipeline.config
+    }
+
+    /// 每个生成器最多返回的候选数
+    pub fn candidate_limit(&self) -> usize {
+        self.pipeline.candidate_limit(self.request)
+    }
+
+    /// 流水线使用的分词器
+    pub fn segmenter(&self) -> &Segmenter {
+        &self.pipeline.segmenter
+    }
+
+    /// 查询文本的嵌入
+    pub async fn query_embedding(&self) -> Result<&[f32]> {
+        let embedding = self
+            .query_embedding
+            .get_or_try_init(|| embed_text(&self.request.query.text))
+            .await?;
+        Ok(embedding)
+    }
+
+    /// 语义候选，按相似度降序
+    pub async fn semantic_candidates(&self) -> Result<&[RetrievalResult]> {
+        let results = self
+            .semantic
+            .get_or_try_init(|| async {
+                let embedding = self.query_embedding().await?.to_vec();
+                self.pipeline
+                    .vector_candidates(self.request, embedding)
+                    .await
+            })
+            .await?;
+        Ok(results)
+    }
+
+    /// 图检索的起始记忆，未指定时取前 `count` 个语义候选
+    pub async fn start_memories(&self, count: usize) -> Result<Vec<MemoryId>> {
+        if !self.request.start_memories.is_empty() {
+            return Ok(self.request.start_memories.clone());
+        }
+        Ok(self
+            .semantic_candidates()
+            .await?
+            .iter()
+            .take(count)
+            .map(|r| r.memory.id.clone())
+            .collect())
+    }
+
+    pub async fn load_memory(&self, memory_id: &MemoryId) -> Result<Memory> {
+        self.pipeline.load_memory(memory_id).await
+    }
+
+    pub fn memory_from_properties(
+        &self,
+        properties: &HashMap<String, serde_json::Value>,
+    ) -> Result<Memory> {
+        memory_from_metadata(properties, self.pipeline.dimension)
+    }
+
+    pub async fn personalized_pagerank(
+        &self,
+        seeds: &HashMap<MemoryId, f32>,
+    ) -> Result<HashMap<MemoryId, f32>> {
+        self.pipeline.personalized_pagerank(seeds).await
+    }
+
+    /// 查询中提到的实体作为PageRank种子，实体图未启用时为空
+    pub async fn entity_seeds(&self) -> Result<HashMap<String, f32>> {
+        self.pipeline.entity_seeds(&self.request.query.text).await
+    }
+}
+
+/// 生成文本嵌入
+///
+/// 记忆管理器和HippoRAG检索器共用同一个静态模型，只在第一次调用时加载。
+pub async fn embed_text(text: &str) -> Result<Vec<f32>> {
+    use model2vec_rs::model::StaticModel;
+    use std::sync::{Mutex, OnceLock};
+
+    static MODEL: OnceLock<Option<Mutex<StaticModel>>> = OnceLock::new();
+
+    let model = MODEL
+        .get_or_init(|| {
+            match StaticModel::from_pretrained(
+                "minishlab/potion-multilingual-128M", // 使用多语言模型
+                None,                                 // 无需 HF token
+                None,                                 // 使用模型默认的归一化设置
+                None,                                 // 无子文件夹
+            ) {
+                Ok(m) => Some(Mutex::new(m)),
+                Err(e) => {
+                    log::error!("Failed to load embedding model: {}", e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+        .ok_or_else(|| MemoryError::Internal {
+            message: "Failed to load embedding model".to_string(),
+        })?;
+
+    let model_guard = model.lock().map_err(|e| MemoryError::Internal {
+        message: format!("Failed to acquire model lock: {}", e),
+    })?;
+
+    let embeddings = model_guard.encode(&[text.to_string()]);
+    embeddings
+        .into_iter()
+        .next()
+        .ok_or_else(|| MemoryError::Internal {
+            message: "Failed to generate embedding: empty result".to_string(),
+        })
+}
+
+/// 依次应用过滤器，返回保留的候选和被排除的替代结果
+pub fn apply_filters(
+    filters: &[Arc<dyn CandidateFilter>],
+    request: &RetrievalRequest,
+    results: Vec<RetrievalResult>,
+) -> (Vec<RetrievalResult>, Vec<AlternativeResult>) {
+    let mut kept = Vec::with_capacity(results.len());
+    let mut excluded = Vec::new();
+    for result in results {
+        match filters
+            .iter()
+            .find_map(|f| f.exclude(request, &result.memory))
+        {
+            Some(reason) => excluded.push(AlternativeResult {
+                memory_id: result.memory.id,
+                score: result.relevance_score,
+                reason_excluded: reason,
+            }),
+            None => kept.push(result),
+        }
+    }
+    (kept, excluded)
+}
+
+/// 以检索结果为种子，命中用户偏好的记忆获得更高的种子权重
+fn pagerank_seeds(
+    results: &[RetrievalResult],
+    preferences: &HashMap<String, f32>,
+) -> HashMap<MemoryId, f32> {
+    results
+        .iter()
+        .map(|result| {
+            let attributes = &result.memory.attributes;
+            let preference: f32 = preferences
+                .iter()
+                .filter(|(preference, _)| {
+                    attributes.keywords.contains(preference) || attributes.tags.contains(preference)
+                })
+                .map(|(_, weight)| weight.max(0.0))
+                .sum();
+            (
+                result.memory.id.clone(),
+                result.relevance_score.max(0.0) * (1.0 + preference),
+            )
+        })
+        .collect()
+}
+
+/// 记忆内容命中用户偏好、上下文命中感兴趣主题的程度
+fn preference_score(memory: &Memory, request: &RetrievalRequest) -> f32 {
+    let content = memory.content.to_lowercase();
+    let context = memory.attributes.context.to_lowercase();
+    let preferences: f32 = request
+        .preferences
+        .iter()
+        .filter(|(preference, _)| content.contains(&preference.to_lowercase()))
+        .map(|(_, weight)| weight)
+        .sum();
+    let topics: f32 = request
+        .topic_interests
+        .iter()
+        .filter(|(topic, _)| context.contains(&topic.to_lowercase()))
+        .map(|(_, interest)| interest * 0.5)
+        .sum();
+    (preferences + topics).min(1.0)
+}
+
+/// 只有重要性分数的检索解释
+fn explanation(memory: &Memory, reasoning: String) -> RetrievalExplanation {
+    RetrievalExplanation {
+        semantic_score: 0.0,
+        temporal_score: 0.0,
+        importance_score: memory.attributes.importance,
+        personalization_score: 0.0,
+        connection_paths: Vec::new(),
+        reasoning,
+        rerank_score: None,
+    }
+}
+
+impl RetrievalExplanation {
+    fn with_semantic(mut self, score: f32) -> Self {
+        self.semantic_score = score;
+        self
+    }
+}
+
+fn sort_by_relevance(results: &mut [RetrievalResult]) {
+    results.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+}
+
+// 内置候选生成器
+
+/// 语义候选生成器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SemanticGenerator;
+
+#[async_trait]
+impl CandidateGenerator for SemanticGenerator {
+    fn name(&self) -> &str {
+        SEMANTIC_GENERATOR
+    }
+
+    fn component(&self) -> ScoreComponent {
+        ScoreComponent::Semantic
+    }
+
+    async fn generate(&self, ctx: &GenerationContext<'_>) -> Result<Vec<RetrievalResult>> {
+        Ok(ctx.semantic_candidates().await?.to_vec())
+    }
+}
+
+/// 时间感知候选生成器
+///
+/// 语义候选按时间窗口内的时间相关性重新评分，分数为语义分数与时间分数按查询权重加权。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TemporalGenerator;
+
+#[async_trait]
+impl CandidateGenerator for TemporalGenerator {
+    fn name(&self) -> &str {
+        TEMPORAL_GENERATOR
+    }
+
+    fn component(&self) -> ScoreComponent {
+        ScoreComponent::Temporal
+    }
+
+    async fn generate(&self, ctx: &GenerationContext<'_>) -> Result<Vec<RetrievalResult>> {
+        let request = ctx.request();
+        let time_window = request.time_window.unwrap_or_else(|| {
+            let now = Utc::now();
+            (now - Duration::days(ctx.config().time_window_days), now)
+        });
+        let weights = &request.query.weights;
+
+        let mut results = ctx.semantic_candidates().await?.to_vec();
+        for result in &mut results {
+            let temporal_score = window_relevance(result.memory.metadata.created_at, &time_window);
+            result.relevance_score = result.explanation.semantic_score * weights.semantic_weight
+                + temporal_score * weights.temporal_weight;
+            result.explanation.temporal_score = temporal_score;
+            result.explanation.reasoning = format!(
+                "Temporal-semantic fusion: semantic={:.3}, temporal={:.3}",
+                result.explanation.semantic_score, temporal_score
+            );
+        }
+        sort_by_relevance(&mut results);
+        Ok(results)
+    }
+}
+
+/// 因果候选生成器
+///
+/// 从起始记忆沿因果边扩展，记忆的因果分数为相连因果边的最大置信度，
+/// 查询中有因果连接词时再按记忆内容对连接词的覆盖率调整。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CausalGenerator;
+
+#[async_trait]
+impl CandidateGenerator for CausalGenerator {
+    fn name(&self) -> &str {
+        CAUSAL_GENERATOR
+    }
+
+    fn component(&self) -> ScoreComponent {
+        ScoreComponent::Structural
+    }
+
+    async fn generate(&self, ctx: &GenerationContext<'_>) -> Result<Vec<RetrievalResult>> {
+        let request = ctx.request();
+        let keywords = RuleCausalDetector::connectives(&request.query.text);
+        let start_nodes = ctx.start_memories(usize::MAX).await?;
+        if start_nodes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let graph_request = GraphQueryRequest {
+            start_nodes: start_nodes.clone(),
+            edge_types: Some(vec![format!("{:?}", ConnectionType::Causal)]),
+            max_depth: Some(ctx.config().causal_depth),
+            limit: Some(ctx.candidate_limit()),
+            filters: None,
+        };
+        let graph_results = ctx.db().query_graph(&graph_request).await?;
+
+        // 节点的因果得分取其相连因果边的最大置信度
+        let mut edge_scores: HashMap<&str, f32> = HashMap::new();
+        for edge in &graph_results.edges {
+            for node in [edge.from_node.as_str(), edge.to_node.as_str()] {
+                let score = edge_scores.entry(node).or_insert(0.0);
+                *score = score.max(edge.weight);
+            }
+        }
+
+        let mut results = Vec::new();
+        for node in &graph_results.nodes {
+            if start_nodes.contains(&node.id) {
+                continue;
+            }
+            let Ok(memory) = ctx.memory_from_properties(&node.properties) else {
+                continue;
+            };
+            let edge_score = edge_scores.get(node.id.as_str()).copied().unwrap_or(0.0);
+            let keyword_score = if keywords.is_empty() {
+                1.0
+            } else {
+                let content = memory.content.to_lowercase();
+                let matches = keywords
+                    .iter()
+                    .filter(|k| content.contains(&k.to_lowercase()))
+                    .count();
+                0.5 + 0.5 * matches as f32 / keywords.len() as f32
+            };
+            let causal_score = edge_score * keyword_score;
+            if causal_score > 0.1 {
+                results.push(RetrievalResult {
+                    explanation: explanation(
+                        &memory,
+                        format!("Causal relevance: {:.3}", causal_score),
+                    ),
+                    relevance_score: causal_score,
+                    memory,
+                });
+            }
+        }
+        sort_by_relevance(&mut results);
+        Ok(results)
+    }
+}
+
+/// 主题候选生成器
+///
+/// 以关键词与查询匹配的主题社区为来源，返回社区成员。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThematicGenerator;
+
+#[async_trait]
+impl CandidateGenerator for ThematicGenerator {
+    fn name(&self) -> &str {
+        THEMATIC_GENERATOR
+    }
+
+    fn component(&self) -> ScoreComponent {
+        ScoreComponent::Structural
+    }
+
+    async fn generate(&self, ctx: &GenerationContext<'_>) -> Result<Vec<RetrievalResult>> {
+        let text = &ctx.request().query.text;
+        let themes = ctx.segmenter().extract_keywords(text, 10);
+        let query_text = text.to_lowercase();
+
+        let mut results = Vec::new();
+        let mut seen = HashSet::new();
+        for community in ctx.db().get_communities().await? {
+            let matched = community
+                .keywords
+                .iter()
+                .filter(|k| {
+                    let keyword = k.to_lowercase();
+                    query_text.contains(&keyword)
+                        || themes.iter().any(|t| t.to_lowercase() == keyword)
+                })
+                .count();
+            if matched == 0 {
+                continue;
+            }
+            let community_score = matched as f32 / community.keywords.len().max(1) as f32;
+
+            for member_id in &community.members {
+                if !seen.insert(member_id.clone()) {
+                    continue;
+                }
+                let Ok(memory) = ctx.load_memory(member_id).await else {
+                    continue;
+                };
+                let content = memory.content.to_lowercase();
+                let theme_matches = themes
+                    .iter()
+                    .filter(|theme| content.contains(&theme.to_lowercase()))
+                    .count();
+                let theme_score = theme_matches as f32 / themes.len().max(1) as f32;
+                let score = 0.5 * community_score + 0.5 * theme_score;
+
+                results.push(RetrievalResult {
+                    explanation: explanation(
+                        &memory,
+                        format!(
+                            "Thematic relevance: {:.3} (community: {})",
+                            score, community.label
+                        ),
+                    ),
+                    relevance_score: score,
+                    memory,
+                });
+            }
+        }
+
+        sort_by_relevance(&mut results);
+        results.truncate(ctx.candidate_limit());
+        Ok(results)
+    }
+}
+
+/// 图遍历候选生成器
+///
+/// 从起始记忆出发沿任意类型的边遍历，越早到达的记忆分数越高。
+#[derive(Debug, Clone, Copy)]
+pub struct GraphTraversalGenerator {
+    max_depth: usize,
+}
+
+#[async_trait]
+impl CandidateGenerator for GraphTraversalGenerator {
+    fn name(&self) -> &str {
+        GRAPH_GENERATOR
+    }
+
+    fn component(&self) -> ScoreComponent {
+        ScoreComponent::Structural
+    }
+
+    async fn generate(&self, ctx: &GenerationContext<'_>) -> Result<Vec<RetrievalResult>> {
+        let start_nodes = ctx.start_memories(ctx.config().graph_seeds).await?;
+        if start_nodes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let graph_request = GraphQueryRequest {
+            start_nodes,
+            edge_types: None,
+            max_depth: Some(self.max_depth),
+            limit: Some(ctx.candidate_limit()),
+            filters: None,
+        };
+        let graph_results = ctx.db().query_graph(&graph_request).await?;
+
+        // 实体等非记忆节点无法还原为记忆，直接跳过
+        let mut results = Vec::new();
+        for node in &graph_results.nodes {
+            let Ok(memory) = ctx.memory_from_properties(&node.properties) else {
+                continue;
+            };
+            let path_score = 1.0 / (results.len() as f32 + 1.0);
+            let mut explanation =
+                explanation(&memory, format!("Graph traversal score: {:.3}", path_score));
+            explanation.connection_paths = graph_results.paths.clone();
+            results.push(RetrievalResult {
+                memory,
+                relevance_score: path_score,
+                explanation,
+            });
+        }
+        Ok(results)
+    }
+}
+
+/// 个性化PageRank候选生成器
+///
+/// 以语义候选（命中用户偏好的加权）和查询中的实体为种子执行个性化PageRank，
+/// 既对语义候选重新评分，也召回只能通过图连接到达的记忆。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PersonalizedPageRankGenerator;
+
+#[async_trait]
+impl CandidateGenerator for PersonalizedPageRankGenerator {
+    fn name(&self) -> &str {
+        PAGERANK_GENERATOR
+    }
+
+    fn component(&self) -> ScoreComponent {
+        ScoreComponent::Personalization
+    }
+
+    async fn generate(&self, ctx: &GenerationContext<'_>) -> Result<Vec<RetrievalResult>> {
+        let request = ctx.request();
+        let weights = &request.query.weights;
+        let semantic_results = ctx.semantic_candidates().await?;
+
+        let mut seeds = pagerank_seeds(semantic_results, &request.preferences);
+        for (entity_id, weight) in ctx.entity_seeds().await? {
+            *seeds.entry(entity_id).or_insert(0.0) += weight;
+        }
+        let pagerank_scores = ctx.personalized_pagerank(&seeds).await?;
+
+        let mut seen = HashSet::new();
+        let mut results = Vec::new();
+        for semantic_result in semantic_results {
+            let pagerank_score = pagerank_scores
+                .get(&semantic_result.memory.id)
+                .copied()
+                .unwrap_or(0.0);
+            let preference = preference_score(&semantic_result.memory, request);
+            let semantic_score = semantic_result.explanation.semantic_score;
+
+            let mut result = semantic_result.clone();
+            result.relevance_score = semantic_score * weights.semantic_weight
+                + (pagerank_score + preference) * weights.personalization_weight;
+            result.explanation.personalization_score = (pagerank_score + preference).min(1.0);
+            result.explanation.reasoning = format!(
+                "Personalized score: semantic={:.3}, pagerank={:.3}, preference={:.3}",
+                semantic_score, pagerank_score, preference
+            );
+            seen.insert(result.memory.id.clone());
+            results.push(result);
+        }
+
+        // 只能通过图连接到达的记忆
+        let mut reached: Vec<(&MemoryId, &f32)> = pagerank_scores
+            .iter()
+            .filter(|(id, _)| !seen.contains(*id))
+            .collect();
+        reached.sort_by(|a, b| b.1.total_cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+        let mut remaining = request.query.limit.unwrap_or(ctx.config().max_reached);
+        for (memory_id, pagerank_score) in reached {
+            if remaining == 0 {
+                break;
+            }
+            // 实体等非记忆节点无法加载，直接跳过
+            let Ok(memory) = ctx.load_memory(memory_id).await else {
+                continue;
+            };
+            if memory.metadata.is_deleted {
+                continue;
+            }
+            remaining -= 1;
+
+            let mut explanation = explanation(
+                &memory,
+                format!(
+                    "Reached through memory graph: pagerank={:.3}",
+                    pagerank_score
+                ),
+            );
+            explanation.personalization_score = *pagerank_score;
+            results.push(RetrievalResult {
+                relevance_score: pagerank_score * weights.personalization_weight,
+                explanation,
+                memory,
+            });
+        }
+
+        sort_by_relevance(&mut results);
+        Ok(results)
+    }
+}
+
+// 内置过滤器
+
+/// 排除已软删除的记忆
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeletedFilter;
+
+impl CandidateFilter for DeletedFilter {
+    fn name(&self) -> &str {
+        "deleted"
+    }
+
+    fn exclude(&self, _request: &RetrievalRequest, memory: &Memory) -> Option<String> {
+        memory
+            .metadata
+            .is_deleted
+            .then(|| "Memory is deleted".to_string())
+    }
+}
+
+/// 按查询过滤器的时间范围排除
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeRangeFilter;
+
+impl CandidateFilter for TimeRangeFilter {
+    fn name(&self) -> &str {
+        "time_range"
+    }
+
+    fn exclude(&self, request: &RetrievalRequest, memory: &Memory) -> Option<String> {
+        let (start, end) = request.query.filters.time_range?;
+        let created_at = memory.metadata.created_at;
+        (created_at < start || created_at > end).then(|| {
+            format!(
+                "Created at {} outside time range {} - {}",
+                created_at.to_rfc3339(),
+                start.to_rfc3339(),
+                end.to_rfc3339()
+            )
+        })
+    }
+}
+
+/// 按查询过滤器的其余条件（标签、重要性、来源、自定义属性等）排除
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueryFilter;
+
+impl CandidateFilter for QueryFilter {
+    fn name(&self) -> &str {
+        "query_filters"
+    }
+
+    fn exclude(&self, request: &RetrievalRequest, memory: &Memory) -> Option<String> {
+        (!memory.matches_filters(&request.query.filters))
+            .then(|| "Does not match query filters".to_string())
+    }
+}
+
+/// 按请求的必需标签、排除标签和来源约束排除
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConstraintFilter;
+
+impl CandidateFilter for ConstraintFilter {
+    fn name(&self) -> &str {
+        "constraints"
+    }
+
+    fn exclude(&self, request: &RetrievalRequest, memory: &Memory) -> Option<String> {
+        let tags = &memory.attributes.tags;
+        if let Some(tag) = request.required_tags.iter().find(|t| !tags.contains(t)) {
+            return Some(format!("Missing required tag {}", tag));
+        }
+        if let Some(tag) = request.excluded_tags.iter().find(|t| tags.contains(t)) {
+            return Some(format!("Tagged with excluded tag {}", tag));
+        }
+        let sources = request.sources.as_ref()?;
+        let source = memory.attributes.source.as_ref();
+        (!source.is_some_and(|s| sources.contains(s))).then(|| {
+            format!(
+                "Source {} not in source filter",
+                source.map_or("(none)", |s| s.as_str())
+            )
+        })
+    }
+}
+
+// 内置后处理器
+
+/// 按保持率加权，久未强化的记忆排序靠后
+#[derive(Debug, Clone)]
+pub struct RetentionWeighting {
+    model: RetentionModel,
+    weight: f32,
+}
+
+#[async_trait]
+impl PostProcessor for RetentionWeighting {
+    fn name(&self) -> &str {
+        "retention"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.weight > 0.0 && self.model.is_enabled()
+    }
+
+    fn describe(&self) -> String {
+        format!("retention weight {:.2}", self.weight)
+    }
+
+    async fn process(
+        &self,
+        _db: &VectorGraphDB,
+        mut results: Vec<RetrievalResult>,
+    ) -> Result<Vec<RetrievalResult>> {
+        for result in &mut results {
+            let retention = self.model.retention(&result.memory);
+            result.relevance_score *= 1.0 - self.weight + self.weight * retention;
+        }
+        sort_by_relevance(&mut results);
+        Ok(results)
+    }
+}
+
+/// 压低与前面结果近重复的记忆
+#[derive(Debug, Clone)]
+pub struct Diversify {
+    diversifier: Diversifier,
+}
+
+#[async_trait]
+impl PostProcessor for Diversify {
+    fn name(&self) -> &str {
+        "diversify"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.diversifier.is_enabled()
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{:?} (lambda {:.2})",
+            self.diversifier.method(),
+            self.diversifier.lambda()
+        )
+    }
+
+    async fn process(
+        &self,
+        db: &VectorGraphDB,
+        results: Vec<RetrievalResult>,
+    ) -> Result<Vec<RetrievalResult>> {
+        self.diversifier.diversify(db, results).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(text: &str) -> Query {
+        Query {
+            text: text.to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: None,
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+            expansion: None,
+        }
+    }
+
+    /// 返回固定候选的生成器
+    #[derive(Debug)]
+    struct FixedGenerator {
+        name: &'static str,
+        component: ScoreComponent,
+        results: Vec<RetrievalResult>,
+    }
+
+    #[async_trait]
+    impl CandidateGenerator for FixedGenerator {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn component(&self) -> ScoreComponent {
+            self.component
+        }
+
+        async fn generate(&self, ctx: &GenerationContext<'_>) -> Result<Vec<RetrievalResult>> {
+            Ok(self
+                .results
+                .iter()
+                .take(ctx.candidate_limit())
+                .cloned()
+                .collect())
+        }
+    }
+
+    async fn pipeline(config: &Config) -> RetrievalPipeline {
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        RetrievalPipeline::new(db, config).unwrap()
+    }
+
+    fn config() -> Config {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.rerank.enabled = false;
+        config
+    }
+
+    #[test]
+    fn test_filters() {
+        let now = Utc::now();
+        let filters: Vec<Arc<dyn CandidateFilter>> = vec![
+            Arc::new(DeletedFilter),
+            Arc::new(TimeRangeFilter),
+            Arc::new(ConstraintFilter),
+        ];
+
+        let mut deleted = RetrievalResult::for_test("deleted", 0.9);
+        deleted.memory.metadata.is_deleted = true;
+        let mut old = RetrievalResult::for_test("old", 0.8);
+        old.memory.metadata.created_at = now - Duration::days(10);
+        let mut tagged = RetrievalResult::for_test("tagged", 0.7);
+        tagged.memory.attributes.tags = vec!["private".to_string()];
+        let kept = RetrievalResult::for_test("kept", 0.6);
+
+        let mut request = RetrievalRequest::new(query("x"));
+        request.query.filters.time_range = Some((now - Duration::days(1), now + Duration::days(1)));
+        request.excluded_tags = vec!["private".to_string()];
+
+        let (results, excluded) =
+            apply_filters(&filters, &request, vec![deleted, old, tagged, kept]);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].memory.id, "kept");
+        let reasons: Vec<&str> = excluded
+            .iter()
+            .map(|a| a.reason_excluded.as_str())
+            .collect();
+        assert_eq!(reasons[0], "Memory is deleted");
+        assert!(reasons[1].contains("outside time range"));
+        assert_eq!(reasons[2], "Tagged with excluded tag private");
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_stages() {
+        let config = config();
+        let mut excluded = RetrievalResult::for_test("c", 0.9);
+        excluded.memory.attributes.tags = vec!["private".to_string()];
+        let pipeline = pipeline(&config)
+            .await
+            .with_generator(Arc::new(FixedGenerator {
+                name: "first",
+                component: ScoreComponent::Semantic,
+                results: vec![
+                    RetrievalResult::for_test("a", 0.9),
+                    RetrievalResult::for_test("b", 0.5),
+                    excluded,
+                ],
+            }))
+            .with_generator(Arc::new(FixedGenerator {
+                name: "second",
+                component: ScoreComponent::Structural,
+                results: vec![
+                    RetrievalResult::for_test("b", 1.0),
+                    RetrievalResult::for_test("d", 0.2),
+                ],
+            }));
+
+        let mut request = RetrievalRequest::new(query("x")).with_generators(vec![
+            ("first".to_string(), 1.0),
+            ("second".to_string(), 1.0),
+        ]);
+        request.fusion = FusionMethod::RankFusion;
+        request.excluded_tags = vec!["private".to_string()];
+        request.query.limit = Some(2);
+
+        let output = pipeline.run(request.clone()).await.unwrap();
+        let ids: Vec<&str> = output
+            .results
+            .iter()
+            .map(|r| r.memory.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["b", "a"]);
+        assert_eq!(output.trace.retrievers.len(), 2);
+        assert_eq!(output.trace.fused.len(), 3);
+        assert_eq!(output.trace.final_ranking.len(), 2);
+        assert_eq!(output.alternatives[0].memory_id, "c");
+        assert_eq!(output.shown.len(), 2);
+
+        // 单个生成器保持其顺序，不经融合
+        let single = pipeline
+            .run(request.with_generators(vec![("second".to_string(), 1.0)]))
+            .await
+            .unwrap();
+        assert!(single.trace.fused.is_empty());
+        assert!(single.shown.is_empty());
+        assert_eq!(single.results[0].memory.id, "b");
+
+        let unknown =
+            RetrievalRequest::new(query("x")).with_generators(vec![("missing".to_string(), 1.0)]);
+        assert!(pipeline.run(unknown).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_logs_impressions_for_fused_results() {
+        let config = config();
+        let pipeline = pipeline(&config)
+            .await
+            .with_generator(Arc::new(FixedGenerator {
+                name: "first",
+                component: ScoreComponent::Semantic,
+                results: vec![
+                    RetrievalResult::for_test("a", 0.9),
+                    RetrievalResult::for_test("b", 0.5),
+                ],
+            }))
+            .with_generator(Arc::new(FixedGenerator {
+                name: "second",
+                component: ScoreComponent::Structural,
+                results: vec![RetrievalResult::for_test("b", 1.0)],
+            }));
+        assert!(!pipeline.reload_ranking_model().await.unwrap());
+
+        let mut request = RetrievalRequest::new(query("x")).with_generators(vec![
+            ("first".to_string(), 1.0),
+            ("second".to_string(), 1.0),
+        ]);
+        request.session_id = Some("s1".to_string());
+        request.query.limit = Some(1);
+        pipeline.run(request.clone()).await.unwrap();
+
+        // 只运行单个生成器时没有融合结果，不记录展示
+        pipeline
+            .run(request.with_generators(vec![("first".to_string(), 1.0)]))
+            .await
+            .unwrap();
+
+        let now = Utc::now();
+        let impressions = pipeline
+            .db
+            .get_impressions((now - Duration::hours(1), now + Duration::hours(1)), 10)
+            .await
+            .unwrap();
+        assert_eq!(impressions.len(), 1);
+        assert_eq!(impressions[0].query, "x");
+        assert_eq!(impressions[0].session_id.as_deref(), Some("s1"));
+        assert_eq!(impressions[0].items.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_filtered_candidates_fill_limit() {
+        let mut config = config();
+        config.retrieval.pipeline.candidate_limit = 10;
+        let results = (0..8)
+            .map(|i| {
+                let mut r = RetrievalResult::for_test(&format!("m{}", i), 1.0 - i as f32 * 0.1);
+                if i < 3 {
+                    r.memory.attributes.tags = vec!["private".to_string()];
+                }
+                r
+            })
+            .collect();
+        let pipeline = pipeline(&config)
+            .await
+            .with_generator(Arc::new(FixedGenerator {
+                name: "fixed",
+                component: ScoreComponent::Semantic,
+                results,
+            }));
+
+        // 前三个候选被过滤，仍能返回满额结果
+        let mut request =
+            RetrievalRequest::new(query("x")).with_generators(vec![("fixed".to_string(), 1.0)]);
+        request.excluded_tags = vec!["private".to_string()];
+        request.query.limit = Some(3);
+        let output = pipeline.run(request.clone()).await.unwrap();
+        let ids: Vec<&str> = output
+            .results
+            .iter()
+            .map(|r| r.memory.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["m3", "m4", "m5"]);
+        assert_eq!(output.alternatives.len(), 3);
+
+        // 候选数不小于结果数上限
+        request.candidate_limit = Some(2);
+        request.excluded_tags.clear();
+        let output = pipeline.run(request).await.unwrap();
+        assert_eq!(output.results.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_empty_generator_set() {
+        let config = config();
+        let pipeline = pipeline(&config).await;
+        let request = RetrievalRequest::new(query("x")).with_generators(Vec::new());
+
+        let output = pipeline.run(request).await.unwrap();
+        assert!(output.results.is_empty());
+        assert!(output.alternatives.is_empty());
+        assert!(output.trace.retrievers.is_empty());
+        assert!(output.trace.final_ranking.is_empty());
+    }
+}
@@ -0,0 +1,321 @@
+//! 查询分析模块
+//!
+//! 按查询文本判断检索意图（时间、因果、实体查找或开放式），据此为动态融合
+//! 生成检索计划：运行哪些检索器、各自的融合权重，以及是否需要PageRank重排序。
+
+use crate::config::{Config, FusionWeights};
+use crate::core::QueryType;
+use crate::fusion::ScoreComponent;
+use crate::utils::TextUtils;
+use serde::{Deserialize, Serialize};
+
+/// 时间意图线索
+const TEMPORAL_CUES: &[&str] = &[
+    "昨天",
+    "前天",
+    "今天",
+    "最近",
+    "上周",
+    "这周",
+    "本周",
+    "上个月",
+    "这个月",
+    "去年",
+    "今年",
+    "什么时候",
+    "何时",
+    "天前",
+    "星期",
+    "yesterday",
+    "today",
+    "tonight",
+    "recently",
+    "lately",
+    "ago",
+    "when",
+    "last week",
+    "last month",
+    "last year",
+    "this week",
+    "this month",
+    "last monday",
+    "last tuesday",
+    "last wednesday",
+    "last thursday",
+    "last friday",
+    "last saturday",
+    "last sunday",
+];
+
+/// 因果意图线索
+const CAUSAL_CUES: &[&str] = &[
+    "为什么",
+    "为何",
+    "原因",
+    "导致",
+    "因为",
+    "怎么会",
+    "why",
+    "because",
+    "cause",
+    "caused",
+    "reason",
+    "how come",
+    "led to",
+];
+
+/// 实体查找线索
+const LOOKUP_CUES: &[&str] = &[
+    "是谁",
+    "是什么",
+    "在哪",
+    "电话",
+    "邮箱",
+    "地址",
+    "联系方式",
+    "who is",
+    "who was",
+    "what is",
+    "where is",
+    "phone",
+    "email",
+    "address",
+    "contact",
+];
+
+/// 不超过该词数（英文等空格分词语言）的查询视为实体查找
+const LOOKUP_MAX_WORDS: usize = 3;
+
+/// 不超过该字数（中日韩文字）的查询视为实体查找
+const LOOKUP_MAX_CJK_CHARS: usize = 6;
+
+/// 查询意图
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryIntent {
+    /// 与时间相关的查询，如“最近”“last Tuesday”
+    Temporal,
+    /// 询问原因的查询，如“为什么”“why”
+    Causal,
+    /// 查找具体的人、物或属性
+    EntityLookup,
+    /// 开放式查询
+    OpenEnded,
+}
+
+impl QueryIntent {
+    /// 各检索器权重相对于配置权重的倍数，0表示不运行该检索器
+    fn multipliers(&self) -> [(ScoreComponent, f32); 4] {
+        use ScoreComponent::*;
+        match self {
+            QueryIntent::Temporal => [
+                (Semantic, 1.0),
+                (Temporal, 3.0),
+                (Structural, 0.5),
+                (Personalization, 1.0),
+            ],
+            QueryIntent::Causal => [
+                (Semantic, 1.0),
+                (Temporal, 0.5),
+                (Structural, 3.0),
+                (Personalization, 1.0),
+            ],
+            QueryIntent::EntityLookup => [
+                (Semantic, 2.0),
+                (Temporal, 0.0),
+                (Structural, 1.0),
+                (Personalization, 0.0),
+            ],
+            QueryIntent::OpenEnded => [
+                (Semantic, 1.0),
+                (Temporal, 1.0),
+                (Structural, 1.0),
+                (Personalization, 1.0),
+            ],
+        }
+    }
+}
+
+/// 检索计划
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalPlan {
+    pub intent: QueryIntent,
+    /// 意图判断的置信度
+    pub confidence: f32,
+    /// 命中的意图线索
+    pub cues: Vec<String>,
+    /// 要运行的检索器及其融合权重
+    pub retrievers: Vec<(ScoreComponent, f32)>,
+    /// 是否需要PageRank重排序
+    pub use_pagerank: bool,
+}
+
+impl RetrievalPlan {
+    /// 检索器的融合权重，不运行时为空
+    pub fn weight(&self, component: ScoreComponent) -> Option<f32> {
+        self.retrievers
+            .iter()
+            .find(|(c, _)| *c == component)
+            .map(|(_, w)| *w)
+    }
+
+    /// 与意图对应的记忆管理器查询类型
+    pub fn query_type(&self) -> QueryType {
+        match self.intent {
+            QueryIntent::Temporal => QueryType::Temporal,
+            QueryIntent::Causal => QueryType::Causal,
+            QueryIntent::EntityLookup => QueryType::Semantic,
+            QueryIntent::OpenEnded => QueryType::Mixed,
+        }
+    }
+}
+
+/// 查询分析器
+#[derive(Debug, Clone)]
+pub struct QueryAnalyzer {
+    weights: FusionWeights,
+}
+
+impl QueryAnalyzer {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            weights: config.retrieval.fusion_weights.clone(),
+        }
+    }
+
+    fn base_weight(&self, component: ScoreComponent) -> f32 {
+        match component {
+            ScoreComponent::Semantic => self.weights.semantic_weight,
+            ScoreComponent::Temporal => self.weights.temporal_weight,
+            ScoreComponent::Structural => self.weights.structural_weight,
+            ScoreComponent::Importance => self.weights.importance_weight,
+            ScoreComponent::Personalization => self.weights.personalization_weight,
+        }
+    }
+
+    /// 按配置权重运行全部检索器的计划
+    pub fn fixed_plan(&self) -> RetrievalPlan {
+        self.plan(QueryIntent::OpenEnded, 1.0, Vec::new())
+    }
+
+    /// 分析查询文本，生成检索计划
+    ///
+    /// 因果线索优先于时间线索；都没有时，带查找线索或足够短的查询视为实体查找。
+    pub fn analyze(&self, text: &str) -> RetrievalPlan {
+        let lower = text.to_lowercase();
+        let words: Vec<&str> = lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        let padded = format!(" {} ", words.join(" "));
+        let matches = |cues: &[&str]| -> Vec<String> {
+            cues.iter()
+                .filter(|cue| {
+                    // 英文线索按整词匹配，避免“ago”命中“chicago”
+                    if cue.is_ascii() {
+                        padded.contains(&format!(" {} ", cue))
+                    } else {
+                        lower.contains(*cue)
+                    }
+                })
+                .map(|cue| cue.to_string())
+                .collect()
+        };
+
+        let causal = matches(CAUSAL_CUES);
+        if !causal.is_empty() {
+            return self.plan(QueryIntent::Causal, 0.8, causal);
+        }
+        let temporal = matches(TEMPORAL_CUES);
+        if !temporal.is_empty() {
+            return self.plan(QueryIntent::Temporal, 0.8, temporal);
+        }
+        let lookup = matches(LOOKUP_CUES);
+        if !lookup.is_empty() {
+            return self.plan(QueryIntent::EntityLookup, 0.7, lookup);
+        }
+
+        let cjk_chars = text.chars().filter(|c| TextUtils::is_cjk(*c)).count();
+        let ascii_words = words.iter().filter(|w| w.is_ascii()).count();
+        if !words.is_empty()
+            && ascii_words <= LOOKUP_MAX_WORDS
+            && cjk_chars <= LOOKUP_MAX_CJK_CHARS
+            && !text.contains(['?', '？'])
+        {
+            return self.plan(QueryIntent::EntityLookup, 0.6, Vec::new());
+        }
+
+        self.plan(QueryIntent::OpenEnded, 0.5, Vec::new())
+    }
+
+    fn plan(&self, intent: QueryIntent, confidence: f32, cues: Vec<String>) -> RetrievalPlan {
+        let retrievers = intent
+            .multipliers()
+            .iter()
+            .map(|(component, m)| (*component, self.base_weight(*component) * m))
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+
+        RetrievalPlan {
+            intent,
+            confidence,
+            cues,
+            retrievers,
+            use_pagerank: intent != QueryIntent::EntityLookup,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_intent() {
+        let analyzer = QueryAnalyzer::new(&Config::default());
+        let intent = |text: &str| analyzer.analyze(text).intent;
+
+        assert_eq!(intent("what did I do last Tuesday"), QueryIntent::Temporal);
+        assert_eq!(intent("最近读了哪些书"), QueryIntent::Temporal);
+        assert_eq!(intent("为什么项目延期了"), QueryIntent::Causal);
+        assert_eq!(
+            intent("why did the build fail yesterday"),
+            QueryIntent::Causal
+        );
+        assert_eq!(intent("张三的电话"), QueryIntent::EntityLookup);
+        assert_eq!(intent("Alice"), QueryIntent::EntityLookup);
+        assert_eq!(
+            intent("trip to chicago with the team"),
+            QueryIntent::OpenEnded
+        );
+        assert_eq!(
+            intent("我对机器学习有哪些想法和总结"),
+            QueryIntent::OpenEnded
+        );
+    }
+
+    #[test]
+    fn test_retrieval_plan() {
+        let analyzer = QueryAnalyzer::new(&Config::default());
+        let weights = &Config::default().retrieval.fusion_weights;
+
+        // 实体查找不运行时间和个性化检索，也不做PageRank重排序
+        let lookup = analyzer.analyze("who is Alice");
+        assert_eq!(lookup.cues, vec!["who is"]);
+        assert_eq!(lookup.weight(ScoreComponent::Temporal), None);
+        assert!(!lookup.use_pagerank);
+        assert_eq!(lookup.query_type(), QueryType::Semantic);
+
+        let temporal = analyzer.analyze("上周三开会说了什么");
+        let boosted = temporal.weight(ScoreComponent::Temporal).unwrap();
+        assert!((boosted - 3.0 * weights.temporal_weight).abs() < 1e-6);
+        assert!(temporal.use_pagerank);
+
+        let fixed = analyzer.fixed_plan();
+        assert_eq!(fixed.retrievers.len(), 4);
+        assert_eq!(
+            fixed.weight(ScoreComponent::Semantic),
+            Some(weights.semantic_weight)
+        );
+    }
+}
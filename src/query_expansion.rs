@@ -0,0 +1,383 @@
+//! 查询扩展模块
+//!
+//! 简短查询的单个嵌入常常漏掉措辞不同的记忆。按查询的扩展选项生成变体：
+//! 实体库中的同义写法、基于前几条结果嵌入的Rocchio伪相关反馈，以及大语言模型改写。
+//! 各变体并行检索后与原查询的结果按倒数排名融合。
+
+use crate::config::{Config, QueryExpansionConfig};
+use crate::core::{MemoryId, QueryExpansion};
+use crate::database::VectorGraphDB;
+use crate::entity::EntityLinker;
+use crate::error::Result;
+use crate::fusion::{RankedList, ResultFuser, ScoreComponent};
+use crate::llm::LlmTaskRunner;
+use crate::llm_tasks::{QueryParaphraseRequest, QueryParaphraseTask};
+use crate::memory::RetrievalResult;
+use crate::retrieval::FusionMethod;
+use crate::utils::VectorUtils;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 查询变体的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpansionKind {
+    /// 实体同义替换
+    Synonym,
+    /// 伪相关反馈
+    Feedback,
+    /// 大语言模型改写
+    Paraphrase,
+}
+
+/// 查询变体
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryVariant {
+    pub kind: ExpansionKind,
+    /// 变体的查询文本，伪相关反馈变体为原查询文本
+    pub text: String,
+    /// 变体的生成依据（替换的实体、参与反馈的记忆等）
+    pub description: String,
+    /// 直接用于向量检索的嵌入，只有伪相关反馈变体有
+    #[serde(skip)]
+    pub embedding: Option<Vec<f32>>,
+}
+
+impl QueryVariant {
+    /// 简短标签，用于标注结果由哪些变体检索到
+    pub fn label(&self) -> String {
+        match self.kind {
+            ExpansionKind::Synonym => format!("synonym({})", self.description),
+            ExpansionKind::Feedback => "feedback".to_string(),
+            ExpansionKind::Paraphrase => format!("paraphrase({})", self.text),
+        }
+    }
+}
+
+/// 查询扩展器
+#[derive(Debug, Clone)]
+pub struct QueryExpander {
+    config: QueryExpansionConfig,
+    entity_linker: EntityLinker,
+    fuser: ResultFuser,
+    llm: Option<LlmTaskRunner>,
+}
+
+impl QueryExpander {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.query_expansion.clone(),
+            entity_linker: EntityLinker::new(config),
+            fuser: ResultFuser::new(config),
+            llm: None,
+        }
+    }
+
+    /// 使用大语言模型生成查询改写
+    pub fn with_llm_runner(mut self, runner: LlmTaskRunner) -> Self {
+        self.llm = Some(runner);
+        self
+    }
+
+    /// 按扩展选项生成查询变体
+    ///
+    /// 伪相关反馈需要原查询的嵌入和原查询的结果，缺少任一项时跳过。
+    pub async fn expand(
+        &self,
+        db: &VectorGraphDB,
+        text: &str,
+        options: &QueryExpansion,
+        query_embedding: Option<&[f32]>,
+        feedback: &[RetrievalResult],
+    ) -> Result<Vec<QueryVariant>> {
+        let mut variants = Vec::new();
+        if options.synonyms {
+            variants.extend(self.synonym_variants(db, text).await?);
+        }
+        if options.pseudo_relevance_feedback {
+            if let Some(embedding) = query_embedding {
+                variants.extend(self.feedback_variant(db, text, embedding, feedback).await?);
+            }
+        }
+        if options.paraphrases {
+            variants.extend(self.paraphrase_variants(text).await);
+        }
+        Ok(variants)
+    }
+
+    /// 把查询中的实体替换为其同义写法
+    pub async fn synonym_variants(
+        &self,
+        db: &VectorGraphDB,
+        text: &str,
+    ) -> Result<Vec<QueryVariant>> {
+        let mut variants: Vec<QueryVariant> = Vec::new();
+        if !self.entity_linker.is_enabled() || self.config.max_synonyms == 0 {
+            return Ok(variants);
+        }
+
+        for entity in self.entity_linker.extract(text).await? {
+            for synonym in self.entity_linker.synonyms(db, &entity).await? {
+                let replaced = text.replace(&entity.name, &synonym);
+                if replaced == text || variants.iter().any(|v| v.text == replaced) {
+                    continue;
+                }
+                variants.push(QueryVariant {
+                    kind: ExpansionKind::Synonym,
+                    text: replaced,
+                    description: format!("{} -> {}", entity.name, synonym),
+                    embedding: None,
+                });
+                if variants.len() >= self.config.max_synonyms {
+                    return Ok(variants);
+                }
+            }
+        }
+        Ok(variants)
+    }
+
+    /// 用原查询前几条结果的嵌入做Rocchio更新
+    ///
+    /// 结果中没有嵌入的记忆从向量表读取，维度与查询嵌入不一致的跳过。
+    pub async fn feedback_variant(
+        &self,
+        db: &VectorGraphDB,
+        text: &str,
+        query_embedding: &[f32],
+        feedback: &[RetrievalResult],
+    ) -> Result<Option<QueryVariant>> {
+        let mut ids: Vec<&MemoryId> = Vec::new();
+        let mut embeddings = Vec::new();
+        for result in feedback.iter().take(self.config.feedback_docs) {
+            let memory = &result.memory;
+            let embedding = if memory.embedding.is_empty() {
+                db.get_vector(&memory.id)
+                    .await
+                    .map(|v| v.embedding)
+                    .unwrap_or_default()
+            } else {
+                memory.embedding.clone()
+            };
+            if embedding.len() == query_embedding.len() {
+                ids.push(&memory.id);
+                embeddings.push(embedding);
+            }
+        }
+        if embeddings.is_empty() {
+            return Ok(None);
+        }
+
+        let embedding = self.rocchio(query_embedding, &embeddings)?;
+        let ids: Vec<&str> = ids.iter().map(|id| id.as_str()).collect();
+        Ok(Some(QueryVariant {
+            kind: ExpansionKind::Feedback,
+            text: text.to_string(),
+            description: format!("Rocchio update over {}", ids.join(", ")),
+            embedding: Some(embedding),
+        }))
+    }
+
+    /// Rocchio更新：`α·q + β·mean(d)`
+    pub fn rocchio(&self, query_embedding: &[f32], feedback: &[Vec<f32>]) -> Result<Vec<f32>> {
+        let beta = self.config.rocchio_beta / feedback.len().max(1) as f32;
+        let mut vectors = vec![query_embedding.to_vec()];
+        let mut weights = vec![self.config.rocchio_alpha];
+        for embedding in feedback {
+            vectors.push(embedding.clone());
+            weights.push(beta);
+        }
+        VectorUtils::weighted_average(&vectors, &weights)
+    }
+
+    /// 由大语言模型改写查询，未配置模型或调用失败时不生成改写
+    pub async fn paraphrase_variants(&self, text: &str) -> Vec<QueryVariant> {
+        let Some(runner) = &self.llm else {
+            return Vec::new();
+        };
+        if self.config.max_paraphrases == 0 {
+            return Vec::new();
+        }
+
+        let request = QueryParaphraseRequest {
+            query: text.to_string(),
+            count: self.config.max_paraphrases,
+        };
+        let paraphrases = match runner.run(&QueryParaphraseTask, &request).await {
+            Ok(response) => response.paraphrases,
+            Err(e) => {
+                warn!("Query paraphrasing failed: {}", e);
+                Vec::new()
+            }
+        };
+        paraphrases
+            .into_iter()
+            .filter(|p| p != text)
+            .take(self.config.max_paraphrases)
+            .map(|p| QueryVariant {
+                kind: ExpansionKind::Paraphrase,
+                text: p,
+                description: "LLM paraphrase".to_string(),
+                embedding: None,
+            })
+            .collect()
+    }
+
+    /// 用倒数排名融合原查询和各变体的结果
+    ///
+    /// 原查询结果的权重为1，变体结果使用配置的权重；每个结果的解释中注明检索到它的变体。
+    pub fn fuse(
+        &self,
+        original: Vec<RetrievalResult>,
+        variants: Vec<(QueryVariant, Vec<RetrievalResult>)>,
+        limit: Option<usize>,
+    ) -> Vec<RetrievalResult> {
+        let mut matched: HashMap<MemoryId, Vec<String>> = HashMap::new();
+        for result in &original {
+            matched
+                .entry(result.memory.id.clone())
+                .or_default()
+                .push("original".to_string());
+        }
+
+        let mut lists = vec![RankedList::new(ScoreComponent::Semantic, 1.0, original)];
+        for (variant, results) in variants {
+            let label = variant.label();
+            for result in &results {
+                matched
+                    .entry(result.memory.id.clone())
+                    .or_default()
+                    .push(label.clone());
+            }
+            lists.push(RankedList::new(
+                ScoreComponent::Semantic,
+                self.config.variant_weight,
+                results,
+            ));
+        }
+
+        let mut results: Vec<RetrievalResult> = self
+            .fuser
+            .fuse(lists, &FusionMethod::RankFusion)
+            .into_iter()
+            .map(|fused| {
+                let mut result = fused.result;
+                if let Some(sources) = matched.get(&result.memory.id) {
+                    result.explanation.reasoning = format!(
+                        "{}; matched by {}",
+                        result.explanation.reasoning,
+                        sources.join(", ")
+                    );
+                }
+                result
+            })
+            .collect();
+        if let Some(limit) = limit {
+            results.truncate(limit);
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::GraphNode;
+    use crate::entity::ExtractedEntity;
+    use chrono::Utc;
+
+    async fn create_db() -> VectorGraphDB {
+        let mut config = Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        VectorGraphDB::new(config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_feedback_variant() {
+        let db = create_db().await;
+        let expander = QueryExpander::new(&Config::default());
+
+        // α=1, β=0.75：(1·q + 0.75·mean(d)) / 1.75
+        let feedback = vec![
+            RetrievalResult::for_test("a", 0.9).with_embedding(vec![0.0, 1.0]),
+            RetrievalResult::for_test("b", 0.8).with_embedding(vec![0.0, 1.0]),
+            RetrievalResult::for_test("c", 0.7).with_embedding(vec![1.0]),
+        ];
+        let variant = expander
+            .feedback_variant(&db, "query", &[1.0, 0.0], &feedback)
+            .await
+            .unwrap()
+            .unwrap();
+        let embedding = variant.embedding.unwrap();
+        assert!((embedding[0] - 1.0 / 1.75).abs() < 1e-6);
+        assert!((embedding[1] - 0.75 / 1.75).abs() < 1e-6);
+        assert_eq!(variant.description, "Rocchio update over a, b");
+
+        let none = expander
+            .feedback_variant(&db, "query", &[1.0, 0.0], &[])
+            .await
+            .unwrap();
+        assert!(none.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_synonyms_and_fusion() {
+        let db = create_db().await;
+        let config = Config::default();
+        let linker = EntityLinker::new(&config);
+        db.insert_node(&GraphNode {
+            id: "m1".to_string(),
+            node_type: "memory".to_string(),
+            properties: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+        let entities = [
+            (
+                ExtractedEntity::new("Alice", "person"),
+                Some(vec![1.0, 0.0]),
+            ),
+            (
+                ExtractedEntity::new("Alice Chen", "person"),
+                Some(vec![0.95, 0.1]),
+            ),
+        ];
+        linker
+            .link_memory(&db, &"m1".to_string(), &entities)
+            .await
+            .unwrap();
+
+        let expander = QueryExpander::new(&config);
+        let options = QueryExpansion {
+            synonyms: true,
+            pseudo_relevance_feedback: false,
+            paraphrases: true,
+        };
+        let variants = expander
+            .expand(&db, "What does Alice drink?", &options, None, &[])
+            .await
+            .unwrap();
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].text, "What does Alice Chen drink?");
+        assert_eq!(variants[0].label(), "synonym(Alice -> Alice Chen)");
+
+        // 两边都检索到的记忆排在前面
+        let original = vec![
+            RetrievalResult::for_test("a", 0.9),
+            RetrievalResult::for_test("b", 0.8),
+        ];
+        let expanded = vec![
+            RetrievalResult::for_test("b", 0.9),
+            RetrievalResult::for_test("c", 0.8),
+        ];
+        let fused = expander.fuse(original, vec![(variants[0].clone(), expanded)], Some(2));
+        let ids: Vec<&str> = fused.iter().map(|r| r.memory.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "a"]);
+        assert!(fused[0]
+            .explanation
+            .reasoning
+            .ends_with("matched by original, synonym(Alice -> Alice Chen)"));
+    }
+}
@@ -0,0 +1,538 @@
+//! 排序学习模块
+//!
+//! 记录检索展示（查询、展示的结果及其特征），结合点击、停留、收藏、忽略等反馈
+//! 训练逻辑回归排序模型，供 `FusionMethod::LearnedFusion` 对融合结果重新打分。
+//! 特征为融合后的各分量分数加上新近度和访问频次。
+
+use crate::config::{Config, RankingConfig};
+use crate::core::{Memory, MemoryId};
+use crate::fusion::FusedResult;
+use crate::learning::{FeedbackRecord, FeedbackType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 融合排序模型的名称
+pub const LEARNED_FUSION_MODEL: &str = "learned_fusion";
+
+/// 特征数
+pub const FEATURE_COUNT: usize = 7;
+
+/// 新近度特征的时间尺度（天）
+const RECENCY_SCALE_DAYS: f32 = 30.0;
+
+/// 访问次数达到该值时访问频次特征饱和
+const ACCESS_SATURATION: f32 = 20.0;
+
+/// 每天的秒数
+const SECONDS_PER_DAY: f32 = 86_400.0;
+
+/// 留作验证集的展示比例的倒数
+const VALIDATION_STRIDE: usize = 5;
+
+/// 排序特征，各项取值0到1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RankingFeatures {
+    pub semantic: f32,
+    pub temporal: f32,
+    pub structural: f32,
+    pub importance: f32,
+    pub personalization: f32,
+    /// 按创建时间计算的新近度
+    pub recency: f32,
+    /// 按访问次数计算的访问频次
+    pub access_frequency: f32,
+}
+
+impl RankingFeatures {
+    /// 从融合结果提取特征
+    pub fn from_fused(fused: &FusedResult, now: DateTime<Utc>) -> Self {
+        let b = &fused.breakdown;
+        let memory = &fused.result.memory;
+        Self {
+            semantic: b.semantic_score,
+            temporal: b.temporal_score,
+            structural: b.structural_score,
+            importance: b.importance_score,
+            personalization: b.personalization_score,
+            recency: Self::recency(memory, now),
+            access_frequency: Self::access_frequency(memory),
+        }
+    }
+
+    fn recency(memory: &Memory, now: DateTime<Utc>) -> f32 {
+        let age = (now - memory.metadata.created_at).num_seconds().max(0) as f32;
+        (-age / SECONDS_PER_DAY / RECENCY_SCALE_DAYS).exp()
+    }
+
+    fn access_frequency(memory: &Memory) -> f32 {
+        let count = memory.metadata.access_count as f32;
+        ((1.0 + count).ln() / (1.0 + ACCESS_SATURATION).ln()).min(1.0)
+    }
+
+    pub fn to_array(&self) -> [f32; FEATURE_COUNT] {
+        [
+            self.semantic,
+            self.temporal,
+            self.structural,
+            self.importance,
+            self.personalization,
+            self.recency,
+            self.access_frequency,
+        ]
+    }
+}
+
+/// 展示给用户的一个结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImpressionItem {
+    pub memory_id: MemoryId,
+    /// 展示位置，从0开始
+    pub position: usize,
+    pub features: RankingFeatures,
+}
+
+/// 一次检索展示
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalImpression {
+    pub id: String,
+    pub query: String,
+    pub session_id: Option<String>,
+    pub items: Vec<ImpressionItem>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl RetrievalImpression {
+    /// 按展示顺序记录融合结果
+    pub fn new(query: &str, session_id: Option<String>, shown: &[FusedResult]) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            query: query.to_string(),
+            session_id,
+            items: shown
+                .iter()
+                .enumerate()
+                .map(|(position, fused)| ImpressionItem {
+                    memory_id: fused.result.memory.id.clone(),
+                    position,
+                    features: RankingFeatures::from_fused(fused, now),
+                })
+                .collect(),
+            timestamp: now,
+        }
+    }
+}
+
+/// 一次展示中带标注的结果，按展示顺序排列
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledImpression {
+    pub items: Vec<(RankingFeatures, f32)>,
+}
+
+/// 离线评估指标
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RankingMetrics {
+    pub train_samples: usize,
+    pub validation_samples: usize,
+    /// 验证集上的对数损失
+    pub log_loss: f32,
+    /// 验证集上以0.5为界的分类准确率
+    pub accuracy: f32,
+    /// 模型排序在验证集上的NDCG@k
+    pub ndcg: f32,
+    /// 原展示顺序在验证集上的NDCG@k
+    pub baseline_ndcg: f32,
+}
+
+/// 逻辑回归排序模型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearnedRanker {
+    pub weights: Vec<f32>,
+    pub bias: f32,
+    pub trained_at: DateTime<Utc>,
+    pub metrics: RankingMetrics,
+}
+
+impl LearnedRanker {
+    /// 结果被用户采纳的概率
+    pub fn score(&self, features: &RankingFeatures) -> f32 {
+        let z = self
+            .weights
+            .iter()
+            .zip(features.to_array())
+            .map(|(w, x)| w * x)
+            .sum::<f32>()
+            + self.bias;
+        sigmoid(z)
+    }
+
+    /// 用模型分数替换融合分数并重新排序
+    pub fn rerank(&self, results: &mut [FusedResult]) {
+        let now = Utc::now();
+        for fused in results.iter_mut() {
+            let score = self.score(&RankingFeatures::from_fused(fused, now));
+            fused.breakdown.final_score = score;
+            fused.result.relevance_score = score;
+            let reasoning = &mut fused.result.explanation.reasoning;
+            reasoning.push_str(&format!(", learned={:.3}", score));
+        }
+        results.sort_by(|a, b| {
+            b.breakdown
+                .final_score
+                .total_cmp(&a.breakdown.final_score)
+                .then_with(|| a.result.memory.id.cmp(&b.result.memory.id))
+        });
+    }
+}
+
+/// 排序模型训练器
+#[derive(Debug, Clone)]
+pub struct RankerTrainer {
+    min_samples: usize,
+    learning_rate: f32,
+    epochs: usize,
+    l2_penalty: f32,
+    positive_threshold: f32,
+    eval_k: usize,
+}
+
+impl RankerTrainer {
+    pub fn new(config: &Config) -> Self {
+        let RankingConfig {
+            min_samples,
+            learning_rate,
+            epochs,
+            l2_penalty,
+            positive_threshold,
+            eval_k,
+            ..
+        } = config.ranking;
+
+        Self {
+            min_samples,
+            learning_rate,
+            epochs,
+            l2_penalty,
+            positive_threshold,
+            eval_k: eval_k.max(1),
+        }
+    }
+
+    /// 反馈对应的标签，点击、分享、收藏为正例，忽略为负例，评分类反馈按阈值划分
+    fn feedback_label(&self, feedback: &FeedbackRecord) -> f32 {
+        let positive = match feedback.feedback_type {
+            FeedbackType::Click | FeedbackType::Share | FeedbackType::Bookmark => true,
+            FeedbackType::Ignore => false,
+            FeedbackType::Explicit | FeedbackType::Implicit | FeedbackType::DwellTime => {
+                feedback.score >= self.positive_threshold
+            }
+        };
+        if positive {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// 把反馈关联到展示，生成标注数据
+    ///
+    /// 反馈归属于同一查询（和会话）中在它之前、包含该记忆的最近一次展示。
+    /// 同一结果有多条反馈时正例优先；排在正例之上却没有反馈的结果视为被跳过的负例，
+    /// 最后一个有反馈的结果之后的未标注结果不参与训练。
+    pub fn build_dataset(
+        &self,
+        impressions: &[RetrievalImpression],
+        feedback: &[FeedbackRecord],
+    ) -> Vec<LabeledImpression> {
+        let mut order: Vec<usize> = (0..impressions.len()).collect();
+        order.sort_by_key(|&i| impressions[i].timestamp);
+
+        let mut labels: HashMap<(usize, usize), f32> = HashMap::new();
+        for record in feedback {
+            let matched = order.iter().rev().copied().find_map(|i| {
+                let impression = &impressions[i];
+                let same_session = impression
+                    .session_id
+                    .as_ref()
+                    .is_none_or(|s| *s == record.context.session_id);
+                if impression.query != record.context.query
+                    || !same_session
+                    || impression.timestamp > record.timestamp
+                {
+                    return None;
+                }
+                let item = impression
+                    .items
+                    .iter()
+                    .position(|item| item.memory_id == record.memory_id)?;
+                Some((i, item))
+            });
+            if let Some(key) = matched {
+                let label = self.feedback_label(record);
+                let entry = labels.entry(key).or_insert(label);
+                *entry = entry.max(label);
+            }
+        }
+
+        impressions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, impression)| {
+                let last_labeled = (0..impression.items.len())
+                    .rev()
+                    .find(|j| labels.contains_key(&(i, *j)))?;
+                let last_positive = (0..impression.items.len())
+                    .rev()
+                    .find(|j| labels.get(&(i, *j)) == Some(&1.0));
+                let items = impression.items[..=last_labeled]
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(j, item)| {
+                        let label = labels
+                            .get(&(i, j))
+                            .copied()
+                            .or_else(|| last_positive.filter(|p| j < *p).map(|_| 0.0))?;
+                        Some((item.features, label))
+                    })
+                    .collect();
+                Some(LabeledImpression { items })
+            })
+            .collect()
+    }
+
+    /// 训练模型，样本不足时返回空
+    ///
+    /// 每第5个展示留作验证集（展示少于5个时在训练集上评估），
+    /// 用批量梯度下降最小化带L2正则的对数损失。
+    pub fn train(&self, dataset: &[LabeledImpression]) -> Option<LearnedRanker> {
+        let samples: usize = dataset.iter().map(|d| d.items.len()).sum();
+        if samples == 0 || samples < self.min_samples {
+            return None;
+        }
+
+        let (train, validation): (Vec<_>, Vec<_>) = if dataset.len() >= VALIDATION_STRIDE {
+            dataset
+                .iter()
+                .enumerate()
+                .partition(|(i, _)| i % VALIDATION_STRIDE != VALIDATION_STRIDE - 1)
+        } else {
+            (dataset.iter().enumerate().collect(), Vec::new())
+        };
+        let train: Vec<&LabeledImpression> = train.into_iter().map(|(_, d)| d).collect();
+        let validation: Vec<&LabeledImpression> = if validation.is_empty() {
+            train.clone()
+        } else {
+            validation.into_iter().map(|(_, d)| d).collect()
+        };
+
+        let examples: Vec<([f32; FEATURE_COUNT], f32)> = train
+            .iter()
+            .flat_map(|d| d.items.iter().map(|(f, y)| (f.to_array(), *y)))
+            .collect();
+        let n = examples.len() as f32;
+        let mut ranker = LearnedRanker {
+            weights: vec![0.0; FEATURE_COUNT],
+            bias: 0.0,
+            trained_at: Utc::now(),
+            metrics: RankingMetrics::default(),
+        };
+
+        for _ in 0..self.epochs {
+            let mut grad = [0.0; FEATURE_COUNT];
+            let mut grad_bias = 0.0;
+            for (x, y) in &examples {
+                let z = ranker
+                    .weights
+                    .iter()
+                    .zip(x)
+                    .map(|(w, x)| w * x)
+                    .sum::<f32>();
+                let error = sigmoid(z + ranker.bias) - y;
+                for (g, x) in grad.iter_mut().zip(x) {
+                    *g += error * x;
+                }
+                grad_bias += error;
+            }
+            for (w, g) in ranker.weights.iter_mut().zip(grad) {
+                *w -= self.learning_rate * (g / n + self.l2_penalty * *w);
+            }
+            ranker.bias -= self.learning_rate * grad_bias / n;
+        }
+
+        ranker.metrics = self.evaluate(&ranker, &validation);
+        ranker.metrics.train_samples = examples.len();
+        Some(ranker)
+    }
+
+    /// 在验证集上评估模型
+    fn evaluate(
+        &self,
+        ranker: &LearnedRanker,
+        validation: &[&LabeledImpression],
+    ) -> RankingMetrics {
+        let mut metrics = RankingMetrics::default();
+        let mut correct = 0;
+        let mut loss = 0.0;
+        let mut ranked_impressions = 0;
+
+        for impression in validation {
+            let scores: Vec<f32> = impression
+                .items
+                .iter()
+                .map(|(features, _)| ranker.score(features))
+                .collect();
+            for ((_, label), p) in impression.items.iter().zip(&scores) {
+                let p = p.clamp(1e-6, 1.0 - 1e-6);
+                loss -= label * p.ln() + (1.0 - label) * (1.0 - p).ln();
+                if (p >= 0.5) == (*label >= 0.5) {
+                    correct += 1;
+                }
+                metrics.validation_samples += 1;
+            }
+
+            let shown: Vec<f32> = impression.items.iter().map(|(_, y)| *y).collect();
+            if !shown.iter().any(|y| *y > 0.0) {
+                continue;
+            }
+            // 模型分数相同时保持原展示顺序
+            let mut order: Vec<usize> = (0..shown.len()).collect();
+            order.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+            let reranked: Vec<f32> = order.iter().map(|i| shown[*i]).collect();
+            metrics.ndcg += ndcg(&reranked, self.eval_k);
+            metrics.baseline_ndcg += ndcg(&shown, self.eval_k);
+            ranked_impressions += 1;
+        }
+
+        if metrics.validation_samples > 0 {
+            let total = metrics.validation_samples as f32;
+            metrics.log_loss = loss / total;
+            metrics.accuracy = correct as f32 / total;
+        }
+        if ranked_impressions > 0 {
+            metrics.ndcg /= ranked_impressions as f32;
+            metrics.baseline_ndcg /= ranked_impressions as f32;
+        }
+        metrics
+    }
+}
+
+fn sigmoid(z: f32) -> f32 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+/// 按给定顺序的标签计算NDCG@k
+fn ndcg(labels: &[f32], k: usize) -> f32 {
+    let dcg = |labels: &[f32]| -> f32 {
+        labels
+            .iter()
+            .take(k)
+            .enumerate()
+            .map(|(i, y)| y / (i as f32 + 2.0).log2())
+            .sum()
+    };
+    let mut ideal = labels.to_vec();
+    ideal.sort_by(|a, b| b.total_cmp(a));
+    let best = dcg(&ideal);
+    if best > 0.0 {
+        dcg(labels) / best
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::learning::FeedbackContext;
+
+    fn features(semantic: f32, temporal: f32) -> RankingFeatures {
+        RankingFeatures {
+            semantic,
+            temporal,
+            structural: 0.0,
+            importance: 0.5,
+            personalization: 0.0,
+            recency: 0.5,
+            access_frequency: 0.0,
+        }
+    }
+
+    fn feedback(query: &str, memory_id: &str, feedback_type: FeedbackType) -> FeedbackRecord {
+        FeedbackRecord {
+            memory_id: memory_id.to_string(),
+            feedback_type,
+            score: 0.0,
+            context: FeedbackContext {
+                query: query.to_string(),
+                result_position: 0,
+                session_id: "s".to_string(),
+                device_type: None,
+                time_of_day: 12,
+                day_of_week: 1,
+            },
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// 按语义分数展示，但用户总是点击时间分数高的结果
+    fn logs(count: usize) -> (Vec<RetrievalImpression>, Vec<FeedbackRecord>) {
+        let mut impressions = Vec::new();
+        let mut records = Vec::new();
+        for i in 0..count {
+            let query = format!("q{}", i);
+            let items = [(0.9, 0.1), (0.8, 0.2), (0.6, 0.9), (0.5, 0.3)]
+                .iter()
+                .enumerate()
+                .map(|(position, (s, t))| ImpressionItem {
+                    memory_id: format!("m{}", position),
+                    position,
+                    features: features(*s, *t),
+                })
+                .collect();
+            impressions.push(RetrievalImpression {
+                id: query.clone(),
+                query: query.clone(),
+                session_id: Some("s".to_string()),
+                items,
+                timestamp: Utc::now() - chrono::Duration::minutes(1),
+            });
+            records.push(feedback(&query, "m2", FeedbackType::Click));
+        }
+        (impressions, records)
+    }
+
+    #[test]
+    fn test_dataset_labels() {
+        let trainer = RankerTrainer::new(&Config::default());
+        let (impressions, mut records) = logs(2);
+        records.push(feedback("q1", "m3", FeedbackType::Ignore));
+        records.push(feedback("other", "m0", FeedbackType::Click));
+
+        let dataset = trainer.build_dataset(&impressions, &records);
+        assert_eq!(dataset.len(), 2);
+        // 点击之上的结果是跳过的负例，之下未标注的不参与训练
+        let labels: Vec<f32> = dataset[0].items.iter().map(|(_, y)| *y).collect();
+        assert_eq!(labels, vec![0.0, 0.0, 1.0]);
+        assert_eq!(dataset[1].items.len(), 4);
+        assert_eq!(dataset[1].items[3].1, 0.0);
+    }
+
+    #[test]
+    fn test_ranker_training() {
+        let trainer = RankerTrainer::new(&Config::default());
+        let (impressions, records) = logs(10);
+        let dataset = trainer.build_dataset(&impressions, &records);
+        let ranker = trainer.train(&dataset).unwrap();
+
+        assert_eq!(ranker.metrics.train_samples, 24);
+        assert_eq!(ranker.metrics.validation_samples, 6);
+        assert!(ranker.metrics.accuracy > 0.99);
+        assert!((ranker.metrics.ndcg - 1.0).abs() < 1e-6);
+        assert!((ranker.metrics.baseline_ndcg - 0.5).abs() < 1e-6);
+        assert!(ranker.score(&features(0.6, 0.9)) > ranker.score(&features(0.9, 0.1)));
+
+        // 样本不足时不训练
+        assert!(trainer.train(&dataset[..2]).is_none());
+    }
+}
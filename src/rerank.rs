@@ -0,0 +1,460 @@
+//! 重排序模块
+//!
+//! 双编码器的余弦相似度加启发式融合对排在最前面的几条结果区分度不够。
+//! 融合之后把前若干个候选交给 [`Reranker`] 逐条打分，可选实现：
+//!
+//! - 查询词覆盖率基线 [`KeywordOverlapReranker`]
+//! - 由大语言模型判断相关性的 [`LlmReranker`]
+//! - 通过HTTP调用交叉编码器服务的 [`CrossEncoderReranker`]
+
+use crate::config::{Config, CrossEncoderConfig, RerankConfig, RerankMethod};
+use crate::core::Memory;
+use crate::error::{MemoryError, Result};
+use crate::llm::{post_json, LlmProvider, LlmTaskRunner};
+use crate::llm_tasks::{RelevanceJudgmentRequest, RelevanceJudgmentTask};
+use crate::memory::RetrievalResult;
+use crate::utils::TextUtils;
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 重排序器
+///
+/// 为每个候选给出[0, 1]的相关性分数，顺序与输入一致。
+#[async_trait]
+pub trait Reranker: Send + Sync + std::fmt::Debug {
+    /// 重排序器名称，记录在检索解释中
+    fn name(&self) -> &str;
+
+    /// 为候选记忆打分
+    async fn score(&self, query: &str, candidates: &[Memory]) -> Result<Vec<f32>>;
+}
+
+/// 查询词覆盖率重排序器
+///
+/// 分数为查询词（去停用词后）出现在记忆内容、关键词或标签中的比例。
+#[derive(Debug, Clone, Default)]
+pub struct KeywordOverlapReranker;
+
+impl KeywordOverlapReranker {
+    /// 查询词在记忆中的覆盖率
+    pub fn overlap(query: &str, memory: &Memory) -> f32 {
+        let query_terms: HashSet<String> = TextUtils::tokenize(&query.to_lowercase())
+            .into_iter()
+            .collect();
+        if query_terms.is_empty() {
+            return 0.0;
+        }
+
+        let mut memory_terms: HashSet<String> = TextUtils::tokenize(&memory.content.to_lowercase())
+            .into_iter()
+            .collect();
+        let attributes = &memory.attributes;
+        for term in attributes.keywords.iter().chain(&attributes.tags) {
+            memory_terms.insert(term.to_lowercase());
+        }
+
+        let matched = query_terms
+            .iter()
+            .filter(|term| memory_terms.contains(*term))
+            .count();
+        matched as f32 / query_terms.len() as f32
+    }
+}
+
+#[async_trait]
+impl Reranker for KeywordOverlapReranker {
+    fn name(&self) -> &str {
+        "keyword_overlap"
+    }
+
+    async fn score(&self, query: &str, candidates: &[Memory]) -> Result<Vec<f32>> {
+        Ok(candidates
+            .iter()
+            .map(|memory| Self::overlap(query, memory))
+            .collect())
+    }
+}
+
+/// 基于大语言模型的重排序器
+///
+/// 通过 [`RelevanceJudgmentTask`] 一次判断全部候选，模型调用失败或输出未通过校验时
+/// 回退到查询词覆盖率；模型漏判的候选记为0分。
+#[derive(Debug, Clone)]
+pub struct LlmReranker {
+    runner: LlmTaskRunner,
+    task: RelevanceJudgmentTask,
+}
+
+impl LlmReranker {
+    pub fn new(provider: Arc<dyn LlmProvider>, config: &Config) -> Self {
+        Self {
+            runner: LlmTaskRunner::new(provider, &config.llm),
+            task: RelevanceJudgmentTask,
+        }
+    }
+}
+
+#[async_trait]
+impl Reranker for LlmReranker {
+    fn name(&self) -> &str {
+        self.runner.provider().name()
+    }
+
+    async fn score(&self, query: &str, candidates: &[Memory]) -> Result<Vec<f32>> {
+        let request = RelevanceJudgmentRequest {
+            query: query.to_string(),
+            candidates: candidates.to_vec(),
+        };
+        let response = self.runner.run(&self.task, &request).await?;
+        let scores: HashMap<_, _> = response
+            .judgments
+            .into_iter()
+            .map(|j| (j.memory_id, j.score))
+            .collect();
+
+        Ok(candidates
+            .iter()
+            .map(|memory| scores.get(&memory.id).copied().unwrap_or(0.0))
+            .collect())
+    }
+}
+
+/// 交叉编码器重排序器
+///
+/// 调用HTTP重排序服务，请求格式见 [`CrossEncoderConfig`]。
+/// 服务返回的分数超出[0, 1]时视为logit，经sigmoid转换。
+#[derive(Clone)]
+pub struct CrossEncoderReranker {
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    timeout: Duration,
+}
+
+impl std::fmt::Debug for CrossEncoderReranker {
+    // 手动实现以免API密钥出现在日志中
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrossEncoderReranker")
+            .field("endpoint", &self.endpoint)
+            .field("model", &self.model)
+            .field("api_key", &"<redacted>")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl CrossEncoderReranker {
+    pub fn new(config: &CrossEncoderConfig) -> Result<Self> {
+        if config.endpoint.is_empty() {
+            return Err(MemoryError::validation_error(
+                "Cross-encoder endpoint cannot be empty".to_string(),
+            ));
+        }
+
+        let api_key = config
+            .api_key
+            .clone()
+            .or_else(|| std::env::var(&config.api_key_env).ok())
+            .filter(|key| !key.is_empty());
+
+        Ok(Self {
+            endpoint: config.endpoint.clone(),
+            model: config.model.clone(),
+            api_key,
+            timeout: Duration::from_secs(config.timeout_seconds.max(1)),
+        })
+    }
+
+    fn parse_body(body: &str, count: usize) -> Result<Vec<f32>> {
+        let value: Value = serde_json::from_str(body).map_err(MemoryError::Serialization)?;
+        let items = value
+            .get("results")
+            .unwrap_or(&value)
+            .as_array()
+            .ok_or_else(|| MemoryError::retrieval_error("Rerank response contains no results"))?;
+
+        let mut scores = vec![0.0; count];
+        for item in items {
+            let index = item["index"].as_u64().map(|i| i as usize);
+            let score = item["relevance_score"]
+                .as_f64()
+                .or_else(|| item["score"].as_f64());
+            match (index, score) {
+                (Some(index), Some(score)) if index < count => {
+                    let score = score as f32;
+                    scores[index] = if (0.0..=1.0).contains(&score) {
+                        score
+                    } else {
+                        1.0 / (1.0 + (-score).exp())
+                    };
+                }
+                _ => {
+                    return Err(MemoryError::retrieval_error(format!(
+                        "Invalid rerank result: {}",
+                        item
+                    )))
+                }
+            }
+        }
+        Ok(scores)
+    }
+}
+
+#[async_trait]
+impl Reranker for CrossEncoderReranker {
+    fn name(&self) -> &str {
+        &self.model
+    }
+
+    async fn score(&self, query: &str, candidates: &[Memory]) -> Result<Vec<f32>> {
+        let documents: Vec<&str> = candidates.iter().map(|m| m.content.as_str()).collect();
+        let body = json!({
+            "model": self.model,
+            "query": query,
+            "documents": documents,
+        });
+        let response = post_json(
+            &self.endpoint,
+            self.api_key.as_deref(),
+            self.timeout,
+            body.to_string(),
+        )
+        .await?;
+        Self::parse_body(&response, candidates.len())
+    }
+}
+
+/// 重排序阶段
+///
+/// 对融合后的前 `top_n` 个候选打分，最终分数为
+/// `(1-w)·融合分数 + w·重排序分数·最高融合分数`，保持与其余结果相同的分数尺度；
+/// 其余结果保持原顺序排在后面。重排序器出错时保留原顺序。
+#[derive(Debug, Clone)]
+pub struct RerankStage {
+    enabled: bool,
+    top_n: usize,
+    weight: f32,
+    reranker: Arc<dyn Reranker>,
+}
+
+impl RerankStage {
+    pub fn new(config: &Config) -> Result<Self> {
+        let RerankConfig {
+            enabled,
+            method,
+            top_n,
+            weight,
+            ref cross_encoder,
+        } = config.rerank;
+
+        let reranker: Arc<dyn Reranker> = match method {
+            RerankMethod::CrossEncoder => Arc::new(CrossEncoderReranker::new(cross_encoder)?),
+            // 大语言模型重排序器在设置模型提供者后替换
+            RerankMethod::Keyword | RerankMethod::Llm => Arc::new(KeywordOverlapReranker),
+        };
+
+        Ok(Self {
+            enabled,
+            top_n: top_n.max(1),
+            weight: weight.clamp(0.0, 1.0),
+            reranker,
+        })
+    }
+
+    /// 替换重排序器
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = reranker;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn name(&self) -> &str {
+        self.reranker.name()
+    }
+
+    /// 重排序，打过分的结果在解释中记录重排序分数
+    pub async fn rerank(&self, query: &str, results: Vec<RetrievalResult>) -> Vec<RetrievalResult> {
+        if !self.enabled || results.is_empty() {
+            return results;
+        }
+
+        let n = self.top_n.min(results.len());
+        let candidates: Vec<Memory> = results[..n].iter().map(|r| r.memory.clone()).collect();
+        let scores = match self.reranker.score(query, &candidates).await {
+            Ok(scores) if scores.len() == n => scores,
+            Ok(scores) => {
+                warn!(
+                    "Reranker {} returned {} scores for {} candidates",
+                    self.reranker.name(),
+                    scores.len(),
+                    n
+                );
+                return results;
+            }
+            Err(e) => {
+                warn!("Reranker {} failed: {}", self.reranker.name(), e);
+                return results;
+            }
+        };
+
+        let max_score = results[..n]
+            .iter()
+            .map(|r| r.relevance_score)
+            .fold(0.0f32, f32::max);
+        let mut results = results;
+        let tail = results.split_off(n);
+        for (result, score) in results.iter_mut().zip(scores) {
+            let score = if score.is_finite() {
+                score.clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            result.relevance_score =
+                (1.0 - self.weight) * result.relevance_score + self.weight * score * max_score;
+            result.explanation.rerank_score = Some(score);
+            result.explanation.reasoning = format!(
+                "{}; rerank({})={:.3}",
+                result.explanation.reasoning,
+                self.reranker.name(),
+                score
+            );
+        }
+        results.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+        debug!("Reranked top {} results with {}", n, self.reranker.name());
+
+        results.extend(tail);
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ScriptedLlmProvider;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    fn results() -> Vec<RetrievalResult> {
+        vec![
+            RetrievalResult::for_test("a", 0.8).with_content("weekly planning meeting notes"),
+            RetrievalResult::for_test("b", 0.7).with_content("rust compiler error in the build"),
+            RetrievalResult::for_test("c", 0.2).with_content("grocery list"),
+        ]
+    }
+
+    fn stage(weight: f32, top_n: usize) -> RerankStage {
+        let mut config = Config::default();
+        config.rerank.enabled = true;
+        config.rerank.weight = weight;
+        config.rerank.top_n = top_n;
+        RerankStage::new(&config).unwrap()
+    }
+
+    fn ids(results: &[RetrievalResult]) -> Vec<&str> {
+        results.iter().map(|r| r.memory.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_keyword_rerank() {
+        let reranked = stage(0.5, 2).rerank("rust build error", results()).await;
+        assert_eq!(ids(&reranked), vec!["b", "a", "c"]);
+        assert_eq!(reranked[0].explanation.rerank_score, Some(1.0));
+        assert!((reranked[0].relevance_score - 0.75).abs() < 1e-6);
+        assert!(reranked[0]
+            .explanation
+            .reasoning
+            .contains("rerank(keyword_overlap)=1.000"));
+        // top_n之外的结果不参与重排序
+        assert_eq!(reranked[2].explanation.rerank_score, None);
+        assert_eq!(reranked[2].relevance_score, 0.2);
+
+        // 未启用时保持原顺序
+        let mut config = Config::default();
+        config.rerank.enabled = false;
+        let unchanged = RerankStage::new(&config)
+            .unwrap()
+            .rerank("rust build error", results())
+            .await;
+        assert_eq!(ids(&unchanged), vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_llm_rerank() {
+        let provider = Arc::new(ScriptedLlmProvider::new([
+            r#"{"judgments": [{"memory_id": "c", "score": 0.9}, {"memory_id": "a", "score": 1.7}]}"#,
+        ]));
+        let reranker = Arc::new(LlmReranker::new(provider.clone(), &Config::default()));
+        let reranked = stage(1.0, 3)
+            .with_reranker(reranker)
+            .rerank("what to buy", results())
+            .await;
+
+        // 分数截断到[0, 1]，漏判的b记为0分
+        assert_eq!(ids(&reranked), vec!["a", "c", "b"]);
+        assert_eq!(reranked[0].explanation.rerank_score, Some(1.0));
+        assert_eq!(reranked[2].explanation.rerank_score, Some(0.0));
+        let input = &provider.requests()[0].messages[1].content;
+        assert!(input.contains("\"id\":\"b\""));
+    }
+
+    #[tokio::test]
+    async fn test_cross_encoder_rerank() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut buffer).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..n]);
+            }
+            // 第二个候选返回logit
+            let body = r#"{"results":[{"index":1,"relevance_score":3.0},{"index":0,"relevance_score":0.4},{"index":2,"relevance_score":0.1}]}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let mut config = Config::default();
+        config.rerank.enabled = true;
+        config.rerank.weight = 1.0;
+        config.rerank.method = RerankMethod::CrossEncoder;
+        config.rerank.cross_encoder.endpoint = format!("http://{}/rerank", address);
+        config.rerank.cross_encoder.api_key = Some("secret".to_string());
+        let debug = format!(
+            "{:?}",
+            CrossEncoderReranker::new(&config.rerank.cross_encoder).unwrap()
+        );
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains("secret"));
+
+        let stage = RerankStage::new(&config).unwrap();
+        assert_eq!(stage.name(), "bge-reranker-v2-m3");
+
+        let reranked = stage.rerank("compiler error", results()).await;
+        assert_eq!(ids(&reranked), vec!["b", "a", "c"]);
+        let expected = 1.0 / (1.0 + (-3.0f32).exp());
+        assert!((reranked[0].explanation.rerank_score.unwrap() - expected).abs() < 1e-6);
+
+        let raw = server.join().unwrap();
+        assert!(raw.starts_with("POST /rerank"));
+        assert!(raw.contains("\"query\":\"compiler error\""));
+        assert!(raw.contains("\"documents\":[\"weekly planning meeting notes\""));
+    }
+}
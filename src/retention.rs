@@ -0,0 +1,241 @@
+//! 遗忘曲线模块
+//!
+//! Ebbinghaus风格的记忆保持模型：保持率 `R = exp(-t/S)` 随距上次强化的时间衰减，
+//! 稳定度 `S` 随每次检索或正向反馈增长。保持率驱动重要性衰减，也可参与检索排序，
+//! 让不再被用到的琐碎记忆逐渐淡出，而反复强化的记忆长期保留。
+
+use crate::config::{Config, RetentionConfig};
+use crate::core::Memory;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 保持状态记录在自定义属性中的键
+pub const RETENTION_ATTRIBUTE: &str = "retention";
+
+/// 每天的秒数
+const SECONDS_PER_DAY: f32 = 86_400.0;
+
+/// 记忆的保持状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetentionState {
+    /// 稳定度（天），保持率降到 `1/e` 所需的时间
+    pub stability_days: f32,
+    /// 最近一次强化的时间
+    pub reinforced_at: DateTime<Utc>,
+    /// 未衰减的基础重要性
+    pub base_importance: f32,
+}
+
+/// 遗忘曲线模型
+#[derive(Debug, Clone)]
+pub struct RetentionModel {
+    enabled: bool,
+    initial_stability_days: f32,
+    stability_growth: f32,
+    max_stability_days: f32,
+}
+
+impl RetentionModel {
+    pub fn new(config: &Config) -> Self {
+        let RetentionConfig {
+            enabled,
+            initial_stability_days,
+            stability_growth,
+            max_stability_days,
+            ..
+        } = config.retention;
+
+        Self {
+            enabled,
+            initial_stability_days,
+            stability_growth: stability_growth.max(1.0),
+            max_stability_days: max_stability_days.max(initial_stability_days),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// 读取记忆的保持状态，未记录或记录无效时以最后访问时间和当前重要性为起点
+    pub fn state(&self, memory: &Memory) -> RetentionState {
+        memory
+            .attributes
+            .custom_attributes
+            .get(RETENTION_ATTRIBUTE)
+            .and_then(|value| serde_json::from_value::<RetentionState>(value.clone()).ok())
+            .unwrap_or(RetentionState {
+                stability_days: self.initial_stability_days,
+                reinforced_at: memory.metadata.last_accessed,
+                base_importance: memory.attributes.importance,
+            })
+    }
+
+    /// 把保持状态写回记忆的自定义属性
+    pub fn store(&self, memory: &mut Memory, state: &RetentionState) {
+        if let Ok(value) = serde_json::to_value(state) {
+            memory
+                .attributes
+                .custom_attributes
+                .insert(RETENTION_ATTRIBUTE.to_string(), value);
+        }
+    }
+
+    /// 保持状态当前的保持率
+    pub fn retention_of(&self, state: &RetentionState) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+        // 强化时间晚于现在（时钟偏差）时视为刚刚强化；稳定度不为正时立即遗忘
+        let elapsed = (Utc::now() - state.reinforced_at).num_seconds().max(0) as f32;
+        let stability = state.stability_days.max(f32::MIN_POSITIVE) * SECONDS_PER_DAY;
+        (-elapsed / stability).exp().clamp(0.0, 1.0)
+    }
+
+    /// 记忆当前的保持率
+    pub fn retention(&self, memory: &Memory) -> f32 {
+        self.retention_of(&self.state(memory))
+    }
+
+    /// 强化记忆：稳定度按强度增长，并从现在开始重新衰减
+    ///
+    /// `strength` 取值0到1，1表示一次完整的复习（检索命中或明确的正向反馈）。
+    /// 记录的稳定度不为正时从初始稳定度重新开始。
+    pub fn reinforce(&self, memory: &mut Memory, strength: f32) -> RetentionState {
+        let mut state = self.state(memory);
+        if state.stability_days.is_nan() || state.stability_days <= 0.0 {
+            state.stability_days = self.initial_stability_days;
+        }
+        let growth = 1.0 + (self.stability_growth - 1.0) * strength.clamp(0.0, 1.0);
+        state.stability_days = (state.stability_days * growth).min(self.max_stability_days);
+        state.reinforced_at = Utc::now();
+        self.store(memory, &state);
+        state
+    }
+
+    /// 以记忆当前的重要性作为新的基础重要性，用于重要性被显式调整之后
+    pub fn rebase(&self, memory: &mut Memory) {
+        let mut state = self.state(memory);
+        state.base_importance = memory.attributes.importance;
+        self.store(memory, &state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemoryAttributes, MemoryType};
+    use chrono::Duration;
+
+    #[test]
+    fn test_retention_decay_and_reinforcement() {
+        let model = RetentionModel::new(&Config::default());
+        let mut memory = Memory::new(
+            "记忆".to_string(),
+            MemoryType::Knowledge,
+            vec![1.0, 0.0],
+            MemoryAttributes {
+                importance: 0.6,
+                ..Default::default()
+            },
+        );
+        assert!(model.retention(&memory) > 0.99);
+
+        // 两天未强化，初始稳定度为一天时保持率约为 e^-2
+        memory.metadata.last_accessed = Utc::now() - Duration::days(2);
+        assert!((model.retention(&memory) - (-2.0f32).exp()).abs() < 1e-3);
+        assert_eq!(model.state(&memory).base_importance, 0.6);
+
+        // 完整强化后稳定度翻倍，同样的间隔衰减得更慢
+        let state = model.reinforce(&mut memory, 1.0);
+        assert_eq!(state.stability_days, 2.0);
+        assert!(model.retention(&memory) > 0.99);
+        let mut stale = model.state(&memory);
+        stale.reinforced_at = Utc::now() - Duration::days(2);
+        assert!((model.retention_of(&stale) - (-1.0f32).exp()).abs() < 1e-3);
+
+        // 半强度强化只增长一半
+        assert_eq!(model.reinforce(&mut memory, 0.5).stability_days, 3.0);
+    }
+
+    fn state(stability_days: f32, elapsed: Duration) -> RetentionState {
+        RetentionState {
+            stability_days,
+            reinforced_at: Utc::now() - elapsed,
+            base_importance: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_retention_decay_boundaries() {
+        let model = RetentionModel::new(&Config::default());
+
+        // 经过一个稳定度时保持率正好为 1/e
+        let one_stability = model.retention_of(&state(3.0, Duration::days(3)));
+        assert!((one_stability - (-1.0f32).exp()).abs() < 1e-3);
+        assert!(model.retention_of(&state(3.0, Duration::zero())) > 0.999);
+
+        // 强化时间在未来时不超过1，很久以前时趋于0但不为负
+        assert_eq!(model.retention_of(&state(1.0, Duration::days(-5))), 1.0);
+        let ancient = model.retention_of(&state(1.0, Duration::days(365 * 100)));
+        assert!((0.0..1e-6).contains(&ancient));
+
+        // 无效的稳定度立即遗忘，而不是永不衰减
+        assert!(model.retention_of(&state(0.0, Duration::hours(1))) < 1e-6);
+        assert!(model.retention_of(&state(f32::NAN, Duration::hours(1))) < 1e-6);
+
+        // 关闭后保持率恒为1
+        let mut config = Config::default();
+        config.retention.enabled = false;
+        let disabled = RetentionModel::new(&config);
+        assert_eq!(disabled.retention_of(&state(1.0, Duration::days(365))), 1.0);
+    }
+
+    #[test]
+    fn test_reinforcement_boundaries() {
+        let mut config = Config::default();
+        config.retention.max_stability_days = 3.0;
+        let model = RetentionModel::new(&config);
+        let mut memory = Memory::new(
+            "记忆".to_string(),
+            MemoryType::Knowledge,
+            vec![1.0, 0.0],
+            MemoryAttributes::default(),
+        );
+
+        // 强度超出0到1时截断，稳定度不超过上限
+        assert_eq!(model.reinforce(&mut memory, -1.0).stability_days, 1.0);
+        assert_eq!(model.reinforce(&mut memory, 5.0).stability_days, 2.0);
+        assert_eq!(model.reinforce(&mut memory, 1.0).stability_days, 3.0);
+        assert_eq!(model.reinforce(&mut memory, 1.0).stability_days, 3.0);
+
+        // 记录的稳定度不为正时保留原值并立即遗忘，强化时从初始稳定度重新开始
+        memory.attributes.custom_attributes.insert(
+            RETENTION_ATTRIBUTE.to_string(),
+            serde_json::json!({
+                "stability_days": 0.0,
+                "reinforced_at": Utc::now() - Duration::hours(1),
+                "base_importance": 0.9
+            }),
+        );
+        assert_eq!(model.state(&memory).stability_days, 0.0);
+        assert_eq!(model.state(&memory).base_importance, 0.9);
+        assert!(model.retention(&memory) < 1e-6);
+        assert_eq!(model.reinforce(&mut memory, 1.0).stability_days, 2.0);
+
+        // 记录无法解析时回到初始状态
+        memory
+            .attributes
+            .custom_attributes
+            .insert(RETENTION_ATTRIBUTE.to_string(), serde_json::json!("broken"));
+        assert_eq!(
+            model.state(&memory).base_importance,
+            memory.attributes.importance
+        );
+
+        // 增长倍数小于1时按1处理，强化不会让稳定度变小
+        config.retention.stability_growth = 0.5;
+        let model = RetentionModel::new(&config);
+        assert_eq!(model.reinforce(&mut memory, 1.0).stability_days, 1.0);
+    }
+}
@@ -2,10 +2,18 @@
 //!
 //! 实现HippoRAG的个性化PageRank算法和多模态检索功能。
 
-use crate::config::Config;
+use crate::config::{Config, RerankMethod, ScoreNormalization};
 use crate::core::*;
-use crate::database::{GraphQueryRequest, VectorGraphDB, VectorQueryRequest};
+use crate::database::VectorGraphDB;
 use crate::error::{MemoryError, Result};
+use crate::fusion::{FusedResult, ResultFuser, ScoreComponent};
+use crate::llm::{LlmProvider, LlmTaskRunner};
+use crate::pipeline::{
+    RetrievalPipeline, RetrievalRequest, GRAPH_GENERATOR, PAGERANK_GENERATOR, SEMANTIC_GENERATOR,
+    TEMPORAL_GENERATOR,
+};
+use crate::query_analysis::{QueryAnalyzer, RetrievalPlan};
+use crate::rerank::{LlmReranker, Reranker};
 use crate::InteractionRecord;
 use crate::PersonalizationVector;
 use chrono::{DateTime, Duration, Utc};
@@ -18,46 +26,17 @@ use tokio::sync::RwLock;
 /// HippoRAG检索引擎
 ///
 /// 实现神经生物学启发的检索算法，包括个性化PageRank和多模态融合。
+/// 检索本身由与记忆管理器共用的 [`RetrievalPipeline`] 完成，
+/// 检索器只负责把检索策略和上下文转换为流水线请求，并维护缓存和用户档案。
 #[derive(Debug)]
 pub struct HippoRAGRetriever {
-    db: Arc<VectorGraphDB>,
     config: Config,
-    pagerank_engine: PageRankEngine,
-    fusion_engine: FusionEngine,
+    pipeline: RetrievalPipeline,
+    query_analyzer: QueryAnalyzer,
     personalization_manager: PersonalizationManager,
     cache: Arc<RwLock<RetrievalCache>>,
 }
 
-/// PageRank引擎
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
-pub struct PageRankEngine {
-    /// 阻尼因子
-    damping_factor: f32,
-    /// 最大迭代次数
-    max_iterations: usize,
-    /// 收敛阈值
-    convergence_threshold: f32,
-    /// 个性化权重
-    personalization_weight: f32,
-}
-
-/// 融合引擎
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
-pub struct FusionEngine {
-    /// 语义权重
-    semantic_weight: f32,
-    /// 时间权重
-    temporal_weight: f32,
-    /// 结构权重
-    structural_weight: f32,
-    /// 重要性权重
-    importance_weight: f32,
-    /// 个性化权重
-    personalization_weight: f32,
-}
-
 /// 个性化管理器
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -146,8 +125,6 @@ pub struct RetrievalPreferenceLearner {
 pub struct RetrievalCache {
     /// 查询缓存
     query_cache: HashMap<String, CachedResult>,
-    /// PageRank缓存
-    pagerank_cache: HashMap<String, HashMap<MemoryId, f32>>,
     /// 个性化缓存
     personalization_cache: PersonalizationVector,
     /// 最大缓存大小
@@ -189,10 +166,14 @@ pub enum RetrievalStrategy {
 /// 融合方法
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FusionMethod {
-    /// 线性加权融合
+    /// 线性加权融合（分数先按检索器归一化）
     LinearWeighted,
-    /// 排名融合
+    /// 倒数排名融合（RRF）
     RankFusion,
+    /// 归一化分数之和
+    CombSum,
+    /// 归一化分数之和乘以命中的检索器数
+    CombMnz,
     /// 学习融合
     LearnedFusion,
     /// 动态融合
@@ -229,6 +210,9 @@ pub struct DetailedExplanation {
     pub reasoning_path: Vec<ReasoningStep>,
     pub confidence: f32,
     pub alternatives: Vec<AlternativeResult>,
+    /// 各阶段的详细轨迹，仅详细解释模式下返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<RetrievalTrace>,
 }
 
 /// 分数分解
@@ -243,6 +227,20 @@ pub struct ScoreBreakdown {
     pub normalization_factor: f32,
 }
 
+impl Default for ScoreBreakdown {
+    fn default() -> Self {
+        Self {
+            semantic_score: 0.0,
+            temporal_score: 0.0,
+            structural_score: 0.0,
+            importance_score: 0.0,
+            personalization_score: 0.0,
+            final_score: 0.0,
+            normalization_factor: 1.0,
+        }
+    }
+}
+
 /// 推理步骤
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReasoningStep {
@@ -260,370 +258,341 @@ pub struct AlternativeResult {
     pub reason_excluded: String,
 }
 
+/// 检索各阶段的轨迹
+///
+/// 排名均从1开始。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetrievalTrace {
+    /// 各检索器返回的候选
+    pub retrievers: Vec<RetrieverTrace>,
+    /// 融合后的候选及其分数分解，只运行单个检索器时为空
+    pub fused: Vec<FusedCandidate>,
+    /// 融合之后各阶段（PageRank、查询扩展、重排序、多样化）前后的排名变化
+    pub stages: Vec<StageTrace>,
+    /// 最终排名
+    pub final_ranking: Vec<RankedCandidate>,
+}
+
+/// 一个检索器返回的候选
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrieverTrace {
+    pub retriever: ScoreComponent,
+    /// 融合权重
+    pub weight: f32,
+    pub candidates: Vec<CandidateScore>,
+}
+
+/// 检索器给出的原始分数和按融合配置归一化后的分数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidateScore {
+    pub memory_id: MemoryId,
+    pub rank: usize,
+    pub raw_score: f32,
+    pub normalized_score: f32,
+}
+
+/// 融合后的候选
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FusedCandidate {
+    pub memory_id: MemoryId,
+    pub rank: usize,
+    pub breakdown: ScoreBreakdown,
+}
+
+/// 一个阶段前后的排名变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageTrace {
+    pub stage: String,
+    pub changes: Vec<RankChange>,
+}
+
+/// 单个结果在一个阶段前后的排名和分数，阶段中新加入的结果没有之前的排名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankChange {
+    pub memory_id: MemoryId,
+    pub before_rank: Option<usize>,
+    pub after_rank: usize,
+    pub score_before: Option<f32>,
+    pub score_after: f32,
+}
+
+/// 最终结果的排名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedCandidate {
+    pub memory_id: MemoryId,
+    pub rank: usize,
+    pub score: f32,
+    pub rerank_score: Option<f32>,
+}
+
+impl RetrievalTrace {
+    /// 记录一个检索器的候选
+    pub(crate) fn record_retriever(
+        &mut self,
+        retriever: ScoreComponent,
+        weight: f32,
+        results: &[RetrievalResult],
+        normalization: ScoreNormalization,
+    ) {
+        let raw: Vec<f32> = results.iter().map(|r| r.relevance_score).collect();
+        let normalized = ResultFuser::normalize(&raw, normalization);
+        let candidates = results
+            .iter()
+            .zip(normalized)
+            .enumerate()
+            .map(|(i, (result, normalized_score))| CandidateScore {
+                memory_id: result.memory.id.clone(),
+                rank: i + 1,
+                raw_score: result.relevance_score,
+                normalized_score,
+            })
+            .collect();
+        self.retrievers.push(RetrieverTrace {
+            retriever,
+            weight,
+            candidates,
+        });
+    }
+
+    /// 记录融合结果
+    pub(crate) fn record_fused(&mut self, fused: &[FusedResult]) {
+        self.fused = fused
+            .iter()
+            .enumerate()
+            .map(|(i, f)| FusedCandidate {
+                memory_id: f.result.memory.id.clone(),
+                rank: i + 1,
+                breakdown: f.breakdown.clone(),
+            })
+            .collect();
+    }
+
+    /// 记录一个阶段前后的排名变化，返回该阶段丢弃的结果
+    pub(crate) fn record_stage(
+        &mut self,
+        stage: &str,
+        before: &[(MemoryId, f32)],
+        after: &[RetrievalResult],
+    ) -> Vec<AlternativeResult> {
+        let mut previous: HashMap<&str, (usize, f32)> = before
+            .iter()
+            .enumerate()
+            .map(|(i, (id, score))| (id.as_str(), (i + 1, *score)))
+            .collect();
+        let changes = after
+            .iter()
+            .enumerate()
+            .map(|(i, result)| {
+                let previous = previous.remove(result.memory.id.as_str());
+                RankChange {
+                    memory_id: result.memory.id.clone(),
+                    before_rank: previous.map(|(rank, _)| rank),
+                    after_rank: i + 1,
+                    score_before: previous.map(|(_, score)| score),
+                    score_after: result.relevance_score,
+                }
+            })
+            .collect();
+        self.stages.push(StageTrace {
+            stage: stage.to_string(),
+            changes,
+        });
+
+        let mut dropped: Vec<AlternativeResult> = previous
+            .into_iter()
+            .map(|(id, (_, score))| AlternativeResult {
+                memory_id: id.to_string(),
+                score,
+                reason_excluded: format!("Dropped by {} stage", stage),
+            })
+            .collect();
+        dropped.sort_by(|a, b| b.score.total_cmp(&a.score));
+        dropped
+    }
+
+    /// 记录最终排名
+    pub(crate) fn record_final(&mut self, results: &[RetrievalResult]) {
+        self.final_ranking = results
+            .iter()
+            .enumerate()
+            .map(|(i, r)| RankedCandidate {
+                memory_id: r.memory.id.clone(),
+                rank: i + 1,
+                score: r.relevance_score,
+                rerank_score: r.explanation.rerank_score,
+            })
+            .collect();
+    }
+}
+
+/// 结果的ID和分数，作为阶段之前的排名快照
+pub(crate) fn rank_snapshot(results: &[RetrievalResult]) -> Vec<(MemoryId, f32)> {
+    results
+        .iter()
+        .map(|r| (r.memory.id.clone(), r.relevance_score))
+        .collect()
+}
+
+/// 排名第一的结果的分数分解
+///
+/// 经过融合的结果取融合时的分数分解，否则由检索解释构造；`final_score` 为最终分数。
+fn top_score_breakdown(results: &[RetrievalResult], trace: &RetrievalTrace) -> ScoreBreakdown {
+    let Some(top) = results.first() else {
+        return ScoreBreakdown::default();
+    };
+    let mut breakdown = trace
+        .fused
+        .iter()
+        .find(|f| f.memory_id == top.memory.id)
+        .map(|f| f.breakdown.clone())
+        .unwrap_or_else(|| ScoreBreakdown {
+            semantic_score: top.explanation.semantic_score,
+            temporal_score: top.explanation.temporal_score,
+            importance_score: top.explanation.importance_score,
+            personalization_score: top.explanation.personalization_score,
+            ..Default::default()
+        });
+    breakdown.final_score = top.relevance_score;
+    breakdown
+}
+
 impl HippoRAGRetriever {
     /// 创建新的HippoRAG检索器
     pub async fn new(db: Arc<VectorGraphDB>, config: Config) -> Result<Self> {
-        let pagerank_engine = PageRankEngine::new(&config);
-        let fusion_engine = FusionEngine::new(&config);
+        let pipeline = RetrievalPipeline::new(db, &config)?;
+        pipeline.reload_ranking_model().await?;
+        let query_analyzer = QueryAnalyzer::new(&config);
         let personalization_manager = PersonalizationManager::new(&config)?;
+        let cache = RetrievalCache::new(1000);
 
         Ok(Self {
-            db,
             config,
-            pagerank_engine,
-            fusion_engine,
+            pipeline,
+            query_analyzer,
             personalization_manager,
-            cache: Arc::new(RwLock::new(RetrievalCache::new(1000))),
+            cache: Arc::new(RwLock::new(cache)),
         })
     }
 
-    /// 执行HippoRAG检索
-    pub async fn retrieve(
-        &mut self,
-        query: &Query,
-        context: &RetrievalContext,
-        strategy: &RetrievalStrategy,
-    ) -> Result<Vec<RetrievalResult>> {
-        info!("Starting HippoRAG retrieval with strategy: {:?}", strategy);
-
-        // 检查缓存
-        if let Some(cached) = self.check_cache(query, context).await? {
-            debug!("Returning cached results");
-            return Ok(cached);
+    /// 使用大语言模型生成查询改写，配置了大语言模型重排序时同时替换重排序器
+    pub fn with_llm_provider(mut self, provider: Arc<dyn LlmProvider>) -> Self {
+        if self.config.rerank.method == RerankMethod::Llm {
+            let reranker = Arc::new(LlmReranker::new(provider.clone(), &self.config));
+            self = self.with_reranker(reranker);
         }
+        let runner = LlmTaskRunner::new(provider, &self.config.llm);
+        self.pipeline = self.pipeline.with_llm_runner(runner);
+        self
+    }
 
-        let results = match strategy {
-            RetrievalStrategy::Semantic => self.semantic_retrieval(query, context).await?,
-            RetrievalStrategy::Structural => self.structural_retrieval(query, context).await?,
-            RetrievalStrategy::Temporal => self.temporal_retrieval(query, context).await?,
-            RetrievalStrategy::Personalized => self.personalized_retrieval(query, context).await?,
-            RetrievalStrategy::Hybrid {
-                strategies,
-                weights,
-            } => {
-                self.hybrid_retrieval(query, context, strategies, weights)
-                    .await?
-            }
-            RetrievalStrategy::HippoRAG {
-                use_pagerank,
-                use_personalization,
-                fusion_method,
-            } => {
-                self.hippocampus_retrieval(
-                    query,
-                    context,
-                    *use_pagerank,
-                    *use_personalization,
-                    fusion_method,
-                )
-                .await?
-            }
-        };
-
-        // 缓存结果
-        self.cache_results(query, context, &results).await?;
+    /// 替换重排序器（例如换成基于大语言模型的重排序器）
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.pipeline = self.pipeline.with_reranker(reranker);
+        self
+    }
 
-        // 记录交互
-        self.record_interaction(query, &results).await?;
+    /// 替换检索流水线（例如注册自定义的候选生成器、过滤器或后处理器）
+    pub fn with_pipeline(mut self, pipeline: RetrievalPipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
 
-        info!("Retrieved {} results", results.len());
-        Ok(results)
+    /// 重新加载学习融合使用的排序模型，返回是否存在已训练的模型
+    pub async fn reload_ranking_model(&self) -> Result<bool> {
+        self.pipeline.reload_ranking_model().await
     }
 
-    /// 语义检索
-    async fn semantic_retrieval(
-        &self,
+    /// 执行HippoRAG检索
+    pub async fn retrieve(
+        &mut self,
         query: &Query,
         context: &RetrievalContext,
+        strategy: &RetrievalStrategy,
     ) -> Result<Vec<RetrievalResult>> {
-        debug!("Performing semantic retrieval");
-
-        // 生成查询嵌入
-        let query_embedding = self.generate_query_embedding(&query.text).await?;
-
-        // 构建向量查询请求
-        let vector_request = VectorQueryRequest {
-            query_vector: query_embedding,
-            limit: Some(context.constraints.max_results.unwrap_or(50)),
-            threshold: context.constraints.min_relevance,
-            filters: self.build_metadata_filters(&context.constraints)?,
-        };
-
-        // 执行向量检索
-        let vector_results = self.db.query_vectors(&vector_request).await?;
-
-        // 转换为检索结果
-        let mut results = Vec::new();
-        for vector_result in vector_results {
-            let memory = self.vector_to_memory(&vector_result.vector)?;
-
-            // 保存需要的值，避免移动后访问
-            let importance_score = memory.attributes.importance;
-
-            let _explanation = DetailedExplanation {
-                strategy_used: RetrievalStrategy::Semantic,
-                score_breakdown: ScoreBreakdown {
-                    semantic_score: vector_result.similarity,
-                    temporal_score: 0.0,
-                    structural_score: 0.0,
-                    importance_score,
-                    personalization_score: 0.0,
-                    final_score: vector_result.similarity,
-                    normalization_factor: 1.0,
-                },
-                reasoning_path: vec![ReasoningStep {
-                    step_type: "semantic_matching".to_string(),
-                    description: format!("Vector similarity: {:.3}", vector_result.similarity),
-                    score_impact: vector_result.similarity,
-                    confidence: 0.9,
-                }],
-                confidence: vector_result.similarity,
-                alternatives: Vec::new(),
-            };
-
-            results.push(RetrievalResult {
-                memory,
-                relevance_score: vector_result.similarity,
-                explanation: RetrievalExplanation {
-                    semantic_score: vector_result.similarity,
-                    temporal_score: 0.0,
-                    importance_score,
-                    personalization_score: 0.0,
-                    connection_paths: Vec::new(),
-                    reasoning: format!("Semantic similarity: {:.3}", vector_result.similarity),
-                },
-            });
-        }
-
+        let (results, _) = self
+            .retrieve_with_explanation(query, context, strategy)
+            .await?;
         Ok(results)
     }
 
-    /// 结构化检索（基于图遍历）
-    async fn structural_retrieval(
-        &self,
+    /// 执行HippoRAG检索，同时返回检索过程的解释
+    pub async fn retrieve_with_explanation(
+        &mut self,
         query: &Query,
         context: &RetrievalContext,
-    ) -> Result<Vec<RetrievalResult>> {
-        debug!("Performing structural retrieval");
-
-        // 首先找到种子节点
-        let seed_nodes = if !context.recent_queries.is_empty() {
-            // 基于最近查询找到相关节点
-            self.find_seed_nodes_from_queries(&context.recent_queries)
-                .await?
-        } else {
-            // 使用语义检索找到初始节点
-            let semantic_results = self.semantic_retrieval(query, context).await?;
-            semantic_results
-                .into_iter()
-                .take(5)
-                .map(|r| r.memory.id)
-                .collect()
-        };
-
-        if seed_nodes.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        // 构建图查询请求
-        let graph_request = GraphQueryRequest {
-            start_nodes: seed_nodes,
-            edge_types: None, // 考虑所有边类型
-            max_depth: Some(self.config.graph.traversal.max_depth),
-            limit: Some(context.constraints.max_results.unwrap_or(50)),
-            filters: self.build_graph_filters(&context.constraints)?,
-        };
-
-        // 执行图遍历
-        let graph_results = self.db.query_graph(&graph_request).await?;
-
-        // 计算结构化分数
-        let mut results = Vec::new();
-        for (i, node) in graph_results.nodes.iter().enumerate() {
-            let memory = self.node_to_memory(node)?;
-
-            // 基于图距离计算结构分数
-            let structural_score = self.calculate_structural_score(i, &graph_results.paths)?;
-
-            // 保存需要的值，避免移动后访问
-            let importance_score = memory.attributes.importance;
-
-            let _explanation = DetailedExplanation {
-                strategy_used: RetrievalStrategy::Structural,
-                score_breakdown: ScoreBreakdown {
-                    semantic_score: 0.0,
-                    temporal_score: 0.0,
-                    structural_score,
-                    importance_score,
-                    personalization_score: 0.0,
-                    final_score: structural_score,
-                    normalization_factor: 1.0,
-                },
-                reasoning_path: vec![ReasoningStep {
-                    step_type: "graph_traversal".to_string(),
-                    description: format!("Graph distance score: {:.3}", structural_score),
-                    score_impact: structural_score,
-                    confidence: 0.8,
-                }],
-                confidence: structural_score,
-                alternatives: Vec::new(),
-            };
-
-            results.push(RetrievalResult {
-                memory,
-                relevance_score: structural_score,
-                explanation: RetrievalExplanation {
-                    semantic_score: 0.0,
-                    temporal_score: 0.0,
-                    importance_score,
-                    personalization_score: 0.0,
-                    connection_paths: graph_results.paths.clone(),
-                    reasoning: format!("Structural relevance: {:.3}", structural_score),
-                },
-            });
-        }
-
-        // 按分数排序
-        results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
-
-        Ok(results)
+        strategy: &RetrievalStrategy,
+    ) -> Result<(Vec<RetrievalResult>, DetailedExplanation)> {
+        self.retrieve_traced(query, context, strategy, false).await
     }
 
-    /// 时间感知检索
-    async fn temporal_retrieval(
-        &self,
+    /// 执行HippoRAG检索，返回带各阶段详细轨迹的解释
+    ///
+    /// 解释的 `trace` 记录各检索器的候选、归一化和融合后的分数、融合之后各阶段的排名变化
+    /// 以及最终排名，被过滤或丢弃的结果记录在 `alternatives` 中。为得到完整的轨迹，
+    /// 该模式不读取缓存。
+    pub async fn retrieve_detailed(
+        &mut self,
         query: &Query,
         context: &RetrievalContext,
-    ) -> Result<Vec<RetrievalResult>> {
-        debug!("Performing temporal retrieval");
-
-        // 首先执行语义检索获取候选
-        let mut semantic_results = self.semantic_retrieval(query, context).await?;
-
-        // 获取时间窗口
-        let time_window = context
-            .time_window
-            .unwrap_or((Utc::now() - Duration::days(30), Utc::now()));
-
-        // 重新计算时间相关性分数
-        for result in &mut semantic_results {
-            let temporal_score = self
-                .calculate_temporal_relevance(&result.memory, &time_window, &query.text)
-                .await?;
-
-            // 融合语义和时间分数
-            let fused_score = result.relevance_score * self.fusion_engine.semantic_weight
-                + temporal_score * self.fusion_engine.temporal_weight;
-
-            result.relevance_score = fused_score;
-            result.explanation.temporal_score = temporal_score;
-            result.explanation.reasoning = format!(
-                "Temporal-semantic fusion: semantic={:.3}, temporal={:.3}",
-                result.explanation.semantic_score, temporal_score
-            );
-        }
-
-        // 重新排序
-        semantic_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
-
-        Ok(semantic_results)
+        strategy: &RetrievalStrategy,
+    ) -> Result<(Vec<RetrievalResult>, DetailedExplanation)> {
+        self.retrieve_traced(query, context, strategy, true).await
     }
 
-    /// 个性化检索
-    async fn personalized_retrieval(
-        &self,
+    async fn retrieve_traced(
+        &mut self,
         query: &Query,
         context: &RetrievalContext,
-    ) -> Result<Vec<RetrievalResult>> {
-        debug!("Performing personalized retrieval");
-
-        // 获取用户档案
-        let user_profile = self
-            .personalization_manager
-            .get_user_profile()?;
-
-        // 执行个性化PageRank
-        let pagerank_scores = self
-            .pagerank_engine
-            .compute_personalized_pagerank(&user_profile, &query.text, &self.db)
-            .await?;
+        strategy: &RetrievalStrategy,
+        detailed: bool,
+    ) -> Result<(Vec<RetrievalResult>, DetailedExplanation)> {
+        info!("Starting HippoRAG retrieval with strategy: {:?}", strategy);
+        let mut explanation = DetailedExplanation {
+            strategy_used: strategy.clone(),
+            score_breakdown: ScoreBreakdown::default(),
+            reasoning_path: Vec::new(),
+            confidence: 1.0,
+            alternatives: Vec::new(),
+            trace: None,
+        };
 
-        // 获取语义候选
-        let semantic_results = self.semantic_retrieval(query, context).await?;
-
-        // 融合个性化分数
-        let mut personalized_results = Vec::new();
-        for semantic_result in semantic_results {
-            let pagerank_score = pagerank_scores
-                .get(&semantic_result.memory.id)
-                .unwrap_or(&0.0);
-
-            let personalization_score = self
-                .calculate_personalization_score(&semantic_result.memory, &user_profile)
-                .await?;
-
-            let fused_score = semantic_result.relevance_score * self.fusion_engine.semantic_weight
-                + pagerank_score * self.fusion_engine.structural_weight
-                + personalization_score * self.fusion_engine.personalization_weight;
-
-            // 保存需要的值，避免移动后访问
-            let importance_score = semantic_result.memory.attributes.importance;
-            let semantic_score = semantic_result.explanation.semantic_score;
-
-            let _explanation = DetailedExplanation {
-                strategy_used: RetrievalStrategy::Personalized,
-                score_breakdown: ScoreBreakdown {
-                    semantic_score,
-                    temporal_score: 0.0,
-                    structural_score: *pagerank_score,
-                    importance_score,
-                    personalization_score,
-                    final_score: fused_score,
-                    normalization_factor: 1.0,
-                },
-                reasoning_path: vec![
-                    ReasoningStep {
-                        step_type: "personalization".to_string(),
-                        description: format!(
-                            "User preference alignment: {:.3}",
-                            personalization_score
-                        ),
-                        score_impact: personalization_score,
-                        confidence: 0.85,
-                    },
-                    ReasoningStep {
-                        step_type: "pagerank".to_string(),
-                        description: format!("Personalized PageRank: {:.3}", pagerank_score),
-                        score_impact: *pagerank_score,
-                        confidence: 0.9,
-                    },
-                ],
-                confidence: fused_score,
-                alternatives: Vec::new(),
-            };
+        // 检查缓存
+        if !detailed {
+            if let Some(cached) = self.check_cache(query, context).await? {
+                debug!("Returning cached results");
+                explanation.reasoning_path.push(ReasoningStep {
+                    step_type: "cache".to_string(),
+                    description: format!("Returned {} cached results", cached.len()),
+                    score_impact: 0.0,
+                    confidence: 1.0,
+                });
+                explanation.score_breakdown =
+                    top_score_breakdown(&cached, &RetrievalTrace::default());
+                return Ok((cached, explanation));
+            }
+        }
 
-            personalized_results.push(RetrievalResult {
-                memory: semantic_result.memory,
-                relevance_score: fused_score,
-                explanation: RetrievalExplanation {
-                    semantic_score,
-                    temporal_score: 0.0,
-                    importance_score,
-                    personalization_score,
-                    connection_paths: Vec::new(),
-                    reasoning: format!(
-                        "Personalized score: semantic={:.3}, pagerank={:.3}, preference={:.3}",
-                        semantic_score, pagerank_score, personalization_score
-                    ),
-                },
-            });
+        let (request, confidence) =
+            self.build_request(query, context, strategy, &mut explanation.reasoning_path)?;
+        explanation.confidence = confidence;
+        let output = self.pipeline.run(request).await?;
+
+        let results = output.results;
+        explanation.reasoning_path.extend(output.steps);
+        explanation.alternatives = output.alternatives;
+        explanation.score_breakdown = top_score_breakdown(&results, &output.trace);
+        if detailed {
+            explanation.trace = Some(output.trace);
         }
 
-        // 排序
-        personalized_results
-            .sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
+        // 缓存结果
+        self.cache_results(query, context, &results).await?;
 
-        Ok(personalized_results)
+        // 记录交互
+        self.record_interaction(query, &results).await?;
+
+        info!("Retrieved {} results", results.len());
+        Ok((results, explanation))
     }
 
     /// 混合检索
@@ -634,43 +603,16 @@ impl HippoRAGRetriever {
         strategies: &[RetrievalStrategy],
         weights: &[f32],
     ) -> Result<Vec<RetrievalResult>> {
-        debug!(
-            "Performing hybrid retrieval with {} strategies",
-            strategies.len()
-        );
-
-        if strategies.len() != weights.len() {
-            return Err(MemoryError::validation_error(
-                "Strategies and weights must have the same length",
-            ));
-        }
-
-        // 执行各种检索策略
-        let mut all_results = Vec::new();
-        for (strategy, weight) in strategies.iter().zip(weights.iter()) {
-            let strategy_results = match strategy {
-                RetrievalStrategy::Semantic => self.semantic_retrieval(query, context).await?,
-                RetrievalStrategy::Structural => self.structural_retrieval(query, context).await?,
-                RetrievalStrategy::Temporal => self.temporal_retrieval(query, context).await?,
-                RetrievalStrategy::Personalized => {
-                    self.personalized_retrieval(query, context).await?
-                }
-                _ => continue, // 跳过嵌套的混合策略
-            };
-
-            all_results.push((strategy_results, *weight));
-        }
-
-        // 融合结果
-        let fused_results = self
-            .fusion_engine
-            .fuse_results(all_results, FusionMethod::LinearWeighted)
-            .await?;
-
-        Ok(fused_results)
+        let strategy = RetrievalStrategy::Hybrid {
+            strategies: strategies.to_vec(),
+            weights: weights.to_vec(),
+        };
+        self.run_strategy(query, context, &strategy).await
     }
 
     /// HippoRAG完整检索
+    ///
+    /// 动态融合按查询意图生成检索计划，其他融合方法按配置权重运行全部检索器。
     pub async fn hippocampus_retrieval(
         &self,
         query: &Query,
@@ -679,132 +621,145 @@ impl HippoRAGRetriever {
         use_personalization: bool,
         fusion_method: &FusionMethod,
     ) -> Result<Vec<RetrievalResult>> {
-        info!("Performing full HippoRAG retrieval");
-
-        // 第一阶段：多模态检索
-        let semantic_results = self.semantic_retrieval(query, context).await?;
-        let structural_results = self.structural_retrieval(query, context).await?;
-        let temporal_results = self.temporal_retrieval(query, context).await?;
-
-        let mut all_results = vec![
-            (semantic_results, self.fusion_engine.semantic_weight),
-            (structural_results, self.fusion_engine.structural_weight),
-            (temporal_results, self.fusion_engine.temporal_weight),
-        ];
-
-        // 第二阶段：个性化（如果启用）
-        if use_personalization {
-            let personalized_results = self.personalized_retrieval(query, context).await?;
-            all_results.push((
-                personalized_results,
-                self.fusion_engine.personalization_weight,
-            ));
-        }
-
-        // 第三阶段：融合
-        let mut fused_results = self
-            .fusion_engine
-            .fuse_results(all_results, fusion_method.clone())
-            .await?;
-
-        // 第四阶段：PageRank重排序（如果启用）
-        if use_pagerank {
-            fused_results = self
-                .apply_pagerank_reranking(fused_results, query, context)
-                .await?;
-        }
-
-        // 第五阶段：后处理和解释生成
-        for result in &mut fused_results {
-            result.explanation.reasoning = format!(
-                "HippoRAG retrieval: semantic={:.3}, structural={:.3}, temporal={:.3}, personalization={:.3}",
-                result.explanation.semantic_score,
-                0.0, // structural score would be computed
-                result.explanation.temporal_score,
-                result.explanation.personalization_score
-            );
-        }
-
-        Ok(fused_results)
+        let strategy = RetrievalStrategy::HippoRAG {
+            use_pagerank,
+            use_personalization,
+            fusion_method: fusion_method.clone(),
+        };
+        self.run_strategy(query, context, &strategy).await
     }
 
-    /// 应用PageRank重排序
-    async fn apply_pagerank_reranking(
+    /// 不经缓存运行一个检索策略
+    async fn run_strategy(
         &self,
-        mut results: Vec<RetrievalResult>,
         query: &Query,
-        _context: &RetrievalContext,
+        context: &RetrievalContext,
+        strategy: &RetrievalStrategy,
     ) -> Result<Vec<RetrievalResult>> {
-        debug!("Applying PageRank reranking");
-
-        // 构建子图
-        let memory_ids: Vec<MemoryId> = results.iter().map(|r| r.memory.id.clone()).collect();
-        let _subgraph = self.build_subgraph(&memory_ids).await?;
-
-        // 计算PageRank分数
-        let user_profile = self
-            .personalization_manager
-            .get_user_profile()?;
-        let pagerank_scores = self.pagerank_engine
-            .compute_personalized_pagerank(&user_profile, &query.text, &self.db)
-            .await?;
-
-        // 重新计算分数
-        for result in &mut results {
-            if let Some(pagerank_score) = pagerank_scores.get(&result.memory.id) {
-                result.relevance_score = result.relevance_score * 0.7 + pagerank_score * 0.3;
-            }
-        }
-
-        // 重新排序
-        results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
-
-        Ok(results)
+        let (request, _) = self.build_request(query, context, strategy, &mut Vec::new())?;
+        let output = self.pipeline.run(request).await?;
+        Ok(output.results)
     }
 
-    // 辅助方法
-
-    async fn generate_query_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        use model2vec_rs::model::StaticModel;
-        use std::sync::OnceLock;
-
-        // 使用静态变量缓存模型，避免重复加载
-        static MODEL: OnceLock<Option<StaticModel>> = OnceLock::new();
-
-        let model = MODEL.get_or_init(|| {
-            match StaticModel::from_pretrained(
-                "minishlab/potion-multilingual-128M", // 使用多语言模型
-                None,                                 // 无需 HF token
-                None,                                 // 使用模型默认的归一化设置
-                None,                                 // 无子文件夹
-            ) {
-                Ok(m) => Some(m),
-                Err(e) => {
-                    eprintln!("Failed to load embedding model: {}", e);
-                    None
+    /// 把检索策略和上下文转换为流水线请求
+    ///
+    /// 返回请求和查询意图的置信度，查询分析和跳过的检索器记入推理步骤。
+    fn build_request(
+        &self,
+        query: &Query,
+        context: &RetrievalContext,
+        strategy: &RetrievalStrategy,
+        steps: &mut Vec<ReasoningStep>,
+    ) -> Result<(RetrievalRequest, f32)> {
+        let mut request = RetrievalRequest::new(query.clone());
+        request.session_id = context.session_id.clone();
+        let mut confidence = 1.0;
+        match strategy {
+            RetrievalStrategy::Hybrid {
+                strategies,
+                weights,
+            } => {
+                if strategies.len() != weights.len() {
+                    return Err(MemoryError::validation_error(
+                        "Strategies and weights must have the same length",
+                    ));
                 }
+                // 跳过嵌套的混合策略
+                request.generators = strategies
+                    .iter()
+                    .zip(weights)
+                    .filter_map(|(strategy, weight)| {
+                        strategy_generator(strategy).map(|name| (name.to_string(), *weight))
+                    })
+                    .collect();
             }
-        });
-
-        if let Some(model) = model {
-            // 生成嵌入向量
-            let sentences = vec![text.to_string()];
-            let embeddings = model.encode(&sentences);
-
-            if embeddings.is_empty() {
-                return Err(MemoryError::Internal {
-                    message: "Failed to generate embedding: empty result".to_string(),
+            RetrievalStrategy::HippoRAG {
+                use_pagerank,
+                use_personalization,
+                fusion_method,
+            } => {
+                // 意图分析使用原始查询，时间短语由流水线去掉
+                let plan = self.plan_for(query, fusion_method);
+                confidence = plan.confidence;
+                steps.push(ReasoningStep {
+                    step_type: "query_analysis".to_string(),
+                    description: if plan.cues.is_empty() {
+                        format!("Intent {:?}", plan.intent)
+                    } else {
+                        format!("Intent {:?} (cues: {})", plan.intent, plan.cues.join(", "))
+                    },
+                    score_impact: 0.0,
+                    confidence: plan.confidence,
                 });
+
+                // 按计划运行各检索器，个性化检索还需要调用方启用
+                let mut generators = Vec::new();
+                for component in [
+                    ScoreComponent::Semantic,
+                    ScoreComponent::Structural,
+                    ScoreComponent::Temporal,
+                    ScoreComponent::Personalization,
+                ] {
+                    let enabled =
+                        component != ScoreComponent::Personalization || *use_personalization;
+                    match plan.weight(component).filter(|_| enabled) {
+                        Some(weight) => {
+                            generators.push((component_generator(component).to_string(), weight))
+                        }
+                        None => steps.push(ReasoningStep {
+                            step_type: "skip_retriever".to_string(),
+                            description: format!("Skipped {} retrieval", component.name()),
+                            score_impact: 0.0,
+                            confidence: plan.confidence,
+                        }),
+                    }
+                }
+                if *use_pagerank && !plan.use_pagerank {
+                    steps.push(ReasoningStep {
+                        step_type: "skip_pagerank".to_string(),
+                        description: format!("Skipped PageRank for {:?} query", plan.intent),
+                        score_impact: 0.0,
+                        confidence: plan.confidence,
+                    });
+                }
+                request.generators = generators;
+                request.fusion = fusion_method.clone();
+                request.pagerank = *use_pagerank && plan.use_pagerank;
             }
+            strategy => {
+                let name = strategy_generator(strategy).unwrap_or(SEMANTIC_GENERATOR);
+                request.generators = vec![(name.to_string(), 1.0)];
+            }
+        }
 
-            Ok(embeddings[0].clone())
-        } else {
-            Err(MemoryError::Internal {
-                message: "Embedding model not initialized".to_string(),
-            })
+        let constraints = &context.constraints;
+        if request.query.filters.time_range.is_none() {
+            request.query.filters.time_range = constraints.time_range;
         }
+        request.time_window = context.time_window;
+        request.candidate_limit = constraints.max_results.or(request.candidate_limit);
+        request.min_similarity = constraints.min_relevance;
+        request.required_tags = constraints.required_tags.clone();
+        request.excluded_tags = constraints.excluded_tags.clone();
+        request.sources = constraints.source_filter.clone();
+
+        let profile = self.personalization_manager.get_user_profile()?;
+        request.preferences = profile.preferences;
+        request.topic_interests = profile.topic_interests;
+
+        Ok((request, confidence))
     }
 
+    /// 生成检索计划
+    fn plan_for(&self, query: &Query, fusion_method: &FusionMethod) -> RetrievalPlan {
+        match fusion_method {
+            FusionMethod::DynamicFusion => self.query_analyzer.analyze(&query.text),
+            _ => self.query_analyzer.fixed_plan(),
+        }
+    }
+
+    // 辅助方法
+
     async fn check_cache(
         &self,
         query: &Query,
@@ -877,468 +832,26 @@ impl HippoRAGRetriever {
 
         Ok(())
     }
-
-    // 转换方法
-
-    fn vector_to_memory(&self, vector: &crate::database::Vector) -> Result<Memory> {
-        // 从向量元数据构建记忆对象
-        let metadata = &vector.metadata;
-        
-        // 提取必要字段，使用默认值处理缺失字段
-        let content = metadata.get("content")
-            .and_then(|v| v.as_str())
-            .unwrap_or("").to_string();
-            
-        let memory_type_str = metadata.get("memory_type")
-            .and_then(|v| v.as_str())
-            .unwrap_or("Knowledge");
-            
-        let memory_type = match memory_type_str {
-            "Knowledge" => MemoryType::Knowledge,
-            "Event" => MemoryType::Event,
-            "Task" => MemoryType::Task,
-            "Conversation" => MemoryType::Conversation,
-            "Reflection" => MemoryType::Reflection,
-            "Goal" => MemoryType::Goal,
-            "Habit" => MemoryType::Habit,
-            "Emotion" => MemoryType::Emotion,
-            _ => MemoryType::Knowledge,
-        };
-        
-        // 构建记忆属性
-        let attributes = MemoryAttributes {
-            keywords: metadata.get("keywords")
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-                .unwrap_or_default(),
-            tags: metadata.get("tags")
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-                .unwrap_or_default(),
-            context: metadata.get("context")
-                .and_then(|v| v.as_str())
-                .unwrap_or("").to_string(),
-            importance: metadata.get("importance")
-                .and_then(|v| v.as_f64())
-                .map(|v| v as f32)
-                .unwrap_or(0.5),
-            emotion: metadata.get("emotion")
-                .and_then(|v| v.as_str())
-                .map(String::from),
-            source: metadata.get("source")
-                .and_then(|v| v.as_str())
-                .map(String::from),
-            confidence: metadata.get("confidence")
-                .and_then(|v| v.as_f64())
-                .map(|v| v as f32)
-                .unwrap_or(0.8),
-            language: metadata.get("language")
-                .and_then(|v| v.as_str())
-                .map(String::from),
-            custom_attributes: HashMap::new(),
-        };
-        
-        // 构建记忆连接
-        let connections = MemoryConnections::default();
-        
-        // 构建记忆元数据
-        let memory_metadata = MemoryMetadata {
-            created_at: vector.created_at,
-            updated_at: vector.updated_at,
-            access_count: metadata.get("access_count")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0),
-            last_accessed: metadata.get("last_accessed")
-                .and_then(|v| v.as_str())
-                .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or(vector.updated_at),
-            version: metadata.get("version")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as u32)
-                .unwrap_or(1),
-
-            is_deleted: metadata.get("is_deleted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false),
-            deleted_at: None,
-            custom_metadata: HashMap::new(),
-        };
-        
-        // 构建记忆对象
-        Ok(Memory {
-            id: vector.id.clone(),
-            content,
-            memory_type,
-            embedding: vector.embedding.clone(),
-            attributes,
-            connections,
-            metadata: memory_metadata,
-        })
-    }
-
-    fn node_to_memory(&self, node: &crate::database::GraphNode) -> Result<Memory> {
-        // 从图节点构建记忆对象
-        let properties = &node.properties;
-        
-        // 提取必要字段
-        let content = properties.get("content")
-            .and_then(|v| v.as_str())
-            .unwrap_or("").to_string();
-            
-        let memory_type_str = node.node_type.as_str();
-        let memory_type = match memory_type_str {
-            "Knowledge" => MemoryType::Knowledge,
-            "Event" => MemoryType::Event,
-            "Task" => MemoryType::Task,
-            "Conversation" => MemoryType::Conversation,
-            "Reflection" => MemoryType::Reflection,
-            "Goal" => MemoryType::Goal,
-            "Habit" => MemoryType::Habit,
-            "Emotion" => MemoryType::Emotion,
-            _ => MemoryType::Knowledge,
-        };
-        
-        // 构建记忆属性
-        let attributes = MemoryAttributes {
-            keywords: properties.get("keywords")
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-                .unwrap_or_default(),
-            tags: properties.get("tags")
-                .and_then(|v| v.as_array())
-                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-                .unwrap_or_default(),
-            context: properties.get("context")
-                .and_then(|v| v.as_str())
-                .unwrap_or("").to_string(),
-            importance: properties.get("importance")
-                .and_then(|v| v.as_f64())
-                .map(|v| v as f32)
-                .unwrap_or(0.5),
-            emotion: properties.get("emotion")
-                .and_then(|v| v.as_str())
-                .map(String::from),
-            source: properties.get("source")
-                .and_then(|v| v.as_str())
-                .map(String::from),
-            confidence: properties.get("confidence")
-                .and_then(|v| v.as_f64())
-                .map(|v| v as f32)
-                .unwrap_or(0.8),
-            language: properties.get("language")
-                .and_then(|v| v.as_str())
-                .map(String::from),
-            custom_attributes: HashMap::new(),
-        };
-        
-        // 构建记忆连接
-        let connections = MemoryConnections::default();
-        
-        // 构建记忆元数据
-        let memory_metadata = MemoryMetadata {
-            created_at: node.created_at,
-            updated_at: node.updated_at,
-            access_count: properties.get("access_count")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0),
-            last_accessed: properties.get("last_accessed")
-                .and_then(|v| v.as_str())
-                .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or(node.updated_at),
-            version: properties.get("version")
-                .and_then(|v| v.as_u64())
-                .map(|v| v as u32)
-                .unwrap_or(1),
-
-            is_deleted: properties.get("is_deleted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false),
-            deleted_at: None,
-            custom_metadata: HashMap::new(),
-        };
-        
-        // 构建记忆对象
-        Ok(Memory {
-            id: node.id.clone(),
-            content,
-            memory_type,
-            embedding: vec![0.0; 256], // 使用默认向量，因为图节点没有嵌入向量
-            attributes,
-            connections,
-            metadata: memory_metadata,
-        })
-    }
-
-    fn build_metadata_filters(
-        &self,
-        constraints: &RetrievalConstraints,
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        let mut filters = HashMap::new();
-
-        if !constraints.required_tags.is_empty() {
-            filters.insert(
-                "required_tags".to_string(),
-                serde_json::Value::Array(
-                    constraints
-                        .required_tags
-                        .iter()
-                        .map(|t| serde_json::Value::String(t.clone()))
-                        .collect(),
-                ),
-            );
-        }
-
-        if !constraints.excluded_tags.is_empty() {
-            filters.insert(
-                "excluded_tags".to_string(),
-                serde_json::Value::Array(
-                    constraints
-                        .excluded_tags
-                        .iter()
-                        .map(|t| serde_json::Value::String(t.clone()))
-                        .collect(),
-                ),
-            );
-        }
-
-        if filters.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(filters))
-        }
-    }
-
-    fn build_graph_filters(
-        &self,
-        constraints: &RetrievalConstraints,
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        self.build_metadata_filters(constraints)
-    }
-
-    async fn find_seed_nodes_from_queries(&self, _queries: &[String]) -> Result<Vec<MemoryId>> {
-        // 简化实现：返回空向量
-        Ok(Vec::new())
-    }
-
-    fn calculate_structural_score(&self, position: usize, _paths: &[Vec<MemoryId>]) -> Result<f32> {
-        // 基于位置的简单评分
-        Ok(1.0 / (position as f32 + 1.0))
-    }
-
-    async fn calculate_temporal_relevance(
-        &self,
-        memory: &Memory,
-        time_window: &(DateTime<Utc>, DateTime<Utc>),
-        _query: &str,
-    ) -> Result<f32> {
-        let memory_time = memory.metadata.created_at;
-        if memory_time >= time_window.0 && memory_time <= time_window.1 {
-            let window_duration = time_window.1 - time_window.0;
-            let memory_offset = memory_time - time_window.0;
-            Ok(1.0 - (memory_offset.num_seconds() as f32 / window_duration.num_seconds() as f32))
-        } else {
-            Ok(0.0)
-        }
-    }
-
-    async fn calculate_personalization_score(
-        &self,
-        memory: &Memory,
-        user_profile: &UserProfile,
-    ) -> Result<f32> {
-        let mut score = 0.0;
-
-        // 基于用户偏好计算分数
-        for (preference, weight) in &user_profile.preferences {
-            if memory
-                .content
-                .to_lowercase()
-                .contains(&preference.to_lowercase())
-            {
-                score += weight;
-            }
-        }
-
-        // 基于主题兴趣
-        for (topic, interest) in &user_profile.topic_interests {
-            if memory
-                .attributes
-                .context
-                .to_lowercase()
-                .contains(&topic.to_lowercase())
-            {
-                score += interest * 0.5;
-            }
-        }
-
-        Ok(score.min(1.0))
-    }
-
-    async fn build_subgraph(
-        &self,
-        memory_ids: &[MemoryId],
-    ) -> Result<HashMap<MemoryId, Vec<MemoryId>>> {
-        // 简化的子图构建
-        let mut subgraph = HashMap::new();
-        for id in memory_ids {
-            subgraph.insert(id.clone(), Vec::new());
-        }
-        Ok(subgraph)
-    }
 }
 
-// 实现各个组件
-
-impl PageRankEngine {
-    fn new(config: &Config) -> Self {
-        Self {
-            damping_factor: config.graph.pagerank.damping_factor,
-            max_iterations: config.graph.pagerank.max_iterations,
-            convergence_threshold: config.graph.pagerank.convergence_threshold,
-            personalization_weight: config.graph.pagerank.personalization_weight,
-        }
-    }
-
-    async fn compute_personalized_pagerank(
-        &self,
-        user_profile: &UserProfile,
-        query: &str,
-        _db: &VectorGraphDB,
-    ) -> Result<HashMap<MemoryId, f32>> {
-        // 简化的个性化PageRank实现
-        let mut scores = HashMap::new();
-
-        // 基于用户偏好生成个性化向量
-        for (preference, weight) in &user_profile.preferences {
-            if query.contains(preference) {
-                scores.insert(format!("memory_{}", preference), *weight);
-            }
-        }
-
-        Ok(scores)
-    }
-
-    async fn compute_standard_pagerank(
-        &self,
-        graph: &HashMap<MemoryId, Vec<MemoryId>>,
-    ) -> Result<HashMap<MemoryId, f32>> {
-        let mut scores = HashMap::new();
-        let node_count = graph.len() as f32;
-
-        // 初始化分数
-        for node in graph.keys() {
-            scores.insert(node.clone(), 1.0 / node_count);
-        }
-
-        // 迭代计算PageRank
-        for _ in 0..self.max_iterations {
-            let mut new_scores = HashMap::new();
-
-            for (node, neighbors) in graph {
-                let mut score = (1.0 - self.damping_factor) / node_count;
-
-                for neighbor in neighbors {
-                    if let Some(neighbor_score) = scores.get(neighbor) {
-                        let neighbor_out_degree =
-                            graph.get(neighbor).map(|n| n.len()).unwrap_or(1) as f32;
-                        score += self.damping_factor * neighbor_score / neighbor_out_degree;
-                    }
-                }
-
-                new_scores.insert(node.clone(), score);
-            }
-
-            // 检查收敛
-            let mut converged = true;
-            for (node, new_score) in &new_scores {
-                if let Some(old_score) = scores.get(node) {
-                    if (new_score - old_score).abs() > self.convergence_threshold {
-                        converged = false;
-                        break;
-                    }
-                }
-            }
-
-            scores = new_scores;
-
-            if converged {
-                break;
-            }
-        }
-
-        Ok(scores)
+/// 单一检索策略对应的候选生成器，混合策略没有对应的生成器
+fn strategy_generator(strategy: &RetrievalStrategy) -> Option<&'static str> {
+    match strategy {
+        RetrievalStrategy::Semantic => Some(SEMANTIC_GENERATOR),
+        RetrievalStrategy::Structural => Some(GRAPH_GENERATOR),
+        RetrievalStrategy::Temporal => Some(TEMPORAL_GENERATOR),
+        RetrievalStrategy::Personalized => Some(PAGERANK_GENERATOR),
+        RetrievalStrategy::Hybrid { .. } | RetrievalStrategy::HippoRAG { .. } => None,
     }
 }
 
-impl FusionEngine {
-    fn new(config: &Config) -> Self {
-        Self {
-            semantic_weight: config.retrieval.fusion_weights.semantic_weight,
-            temporal_weight: config.retrieval.fusion_weights.temporal_weight,
-            structural_weight: config.retrieval.fusion_weights.structural_weight,
-            importance_weight: config.retrieval.fusion_weights.importance_weight,
-            personalization_weight: config.retrieval.fusion_weights.personalization_weight,
-        }
-    }
-
-    async fn fuse_results(
-        &self,
-        results_list: Vec<(Vec<RetrievalResult>, f32)>,
-        fusion_method: FusionMethod,
-    ) -> Result<Vec<RetrievalResult>> {
-        match fusion_method {
-            FusionMethod::LinearWeighted => self.linear_weighted_fusion(results_list).await,
-            FusionMethod::RankFusion => self.rank_fusion(results_list).await,
-            FusionMethod::LearnedFusion => self.learned_fusion(results_list).await,
-            FusionMethod::DynamicFusion => self.dynamic_fusion(results_list).await,
-        }
-    }
-
-    async fn linear_weighted_fusion(
-        &self,
-        results_list: Vec<(Vec<RetrievalResult>, f32)>,
-    ) -> Result<Vec<RetrievalResult>> {
-        let mut fused_results = HashMap::new();
-
-        for (results, weight) in results_list {
-            for result in results {
-                let entry = fused_results
-                    .entry(result.memory.id.clone())
-                    .or_insert_with(|| result.clone());
-                entry.relevance_score += result.relevance_score * weight;
-            }
-        }
-
-        let mut final_results: Vec<RetrievalResult> = fused_results.into_values().collect();
-        final_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
-
-        Ok(final_results)
-    }
-
-    async fn rank_fusion(
-        &self,
-        results_list: Vec<(Vec<RetrievalResult>, f32)>,
-    ) -> Result<Vec<RetrievalResult>> {
-        // 简化的排名融合实现
-        self.linear_weighted_fusion(results_list).await
-    }
-
-    async fn learned_fusion(
-        &self,
-        results_list: Vec<(Vec<RetrievalResult>, f32)>,
-    ) -> Result<Vec<RetrievalResult>> {
-        // 简化的学习融合实现
-        self.linear_weighted_fusion(results_list).await
-    }
-
-    async fn dynamic_fusion(
-        &self,
-        results_list: Vec<(Vec<RetrievalResult>, f32)>,
-    ) -> Result<Vec<RetrievalResult>> {
-        // 简化的动态融合实现
-        self.linear_weighted_fusion(results_list).await
+/// 检索计划中的检索器对应的候选生成器
+fn component_generator(component: ScoreComponent) -> &'static str {
+    match component {
+        ScoreComponent::Semantic | ScoreComponent::Importance => SEMANTIC_GENERATOR,
+        ScoreComponent::Structural => GRAPH_GENERATOR,
+        ScoreComponent::Temporal => TEMPORAL_GENERATOR,
+        ScoreComponent::Personalization => PAGERANK_GENERATOR,
     }
 }
 
@@ -1391,7 +904,6 @@ impl RetrievalCache {
     fn new(max_size: usize) -> Self {
         Self {
             query_cache: HashMap::new(),
-            pagerank_cache: HashMap::new(),
             personalization_cache: PersonalizationVector::default(),
             max_cache_size: max_size,
         }
@@ -1429,6 +941,7 @@ impl RetrievalCache {
 mod tests {
     use super::*;
     use crate::database::VectorGraphDB;
+    use crate::pipeline::{apply_filters, CandidateFilter, TimeRangeFilter};
     use tempfile::NamedTempFile;
     use uuid::Uuid;
 
@@ -1450,7 +963,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_semantic_retrieval() {
-        let retriever = create_test_retriever().await;
+        let mut retriever = create_test_retriever().await;
 
         let query = Query {
             text: "人工智能".to_string(),
@@ -1460,6 +973,7 @@ mod tests {
             offset: None,
             sort_by: None,
             weights: QueryWeights::default(),
+            expansion: None,
         };
 
         let context = RetrievalContext {
@@ -1479,7 +993,7 @@ mod tests {
         };
 
         let results = retriever
-            .semantic_retrieval(&query, &context)
+            .retrieve(&query, &context, &RetrievalStrategy::Semantic)
             .await
             .unwrap();
         // 由于没有实际数据，结果应该为空
@@ -1498,6 +1012,7 @@ mod tests {
             offset: None,
             sort_by: None,
             weights: QueryWeights::default(),
+            expansion: None,
         };
 
         let context = RetrievalContext {
@@ -1564,4 +1079,84 @@ mod tests {
         let profile = manager.get_user_profile().unwrap();
         assert_eq!(profile.interaction_history.len(), 1);
     }
+
+    fn result(id: &str, score: f32, created_at: DateTime<Utc>) -> RetrievalResult {
+        let mut result = RetrievalResult::for_test(id, score);
+        result.memory.metadata.created_at = created_at;
+        result
+    }
+
+    #[test]
+    fn test_retrieval_trace() {
+        let now = Utc::now();
+        let old = now - Duration::days(10);
+        let results = vec![
+            result("a", 1.0, now),
+            result("b", 0.5, old),
+            result("c", 0.0, now),
+        ];
+
+        let mut trace = RetrievalTrace::default();
+        trace.record_retriever(
+            ScoreComponent::Semantic,
+            0.5,
+            &results,
+            ScoreNormalization::MinMax,
+        );
+        let normalized: Vec<f32> = trace.retrievers[0]
+            .candidates
+            .iter()
+            .map(|c| c.normalized_score)
+            .collect();
+        assert_eq!(normalized, vec![1.0, 0.5, 0.0]);
+
+        // 时间范围外的结果记为替代结果
+        let filters: Vec<Arc<dyn CandidateFilter>> = vec![Arc::new(TimeRangeFilter)];
+        let request = RetrievalRequest::new(Query {
+            text: "x".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters {
+                time_range: Some((now - Duration::days(1), now)),
+                ..Default::default()
+            },
+            limit: None,
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+            expansion: None,
+        });
+        let (kept, excluded) = apply_filters(&filters, &request, results);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(excluded[0].memory_id, "b");
+        assert!(excluded[0].reason_excluded.contains("outside time range"));
+
+        // 阶段前后的排名变化，被丢弃的结果返回为替代结果
+        let before = rank_snapshot(&kept);
+        let mut after = vec![kept[1].clone(), result("d", 0.2, now)];
+        after[0].explanation.rerank_score = Some(0.8);
+        let dropped = trace.record_stage("rerank", &before, &after);
+        assert_eq!(dropped[0].memory_id, "a");
+        let changes = &trace.stages[0].changes;
+        assert_eq!(changes[0].before_rank, Some(2));
+        assert_eq!(changes[0].after_rank, 1);
+        assert_eq!(changes[1].before_rank, None);
+
+        trace.record_final(&after);
+        assert_eq!(trace.final_ranking[0].rerank_score, Some(0.8));
+        let breakdown = top_score_breakdown(&after, &trace);
+        assert_eq!(breakdown.final_score, 0.0);
+
+        let explanation = DetailedExplanation {
+            strategy_used: RetrievalStrategy::Semantic,
+            score_breakdown: breakdown,
+            reasoning_path: Vec::new(),
+            confidence: 1.0,
+            alternatives: excluded,
+            trace: Some(trace),
+        };
+        let json = serde_json::to_value(&explanation).unwrap();
+        assert_eq!(json["trace"]["retrievers"][0]["retriever"], "semantic");
+        assert_eq!(json["trace"]["stages"][0]["changes"][1]["memory_id"], "d");
+        assert_eq!(json["alternatives"][0]["memory_id"], "b");
+    }
 }
@@ -0,0 +1,355 @@
+//! 中文分词模块
+//!
+//! 基于词典的正向最大匹配分词，词典未覆盖的汉字串退化为相邻字二元组，
+//! 英文和数字按字母数字串切分并转为小写。关键词提取、Jaccard相似度、
+//! 主题社区和近重复检测统一使用这里的分词结果和停用词过滤。
+//! 本模块只负责分词，不建立全文检索索引：检索候选来自向量相似度、
+//! 时间、标签和图结构。
+//!
+//! 自定义词典只作用于按该配置创建的分词器：`MemoryManager` 持有自己的
+//! 分词器并传给它创建的组件，`TextUtils` 的函数始终使用默认配置。
+
+use crate::config::SegmentationConfig;
+use crate::error::Result;
+use crate::utils::TextUtils;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+
+/// 内置常用词词典，以空白分隔
+const BUILTIN_DICTIONARY: &str =
+    "今天 昨天 明天 去年 今年 明年 上午 下午 晚上 早上 周末 时间 时候 现在 以前 以后 最近 已经 \
+     正在 一个 一些 这些 那些 这个 那个 什么 怎么 为什么 如何 可以 需要 应该 能够 没有 所有 具有 \
+     有效 有关 有趣 非常 比较 特别 因为 所以 如果 但是 然后 虽然 而且 或者 以及 因此 导致 由于 \
+     结果 我们 你们 他们 她们 它们 自己 大家 用户 朋友 家人 同事 问题 方法 原理 基本 基础 概念 \
+     重要 主要 分支 领域 内容 信息 知识 经验 能力 效率 质量 速度 结构 系统 功能 目标 计划 任务 \
+     习惯 情绪 感觉 心情 想法 反思 总结 记录 记忆 学习 课程 考试 复习 阅读 读书 笔记 教程 练习 \
+     研究 论文 工作 项目 会议 开会 报告 客户 老板 团队 公司 加班 演示 面试 完成 开始 结束 讨论 \
+     安排 提交 发布 上线 需求 方案 技术 编程 代码 程序 软件 硬件 开发 测试 部署 调试 优化 算法 \
+     数据 数据库 查询 索引 缓存 服务器 接口 框架 架构 网络 性能 响应 延迟 内存 存储 向量 模型 \
+     训练 推理 计算机 互联网 人工智能 机器学习 深度学习 神经网络 卷积 卷积神经网络 自然语言 \
+     自然语言处理 大模型 语言模型 知识图谱 检索 搜索 推荐 生活 家庭 旅行 旅游 电影 音乐 做饭 吃饭 \
+     购物 天气 下雨 健康 身体 运动 跑步 健身 睡眠 睡觉 医生 医院 感冒 饮食 开心 高兴 快乐 满意 \
+     喜欢 兴奋 期待 难过 伤心 失望 生气 愤怒 担心 焦虑 害怕 成功 失败 压力 放松";
+
+/// 内置停用词
+const BUILTIN_STOP_WORDS: &[&str] = &[
+    "the", "a", "an", "and", "or", "but", "in", "on", "at", "to", "for", "of", "with", "by", "is",
+    "are", "was", "were", "be", "been", "have", "has", "had", "do", "does", "did", "will", "would",
+    "could", "should", "that", "this", "from", "then", "our", "的", "了", "在", "是", "我", "你",
+    "他", "她", "它", "们", "这", "那", "有", "和", "与", "或", "但", "也", "都", "就", "被", "把",
+    "及", "等", "很", "吗", "呢", "吧", "啊", "着", "过", "地", "得", "如果", "因为", "所以",
+    "然后", "现在", "一个", "多个", "包含", "还有", "我们", "你们", "他们", "这个", "那个", "这些",
+    "那些",
+];
+
+/// 分词结果的词元类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// 词典中的词
+    Word,
+    /// 词典未覆盖时的相邻字二元组
+    Bigram,
+    /// 单个汉字
+    Char,
+    /// 英文单词或数字（已转为小写）
+    Alphanumeric,
+}
+
+/// 词元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    pub kind: TokenKind,
+}
+
+/// 中文分词器
+#[derive(Clone)]
+pub struct Segmenter {
+    dictionary: HashSet<String>,
+    stop_words: HashSet<String>,
+    max_word_length: usize,
+}
+
+static SHARED_SEGMENTER: OnceLock<Arc<Segmenter>> = OnceLock::new();
+
+impl Segmenter {
+    /// 按配置创建分词器，会读取配置中的自定义词典文件
+    pub fn new(config: &SegmentationConfig) -> Result<Self> {
+        let mut segmenter = Self::without_file(config);
+        if let Some(path) = &config.dictionary_path {
+            let content = std::fs::read_to_string(path)?;
+            for line in content.lines() {
+                let line = line.trim();
+                if line.starts_with('#') {
+                    continue;
+                }
+                if let Some(word) = line.split_whitespace().next() {
+                    segmenter.add_word(word);
+                }
+            }
+        }
+        Ok(segmenter)
+    }
+
+    fn without_file(config: &SegmentationConfig) -> Self {
+        let mut segmenter = Self {
+            dictionary: HashSet::new(),
+            stop_words: BUILTIN_STOP_WORDS
+                .iter()
+                .map(|w| w.to_string())
+                .chain(config.stop_words.iter().map(|w| w.to_lowercase()))
+                .collect(),
+            max_word_length: config.max_word_length.max(2),
+        };
+        if config.builtin_dictionary {
+            for word in BUILTIN_DICTIONARY.split_whitespace() {
+                segmenter.add_word(word);
+            }
+        }
+        for word in &config.custom_dictionary {
+            segmenter.add_word(word);
+        }
+        segmenter
+    }
+
+    /// 添加词典词，超过最大词长的词会相应放宽最大词长
+    pub fn add_word(&mut self, word: &str) {
+        let word = word.trim().to_lowercase();
+        if word.is_empty() {
+            return;
+        }
+        self.max_word_length = self.max_word_length.max(word.chars().count());
+        self.dictionary.insert(word);
+    }
+
+    /// 是否为词典词
+    pub fn contains_word(&self, word: &str) -> bool {
+        self.dictionary.contains(word)
+    }
+
+    /// 是否为停用词
+    pub fn is_stop_word(&self, token: &str) -> bool {
+        self.stop_words.contains(token)
+    }
+
+    /// 切分文本，保留停用词，丢弃标点和空白
+    pub fn segment(&self, text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut cjk: Vec<char> = Vec::new();
+        let mut word = String::new();
+
+        for c in text.chars() {
+            if TextUtils::is_cjk(c) {
+                self.flush_word(&mut word, &mut tokens);
+                cjk.push(c);
+            } else if c.is_alphanumeric() {
+                self.flush_cjk(&mut cjk, &mut tokens);
+                word.extend(c.to_lowercase());
+            } else {
+                self.flush_cjk(&mut cjk, &mut tokens);
+                self.flush_word(&mut word, &mut tokens);
+            }
+        }
+        self.flush_cjk(&mut cjk, &mut tokens);
+        self.flush_word(&mut word, &mut tokens);
+        tokens
+    }
+
+    /// 切分文本并去除停用词，用于关键词提取和相似度计算
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        self.segment(text)
+            .into_iter()
+            .filter(|token| !self.is_stop_word(&token.text))
+            .map(|token| token.text)
+            .collect()
+    }
+
+    fn flush_word(&self, word: &mut String, tokens: &mut Vec<Token>) {
+        if !word.is_empty() {
+            tokens.push(Token {
+                text: std::mem::take(word),
+                kind: TokenKind::Alphanumeric,
+            });
+        }
+    }
+
+    /// 正向最大匹配；未匹配的连续汉字在遇到词典词或单字停用词时作为一段输出
+    fn flush_cjk(&self, chars: &mut Vec<char>, tokens: &mut Vec<Token>) {
+        let mut unmatched_start = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            let longest = self.max_word_length.min(chars.len() - i);
+            let matched = (1..=longest)
+                .rev()
+                .map(|len| (len, chars[i..i + len].iter().collect::<String>()))
+                .find(|(_, word)| self.dictionary.contains(word));
+
+            if let Some((len, word)) = matched {
+                Self::push_unmatched(&chars[unmatched_start..i], tokens);
+                tokens.push(Token {
+                    text: word,
+                    kind: TokenKind::Word,
+                });
+                i += len;
+                unmatched_start = i;
+            } else if self.stop_words.contains(&chars[i].to_string()) {
+                Self::push_unmatched(&chars[unmatched_start..i], tokens);
+                tokens.push(Token {
+                    text: chars[i].to_string(),
+                    kind: TokenKind::Char,
+                });
+                i += 1;
+                unmatched_start = i;
+            } else {
+                i += 1;
+            }
+        }
+        Self::push_unmatched(&chars[unmatched_start..], tokens);
+        chars.clear();
+    }
+
+    /// 未登录的汉字串：单字原样输出，多字拆成相邻字二元组
+    fn push_unmatched(chars: &[char], tokens: &mut Vec<Token>) {
+        if chars.len() == 1 {
+            tokens.push(Token {
+                text: chars[0].to_string(),
+                kind: TokenKind::Char,
+            });
+        }
+        for pair in chars.windows(2) {
+            tokens.push(Token {
+                text: pair.iter().collect(),
+                kind: TokenKind::Bigram,
+            });
+        }
+    }
+
+    /// 提取关键词
+    ///
+    /// 去除停用词后，词典词和长度大于2的英文单词记1分，未登录汉字串的
+    /// 二元组记0.5分，单字不作为关键词；结果按得分排序，得分相同时按
+    /// 首次出现的顺序。
+    pub fn extract_keywords(&self, text: &str, max_keywords: usize) -> Vec<String> {
+        // 词 -> (得分, 首次出现位置)
+        let mut word_freq: HashMap<String, (f32, usize)> = HashMap::new();
+        for token in self.segment(text) {
+            if self.is_stop_word(&token.text) {
+                continue;
+            }
+            let score = match token.kind {
+                TokenKind::Word => 1.0,
+                TokenKind::Alphanumeric if token.text.len() > 2 => 1.0,
+                TokenKind::Bigram => 0.5,
+                _ => continue,
+            };
+            let next = word_freq.len();
+            word_freq.entry(token.text).or_insert((0.0, next)).0 += score;
+        }
+
+        let mut keywords: Vec<(String, (f32, usize))> = word_freq.into_iter().collect();
+        keywords.sort_by(|a, b| b.1 .0.total_cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+
+        keywords
+            .into_iter()
+            .take(max_keywords)
+            .map(|(word, _)| word)
+            .collect()
+    }
+
+    /// 默认配置的共享分词器，不受任何自定义分词配置影响
+    pub fn shared() -> Arc<Segmenter> {
+        SHARED_SEGMENTER
+            .get_or_init(|| Arc::new(Segmenter::default()))
+            .clone()
+    }
+}
+
+impl std::fmt::Debug for Segmenter {
+    // 词典可能很大，只输出词数
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Segmenter")
+            .field("dictionary", &self.dictionary.len())
+            .field("stop_words", &self.stop_words.len())
+            .field("max_word_length", &self.max_word_length)
+            .finish()
+    }
+}
+
+impl Default for Segmenter {
+    fn default() -> Self {
+        Self::without_file(&SegmentationConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(tokens: &[Token]) -> Vec<&str> {
+        tokens.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_maximum_matching_and_bigram_fallback() {
+        let segmenter = Segmenter::default();
+        let tokens = segmenter.segment("今天学习了卷积神经网络(CNN)的基本原理");
+        assert_eq!(
+            texts(&tokens),
+            vec![
+                "今天",
+                "学习",
+                "了",
+                "卷积神经网络",
+                "cnn",
+                "的",
+                "基本",
+                "原理"
+            ]
+        );
+        assert_eq!(tokens[3].kind, TokenKind::Word);
+        assert_eq!(tokens[4].kind, TokenKind::Alphanumeric);
+
+        // 未登录词退化为二元组
+        let tokens = segmenter.segment("量子纠缠");
+        assert_eq!(texts(&tokens), vec!["量子", "子纠", "纠缠"]);
+        assert!(tokens.iter().all(|t| t.kind == TokenKind::Bigram));
+
+        // 不使用词典时全部按二元组切分，停用词单独成词
+        let config = SegmentationConfig {
+            builtin_dictionary: false,
+            ..Default::default()
+        };
+        let segmenter = Segmenter::new(&config).unwrap();
+        assert_eq!(
+            segmenter.tokenize("机器学习的原理"),
+            vec!["机器", "器学", "学习", "原理"]
+        );
+    }
+
+    #[test]
+    fn test_custom_dictionary_and_stop_words() {
+        let path = std::env::temp_dir().join(format!("dict-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "# 自定义词典\n量子纠缠 10 n\n\n").unwrap();
+
+        let config = SegmentationConfig {
+            custom_dictionary: vec!["小红书".to_string()],
+            dictionary_path: Some(path.to_string_lossy().to_string()),
+            stop_words: vec!["今天".to_string()],
+            ..Default::default()
+        };
+        let segmenter = Segmenter::new(&config).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            segmenter.tokenize("今天在小红书看到量子纠缠"),
+            vec!["小红书", "看到", "量子纠缠"]
+        );
+        assert!(segmenter.contains_word("量子纠缠"));
+
+        let missing = SegmentationConfig {
+            dictionary_path: Some("/nonexistent/dict.txt".to_string()),
+            ..Default::default()
+        };
+        assert!(Segmenter::new(&missing).is_err());
+    }
+}
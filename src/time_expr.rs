@@ -0,0 +1,1022 @@
+//! 时间表达式解析模块
+//!
+//! 从查询文本中识别中英文时间表达式（“昨天”“明天”“上周三”“周末”“三天前”
+//! “2025年3月”“2025-03-05”、“last month”“last weekend”“two days ago”
+//! “on 5 March 2025”等），按参考时间和时区解析为时间范围，并从查询中去掉该短语，
+//! 剩余文本用于语义检索。
+
+use crate::config::{Config, TimeParsingConfig};
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, Months, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use serde::{Deserialize, Serialize};
+
+/// 时间范围的粒度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeGranularity {
+    Day,
+    Week,
+    /// 周六到周日
+    Weekend,
+    Month,
+    Year,
+    /// 截至参考时间的一段时间，如“最近三天”
+    Span,
+}
+
+/// 解析出的时间表达式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeExpression {
+    /// 查询中的原始短语
+    pub text: String,
+    pub start: DateTime<Utc>,
+    /// 结束时间（含）
+    pub end: DateTime<Utc>,
+    pub granularity: TimeGranularity,
+}
+
+/// 时间表达式解析结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedTimeQuery {
+    pub expression: TimeExpression,
+    /// 去掉时间短语后的查询文本
+    pub remaining: String,
+}
+
+impl TimeExpression {
+    /// 时间范围（含两端）
+    pub fn range(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        (self.start, self.end)
+    }
+}
+
+/// 时间窗口内的时间相关性
+///
+/// 窗口内从起点的0.5线性增长到终点的1.0，越新的记忆越相关；窗口外为0。
+pub fn window_relevance(time: DateTime<Utc>, window: &(DateTime<Utc>, DateTime<Utc>)) -> f32 {
+    let (start, end) = *window;
+    if time < start || time > end {
+        return 0.0;
+    }
+    let duration = (end - start).num_seconds();
+    if duration <= 0 {
+        return 1.0;
+    }
+    1.0 - 0.5 * (end - time).num_seconds() as f32 / duration as f32
+}
+
+/// 相对时间的单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    fn granularity(self) -> TimeGranularity {
+        match self {
+            Unit::Day => TimeGranularity::Day,
+            Unit::Week => TimeGranularity::Week,
+            Unit::Month => TimeGranularity::Month,
+            Unit::Year => TimeGranularity::Year,
+        }
+    }
+}
+
+/// 候选匹配，位置按字符计，范围为本地时间的左闭右开区间
+#[derive(Debug, Clone)]
+struct Candidate {
+    start: usize,
+    end: usize,
+    range: (NaiveDateTime, NaiveDateTime),
+    granularity: TimeGranularity,
+}
+
+/// 固定的中文时间词及其对应的相对时间
+const CHINESE_FIXED: &[(&str, Unit, i32)] = &[
+    ("大后天", Unit::Day, 3),
+    ("后天", Unit::Day, 2),
+    ("明天", Unit::Day, 1),
+    ("明日", Unit::Day, 1),
+    ("大前天", Unit::Day, -3),
+    ("前天", Unit::Day, -2),
+    ("昨天", Unit::Day, -1),
+    ("昨日", Unit::Day, -1),
+    ("今天", Unit::Day, 0),
+    ("今日", Unit::Day, 0),
+    ("上上个月", Unit::Month, -2),
+    ("上个月", Unit::Month, -1),
+    ("上月", Unit::Month, -1),
+    ("这个月", Unit::Month, 0),
+    ("本月", Unit::Month, 0),
+    ("下个月", Unit::Month, 1),
+    ("下月", Unit::Month, 1),
+];
+
+/// 固定的英文时间短语及其对应的相对时间
+const ENGLISH_FIXED: &[(&str, Unit, i32)] = &[
+    ("the day after tomorrow", Unit::Day, 2),
+    ("tomorrow", Unit::Day, 1),
+    ("the day before yesterday", Unit::Day, -2),
+    ("yesterday", Unit::Day, -1),
+    ("today", Unit::Day, 0),
+    ("last week", Unit::Week, -1),
+    ("this week", Unit::Week, 0),
+    ("next week", Unit::Week, 1),
+    ("last month", Unit::Month, -1),
+    ("this month", Unit::Month, 0),
+    ("next month", Unit::Month, 1),
+    ("last year", Unit::Year, -1),
+    ("this year", Unit::Year, 0),
+    ("next year", Unit::Year, 1),
+];
+
+/// 中文周的写法及相对本周的偏移，偏移为空时表示不带修饰的“周三”
+const CHINESE_WEEKS: &[(&str, Option<i32>)] = &[
+    ("上上周", Some(-2)),
+    ("上上个星期", Some(-2)),
+    ("上周", Some(-1)),
+    ("上星期", Some(-1)),
+    ("上个星期", Some(-1)),
+    ("上礼拜", Some(-1)),
+    ("上个礼拜", Some(-1)),
+    ("本周", Some(0)),
+    ("这周", Some(0)),
+    ("这星期", Some(0)),
+    ("这个星期", Some(0)),
+    ("这个礼拜", Some(0)),
+    ("下周", Some(1)),
+    ("下星期", Some(1)),
+    ("下个星期", Some(1)),
+    ("下礼拜", Some(1)),
+    ("下个礼拜", Some(1)),
+    ("周", None),
+    ("星期", None),
+    ("礼拜", None),
+];
+
+/// 中文相对时间的单位写法
+const CHINESE_UNITS: &[(&str, Unit)] = &[
+    ("天", Unit::Day),
+    ("日", Unit::Day),
+    ("个星期", Unit::Week),
+    ("个礼拜", Unit::Week),
+    ("星期", Unit::Week),
+    ("周", Unit::Week),
+    ("个月", Unit::Month),
+    ("年", Unit::Year),
+];
+
+const ENGLISH_MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const ENGLISH_WEEKDAYS: &[&str] = &[
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// 跟在时间词后时说明该词是句子主语的动词，如“today is fine”
+const ENGLISH_SUBJECT_VERBS: &[&str] = &["is", "was", "will", "would", "works"];
+
+const ENGLISH_NUMBERS: &[&str] = &[
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve",
+];
+
+/// 时间表达式解析器
+#[derive(Debug, Clone)]
+pub struct TimeExpressionParser {
+    offset: FixedOffset,
+    recent_days: u32,
+}
+
+impl TimeExpressionParser {
+    pub fn new(config: &Config) -> Self {
+        let TimeParsingConfig {
+            utc_offset_minutes,
+            recent_days,
+            ..
+        } = config.time_parsing;
+
+        Self {
+            offset: FixedOffset::east_opt(utc_offset_minutes * 60)
+                .unwrap_or(FixedOffset::east_opt(0).unwrap()),
+            recent_days: recent_days.max(1),
+        }
+    }
+
+    /// 使用指定的时区
+    pub fn with_offset(mut self, offset: FixedOffset) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// 解析查询中的时间表达式，多个候选时取最长的一个
+    pub fn parse(&self, text: &str, reference: DateTime<Utc>) -> Option<ParsedTimeQuery> {
+        // 只转换ASCII大小写，字节位置与原文一致
+        let lower = text.to_ascii_lowercase();
+        let chars: Vec<char> = lower.chars().collect();
+        let now = reference.with_timezone(&self.offset).naive_local();
+
+        let best = (0..chars.len())
+            .flat_map(|i| self.candidates_at(&chars, i, now))
+            .max_by(|a, b| {
+                (a.end - a.start)
+                    .cmp(&(b.end - b.start))
+                    .then(b.start.cmp(&a.start))
+            })?;
+
+        let byte = |i: usize| text.char_indices().nth(i).map_or(text.len(), |(b, _)| b);
+        let (start, mut end) = (byte(best.start), byte(best.end));
+        let phrase = text[start..end].to_string();
+        // “上周三的会议”“yesterday's notes”去掉时间短语后不保留“的”和所有格
+        if let Some(suffix) = ["的", "'s", "’s"]
+            .iter()
+            .find(|s| lower[end..].starts_with(**s))
+        {
+            end += suffix.len();
+        }
+        let remaining = format!("{} {}", &text[..start], &text[end..]);
+        let remaining = remaining
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_matches(|c: char| c.is_whitespace() || ",，。.?？".contains(c))
+            .to_string();
+
+        let (local_start, local_end) = best.range;
+        let end_time = if best.granularity == TimeGranularity::Span {
+            reference
+        } else {
+            self.to_utc(local_end) - chrono::Duration::nanoseconds(1)
+        };
+
+        Some(ParsedTimeQuery {
+            expression: TimeExpression {
+                text: phrase,
+                start: self.to_utc(local_start),
+                end: end_time,
+                granularity: best.granularity,
+            },
+            remaining,
+        })
+    }
+
+    fn to_utc(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        self.offset
+            .from_local_datetime(&local)
+            .single()
+            .map_or_else(|| Utc.from_utc_datetime(&local), |t| t.with_timezone(&Utc))
+    }
+
+    fn candidates_at(&self, chars: &[char], i: usize, now: NaiveDateTime) -> Vec<Candidate> {
+        let today = now.date();
+        let mut found = Vec::new();
+
+        for (phrase, unit, n) in CHINESE_FIXED {
+            if let Some(end) = match_phrase(chars, i, phrase) {
+                found.extend(point(i, end, today, *unit, *n));
+            }
+        }
+        for (phrase, unit, n) in ENGLISH_FIXED {
+            // “I said today is fine”中的“today”不是查询的时间条件
+            if let Some(end) = match_phrase(chars, i, phrase).filter(|end| !is_subject(chars, *end))
+            {
+                found.extend(point(i, end, today, *unit, *n));
+            }
+        }
+        found.extend(self.chinese_week(chars, i, today));
+        found.extend(self.chinese_relative(chars, i, now));
+        found.extend(chinese_date(chars, i, today));
+        found.extend(self.english_relative(chars, i, now));
+        found.extend(english_weekday(chars, i, today));
+        found.extend(english_date(chars, i, today));
+        found.extend(iso_date(chars, i));
+        found
+    }
+
+    /// “上周”“上周三”“周日”“上周末”“周末”
+    fn chinese_week(&self, chars: &[char], i: usize, today: NaiveDate) -> Option<Candidate> {
+        CHINESE_WEEKS.iter().find_map(|(prefix, offset)| {
+            let end = match_phrase(chars, i, prefix)?;
+            if prefix.ends_with('周') && chars.get(end) == Some(&'末') {
+                return Some(weekend(i, end + 1, today, *offset));
+            }
+            let weekday = chars.get(end).and_then(|c| chinese_weekday(*c));
+            match (offset, weekday) {
+                (Some(n), Some(weekday)) => {
+                    let monday = monday_of(today) + chrono::Duration::weeks(*n as i64);
+                    let day = monday + Days::new(weekday.num_days_from_monday() as u64);
+                    Some(day_candidate(i, end + 1, day))
+                }
+                (Some(n), None) => point(i, end, today, Unit::Week, *n),
+                (None, Some(weekday)) => Some(day_candidate(
+                    i,
+                    end + 1,
+                    last_weekday(today, weekday, true),
+                )),
+                (None, None) => None,
+            }
+        })
+    }
+
+    /// “三天前”“两个月之前”“三天后”“最近三天”“过去两周”“最近”
+    fn chinese_relative(&self, chars: &[char], i: usize, now: NaiveDateTime) -> Option<Candidate> {
+        let prefix_end = ["最近", "过去", "近"]
+            .iter()
+            .find_map(|p| match_phrase(chars, i, p));
+        let number_start = prefix_end.unwrap_or(i);
+
+        let parsed = parse_chinese_number(chars, number_start).and_then(|(n, j)| {
+            let (unit, k) = CHINESE_UNITS
+                .iter()
+                .find_map(|(u, unit)| match_phrase(chars, j, u).map(|k| (*unit, k)))?;
+            Some((n, unit, k))
+        });
+
+        match (prefix_end, parsed) {
+            (Some(_), Some((n, unit, end))) => span(i, end, now, unit, n),
+            (None, Some((n, unit, end))) => {
+                if let Some(end) = ["以前", "之前", "前"]
+                    .iter()
+                    .find_map(|p| match_phrase(chars, end, p))
+                {
+                    return point(i, end, now.date(), unit, -i32::try_from(n).ok()?);
+                }
+                let end = ["以后", "之后", "后"]
+                    .iter()
+                    .find_map(|p| match_phrase(chars, end, p))?;
+                point(i, end, now.date(), unit, i32::try_from(n).ok()?)
+            }
+            (Some(end), None) if match_phrase(chars, i, "最近").is_some() => {
+                span(i, end, now, Unit::Day, self.recent_days)
+            }
+            _ => None,
+        }
+    }
+
+    /// “last 3 days”“past two weeks”“two days ago”“in three days”“recently”
+    fn english_relative(&self, chars: &[char], i: usize, now: NaiveDateTime) -> Option<Candidate> {
+        let (word, j) = word_at(chars, i)?;
+        if word == "recently" || word == "lately" {
+            return span(i, j, now, Unit::Day, self.recent_days);
+        }
+        if word == "in" {
+            let (n, k) = english_number(chars, skip_spaces(chars, j))?;
+            let (unit, end) = english_unit(chars, skip_spaces(chars, k))?;
+            return point(i, end, now.date(), unit, i32::try_from(n).ok()?);
+        }
+        if word == "last" || word == "past" {
+            let (n, k) = english_number(chars, skip_spaces(chars, j))?;
+            let (unit, end) = english_unit(chars, skip_spaces(chars, k))?;
+            return span(i, end, now, unit, n);
+        }
+
+        let (n, _) = english_number(chars, i)?;
+        let (unit, k) = english_unit(chars, skip_spaces(chars, j))?;
+        let (ago, end) = word_at(chars, skip_spaces(chars, k))?;
+        if ago != "ago" {
+            return None;
+        }
+        point(i, end, now.date(), unit, -i32::try_from(n).ok()?)
+    }
+}
+
+/// 相对今天偏移 `n` 个单位的日、周、月或年，超出日期范围时没有候选
+fn point(start: usize, end: usize, today: NaiveDate, unit: Unit, n: i32) -> Option<Candidate> {
+    let (first, last) = match unit {
+        Unit::Day => {
+            let day = today.checked_add_signed(chrono::Duration::try_days(n as i64)?)?;
+            (day, day.checked_add_days(Days::new(1))?)
+        }
+        Unit::Week => {
+            let monday =
+                monday_of(today).checked_add_signed(chrono::Duration::try_weeks(n as i64)?)?;
+            (monday, monday.checked_add_days(Days::new(7))?)
+        }
+        Unit::Month => {
+            let first = shift_months(first_of_month(today), n)?;
+            (first, shift_months(first, 1)?)
+        }
+        Unit::Year => {
+            let first = NaiveDate::from_ymd_opt(today.year().checked_add(n)?, 1, 1)?;
+            (first, shift_months(first, 12)?)
+        }
+    };
+    Some(Candidate {
+        start,
+        end,
+        range: (midnight(first), midnight(last)),
+        granularity: unit.granularity(),
+    })
+}
+
+/// 截至现在的最近 `n` 个单位，超出日期范围时没有候选
+fn span(start: usize, end: usize, now: NaiveDateTime, unit: Unit, n: u32) -> Option<Candidate> {
+    let from = match unit {
+        Unit::Day => now.checked_sub_signed(chrono::Duration::try_days(n as i64)?)?,
+        Unit::Week => now.checked_sub_signed(chrono::Duration::try_weeks(n as i64)?)?,
+        Unit::Month => now.checked_sub_months(Months::new(n))?,
+        Unit::Year => now.checked_sub_months(Months::new(n.checked_mul(12)?))?,
+    };
+    Some(Candidate {
+        start,
+        end,
+        range: (from, now),
+        granularity: TimeGranularity::Span,
+    })
+}
+
+fn day_candidate(start: usize, end: usize, day: NaiveDate) -> Candidate {
+    Candidate {
+        start,
+        end,
+        range: (midnight(day), midnight(day + Days::new(1))),
+        granularity: TimeGranularity::Day,
+    }
+}
+
+/// 相对本周偏移 `offset` 周的周末，偏移为空时取不晚于今天的最近一个周末
+fn weekend(start: usize, end: usize, today: NaiveDate, offset: Option<i32>) -> Candidate {
+    let weeks = offset.unwrap_or(if today.weekday().num_days_from_monday() >= 5 {
+        0
+    } else {
+        -1
+    });
+    let saturday = monday_of(today) + chrono::Duration::weeks(weeks as i64) + Days::new(5);
+    Candidate {
+        start,
+        end,
+        range: (midnight(saturday), midnight(saturday + Days::new(2))),
+        granularity: TimeGranularity::Weekend,
+    }
+}
+
+fn month_candidate(start: usize, end: usize, year: i32, month: u32) -> Option<Candidate> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    Some(Candidate {
+        start,
+        end,
+        range: (midnight(first), midnight(shift_months(first, 1)?)),
+        granularity: TimeGranularity::Month,
+    })
+}
+
+/// “2025年”“去年三月”“2025年3月5日”“3月5号”
+fn chinese_date(chars: &[char], i: usize, today: NaiveDate) -> Option<Candidate> {
+    let year_prefix = [("明年", 1), ("今年", 0), ("去年", -1), ("前年", -2)]
+        .iter()
+        .find_map(|(p, n)| match_phrase(chars, i, p).map(|end| (today.year() + n, end)))
+        .or_else(|| {
+            let (year, j) =
+                parse_digits(chars, i).filter(|(_, j)| j - i == 4 && number_start(chars, i))?;
+            match_phrase(chars, j, "年").map(|end| (year as i32, end))
+        });
+    let month_start = year_prefix.map_or(i, |(_, end)| end);
+
+    let month = parse_chinese_number(chars, month_start)
+        .filter(|(m, _)| (1..=12).contains(m))
+        .and_then(|(m, j)| match_phrase(chars, j, "月").map(|end| (m, end)));
+    let Some((month, month_end)) = month else {
+        let (year, end) = year_prefix?;
+        return point(i, end, NaiveDate::from_ymd_opt(year, 1, 1)?, Unit::Year, 0);
+    };
+
+    let day = parse_chinese_number(chars, month_end)
+        .filter(|(d, _)| (1..=31).contains(d))
+        .and_then(|(d, j)| {
+            let end = match_phrase(chars, j, "日").or_else(|| match_phrase(chars, j, "号"))?;
+            Some((d, end))
+        });
+
+    // 不带年份的月份和日期取不晚于今天的最近一次
+    let year = year_prefix.map_or_else(
+        || {
+            let (d, _) = day.unwrap_or((1, 0));
+            match NaiveDate::from_ymd_opt(today.year(), month, d) {
+                Some(date) if date > today => today.year() - 1,
+                _ => today.year(),
+            }
+        },
+        |(year, _)| year,
+    );
+    match day {
+        Some((d, end)) => Some(day_candidate(
+            i,
+            end,
+            NaiveDate::from_ymd_opt(year, month, d)?,
+        )),
+        None => month_candidate(i, month_end, year, month),
+    }
+}
+
+/// “last Tuesday”“this Friday”“next Monday”“on Monday”“last weekend”“over the weekend”
+fn english_weekday(chars: &[char], i: usize, today: NaiveDate) -> Option<Candidate> {
+    let (word, j) = word_at(chars, i)?;
+    if !matches!(word.as_str(), "last" | "this" | "next" | "on" | "over") {
+        return None;
+    }
+    let (mut name, mut end) = word_at(chars, skip_spaces(chars, j))?;
+    if name == "the" && matches!(word.as_str(), "on" | "over") {
+        (name, end) = word_at(chars, skip_spaces(chars, end))?;
+    }
+    if name == "weekend" {
+        let offset = match word.as_str() {
+            "last" => Some(-1),
+            "this" => Some(0),
+            "next" => Some(1),
+            _ => None,
+        };
+        return Some(weekend(i, end, today, offset));
+    }
+    if word == "over" {
+        return None;
+    }
+
+    let index = ENGLISH_WEEKDAYS.iter().position(|d| *d == name)?;
+    let weekday = Weekday::try_from(index as u8).ok()?;
+    let day = match word.as_str() {
+        // “last Tuesday”指今天之前最近的一个周二，“next Monday”指下周一
+        "last" => last_weekday(today, weekday, false),
+        "this" => monday_of(today) + Days::new(index as u64),
+        "next" => monday_of(today) + Days::new(7 + index as u64),
+        _ => last_weekday(today, weekday, true),
+    };
+    Some(day_candidate(i, end, day))
+}
+
+/// “in March 2025”“March 5, 2025”“on 5 March 2025”“5th of March”“in March”“in 2025”
+///
+/// 月份名需要“in”“on”前缀或跟着日期、年份，避免把“may”“march”当作月份。
+fn english_date(chars: &[char], i: usize, today: NaiveDate) -> Option<Candidate> {
+    let (first, j) = word_at(chars, i)?;
+    let prefixed = matches!(first.as_str(), "in" | "on" | "during");
+    let (mut name, mut month_end) = if prefixed {
+        word_at(chars, skip_spaces(chars, j))?
+    } else {
+        (first, j)
+    };
+
+    // 日期在月份之前：“5 March”“5th of March”
+    let mut day = None;
+    if let Some(d) = ordinal_day(&name) {
+        let mut k = skip_spaces(chars, month_end);
+        if let Some((_, of_end)) = word_at(chars, k).filter(|(w, _)| w == "of") {
+            k = skip_spaces(chars, of_end);
+        }
+        if let Some((next, next_end)) =
+            word_at(chars, k).filter(|(w, _)| ENGLISH_MONTHS.contains(&w.as_str()))
+        {
+            day = Some(d);
+            (name, month_end) = (next, next_end);
+        }
+    }
+
+    let Some(index) = ENGLISH_MONTHS.iter().position(|m| *m == name) else {
+        let k = skip_spaces(chars, j);
+        // “on 2025-03-05”
+        if let Some(date) = iso_date(chars, k).filter(|_| prefixed) {
+            return Some(Candidate { start: i, ..date });
+        }
+        // “in 2025”
+        let (year, end) = parse_digits(chars, k).filter(|(_, end)| prefixed && *end - k == 4)?;
+        let first_day = NaiveDate::from_ymd_opt(year as i32, 1, 1)?;
+        return point(i, end, first_day, Unit::Year, 0);
+    };
+    let month = index as u32 + 1;
+
+    let mut end = month_end;
+    if day.is_none() {
+        if let Some((d, j)) =
+            word_at(chars, skip_spaces(chars, end)).and_then(|(w, j)| Some((ordinal_day(&w)?, j)))
+        {
+            day = Some(d);
+            end = j;
+        }
+    }
+    let mut k = skip_spaces(chars, end);
+    if chars.get(k) == Some(&',') {
+        k = skip_spaces(chars, k + 1);
+    }
+    let mut year = None;
+    if let Some((y, j)) = parse_digits(chars, k).filter(|(_, j)| j - k == 4) {
+        year = Some(y as i32);
+        end = j;
+    }
+    if !prefixed && day.is_none() && year.is_none() {
+        return None;
+    }
+
+    let year = year.unwrap_or_else(|| {
+        match NaiveDate::from_ymd_opt(today.year(), month, day.unwrap_or(1)) {
+            Some(date) if date > today => today.year() - 1,
+            _ => today.year(),
+        }
+    });
+    match day {
+        Some(d) => Some(day_candidate(
+            i,
+            end,
+            NaiveDate::from_ymd_opt(year, month, d)?,
+        )),
+        None => month_candidate(i, end, year, month),
+    }
+}
+
+/// “2025-03-05”“2025/3/5”“2025-03”
+fn iso_date(chars: &[char], i: usize) -> Option<Candidate> {
+    if !word_start(chars, i) {
+        return None;
+    }
+    let (year, j) = parse_digits(chars, i).filter(|(_, j)| j - i == 4)?;
+    let separator = *chars.get(j).filter(|c| matches!(c, '-' | '/'))?;
+    let (month, k) = parse_digits(chars, j + 1).filter(|(_, k)| k - j - 1 <= 2)?;
+
+    if chars.get(k) == Some(&separator) {
+        if let Some((day, end)) = parse_digits(chars, k + 1).filter(|(_, end)| end - k - 1 <= 2) {
+            let date = NaiveDate::from_ymd_opt(year as i32, month, day)?;
+            return word_boundary(chars, end).then(|| day_candidate(i, end, date));
+        }
+    }
+    if !word_boundary(chars, k) {
+        return None;
+    }
+    month_candidate(i, k, year as i32, month)
+}
+
+/// “5”“5th”这样的日期
+fn ordinal_day(word: &str) -> Option<u32> {
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|s| word.strip_suffix(s))
+        .unwrap_or(word);
+    if digits.len() > 2 {
+        return None;
+    }
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn midnight(date: NaiveDate) -> NaiveDateTime {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default()
+}
+
+fn monday_of(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday() as u64)
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn shift_months(date: NaiveDate, n: i32) -> Option<NaiveDate> {
+    if n >= 0 {
+        date.checked_add_months(Months::new(n as u32))
+    } else {
+        date.checked_sub_months(Months::new(n.unsigned_abs()))
+    }
+}
+
+/// 不晚于（或早于）今天的最近一个指定星期几
+fn last_weekday(today: NaiveDate, weekday: Weekday, include_today: bool) -> NaiveDate {
+    let diff = (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    let diff = if diff == 0 && !include_today { 7 } else { diff };
+    today - Days::new(diff as u64)
+}
+
+fn chinese_weekday(c: char) -> Option<Weekday> {
+    Some(match c {
+        '一' => Weekday::Mon,
+        '二' => Weekday::Tue,
+        '三' => Weekday::Wed,
+        '四' => Weekday::Thu,
+        '五' => Weekday::Fri,
+        '六' => Weekday::Sat,
+        '日' | '天' => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// 在位置 `i` 匹配短语，返回结束位置；ASCII短语要求整词匹配
+fn match_phrase(chars: &[char], i: usize, phrase: &str) -> Option<usize> {
+    let mut j = i;
+    for p in phrase.chars() {
+        if chars.get(j) != Some(&p) {
+            return None;
+        }
+        j += 1;
+    }
+    if phrase.is_ascii() && !(word_start(chars, i) && word_boundary(chars, j)) {
+        return None;
+    }
+    Some(j)
+}
+
+fn word_start(chars: &[char], i: usize) -> bool {
+    i == 0 || !chars[i - 1].is_ascii_alphanumeric()
+}
+
+fn word_boundary(chars: &[char], j: usize) -> bool {
+    chars.get(j).is_none_or(|c| !c.is_ascii_alphanumeric())
+}
+
+/// 位置 `i` 开始的英文单词
+fn word_at(chars: &[char], i: usize) -> Option<(String, usize)> {
+    if !word_start(chars, i) {
+        return None;
+    }
+    let end = (i..chars.len())
+        .find(|j| !chars[*j].is_ascii_alphanumeric())
+        .unwrap_or(chars.len());
+    (end > i).then(|| (chars[i..end].iter().collect(), end))
+}
+
+/// 时间词后跟着“is”“was”等动词时，该词是句子主语而非时间状语
+fn is_subject(chars: &[char], end: usize) -> bool {
+    word_at(chars, skip_spaces(chars, end))
+        .is_some_and(|(word, _)| ENGLISH_SUBJECT_VERBS.contains(&word.as_str()))
+}
+
+fn skip_spaces(chars: &[char], mut i: usize) -> usize {
+    while chars.get(i).is_some_and(|c| c.is_whitespace()) {
+        i += 1;
+    }
+    i
+}
+
+fn parse_digits(chars: &[char], i: usize) -> Option<(u32, usize)> {
+    let mut value: u32 = 0;
+    let mut j = i;
+    while let Some(d) = chars.get(j).and_then(|c| c.to_digit(10)) {
+        value = value.checked_mul(10)?.checked_add(d)?;
+        j += 1;
+    }
+    (j > i).then_some((value, j))
+}
+
+/// 阿拉伯数字或一百以内的中文数字
+fn parse_chinese_number(chars: &[char], i: usize) -> Option<(u32, usize)> {
+    if !number_start(chars, i) {
+        return None;
+    }
+    if let Some(parsed) = parse_digits(chars, i) {
+        return Some(parsed);
+    }
+    let digit = |c: char| "零一二三四五六七八九".chars().position(|d| d == c);
+    let (mut value, mut current, mut j) = (0, None, i);
+    while let Some(c) = chars.get(j) {
+        if *c == '两' {
+            current = Some(2);
+        } else if *c == '十' {
+            value = current
+                .unwrap_or(1u32)
+                .checked_mul(10)?
+                .checked_add(value)?;
+            current = None;
+        } else if let Some(d) = digit(*c) {
+            current = Some(d as u32);
+        } else {
+            break;
+        }
+        j += 1;
+    }
+    (j > i).then(|| (value + current.unwrap_or(0), j))
+}
+
+/// 位置 `i` 不在一个数字的中间
+fn number_start(chars: &[char], i: usize) -> bool {
+    i == 0 || !(chars[i - 1].is_ascii_digit() || "零一二三四五六七八九十两".contains(chars[i - 1]))
+}
+
+/// 阿拉伯数字、数词或“a”“an”
+fn english_number(chars: &[char], i: usize) -> Option<(u32, usize)> {
+    let (word, end) = word_at(chars, i)?;
+    let value = match word.as_str() {
+        "a" | "an" => 1,
+        _ => match word.parse() {
+            Ok(n) => n,
+            Err(_) => ENGLISH_NUMBERS.iter().position(|n| *n == word)? as u32,
+        },
+    };
+    Some((value, end))
+}
+
+fn english_unit(chars: &[char], i: usize) -> Option<(Unit, usize)> {
+    let (word, end) = word_at(chars, i)?;
+    let unit = match word.trim_end_matches('s') {
+        "day" => Unit::Day,
+        "week" => Unit::Week,
+        "month" => Unit::Month,
+        "year" => Unit::Year,
+        _ => return None,
+    };
+    Some((unit, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2025-06-18 10:00 (UTC+8)，星期三
+    fn reference() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 18, 2, 0, 0).unwrap()
+    }
+
+    fn parser() -> TimeExpressionParser {
+        TimeExpressionParser::new(&Config::default())
+            .with_offset(FixedOffset::east_opt(8 * 3600).unwrap())
+    }
+
+    /// 解析结果的本地起止日期和剩余查询
+    fn parse_at(text: &str, reference: DateTime<Utc>) -> Option<(String, String, String)> {
+        let parsed = parser().parse(text, reference)?;
+        let local = |t: DateTime<Utc>| {
+            let offset = FixedOffset::east_opt(8 * 3600).unwrap();
+            t.with_timezone(&offset).format("%Y-%m-%d").to_string()
+        };
+        let e = parsed.expression;
+        Some((local(e.start), local(e.end), parsed.remaining))
+    }
+
+    /// 查询及期望的本地起止日期和剩余查询
+    type Case = (
+        &'static str,
+        Option<(&'static str, &'static str, &'static str)>,
+    );
+
+    #[test]
+    fn test_expression_table() {
+        // 期望为空表示没有时间表达式
+        let cases: &[Case] = &[
+            (
+                "昨天吃了什么",
+                Some(("2025-06-17", "2025-06-17", "吃了什么")),
+            ),
+            ("明天的安排", Some(("2025-06-19", "2025-06-19", "安排"))),
+            ("后天开会", Some(("2025-06-20", "2025-06-20", "开会"))),
+            ("上周三的会议", Some(("2025-06-11", "2025-06-11", "会议"))),
+            ("下周三的会议", Some(("2025-06-25", "2025-06-25", "会议"))),
+            ("周日去哪了", Some(("2025-06-15", "2025-06-15", "去哪了"))),
+            ("上周读的书", Some(("2025-06-09", "2025-06-15", "读的书"))),
+            ("周末去哪了", Some(("2025-06-14", "2025-06-15", "去哪了"))),
+            ("上周末的聚会", Some(("2025-06-14", "2025-06-15", "聚会"))),
+            (
+                "这周末有什么安排",
+                Some(("2025-06-21", "2025-06-22", "有什么安排")),
+            ),
+            ("三天前的想法", Some(("2025-06-15", "2025-06-15", "想法"))),
+            ("三天后的提醒", Some(("2025-06-21", "2025-06-21", "提醒"))),
+            ("两个月前买的", Some(("2025-04-01", "2025-04-30", "买的"))),
+            ("下个月的计划", Some(("2025-07-01", "2025-07-31", "计划"))),
+            (
+                "2025年3月的旅行",
+                Some(("2025-03-01", "2025-03-31", "旅行")),
+            ),
+            ("去年十二月", Some(("2024-12-01", "2024-12-31", ""))),
+            ("明年的目标", Some(("2026-01-01", "2026-12-31", "目标"))),
+            ("8月5号见了谁", Some(("2024-08-05", "2024-08-05", "见了谁"))),
+            ("最近三天的笔记", Some(("2025-06-15", "2025-06-18", "笔记"))),
+            (
+                "2025-03-05的会议",
+                Some(("2025-03-05", "2025-03-05", "会议")),
+            ),
+            ("2025/3/5", Some(("2025-03-05", "2025-03-05", ""))),
+            ("2025-03", Some(("2025-03-01", "2025-03-31", ""))),
+            ("关于咖啡的笔记", None),
+            (
+                "what did I eat yesterday?",
+                Some(("2025-06-17", "2025-06-17", "what did I eat")),
+            ),
+            (
+                "what did I do today",
+                Some(("2025-06-18", "2025-06-18", "what did I do")),
+            ),
+            (
+                "yesterday's notes",
+                Some(("2025-06-17", "2025-06-17", "notes")),
+            ),
+            (
+                "tomorrow's meeting",
+                Some(("2025-06-19", "2025-06-19", "meeting")),
+            ),
+            (
+                "the day after tomorrow",
+                Some(("2025-06-20", "2025-06-20", "")),
+            ),
+            (
+                "notes from last month",
+                Some(("2025-05-01", "2025-05-31", "notes from")),
+            ),
+            (
+                "plans for next week",
+                Some(("2025-06-23", "2025-06-29", "plans for")),
+            ),
+            (
+                "trip in March 2025",
+                Some(("2025-03-01", "2025-03-31", "trip")),
+            ),
+            (
+                "meeting on 5 March 2025",
+                Some(("2025-03-05", "2025-03-05", "meeting")),
+            ),
+            ("on March 5", Some(("2025-03-05", "2025-03-05", ""))),
+            ("5th of March", Some(("2025-03-05", "2025-03-05", ""))),
+            (
+                "notes on 2025-03-05",
+                Some(("2025-03-05", "2025-03-05", "notes")),
+            ),
+            (
+                "ideas two days ago",
+                Some(("2025-06-16", "2025-06-16", "ideas")),
+            ),
+            (
+                "reminder in 3 days",
+                Some(("2025-06-21", "2025-06-21", "reminder")),
+            ),
+            (
+                "meeting last Tuesday",
+                Some(("2025-06-17", "2025-06-17", "meeting")),
+            ),
+            (
+                "dinner next Friday",
+                Some(("2025-06-27", "2025-06-27", "dinner")),
+            ),
+            (
+                "what did I do last weekend",
+                Some(("2025-06-14", "2025-06-15", "what did I do")),
+            ),
+            (
+                "hiking over the weekend",
+                Some(("2025-06-14", "2025-06-15", "hiking")),
+            ),
+            ("this weekend", Some(("2025-06-21", "2025-06-22", ""))),
+            ("books in 2024", Some(("2024-01-01", "2024-12-31", "books"))),
+            ("past 2 weeks", Some(("2025-06-04", "2025-06-18", ""))),
+            // 时间短语之外的“may”“march”不是月份
+            ("we may march on", None),
+            // 作主语的“today”不是时间条件
+            ("I said today is fine", None),
+            // 超出日期范围的偏移没有时间表达式，而不是溢出或退回今年
+            ("what happened 99999999 days ago", None),
+            ("99999999天前", None),
+            ("3000000000 days ago", None),
+            ("last 99999999 days", None),
+            ("last 400000000 years", None),
+            ("in 2000000 years", None),
+        ];
+
+        for (text, expected) in cases {
+            let expected = expected.map(|(s, e, r)| (s.to_string(), e.to_string(), r.to_string()));
+            assert_eq!(parse_at(text, reference()), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_granularity() {
+        let span = parser().parse("recently", reference()).unwrap().expression;
+        assert_eq!(span.granularity, TimeGranularity::Span);
+        assert_eq!(span.end, reference());
+
+        let weekend = parser().parse("周末", reference()).unwrap().expression;
+        assert_eq!(weekend.granularity, TimeGranularity::Weekend);
+
+        // 周末当天说“周末”指本周末
+        let saturday = Utc.with_ymd_and_hms(2025, 6, 21, 2, 0, 0).unwrap();
+        assert_eq!(
+            parse_at("周末", saturday),
+            Some(("2025-06-21".into(), "2025-06-22".into(), String::new()))
+        );
+    }
+
+    #[test]
+    fn test_window_relevance() {
+        let window = (reference() - chrono::Duration::days(4), reference());
+        assert_eq!(window_relevance(reference(), &window), 1.0);
+        assert_eq!(window_relevance(window.0, &window), 0.5);
+        let middle = reference() - chrono::Duration::days(1);
+        assert!((window_relevance(middle, &window) - 0.875).abs() < 1e-6);
+        assert_eq!(
+            window_relevance(window.0 - chrono::Duration::seconds(1), &window),
+            0.0
+        );
+    }
+}
@@ -5,6 +5,7 @@
 
 use crate::core::*;
 use crate::error::{MemoryError, Result};
+use crate::segment::Segmenter;
 use chrono::{DateTime, Duration, Timelike, Utc};
 use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use serde::{Deserialize, Serialize};
@@ -251,7 +252,7 @@ impl TextUtils {
     /// 计算文本统计信息
     pub fn analyze_text(text: &str) -> TextStats {
         let char_count = text.chars().count();
-        let word_count = text.split_whitespace().count();
+        let word_count = Segmenter::shared().segment(text).len();
         // 改进句子计数逻辑，支持中英文标点符号
         let sentence_count = if text.trim().is_empty() {
             0
@@ -289,7 +290,11 @@ impl TextUtils {
 
     /// 计算文本复杂度
     fn calculate_complexity(text: &str) -> f32 {
-        let words: Vec<&str> = text.split_whitespace().collect();
+        let words: Vec<String> = Segmenter::shared()
+            .segment(text)
+            .into_iter()
+            .map(|token| token.text)
+            .collect();
         if words.is_empty() {
             return 0.0;
         }
@@ -300,7 +305,7 @@ impl TextUtils {
             .sum::<f32>()
             / words.len() as f32;
 
-        let unique_words: HashSet<&str> = words.iter().cloned().collect();
+        let unique_words: HashSet<&String> = words.iter().collect();
         let vocabulary_diversity = unique_words.len() as f32 / words.len() as f32;
 
         // 简化的复杂度计算
@@ -347,92 +352,57 @@ impl TextUtils {
     }
 
     /// 简单的语言检测
-    fn detect_language(text: &str) -> Option<String> {
-        let chinese_chars = text
-            .chars()
-            .filter(|c| {
-                let code = *c as u32;
-                (0x4E00..=0x9FFF).contains(&code) // CJK统一汉字
-            })
-            .count();
-
+    ///
+    /// 根据字符所属的文字系统判断：含假名为日文，谚文占比较高为韩文，
+    /// 汉字占比较高为中文，否则为英文。
+    pub fn detect_language(text: &str) -> Option<String> {
         let total_chars = text.chars().filter(|c| c.is_alphabetic()).count();
-
         if total_chars == 0 {
             return None;
         }
 
-        if chinese_chars as f32 / total_chars as f32 > 0.3 {
-            Some("zh".to_string())
+        let count = |range: std::ops::RangeInclusive<u32>| {
+            text.chars()
+                .filter(|c| range.contains(&(*c as u32)))
+                .count()
+        };
+        let kana = count(0x3040..=0x30FF); // 平假名和片假名
+        let hangul = count(0xAC00..=0xD7AF); // 谚文音节
+        let chinese_chars = text.chars().filter(|c| Self::is_cjk(*c)).count();
+
+        let language = if kana > 0 && (kana + chinese_chars) as f32 / total_chars as f32 > 0.3 {
+            "ja"
+        } else if hangul as f32 / total_chars as f32 > 0.3 {
+            "ko"
+        } else if chinese_chars as f32 / total_chars as f32 > 0.3 {
+            "zh"
         } else {
-            Some("en".to_string())
-        }
+            "en"
+        };
+        Some(language.to_string())
+    }
+
+    /// 是否为CJK统一汉字
+    pub fn is_cjk(c: char) -> bool {
+        (0x4E00..=0x9FFF).contains(&(c as u32))
     }
 
     /// 提取关键词（简单实现）
+    ///
+    /// 使用默认配置的分词器，评分规则见 [`Segmenter::extract_keywords`]。
     pub fn extract_keywords(text: &str, max_keywords: usize) -> Vec<String> {
-        let stop_words: HashSet<&str> = [
-            "the", "a", "an", "and", "or", "but", "in", "on", "at", "to", "for", "of", "with",
-            "by", "is", "are", "was", "were", "be", "been", "have", "has", "had", "do", "does",
-            "did", "will", "would", "could", "should", "的", "了", "在", "是", "我", "你", "他",
-            "她", "它", "们", "这", "那", "有", "和", "与", "或", "但", "如果", "因为", "所以",
-            "然后", "现在", "一个", "多个", "包含", "还有",
-        ]
-        .iter()
-        .cloned()
-        .collect();
-
-        let mut word_freq: HashMap<String, usize> = HashMap::new();
-
-        // 处理中文文本，按字符分割
-        let chinese_chars = text
-            .chars()
-            .filter(|c| {
-                let code = *c as u32;
-                (0x4E00..=0x9FFF).contains(&code)
-            })
-            .count();
-
-        if chinese_chars > text.chars().count() / 3 {
-            // 中文文本处理
-            for word in text.split_whitespace() {
-                let cleaned = word
-                    .chars()
-                    .filter(|c| c.is_alphanumeric())
-                    .collect::<String>();
-
-                if cleaned.len() >= 2 && !stop_words.contains(cleaned.as_str()) {
-                    *word_freq.entry(cleaned).or_insert(0) += 1;
-                }
-            }
-        } else {
-            // 英文文本处理
-            for word in text.split_whitespace() {
-                let word = word
-                    .to_lowercase()
-                    .trim_matches(|c: char| !c.is_alphanumeric())
-                    .to_string();
-
-                if word.len() > 2 && !stop_words.contains(word.as_str()) {
-                    *word_freq.entry(word).or_insert(0) += 1;
-                }
-            }
-        }
-
-        let mut keywords: Vec<(String, usize)> = word_freq.into_iter().collect();
-        keywords.sort_by(|a, b| b.1.cmp(&a.1));
+        Segmenter::shared().extract_keywords(text, max_keywords)
+    }
 
-        keywords
-            .into_iter()
-            .take(max_keywords)
-            .map(|(word, _)| word)
-            .collect()
+    /// 分词并去除停用词，供相似度计算使用
+    pub fn tokenize(text: &str) -> Vec<String> {
+        Segmenter::shared().tokenize(text)
     }
 
     /// 计算Jaccard相似度
     pub fn jaccard_similarity(text1: &str, text2: &str) -> f32 {
-        let words1: HashSet<&str> = text1.split_whitespace().collect();
-        let words2: HashSet<&str> = text2.split_whitespace().collect();
+        let words1: HashSet<String> = Self::tokenize(text1).into_iter().collect();
+        let words2: HashSet<String> = Self::tokenize(text2).into_iter().collect();
 
         if words1.is_empty() && words2.is_empty() {
             return 1.0;
@@ -681,6 +651,65 @@ impl HashUtils {
     pub fn verify_hash(input: &str, expected_hash: &str) -> bool {
         Self::hash_string(input) == expected_hash
     }
+
+    /// 64位哈希（取SHA-256的前8字节），跨进程稳定
+    pub fn hash64(input: &str) -> u64 {
+        let digest = Sha256::digest(input.as_bytes());
+        u64::from_be_bytes(digest[..8].try_into().expect("digest has 32 bytes"))
+    }
+
+    /// 计算特征集合的SimHash指纹
+    pub fn simhash<S: AsRef<str>>(features: &[S]) -> u64 {
+        let mut weights = [0i32; 64];
+        for feature in features {
+            let hash = Self::hash64(feature.as_ref());
+            for (bit, weight) in weights.iter_mut().enumerate() {
+                *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+            }
+        }
+
+        weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .fold(0u64, |fingerprint, (bit, _)| fingerprint | 1 << bit)
+    }
+
+    /// 两个指纹的汉明距离
+    pub fn hamming_distance(a: u64, b: u64) -> u32 {
+        (a ^ b).count_ones()
+    }
+
+    /// 计算特征集合的MinHash签名
+    ///
+    /// 每个哈希函数由一个奇数乘子和一个偏移量构成的64位置换模拟。
+    pub fn minhash<S: AsRef<str>>(features: &[S], num_hashes: usize) -> Vec<u64> {
+        let hashes: Vec<u64> = features.iter().map(|f| Self::hash64(f.as_ref())).collect();
+        (0..num_hashes)
+            .map(|i| {
+                let multiplier = Self::hash64(&format!("minhash-a-{}", i)) | 1;
+                let offset = Self::hash64(&format!("minhash-b-{}", i));
+                hashes
+                    .iter()
+                    .map(|hash| {
+                        let h = hash.wrapping_mul(multiplier).wrapping_add(offset);
+                        h ^ (h >> 31)
+                    })
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect()
+    }
+
+    /// 由MinHash签名估计Jaccard相似度
+    pub fn minhash_similarity(a: &[u64], b: &[u64]) -> f32 {
+        let len = a.len().min(b.len());
+        if len == 0 {
+            return 0.0;
+        }
+        let equal = a.iter().zip(b).filter(|(x, y)| x == y).count();
+        equal as f32 / len as f32
+    }
 }
 
 impl IdGenerator {
@@ -858,7 +887,25 @@ mod tests {
         let keywords = TextUtils::extract_keywords(text, 3);
 
         assert!(!keywords.is_empty());
-        assert!(keywords.len() > 0);
+        assert_eq!(keywords[0], "机器学习");
+        assert!(keywords.contains(&"人工智能".to_string()));
+
+        let keywords = TextUtils::extract_keywords("今天学习了卷积神经网络(CNN)的基本原理", 10);
+        assert!(keywords.contains(&"cnn".to_string()));
+        assert!(keywords.contains(&"卷积神经网络".to_string()));
+        assert!(keywords.contains(&"原理".to_string()));
+        assert!(!keywords.contains(&"的".to_string()));
+
+        // 中文按词计算相似度，而不是把整句当作一个词
+        let similarity = TextUtils::jaccard_similarity("我喜欢机器学习", "机器学习很有趣");
+        assert!((similarity - 1.0 / 3.0).abs() < 1e-6);
+
+        assert_eq!(
+            TextUtils::detect_language("今日は天気がいいです"),
+            Some("ja".into())
+        );
+        assert_eq!(TextUtils::detect_language("Rust 很好用"), Some("zh".into()));
+        assert_eq!(TextUtils::detect_language("12345"), None);
     }
 
     #[test]
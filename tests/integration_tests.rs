@@ -15,7 +15,6 @@ use ai00_mem::RetrievalContext;
 use ai00_mem::UpdateMemoryRequest;
 use chrono::{Duration, Utc};
 use std::sync::Arc;
-use tokio;
 
 /// 测试基本的记忆管理功能
 #[tokio::test]
@@ -66,6 +65,7 @@ async fn test_basic_memory_management() {
         offset: None,
         sort_by: None,
         weights: QueryWeights::default(),
+        expansion: None,
     };
     let context = Context::default();
     let results = memory_manager
@@ -147,6 +147,7 @@ async fn test_memory_connections() {
         offset: None,
         sort_by: None,
         weights: QueryWeights::default(),
+        expansion: None,
     };
     let context = Context::default();
     let results = memory_manager
@@ -229,6 +230,7 @@ async fn test_different_retrieval_types() {
         offset: None,
         sort_by: None,
         weights: QueryWeights::default(),
+        expansion: None,
     };
 
     let _semantic_results = memory_manager
@@ -248,6 +250,7 @@ async fn test_different_retrieval_types() {
         offset: None,
         sort_by: None,
         weights: QueryWeights::default(),
+        expansion: None,
     };
 
     let temporal_results = memory_manager
@@ -274,19 +277,19 @@ async fn test_hippocampus_retrieval() {
         .unwrap();
 
     // 创建一些相关的记忆
-    let memories = vec![
+    let memories = [
         "机器学习是人工智能的一个分支",
         "深度学习是机器学习的子集",
         "神经网络是深度学习的基础",
         "Transformer是现代NLP的核心架构",
     ];
 
-    for (i, content) in memories.iter().enumerate() {
+    for content in memories.iter() {
         let content = content.to_string();
         let embedding = memory_manager.generate_embedding(&content).await.unwrap();
         let memory = Memory {
             id: IdGenerator::generate_memory_id(),
-            content: content,
+            content,
             memory_type: MemoryType::Knowledge,
             embedding,
             attributes: MemoryAttributes {
@@ -317,6 +320,7 @@ async fn test_hippocampus_retrieval() {
         offset: None,
         sort_by: None,
         weights: QueryWeights::default(),
+        expansion: None,
     };
 
     let context = RetrievalContext {
@@ -359,9 +363,9 @@ async fn test_learning_engine() {
     // 创建记忆
     let content = "学习引擎测试记忆".to_string();
     let embedding = memory_manager.generate_embedding(&content).await.unwrap();
-    let mut memory = Memory {
+    let memory = Memory {
         id: IdGenerator::generate_memory_id(),
-        content: content,
+        content,
         memory_type: MemoryType::Knowledge,
         embedding,
         attributes: MemoryAttributes {
@@ -464,6 +468,7 @@ async fn test_concurrent_operations() {
         offset: None,
         sort_by: None,
         weights: QueryWeights::default(),
+        expansion: None,
     };
 
     let context = Context::default();
@@ -548,6 +553,7 @@ async fn test_filtering() {
         offset: None,
         sort_by: None,
         weights: QueryWeights::default(),
+        expansion: None,
     };
 
     let filtered_results = memory_manager
@@ -619,6 +625,7 @@ async fn test_memory_updates() {
         offset: None,
         sort_by: None,
         weights: QueryWeights::default(),
+        expansion: None,
     };
 
     let results = memory_manager
@@ -1,5 +1,5 @@
 use ai00_mem::database::{VectorGraphDB, Vector, GraphNode, GraphEdge, VectorQueryRequest, GraphQueryRequest};
-use ai00_mem::config::{Config, DatabaseConfig, DatabaseType};
+use ai00_mem::config::{Config, DatabaseType};
 use std::collections::HashMap;
 use chrono::Utc;
 use serde_json::json;
@@ -135,18 +135,16 @@ async fn test_memory_and_sqlite_storage() {
 async fn test_data_persistence() {
     println!("开始测试数据持久化...");
     
-    let db_url = "sqlite:C:\\work\\ai00.art\\ai00-mem\\test_persistence.db?mode=rwc";
-    
-    let db_file = "C:\\work\\ai00.art\\ai00-mem\\test_persistence.db";
-    
-    // 确保清理旧文件
-    let _ = std::fs::remove_file(db_file);
+    // 数据库文件放在临时目录中，目录在测试结束时删除
+    let temp_dir = tempfile::tempdir().expect("创建临时目录失败");
+    let db_file = temp_dir.path().join("test_persistence.db");
+    let db_url = format!("sqlite:{}?mode=rwc", db_file.display());
     
     // 第一次运行：创建数据库并存储数据
     {
         let mut config = Config::default();
         config.database.database_type = DatabaseType::SQLite;
-        config.database.url = db_url.to_string();
+        config.database.url = db_url.clone();
         config.database.table_prefix = "persist_".to_string();
         
         let db = VectorGraphDB::new(config).await.expect("创建数据库失败");
@@ -188,7 +186,7 @@ async fn test_data_persistence() {
     {
         let mut config = Config::default();
         config.database.database_type = DatabaseType::SQLite;
-        config.database.url = db_url.to_string();
+        config.database.url = db_url.clone();
         config.database.table_prefix = "persist_".to_string();
         
         let db = VectorGraphDB::new(config).await.expect("重新打开数据库失败");
@@ -224,9 +222,6 @@ async fn test_data_persistence() {
         println!("✓ 第二次运行：数据验证完成");
     }
     
-    // 清理测试文件
-    let _ = std::fs::remove_file("./test_persistence.db");
-    
     println!("数据持久化测试通过!");
 }
 
@@ -255,7 +250,7 @@ async fn test_memory_cache_functionality() {
     }
     
     // 获取统计信息
-    let (vector_count, node_count, edge_count, cache_stats) = db.get_stats().await.expect("获取统计失败");
+    let (vector_count, _node_count, _edge_count, cache_stats) = db.get_stats().await.expect("获取统计失败");
     
     println!("缓存测试统计:");
     println!("  数据库向量: {}", vector_count);